@@ -7,88 +7,85 @@ use crate::service::v2::block::{
 };
 use crate::service::v2::claim::{get_claim_by_tx_hash, get_claims};
 use crate::service::v2::delegation::{get_delegation_by_tx_hash, get_delegations};
+use crate::service::v2::mint::{get_daily_mints, get_mints};
 use crate::service::v2::other::{
     get_address_count, get_market, get_price, get_statistics, get_tx_distribute,
 };
 use crate::service::v2::prism_evm_to_native::{get_e2n_by_tx_hash, get_e2n_txs};
 use crate::service::v2::prism_native_to_evm::{get_n2e_by_tx_hash, get_n2e_txs};
-use crate::service::v2::transaction::{get_tx_by_hash, get_txs};
+use crate::service::v2::slashing::get_slashing_events;
+use crate::service::v2::transaction::{get_tx_by_hash, get_tx_operations, get_tx_proof, get_txs};
 use crate::service::v2::undelegation::{get_undelegation_by_tx_hash, get_undelegations};
+use crate::service::v2::uptime::{get_validator_uptime, get_validators_uptime};
+use crate::service::v2::validator::{get_validator_detail, get_validator_history};
 use anyhow::Result;
 use axum::http::Method;
 use axum::routing::get;
 use axum::Router;
+use clap::{Parser, Subcommand};
 use log::info;
-use sqlx::pool::PoolOptions;
-use sqlx::{PgPool, Pool, Postgres};
+use module::config::explorer_config::Config;
+use module::network::Network;
+use module::storage::Storage;
+use sqlx::{Pool, Postgres};
 use std::sync::Arc;
 use std::time::Duration;
+use storage::{PgStorage, SqliteStorage};
 use tower_http::cors::{Any, CorsLayer};
 
 struct AppState {
-    pub pool: PgPool,
+    pub storage: Arc<dyn Storage>,
+}
+
+/// Serve the networks of the config at `CONFIG_FILE_PATH`, overridden by the env.
+#[derive(Parser)]
+#[clap(about, version, author)]
+struct ExplorerCmd {
+    #[clap(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    #[clap(subcommand)]
+    Config(ConfigCmd),
+}
+
+/// Config of the file at `CONFIG_FILE_PATH` and the env.
+#[derive(Subcommand)]
+enum ConfigCmd {
+    /// Validate the config and print the effective one, without the database password.
+    Check,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
-    let config_path = std::env::var("CONFIG_FILE_PATH").unwrap();
-    let config = module::config::explorer_config::Config::new(&config_path)?;
-    let postgres_config = format!(
-        "postgres://{}:{}@{}/{}",
-        config.postgres.account,
-        config.postgres.password,
-        config.postgres.addr,
-        config.postgres.database
-    );
-
-    let pool: Pool<Postgres> = PoolOptions::new()
-        .max_connections(20)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(&postgres_config)
-        .await
-        .expect("can't connect to database");
+    let cmd = ExplorerCmd::parse();
+    let config_path = std::env::var("CONFIG_FILE_PATH")
+        .map_err(|_| anyhow::anyhow!("`CONFIG_FILE_PATH` must point to the config file"))?;
+    let config = Config::new(&config_path)?;
+    if let Some(Cmd::Config(ConfigCmd::Check)) = cmd.cmd {
+        println!("{}", config.redacted().to_toml()?);
+        return Ok(());
+    }
+    let networks = config.networks()?;
 
-    info!("Connecting DB...ok");
-
-    let app_state = Arc::new(AppState { pool });
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
         .allow_origin(Any)
         .allow_headers(Any);
     let addr = format!("{}:{}", config.server.addr, config.server.port);
-    let app = Router::new()
-        // chain
-        .route("/api/chain/address/count", get(get_address_count))
-        .route("/api/chain/statistics", get(get_statistics))
-        // block
-        .route("/api/block/hash/:hash", get(get_simple_block_by_hash))
-        .route("/api/block/full/hash/:hash", get(get_full_block_by_hash))
-        .route("/api/block/height/:num", get(get_simple_block_by_height))
-        .route("/api/block/full/height/:num", get(get_full_block_by_height))
-        .route("/api/block", get(get_block_by_hash))
-        .route("/api/blocks", get(get_blocks))
-        // tx
-        .route("/api/tx", get(get_tx_by_hash))
-        .route("/api/txs", get(get_txs))
-        .route("/api/txs/distribute", get(get_tx_distribute))
-        // staking
-        .route("/api/claim", get(get_claim_by_tx_hash))
-        .route("/api/claims", get(get_claims))
-        .route("/api/delegation", get(get_delegation_by_tx_hash))
-        .route("/api/delegations", get(get_delegations))
-        .route("/api/undelegation", get(get_undelegation_by_tx_hash))
-        .route("/api/undelegations", get(get_undelegations))
-        .route("/api/n2e", get(get_n2e_by_tx_hash))
-        .route("/api/n2es", get(get_n2e_txs))
-        .route("/api/e2n", get(get_e2n_by_tx_hash))
-        .route("/api/e2ns", get(get_e2n_txs))
-        // asset
-        .route("/api/assets", get(get_assets))
-        .route("/api/coins/:id/market_chart", get(get_market))
-        .route("/api/simple/price", get(get_price))
-        .layer(cors)
-        .with_state(app_state);
+    let mut app = Router::new();
+    for (i, network) in networks.iter().enumerate() {
+        let routes = network_routes(&config, network).await?;
+        if i == 0 {
+            app = app.nest("/api", routes.clone());
+        }
+        app = app.nest(&format!("/api/{}", network.name), routes);
+        info!("Serving {} at /api/{}", network.name, network.name);
+    }
+    let app = app.layer(cors);
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
 
     info!("Listening at: {}", addr);
@@ -97,3 +94,79 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// The routes of `network`, over its rows of the configured database.
+async fn network_routes(config: &Config, network: &Network) -> Result<Router> {
+    let storage: Arc<dyn Storage> = if let Some(sqlite) = &config.sqlite {
+        let url = format!("sqlite://{}", sqlite.path);
+        let storage = SqliteStorage::connect(&url)
+            .await
+            .expect("can't open database");
+        storage.pin_network(&network.name).await?;
+        Arc::new(storage)
+    } else {
+        let postgres = config
+            .postgres
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("either `postgres` or `sqlite` must be configured"))?;
+        let postgres_config = format!(
+            "postgres://{}:{}@{}/{}",
+            postgres.account, postgres.password, postgres.addr, postgres.database
+        );
+
+        let pool: Pool<Postgres> = storage::db::pool_options(&network.name)
+            .max_connections(postgres.max_connections)
+            .acquire_timeout(Duration::from_secs(postgres.acquire_timeout))
+            .connect(&postgres_config)
+            .await
+            .expect("can't connect to database");
+        storage::db::check_schema(&pool).await?;
+        Arc::new(PgStorage::new(pool))
+    };
+
+    info!("Connecting DB of {}...ok", network.name);
+
+    let app_state = Arc::new(AppState { storage });
+    let routes = Router::new()
+        // chain
+        .route("/chain/address/count", get(get_address_count))
+        .route("/chain/statistics", get(get_statistics))
+        // block
+        .route("/block/hash/:hash", get(get_simple_block_by_hash))
+        .route("/block/full/hash/:hash", get(get_full_block_by_hash))
+        .route("/block/height/:num", get(get_simple_block_by_height))
+        .route("/block/full/height/:num", get(get_full_block_by_height))
+        .route("/block", get(get_block_by_hash))
+        .route("/blocks", get(get_blocks))
+        // tx
+        .route("/tx", get(get_tx_by_hash))
+        .route("/tx/operations", get(get_tx_operations))
+        .route("/tx/proof", get(get_tx_proof))
+        .route("/txs", get(get_txs))
+        .route("/txs/distribute", get(get_tx_distribute))
+        // staking
+        .route("/claim", get(get_claim_by_tx_hash))
+        .route("/claims", get(get_claims))
+        .route("/delegation", get(get_delegation_by_tx_hash))
+        .route("/delegations", get(get_delegations))
+        .route("/undelegation", get(get_undelegation_by_tx_hash))
+        .route("/undelegations", get(get_undelegations))
+        .route("/n2e", get(get_n2e_by_tx_hash))
+        .route("/n2es", get(get_n2e_txs))
+        .route("/e2n", get(get_e2n_by_tx_hash))
+        .route("/e2ns", get(get_e2n_txs))
+        .route("/mints", get(get_mints))
+        .route("/mints/daily", get(get_daily_mints))
+        // validator
+        .route("/validator/:address", get(get_validator_detail))
+        .route("/validator/:address/history", get(get_validator_history))
+        .route("/validator/:address/uptime", get(get_validator_uptime))
+        .route("/validators/uptime", get(get_validators_uptime))
+        .route("/slashing", get(get_slashing_events))
+        // asset
+        .route("/assets", get(get_assets))
+        .route("/coins/:id/market_chart", get(get_market))
+        .route("/simple/price", get(get_price))
+        .with_state(app_state);
+    Ok(routes)
+}
@@ -1,4 +1,4 @@
-use scanner::rpc::TendermintRPC;
+use module::source::TendermintRPC;
 use sqlx::{Pool, Postgres};
 use tokio::sync::Mutex;
 
@@ -1,5 +1,6 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use module::storage::StorageError;
 use sqlx::Error::RowNotFound;
 use std::num::ParseFloatError;
 
@@ -7,6 +8,7 @@ use std::num::ParseFloatError;
 pub enum ExplorerError {
     Custom(String),
     DBErr(sqlx::Error),
+    StorageErr(StorageError),
     IOErr(std::io::Error),
     TomlDeErr(toml::de::Error),
     HexErr(rustc_hex::FromHexError),
@@ -22,6 +24,12 @@ impl From<ParseFloatError> for ExplorerError {
     }
 }
 
+impl From<scanner::Error> for ExplorerError {
+    fn from(e: scanner::Error) -> Self {
+        ExplorerError::Custom(format!("{e:?}"))
+    }
+}
+
 impl From<reqwest::Error> for ExplorerError {
     fn from(e: reqwest::Error) -> Self {
         ExplorerError::ReqwestErr(e)
@@ -64,6 +72,12 @@ impl From<toml::de::Error> for ExplorerError {
     }
 }
 
+impl From<StorageError> for ExplorerError {
+    fn from(e: StorageError) -> Self {
+        ExplorerError::StorageErr(e)
+    }
+}
+
 impl From<sqlx::Error> for ExplorerError {
     fn from(e: sqlx::Error) -> Self {
         ExplorerError::DBErr(e)
@@ -82,6 +96,12 @@ impl IntoResponse for ExplorerError {
                 }
                 _ => e.to_string(),
             },
+            ExplorerError::StorageErr(e) => match e {
+                StorageError::NotFound => {
+                    return (StatusCode::NOT_FOUND, "not found").into_response();
+                }
+                _ => e.to_string(),
+            },
             ExplorerError::IOErr(e) => e.to_string(),
             ExplorerError::TomlDeErr(e) => e.to_string(),
             ExplorerError::HexErr(e) => e.to_string(),
@@ -400,6 +400,7 @@ pub async fn get_evm_tx(api: &Api, tx_hash: Path<String>) -> Result<TxResponse>
             let ty: i32 = row.try_get("ty")?;
             let timestamp: i64 = row.try_get("timestamp")?;
             let height: i64 = row.try_get("height")?;
+            let tx_index: i64 = row.try_get("tx_index")?;
             let code: i64 = row.try_get("code")?;
             let log = "".to_string();
             let origin = row.try_get("origin")?;
@@ -410,6 +411,7 @@ pub async fn get_evm_tx(api: &Api, tx_hash: Path<String>) -> Result<TxResponse>
                 evm_tx_hash,
                 block_hash,
                 height,
+                tx_index,
                 timestamp,
                 code,
                 ty,
@@ -448,6 +450,7 @@ pub async fn get_tx(api: &Api, tx_hash: Path<String>) -> Result<TxResponse> {
     let ty: i32 = row.try_get("ty")?;
     let timestamp: i64 = row.try_get("timestamp")?;
     let height: i64 = row.try_get("height")?;
+    let tx_index: i64 = row.try_get("tx_index")?;
     let code: i64 = row.try_get("code")?;
     let log = "".to_string();
     let origin = row.try_get("origin")?;
@@ -459,6 +462,7 @@ pub async fn get_tx(api: &Api, tx_hash: Path<String>) -> Result<TxResponse> {
         evm_tx_hash: "".to_string(),
         block_hash,
         height,
+        tx_index,
         timestamp,
         code,
         ty,
@@ -518,6 +522,7 @@ pub async fn get_txs_receive_from(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -529,6 +534,7 @@ pub async fn get_txs_receive_from(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -594,6 +600,7 @@ pub async fn get_txs_send_to(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -605,6 +612,7 @@ pub async fn get_txs_send_to(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -717,6 +725,7 @@ pub async fn get_txs(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -728,6 +737,7 @@ pub async fn get_txs(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -873,6 +883,7 @@ pub async fn get_txs_raw(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -884,6 +895,7 @@ pub async fn get_txs_raw(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -986,6 +998,7 @@ pub async fn get_triple_masking_txs(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -997,6 +1010,7 @@ pub async fn get_triple_masking_txs(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -1081,6 +1095,7 @@ pub async fn get_claim_txs(
         let ty: i32 = row.try_get("ty")?;
         let timestamp: i64 = row.try_get("timestamp")?;
         let height: i64 = row.try_get("height")?;
+        let tx_index: i64 = row.try_get("tx_index")?;
         let code: i64 = row.try_get("code")?;
         let log = "".to_string();
         let origin = row.try_get("origin")?;
@@ -1092,6 +1107,7 @@ pub async fn get_claim_txs(
             evm_tx_hash: "".to_string(),
             block_hash,
             height,
+            tx_index,
             timestamp,
             code,
             ty,
@@ -4,9 +4,10 @@ use crate::AppState;
 use axum::extract::{Path, Query, State};
 use axum::Json;
 use module::rpc::block::{Block, BlockHeader, BlockId, BlockRPC};
+use module::storage::BlockRecord;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::Row;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,25 +22,72 @@ pub struct BlockResponse {
     pub block_header: BlockHeader,
 }
 
+impl TryFrom<BlockRecord> for BlockResponse {
+    type Error = serde_json::Error;
+
+    fn try_from(r: BlockRecord) -> core::result::Result<Self, Self::Error> {
+        let block_rpc: BlockRPC = serde_json::from_value(r.block_data)?;
+        Ok(BlockResponse {
+            block_hash: r.block_hash,
+            block_num: r.height,
+            app_hash: r.app_hash,
+            proposer: r.proposer,
+            num_txs: r.tx_count,
+            block_size: r.size,
+            block_id: block_rpc.block_id,
+            block_header: block_rpc.block.header,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FullBlockResponse {
     pub block_id: BlockId,
     pub block: Block,
 }
 
+/// Restore the parts of a block stripped by `scanner-cli prune`.
+pub(crate) async fn load_full_block(
+    state: &AppState,
+    height: i64,
+    block_data: Value,
+) -> Result<BlockRPC> {
+    let pruned = block_data.get("pruned").and_then(Value::as_str);
+    let drop = match pruned {
+        None => return Ok(serde_json::from_value(block_data)?),
+        Some("compact") => false,
+        Some("drop") => true,
+        Some(mode) => return Err(format!("block {height} pruned with unknown mode {mode}").into()),
+    };
+    let mut block_rpc: BlockRPC = serde_json::from_value(block_data)?;
+    let txs = state.storage.tx_origins(height).await?;
+    block_rpc.block.data.txs = (!txs.is_empty()).then_some(txs);
+    if drop {
+        let signatures: HashMap<String, String> = state
+            .storage
+            .commit_signatures(height)
+            .await?
+            .into_iter()
+            .collect();
+        for s in block_rpc.block.last_commit.signatures.iter_mut().flatten() {
+            if s.signature.is_none() {
+                s.signature = s
+                    .validator_address
+                    .as_ref()
+                    .and_then(|a| signatures.get(a))
+                    .cloned();
+            }
+        }
+    }
+    Ok(block_rpc)
+}
+
 pub async fn get_full_block_by_height(
     State(state): State<Arc<AppState>>,
     Path(num): Path<i64>,
 ) -> Result<Json<FullBlockResponse>> {
-    let mut conn = state.pool.acquire().await?;
-
-    let sql_query = r#"SELECT block_data FROM block WHERE height=$1"#;
-    let row = sqlx::query(sql_query)
-        .bind(num)
-        .fetch_one(&mut *conn)
-        .await?;
-    let block_data = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
+    let block = state.storage.block_by_height(num).await?;
+    let block_rpc = load_full_block(&state, block.height, block.block_data).await?;
     let full_block = FullBlockResponse {
         block_id: block_rpc.block_id,
         block: block_rpc.block,
@@ -52,51 +100,16 @@ pub async fn get_simple_block_by_height(
     State(state): State<Arc<AppState>>,
     Path(num): Path<i64>,
 ) -> Result<Json<BlockResponse>> {
-    let mut conn = state.pool.acquire().await?;
-
-    let sql_query =
-        "SELECT block_hash,height,size,tx_count,time,app_hash,proposer,block_data FROM block WHERE height=$1";
-    let row = sqlx::query(sql_query)
-        .bind(num)
-        .fetch_one(&mut *conn)
-        .await?;
-
-    let block_hash: String = row.try_get("block_hash")?;
-    let block_num: i64 = row.try_get("height")?;
-    let app_hash: String = row.try_get("app_hash")?;
-    let proposer: String = row.try_get("proposer")?;
-    let block_size: i64 = row.try_get("size")?;
-    let num_txs: i64 = row.try_get("tx_count")?;
-    let block_data: Value = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
-
-    let block = BlockResponse {
-        block_hash,
-        num_txs,
-        block_size,
-        app_hash,
-        proposer,
-        block_id: block_rpc.block_id,
-        block_header: block_rpc.block.header,
-        block_num,
-    };
-
-    Ok(Json(block))
+    let block = state.storage.block_by_height(num).await?;
+    Ok(Json(block.try_into()?))
 }
 
 pub async fn get_full_block_by_hash(
     State(state): State<Arc<AppState>>,
     Path(hash): Path<String>,
 ) -> Result<Json<FullBlockResponse>> {
-    let mut conn = state.pool.acquire().await?;
-
-    let sql_query = "SELECT block_data FROM block WHERE block_hash=$1";
-    let row = sqlx::query(sql_query)
-        .bind(hash)
-        .fetch_one(&mut *conn)
-        .await?;
-    let block_data = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
+    let block = state.storage.block_by_hash(&hash).await?;
+    let block_rpc = load_full_block(&state, block.height, block.block_data).await?;
     let full_block = FullBlockResponse {
         block_id: block_rpc.block_id,
         block: block_rpc.block,
@@ -109,33 +122,8 @@ pub async fn get_simple_block_by_hash(
     State(state): State<Arc<AppState>>,
     Path(hash): Path<String>,
 ) -> Result<Json<BlockResponse>> {
-    let mut conn = state.pool.acquire().await?;
-
-    let sql_query = r#"SELECT block_hash,height,size,tx_count,time,app_hash,proposer,block_data FROM block WHERE block_hash=$1"#;
-    let row = sqlx::query(sql_query)
-        .bind(hash)
-        .fetch_one(&mut *conn)
-        .await?;
-    let block_hash: String = row.try_get("block_hash")?;
-    let block_num: i64 = row.try_get("height")?;
-    let app_hash: String = row.try_get("app_hash")?;
-    let proposer: String = row.try_get("proposer")?;
-    let block_size: i64 = row.try_get("size")?;
-    let num_txs: i64 = row.try_get("tx_count")?;
-    let block_data: Value = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
-    let block = BlockResponse {
-        block_hash,
-        num_txs,
-        block_size,
-        app_hash,
-        proposer,
-        block_id: block_rpc.block_id,
-        block_header: block_rpc.block.header,
-        block_num,
-    };
-
-    Ok(Json(block))
+    let block = state.storage.block_by_hash(&hash).await?;
+    Ok(Json(block.try_into()?))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -148,33 +136,8 @@ pub async fn get_block_by_num(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetBlockByHeightParams>,
 ) -> Result<Json<BlockResponse>> {
-    let mut pool = state.pool.acquire().await?;
-
-    let sql_query = r#"SELECT block_hash,height,size,tx_count,time,app_hash,proposer,block_data FROM block WHERE height=$1"#;
-    let row = sqlx::query(sql_query)
-        .bind(params.num)
-        .fetch_one(&mut *pool)
-        .await?;
-
-    let block_hash: String = row.try_get("block_hash")?;
-    let block_num: i64 = row.try_get("height")?;
-    let app_hash: String = row.try_get("app_hash")?;
-    let proposer: String = row.try_get("proposer")?;
-    let block_size: i64 = row.try_get("size")?;
-    let num_txs: i64 = row.try_get("tx_count")?;
-    let block_data: Value = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
-
-    Ok(Json(BlockResponse {
-        block_hash,
-        block_num,
-        app_hash,
-        proposer,
-        num_txs,
-        block_size,
-        block_id: block_rpc.block_id,
-        block_header: block_rpc.block.header,
-    }))
+    let block = state.storage.block_by_height(params.num).await?;
+    Ok(Json(block.try_into()?))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -186,35 +149,11 @@ pub async fn get_block_by_hash(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetBlockByHashParams>,
 ) -> Result<Json<BlockResponse>> {
-    let mut pool = state.pool.acquire().await?;
-
-    let sql_query = r#"SELECT block_hash,height,size,tx_count,time,app_hash,proposer,block_data
-        FROM block WHERE block_hash=$1"#;
-
-    let row = sqlx::query(sql_query)
-        .bind(params.hash.to_uppercase())
-        .fetch_one(&mut *pool)
+    let block = state
+        .storage
+        .block_by_hash(&params.hash.to_uppercase())
         .await?;
-
-    let block_hash: String = row.try_get("block_hash")?;
-    let block_num: i64 = row.try_get("height")?;
-    let app_hash: String = row.try_get("app_hash")?;
-    let proposer: String = row.try_get("proposer")?;
-    let block_size: i64 = row.try_get("size")?;
-    let num_txs: i64 = row.try_get("tx_count")?;
-    let block_data: Value = row.try_get("block_data")?;
-    let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
-
-    Ok(Json(BlockResponse {
-        block_hash,
-        block_num,
-        app_hash,
-        proposer,
-        num_txs,
-        block_size,
-        block_id: block_rpc.block_id,
-        block_header: block_rpc.block.header,
-    }))
+    Ok(Json(block.try_into()?))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -227,44 +166,16 @@ pub async fn get_blocks(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetBlocksParams>,
 ) -> Result<Json<QueryResult<Vec<BlockResponse>>>> {
-    let mut pool = state.pool.acquire().await?;
     let page = params.page.unwrap_or(1);
     let page_size = params.page_size.unwrap_or(10);
 
-    let sql_total = "SELECT max(height) FROM block";
-    let row = sqlx::query(sql_total).fetch_one(&mut *pool).await?;
-    let total = row.try_get("max").unwrap_or(0);
-
-    let sql_query = r#"SELECT block_hash,height,size,tx_count,time,app_hash,proposer,block_data
-        FROM block ORDER BY height DESC LIMIT $1 OFFSET $2"#;
-
-    let rows = sqlx::query(sql_query)
-        .bind(page_size)
-        .bind((page - 1) * page_size)
-        .fetch_all(&mut *pool)
+    let (total, rows) = state
+        .storage
+        .blocks(page_size as i64, ((page - 1) * page_size) as i64)
         .await?;
-
     let mut blocks: Vec<BlockResponse> = vec![];
     for row in rows {
-        let block_hash: String = row.try_get("block_hash")?;
-        let block_num: i64 = row.try_get("height")?;
-        let app_hash: String = row.try_get("app_hash")?;
-        let proposer: String = row.try_get("proposer")?;
-        let block_size: i64 = row.try_get("size")?;
-        let num_txs: i64 = row.try_get("tx_count")?;
-        let block_data: Value = row.try_get("block_data")?;
-        let block_rpc: BlockRPC = serde_json::from_value(block_data)?;
-
-        blocks.push(BlockResponse {
-            block_hash,
-            block_num,
-            app_hash,
-            proposer,
-            num_txs,
-            block_size,
-            block_id: block_rpc.block_id,
-            block_header: block_rpc.block.header,
-        })
+        blocks.push(row.try_into()?);
     }
 
     Ok(Json(QueryResult {
@@ -274,3 +185,50 @@ pub async fn get_blocks(
         data: blocks,
     }))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::v2::test_state;
+    use mock_rpc::fixture;
+    use module::rpc::tx::Transaction as ModuleTx;
+    use storage::db::{self, PruneMode};
+
+    #[tokio::test]
+    async fn test_full_block_pruned() -> Result<()> {
+        let Some((state, pool)) = test_state("test_full_block_pruned").await else {
+            return Ok(());
+        };
+        let block: BlockRPC = serde_json::from_value(fixture("block", 3))?;
+        let results = fixture("block_results", 3);
+        let txs = scanner::parse::tx_hashes(&block)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, hash)| ModuleTx {
+                hash: hash.to_uppercase(),
+                height: "3".to_string(),
+                index: i as i64,
+                tx_result: serde_json::from_value(results["txs_results"][i].clone()).unwrap(),
+                tx: block.block.data.txs.as_ref().unwrap()[i].clone(),
+            })
+            .collect();
+        let validators = serde_json::from_value(fixture("validators", 3))?;
+        let parsed =
+            scanner::parse::parse_block(block.clone(), 1024, txs, validators, &Default::default())?;
+        state.storage.save_block(parsed.block).await?;
+        let expected = serde_json::to_value(FullBlockResponse {
+            block_id: block.block_id,
+            block: block.block,
+        })?;
+
+        // rebuilt from the tables whatever the mode.
+        for mode in [PruneMode::Compact, PruneMode::Drop] {
+            db::prune(0, 4, mode, 10, &pool).await?;
+            let Json(full) = get_full_block_by_height(State(state.clone()), Path(3)).await?;
+            assert_eq!(serde_json::to_value(full)?, expected);
+        }
+        let stored = state.storage.block_by_height(3).await?;
+        assert_eq!(stored.block_data["pruned"], "drop");
+        Ok(())
+    }
+}
@@ -3,9 +3,9 @@ use crate::service::QueryResult;
 use crate::AppState;
 use axum::extract::{Query, State};
 use axum::Json;
+use module::schema::V2DelegationTx;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::Row;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize)]
@@ -26,42 +26,28 @@ pub struct DelegationResponse {
     pub value: Value,
 }
 
+impl From<V2DelegationTx> for DelegationResponse {
+    fn from(tx: V2DelegationTx) -> Self {
+        DelegationResponse {
+            tx_hash: tx.tx_hash,
+            block_hash: tx.block_hash,
+            amount: tx.amount,
+            from: tx.sender,
+            validator: tx.validator,
+            new_validator: tx.new_validator,
+            height: tx.height,
+            timestamp: tx.timestamp,
+            value: tx.content,
+        }
+    }
+}
+
 pub async fn get_delegation_by_tx_hash(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetDelegationByHashParams>,
 ) -> Result<Json<DelegationResponse>> {
-    let mut conn = state.pool.acquire().await?;
-    let sql_query = r#"SELECT tx,block,amount,sender,validator,new_validator,height,timestamp,content
-        FROM delegations WHERE tx=$1"#;
-
-    let row = sqlx::query(sql_query)
-        .bind(params.hash)
-        .fetch_one(&mut *conn)
-        .await?;
-
-    let tx_hash: String = row.try_get("tx")?;
-    let block_hash: String = row.try_get("block")?;
-    let amount: i64 = row.try_get("amount")?;
-    let from: String = row.try_get("sender")?;
-    let validator: String = row.try_get("validator")?;
-    let new_validator: String = row.try_get("new_validator")?;
-    let height: i64 = row.try_get("height")?;
-    let timestamp: i64 = row.try_get("timestamp")?;
-    let value: Value = row.try_get("content")?;
-
-    let delegation = DelegationResponse {
-        tx_hash,
-        block_hash,
-        from,
-        amount,
-        validator,
-        new_validator,
-        height,
-        timestamp,
-        value,
-    };
-
-    Ok(Json(delegation))
+    let delegation = state.storage.delegation_by_hash(&params.hash).await?;
+    Ok(Json(delegation.into()))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -76,70 +62,23 @@ pub async fn get_delegations(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetDelegationsParams>,
 ) -> Result<Json<QueryResult<Vec<DelegationResponse>>>> {
-    let mut conn = state.pool.acquire().await?;
     let page = params.page.unwrap_or(1);
     let page_size = params.page_size.unwrap_or(10);
 
-    let (sql_count, sql_query) = if let Some(addr) = params.from {
-        (
-            format!(
-                "SELECT count(height) FROM delegations WHERE sender='{}'",
-                addr.to_lowercase()
-            ),
-            format!(
-                "SELECT tx,block,sender,amount,validator,new_validator,timestamp,height,content \
-                FROM delegations WHERE sender='{}' ORDER BY timestamp DESC LIMIT {} OFFSET {}",
-                addr.to_lowercase(),
-                page_size,
-                (page - 1) * page_size
-            ),
+    let from = params.from.map(|from| from.to_lowercase());
+    let (total, delegations) = state
+        .storage
+        .delegations(
+            from.as_deref(),
+            page_size as i64,
+            ((page - 1) * page_size) as i64,
         )
-    } else {
-        (
-            "SELECT count(height) FROM delegations".to_string(),
-            format!(
-                "SELECT tx,block,sender,amount,validator,new_validator,timestamp,height,content \
-                FROM delegations ORDER BY timestamp DESC LIMIT {} OFFSET {}",
-                page_size,
-                (page - 1) * page_size
-            ),
-        )
-    };
-
-    let row_cnt = sqlx::query(&sql_count).fetch_one(&mut *conn).await?;
-    let total: i64 = row_cnt.try_get("count")?;
-
-    let mut delegations: Vec<DelegationResponse> = vec![];
-    let rows = sqlx::query(&sql_query).fetch_all(&mut *conn).await?;
-
-    for row in rows {
-        let tx_hash: String = row.try_get("tx")?;
-        let block_hash: String = row.try_get("block")?;
-        let amount: i64 = row.try_get("amount")?;
-        let from: String = row.try_get("sender")?;
-        let validator: String = row.try_get("validator")?;
-        let new_validator: String = row.try_get("new_validator")?;
-        let height: i64 = row.try_get("height")?;
-        let timestamp: i64 = row.try_get("timestamp")?;
-        let value: Value = row.try_get("content")?;
-
-        delegations.push(DelegationResponse {
-            tx_hash,
-            block_hash,
-            from,
-            amount,
-            validator,
-            new_validator,
-            height,
-            timestamp,
-            value,
-        });
-    }
+        .await?;
 
     Ok(Json(QueryResult {
         total,
         page,
         page_size,
-        data: delegations,
+        data: delegations.into_iter().map(Into::into).collect(),
     }))
 }
@@ -0,0 +1,187 @@
+use crate::service::error::Result;
+use crate::service::QueryResult;
+use crate::AppState;
+use axum::extract::{Query, State};
+use axum::Json;
+use module::schema::V2MintTx;
+use module::storage::DailyMint;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize)]
+pub struct MintResponse {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub receiver: String,
+    pub amount: i64,
+    pub kind: String,
+    pub height: i64,
+    pub timestamp: i64,
+    pub value: Value,
+}
+
+impl From<V2MintTx> for MintResponse {
+    fn from(tx: V2MintTx) -> Self {
+        MintResponse {
+            tx_hash: tx.tx_hash,
+            block_hash: tx.block_hash,
+            receiver: tx.receiver,
+            amount: tx.amount,
+            kind: tx.kind,
+            height: tx.height,
+            timestamp: tx.timestamp,
+            value: tx.content,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetMintsParams {
+    pub address: Option<String>,
+    pub kind: Option<String>,
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+}
+
+pub async fn get_mints(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetMintsParams>,
+) -> Result<Json<QueryResult<Vec<MintResponse>>>> {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+
+    let receiver = params.address.map(|addr| addr.to_lowercase());
+    let (total, mints) = state
+        .storage
+        .mints(
+            receiver.as_deref(),
+            params.kind.as_deref(),
+            page_size as i64,
+            ((page - 1) * page_size) as i64,
+        )
+        .await?;
+
+    Ok(Json(QueryResult {
+        total,
+        page,
+        page_size,
+        data: mints.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyMintResponse {
+    pub date: String,
+    pub amount: i64,
+    pub count: i64,
+}
+
+impl From<DailyMint> for DailyMintResponse {
+    fn from(mint: DailyMint) -> Self {
+        DailyMintResponse {
+            date: mint.date,
+            amount: mint.amount,
+            count: mint.count,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyMintsResponse {
+    pub total_minted: i64,
+    pub daily: Vec<DailyMintResponse>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetDailyMintsParams {
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+pub async fn get_daily_mints(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetDailyMintsParams>,
+) -> Result<Json<DailyMintsResponse>> {
+    // minted over the same range as the daily rows.
+    let (total_minted, daily) = state
+        .storage
+        .daily_mints(params.start_time, params.end_time)
+        .await?;
+
+    Ok(Json(DailyMintsResponse {
+        total_minted,
+        daily: daily.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::v2::test_state;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_mints() -> Result<()> {
+        let Some((state, pool)) = test_state("test_mints").await else {
+            return Ok(());
+        };
+        let day = 86400;
+        for (tx, receiver, kind, amount, timestamp) in [
+            ("t1", "fra1a", "Coinbase", 10, day),
+            ("t2", "fra1a", "FraDistribution", 20, day + 1),
+            ("t3", "fra1b", "Coinbase", 40, 3 * day),
+        ] {
+            storage::db::save_mint_tx(
+                tx,
+                "b",
+                0,
+                receiver,
+                amount,
+                kind,
+                1,
+                timestamp,
+                &json!({}),
+                &pool,
+            )
+            .await?;
+        }
+
+        let params = |address: Option<&str>, kind: Option<&str>| GetMintsParams {
+            address: address.map(String::from),
+            kind: kind.map(String::from),
+            page: None,
+            page_size: None,
+        };
+        let mints = get_mints(State(state.clone()), Query(params(Some("FRA1A"), None))).await?;
+        assert_eq!(mints.total, 2);
+        let mints = get_mints(State(state.clone()), Query(params(None, Some("Coinbase")))).await?;
+        assert_eq!(mints.total, 2);
+        // parameters are bound, not spliced into the query.
+        let injected = params(Some("x' OR '1'='1"), Some("x' OR '1'='1"));
+        let mints = get_mints(State(state.clone()), Query(injected)).await?;
+        assert_eq!(mints.total, 0);
+
+        let daily = get_daily_mints(
+            State(state.clone()),
+            Query(GetDailyMintsParams {
+                start_time: Some(day),
+                end_time: Some(2 * day),
+            }),
+        )
+        .await?;
+        assert_eq!(daily.total_minted, 30);
+        assert_eq!(daily.daily.len(), 1);
+        assert_eq!((daily.daily[0].amount, daily.daily[0].count), (30, 2));
+        let daily = get_daily_mints(
+            State(state),
+            Query(GetDailyMintsParams {
+                start_time: None,
+                end_time: None,
+            }),
+        )
+        .await?;
+        assert_eq!(daily.total_minted, 70);
+        Ok(())
+    }
+}
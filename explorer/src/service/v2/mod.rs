@@ -4,11 +4,26 @@ pub mod asset;
 pub mod block;
 pub mod claim;
 pub mod delegation;
+pub mod mint;
 pub mod other;
 pub mod prism_evm_to_native;
 pub mod prism_native_to_evm;
+pub mod slashing;
 pub mod transaction;
 pub mod undelegation;
+pub mod uptime;
+pub mod validator;
+
+/// State on a fresh schema `name` of the database at `TEST_DATABASE_URL`, along with its pool,
+/// see `storage::db::test_pool`.
+#[cfg(test)]
+pub(crate) async fn test_state(
+    name: &str,
+) -> Option<(std::sync::Arc<crate::AppState>, sqlx::PgPool)> {
+    let pool = storage::db::test_pool(name).await?;
+    let storage = std::sync::Arc::new(storage::PgStorage::new(pool.clone()));
+    Some((std::sync::Arc::new(crate::AppState { storage }), pool))
+}
 
 #[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
@@ -29,3 +44,99 @@ pub enum TransactionType {
     DefineAsset,
     IssueAsset,
 }
+
+#[cfg(test)]
+mod test {
+    use super::block::get_full_block_by_height;
+    use super::delegation::{get_delegation_by_tx_hash, GetDelegationByHashParams};
+    use super::prism_evm_to_native::{get_e2n_txs, GetE2NTxsParams};
+    use super::test_state;
+    use super::transaction::{get_tx_by_hash, get_tx_proof, GetTxByHashParams};
+    use crate::service::error::Result;
+    use axum::extract::{Path, Query, State};
+    use axum::Json;
+    use mock_rpc::MockNode;
+    use module::rpc::block::BlockRPC;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_scan_and_serve() -> Result<()> {
+        let Some((state, _)) = test_state("test_scan_and_serve").await else {
+            return Ok(());
+        };
+        let dir = mock_rpc::fixtures_dir();
+        let node = MockNode::load(&dir).unwrap();
+        let url: reqwest::Url = node.spawn().await.unwrap().parse().unwrap();
+        let timeout = Duration::from_secs(5);
+        let block: BlockRPC = serde_json::from_slice(&std::fs::read(dir.join("block/3.json"))?)?;
+        // the evm tx and the native one.
+        let hashes = scanner::parse::tx_hashes(&block)?;
+
+        let report =
+            scanner::scanner::RangeScanner::new(timeout, url.clone(), 0, 2, state.storage.clone())
+                .range_scan(1, 4)
+                .await
+                .unwrap();
+        assert_eq!((report.succeed, report.watermark), (3, 3));
+        let prism = prismer::prismer::RangeScanner::new(timeout, url, 0, 2, state.storage.clone());
+        assert_eq!(prism.range_scan(1, 4).await.unwrap(), 3);
+
+        let Json(full) = get_full_block_by_height(State(state.clone()), Path(3)).await?;
+        assert_eq!(full.block.data.txs, block.block.data.txs);
+        let Json(tx) = get_tx_by_hash(
+            State(state.clone()),
+            Query(GetTxByHashParams {
+                hash: hashes[1].clone(),
+            }),
+        )
+        .await?;
+        assert_eq!((tx.height, tx.tx_index), (3, 1));
+        assert!(tx.value["body"]["operations"][0]["Delegation"].is_object());
+
+        // the proof leads to the data hash the node served.
+        let Json(proof) = get_tx_proof(
+            State(state.clone()),
+            Query(GetTxByHashParams {
+                hash: hashes[1].clone(),
+            }),
+        )
+        .await?;
+        assert_eq!(proof.root_hash, block.block.header.data_hash);
+        assert_eq!(proof.block_id.hash, block.block_id.hash);
+        // the leaves are the hashes of the txs.
+        let leaf = |hash: &str| {
+            hex::encode_upper(module::utils::merkle::leaf_hash(
+                &hex::decode(hash).unwrap(),
+            ))
+        };
+        assert_eq!(proof.proof.leaf_hash, leaf(&hashes[1]));
+        assert_eq!(proof.proof.aunts, vec![leaf(&hashes[0])]);
+
+        let Json(delegation) = get_delegation_by_tx_hash(
+            State(state.clone()),
+            Query(GetDelegationByHashParams {
+                hash: hashes[1].clone(),
+            }),
+        )
+        .await?;
+        assert_eq!(
+            delegation.from,
+            "fra18fnyetvs2kc035xz78kyfcygmej8pk5h2kwczy03w6uewdphzfxsk74dym"
+        );
+        assert_eq!(delegation.amount, 1000000);
+
+        let Json(deposits) = get_e2n_txs(
+            State(state),
+            Query(GetE2NTxsParams {
+                from: None,
+                to: None,
+                page: None,
+                page_size: None,
+            }),
+        )
+        .await?;
+        assert_eq!(deposits.total, 1);
+        assert_eq!(deposits.data[0].tx_hash, hashes[0]);
+        Ok(())
+    }
+}
@@ -3,10 +3,9 @@ use crate::service::QueryResult;
 use crate::AppState;
 use axum::extract::{Query, State};
 use axum::Json;
+use module::schema::PrismTxResult;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::Row;
-use std::ops::Add;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize)]
@@ -23,6 +22,23 @@ pub struct E2NTxResponse {
     pub value: Value,
 }
 
+impl From<PrismTxResult> for E2NTxResponse {
+    fn from(tx: PrismTxResult) -> Self {
+        E2NTxResponse {
+            tx_hash: tx.tx_hash,
+            block_hash: tx.block_hash,
+            from: tx.sender,
+            to: tx.receiver,
+            asset: tx.asset,
+            amount: tx.amount,
+            decimal: tx.decimal as i32,
+            height: tx.height,
+            timestamp: tx.timestamp,
+            value: tx.value,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetE2NByTxHashParams {
     pub hash: String,
@@ -32,37 +48,8 @@ pub async fn get_e2n_by_tx_hash(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetE2NByTxHashParams>,
 ) -> Result<Json<E2NTxResponse>> {
-    let mut conn = state.pool.acquire().await?;
-    let sql_query = r#"SELECT tx_hash,block_hash,sender,receiver,asset,amount,decimal,height,timestamp,value FROM e2n WHERE tx_hash=$1"#;
-    let row = sqlx::query(sql_query)
-        .bind(params.hash)
-        .fetch_one(&mut *conn)
-        .await?;
-
-    let tx_hash: String = row.try_get("tx_hash")?;
-    let block_hash: String = row.try_get("block_hash")?;
-    let from: String = row.try_get("sender")?;
-    let to: String = row.try_get("receiver")?;
-    let asset: String = row.try_get("asset")?;
-    let decimal: i32 = row.try_get("decimal")?;
-    let amount: String = row.try_get("amount")?;
-    let height: i64 = row.try_get("height")?;
-    let timestamp: i64 = row.try_get("timestamp")?;
-    let value: Value = row.try_get("value")?;
-    let tx = E2NTxResponse {
-        tx_hash,
-        block_hash,
-        from,
-        to,
-        asset,
-        amount,
-        decimal,
-        height,
-        timestamp,
-        value,
-    };
-
-    Ok(Json(tx))
+    let tx = state.storage.prism_tx_by_hash(&params.hash).await?;
+    Ok(Json(tx.into()))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -77,71 +64,23 @@ pub async fn get_e2n_txs(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetE2NTxsParams>,
 ) -> Result<Json<QueryResult<Vec<E2NTxResponse>>>> {
-    let mut conn = state.pool.acquire().await?;
     let page = params.page.unwrap_or(1);
     let page_size = params.page_size.unwrap_or(10);
 
-    let mut sql_total = "SELECT count(height) FROM e2n ".to_string();
-    let mut sql_query = "SELECT tx_hash,block_hash,sender,receiver,asset,amount,decimal,height,timestamp,value FROM e2n ".to_string();
-
-    let mut query_params: Vec<String> = vec![];
-    if let Some(from) = params.from {
-        query_params.push(format!("sender='{}'", from));
-    }
-    if let Some(to) = params.to {
-        query_params.push(format!("receiver='{}'", to));
-    }
-    if !query_params.is_empty() {
-        sql_total = sql_total
-            .add("WHERE ")
-            .add(query_params.join("AND ").as_str());
-        sql_query = sql_query
-            .add("WHERE ")
-            .add(query_params.join("AND ").as_str());
-    }
-    sql_query = sql_query.add(
-        format!(
-            "ORDER BY timestamp DESC LIMIT {} OFFSET {} ",
-            page_size,
-            (page - 1) * page_size
+    let (total, txs) = state
+        .storage
+        .prism_txs(
+            params.from.as_deref(),
+            params.to.as_deref(),
+            page_size as i64,
+            ((page - 1) * page_size) as i64,
         )
-        .as_str(),
-    );
-
-    let row = sqlx::query(&sql_total).fetch_one(&mut *conn).await?;
-    let total: i64 = row.try_get("count")?;
-
-    let mut txs: Vec<E2NTxResponse> = vec![];
-    let rows = sqlx::query(&sql_query).fetch_all(&mut *conn).await?;
-    for row in rows {
-        let tx_hash: String = row.try_get("tx_hash")?;
-        let block_hash: String = row.try_get("block_hash")?;
-        let from: String = row.try_get("sender")?;
-        let to: String = row.try_get("receiver")?;
-        let asset: String = row.try_get("asset")?;
-        let decimal: i32 = row.try_get("decimal")?;
-        let amount: String = row.try_get("amount")?;
-        let height: i64 = row.try_get("height")?;
-        let timestamp: i64 = row.try_get("timestamp")?;
-        let value: Value = row.try_get("value")?;
-        txs.push(E2NTxResponse {
-            tx_hash,
-            block_hash,
-            from,
-            to,
-            asset,
-            amount,
-            decimal,
-            height,
-            timestamp,
-            value,
-        })
-    }
+        .await?;
 
     Ok(Json(QueryResult {
         total,
         page,
         page_size,
-        data: txs,
+        data: txs.into_iter().map(Into::into).collect(),
     }))
 }
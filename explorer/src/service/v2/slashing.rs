@@ -0,0 +1,122 @@
+use crate::service::error::Result;
+use crate::service::QueryResult;
+use crate::AppState;
+use axum::extract::{Query, State};
+use axum::Json;
+use module::storage::SlashingRecord;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize)]
+pub struct SlashingResponse {
+    pub block_hash: String,
+    pub tx_hash: Option<String>,
+    pub validator: String,
+    pub kind: String,
+    pub source: String,
+    pub evidence_height: Option<i64>,
+    pub power: Option<i64>,
+    pub penalty_rate: Option<Value>,
+    pub penalty_amount: Option<i64>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub value: Value,
+}
+
+impl From<SlashingRecord> for SlashingResponse {
+    fn from(event: SlashingRecord) -> Self {
+        SlashingResponse {
+            block_hash: event.block_hash,
+            tx_hash: event.tx_hash,
+            validator: event.validator,
+            kind: event.kind,
+            source: event.source,
+            evidence_height: event.evidence_height,
+            power: event.power,
+            penalty_rate: event.penalty_rate,
+            penalty_amount: event.penalty_amount,
+            height: event.height,
+            timestamp: event.timestamp,
+            value: event.content,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetSlashingParams {
+    pub validator: Option<String>,
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+}
+
+pub async fn get_slashing_events(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetSlashingParams>,
+) -> Result<Json<QueryResult<Vec<SlashingResponse>>>> {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+
+    let validator = params.validator.map(|v| v.to_uppercase());
+    let (total, events) = state
+        .storage
+        .slashing_events(
+            validator.as_deref(),
+            page_size as i64,
+            ((page - 1) * page_size) as i64,
+        )
+        .await?;
+
+    Ok(Json(QueryResult {
+        total,
+        page,
+        page_size,
+        data: events.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::v2::test_state;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_slashing_events() -> Result<()> {
+        let Some((state, pool)) = test_state("test_slashing_events").await else {
+            return Ok(());
+        };
+        for (tx_index, validator) in [(-1, "aaaa"), (0, "BBBB"), (1, "BBBB")] {
+            storage::db::save_slashing_event(
+                "b",
+                None,
+                tx_index,
+                0,
+                &validator.to_uppercase(),
+                "DuplicateVote",
+                "evidence",
+                Some(9),
+                Some(1000),
+                Some(&json!([5, 100])),
+                10,
+                100,
+                &json!({}),
+                &pool,
+            )
+            .await?;
+        }
+
+        let params = |validator: Option<&str>| GetSlashingParams {
+            validator: validator.map(String::from),
+            page: None,
+            page_size: None,
+        };
+        let events = get_slashing_events(State(state.clone()), Query(params(None))).await?;
+        assert_eq!(events.total, 3);
+        assert_eq!(events.data[0].penalty_amount, Some(50));
+        let events = get_slashing_events(State(state), Query(params(Some("bbbb")))).await?;
+        assert_eq!(events.total, 2);
+        assert!(events.data.iter().all(|e| e.validator == "BBBB"));
+        Ok(())
+    }
+}
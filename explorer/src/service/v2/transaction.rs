@@ -1,21 +1,51 @@
 use crate::service::error::Result;
+use crate::service::v2::block::load_full_block;
 use crate::service::QueryResult;
 use crate::AppState;
 use axum::extract::{Query, State};
 use axum::Json;
-use module::schema::TransactionResponse;
+use base64::{engine, Engine};
+use module::rpc::block::{BlockHeader, BlockId};
+use module::schema::{Transaction, TransactionResponse};
+use module::storage::{StorageError, TxFilter};
+use module::utils::merkle;
 use scanner::types::FindoraEVMTxWrap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::Row;
-use std::ops::Add;
 use std::sync::Arc;
 
+fn tx_response(tx: Transaction) -> TransactionResponse {
+    let evm_tx_hash = if tx.ty == 1 {
+        let evm_tx: FindoraEVMTxWrap = serde_json::from_value(tx.value.clone()).unwrap();
+        let hash = evm_tx.hash();
+        format!("{hash:?}")
+    } else {
+        "".to_string()
+    };
+
+    TransactionResponse {
+        tx_hash: tx.tx_hash,
+        evm_tx_hash,
+        block_hash: tx.block_hash,
+        height: tx.height,
+        tx_index: tx.tx_index,
+        timestamp: tx.timestamp,
+        ty: if tx.ty == 0 { tx.ty_sub } else { tx.ty },
+        code: tx.code,
+        log: tx.log,
+        origin: tx.origin,
+        result: tx.result,
+        value: tx.value,
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetTxsParams {
     pub from: Option<String>,
     pub to: Option<String>,
+    pub address: Option<String>,
     pub ty: Option<i32>,
+    pub height: Option<i64>,
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,
     pub page: Option<i32>,
@@ -26,92 +56,28 @@ pub async fn get_txs(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetTxsParams>,
 ) -> Result<Json<QueryResult<Vec<TransactionResponse>>>> {
-    let mut conn = state.pool.acquire().await?;
     let page = params.page.unwrap_or(1);
     let page_size = params.page_size.unwrap_or(10);
+    let filter = TxFilter {
+        from: params.from,
+        to: params.to,
+        address: params.address,
+        ty: params.ty,
+        height: params.height,
+        start_time: params.start_time,
+        end_time: params.end_time,
+    };
 
-    let mut sql_query = String::from("SELECT tx_hash,block_hash,ty,ty_sub,timestamp,height,code,log,origin,result,value FROM transaction ");
-    let mut sql_total = String::from("SELECT count(height) FROM transaction ");
-    let mut query_params: Vec<String> = vec![];
-    if let Some(from) = params.from {
-        query_params.push(format!("sender='{}' ", from))
-    }
-    if let Some(to) = params.to {
-        query_params.push(format!("(receiver @? '$.addrs[*] ? (@==\"{}\")') ", to));
-    }
-    if let Some(ty) = params.ty {
-        query_params.push(format!("ty={} ", ty));
-    }
-    if let Some(start_time) = params.start_time {
-        query_params.push(format!("timestamp>={} ", start_time));
-    }
-    if let Some(end_time) = params.end_time {
-        query_params.push(format!("timestamp<={} ", end_time));
-    }
-    if !query_params.is_empty() {
-        sql_query = sql_query
-            .add("WHERE ")
-            .add(query_params.join("AND ").as_str());
-        sql_total = sql_total
-            .add("WHERE ")
-            .add(query_params.join("AND ").as_str());
-    }
-
-    sql_query = sql_query.add(
-        format!(
-            "ORDER BY timestamp DESC LIMIT {} OFFSET {} ",
-            page_size,
-            (page - 1) * page_size
-        )
-        .as_str(),
-    );
-
-    let row = sqlx::query(&sql_total).fetch_one(&mut *conn).await?;
-    let total: i64 = row.try_get("count")?;
-
-    let rows = sqlx::query(&sql_query).fetch_all(&mut *conn).await?;
-    let mut txs: Vec<TransactionResponse> = vec![];
-    for row in rows {
-        let tx_hash: String = row.try_get("tx_hash")?;
-        let block_hash: String = row.try_get("block_hash")?;
-        let ty: i32 = row.try_get("ty")?;
-        let ty_sub: i32 = row.try_get("ty_sub")?;
-        let timestamp: i64 = row.try_get("timestamp")?;
-        let height: i64 = row.try_get("height")?;
-        let code: i64 = row.try_get("code")?;
-        let log: String = row.try_get("log")?;
-        let origin: String = row.try_get("origin")?;
-        let result: Value = row.try_get("result")?;
-        let value: Value = row.try_get("value")?;
-
-        let evm_tx_hash = if ty == 1 {
-            let evm_tx: FindoraEVMTxWrap = serde_json::from_value(value.clone()).unwrap();
-            let hash = evm_tx.hash();
-            format!("{hash:?}")
-        } else {
-            "".to_string()
-        };
-
-        txs.push(TransactionResponse {
-            tx_hash,
-            evm_tx_hash,
-            block_hash,
-            height,
-            timestamp,
-            ty: if ty == 0 { ty_sub } else { ty },
-            code,
-            log,
-            origin,
-            result,
-            value,
-        });
-    }
+    let (total, txs) = state
+        .storage
+        .txs(&filter, page_size as i64, ((page - 1) * page_size) as i64)
+        .await?;
 
     Ok(Json(QueryResult {
         total,
         page,
         page_size,
-        data: txs,
+        data: txs.into_iter().map(tx_response).collect(),
     }))
 }
 
@@ -124,49 +90,229 @@ pub async fn get_tx_by_hash(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GetTxByHashParams>,
 ) -> Result<Json<TransactionResponse>> {
-    let mut conn = state.pool.acquire().await?;
+    let tx = state.storage.tx_by_hash(&params.hash).await?;
+    Ok(Json(tx_response(tx)))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OperationResponse {
+    pub tx_hash: String,
+    pub op_index: i32,
+    pub kind: String,
+    pub signer: String,
+    pub counterparty: String,
+    pub asset: Option<String>,
+    pub amount: Option<String>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub value: Value,
+}
+
+pub async fn get_tx_operations(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetTxByHashParams>,
+) -> Result<Json<Vec<OperationResponse>>> {
+    let ops: Vec<OperationResponse> = state
+        .storage
+        .tx_operations(&params.hash.to_lowercase())
+        .await?
+        .into_iter()
+        .map(|op| OperationResponse {
+            tx_hash: op.tx_hash,
+            op_index: op.op_index,
+            kind: op.kind,
+            signer: op.signer,
+            counterparty: op.counterparty,
+            asset: op.asset,
+            amount: op.amount,
+            height: op.height,
+            timestamp: op.timestamp,
+            value: op.content,
+        })
+        .collect();
+
+    if ops.is_empty() {
+        return Err(StorageError::NotFound.into());
+    }
+
+    Ok(Json(ops))
+}
 
-    let sql_query = r#"SELECT tx_hash,block_hash,height,timestamp,ty,ty_sub,code,log,origin,result,value
-        FROM transaction WHERE tx_hash=$1"#;
+#[derive(Serialize, Deserialize)]
+pub struct MerkleProofResponse {
+    pub total: usize,
+    pub index: usize,
+    pub leaf_hash: String,
+    pub aunts: Vec<String>,
+}
 
-    let row = sqlx::query(sql_query)
-        .bind(params.hash)
-        .fetch_one(&mut *conn)
+#[derive(Serialize, Deserialize)]
+pub struct TxProofResponse {
+    pub tx_hash: String,
+    pub height: i64,
+    pub index: i64,
+    /// The raw tx, base64 as in the block.
+    pub data: String,
+    /// The `data_hash` of the header the proof leads to.
+    pub root_hash: String,
+    pub proof: MerkleProofResponse,
+    pub block_id: BlockId,
+    pub header: BlockHeader,
+}
+
+/// Merkle proof of a tx against the `data_hash` of its block, from the txs of the stored block.
+pub async fn get_tx_proof(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetTxByHashParams>,
+) -> Result<Json<TxProofResponse>> {
+    let tx = state
+        .storage
+        .tx_by_hash(&params.hash.to_lowercase())
         .await?;
+    let block = state.storage.block_by_height(tx.height).await?;
+    let block_rpc = load_full_block(&state, block.height, block.block_data).await?;
 
-    let tx_hash: String = row.try_get("tx_hash")?;
-    let block_hash: String = row.try_get("block_hash")?;
-    let ty: i32 = row.try_get("ty")?;
-    let ty_sub: i32 = row.try_get("ty_sub")?;
-    let timestamp: i64 = row.try_get("timestamp")?;
-    let height: i64 = row.try_get("height")?;
-    let code: i64 = row.try_get("code")?;
-    let log: String = row.try_get("log")?;
-    let origin: String = row.try_get("origin")?;
-    let result: Value = row.try_get("result")?;
-    let value: Value = row.try_get("value")?;
-
-    let evm_tx_hash = if ty == 1 {
-        let evm_tx: FindoraEVMTxWrap = serde_json::from_value(value.clone()).unwrap();
-        let hash = evm_tx.hash();
-        format!("{hash:?}")
-    } else {
-        "".to_string()
-    };
+    let txs = block_rpc
+        .block
+        .data
+        .txs
+        .unwrap_or_default()
+        .iter()
+        .map(|tx| engine::general_purpose::STANDARD.decode(tx))
+        .collect::<core::result::Result<Vec<_>, _>>()
+        .map_err(|e| format!("block {}: {e}", block.height))?;
+    // tendermint hashes each tx into its leaf.
+    let leaves = merkle::tx_leaves(&txs);
+    let proof = usize::try_from(tx.tx_index)
+        .ok()
+        .and_then(|index| merkle::proof(&leaves, index))
+        .ok_or_else(|| format!("block {} holds no tx {}", block.height, tx.tx_index))?;
 
-    let tx = TransactionResponse {
-        tx_hash,
-        evm_tx_hash,
-        block_hash,
-        height,
-        timestamp,
-        ty: if ty == 0 { ty_sub } else { ty },
-        code,
-        log,
-        origin,
-        result,
-        value,
-    };
+    let root = merkle::root(&leaves);
+    let root_hash = hex::encode_upper(root);
+    if !root_hash.eq_ignore_ascii_case(&block_rpc.block.header.data_hash) {
+        return Err(format!(
+            "txs of block {} hash to {root_hash}, not its data_hash {}",
+            block.height, block_rpc.block.header.data_hash
+        )
+        .into());
+    }
+    let data = engine::general_purpose::STANDARD.encode(&txs[proof.index]);
+
+    Ok(Json(TxProofResponse {
+        tx_hash: tx.tx_hash,
+        height: tx.height,
+        index: tx.tx_index,
+        data,
+        root_hash,
+        proof: MerkleProofResponse {
+            total: proof.total,
+            index: proof.index,
+            leaf_hash: hex::encode_upper(proof.leaf_hash),
+            aunts: proof.aunts.iter().map(hex::encode_upper).collect(),
+        },
+        block_id: block_rpc.block_id,
+        header: block_rpc.block.header,
+    }))
+}
 
-    Ok(Json(tx))
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::v2::test_state;
+    use mock_rpc::fixture;
+    use module::rpc::block::BlockRPC;
+    use module::rpc::tx::Transaction as ModuleTx;
+    use storage::db::{self, PruneMode};
+
+    #[tokio::test]
+    async fn test_tx_proof() -> Result<()> {
+        let Some((state, pool)) = test_state("test_tx_proof").await else {
+            return Ok(());
+        };
+        // block 3 with a tx of unknown catalog between the evm tx and the native one, the tx
+        // sent again at height 2.
+        let mut block: BlockRPC = serde_json::from_value(fixture("block", 3))?;
+        let mut results = fixture("block_results", 3)["txs_results"].clone();
+        let unknown = fixture("block", 2)["block"]["data"]["txs"][0].clone();
+        let block_txs = block.block.data.txs.as_mut().unwrap();
+        block_txs.insert(1, serde_json::from_value(unknown)?);
+        let unknown_result = fixture("block_results", 2)["txs_results"][0].clone();
+        results.as_array_mut().unwrap().insert(1, unknown_result);
+        let raw: Vec<Vec<u8>> = block_txs
+            .iter()
+            .map(|tx| engine::general_purpose::STANDARD.decode(tx).unwrap())
+            .collect();
+        let data_hash = hex::encode_upper(merkle::txs_root(&raw));
+        block.block.header.data_hash = data_hash.clone();
+
+        let hashes = scanner::parse::tx_hashes(&block)?;
+        let blocks = [
+            (block, results),
+            (
+                serde_json::from_value(fixture("block", 2))?,
+                fixture("block_results", 2)["txs_results"].clone(),
+            ),
+        ];
+        for (block, results) in blocks {
+            let height = block.block.header.height.clone();
+            let txs = scanner::parse::tx_hashes(&block)?
+                .iter()
+                .enumerate()
+                .map(|(i, hash)| ModuleTx {
+                    hash: hash.to_uppercase(),
+                    height: height.clone(),
+                    index: i as i64,
+                    tx_result: serde_json::from_value(results[i].clone()).unwrap(),
+                    tx: block.block.data.txs.as_ref().unwrap()[i].clone(),
+                })
+                .collect();
+            let validators =
+                serde_json::from_value(fixture("validators", height.parse().unwrap()))?;
+            let parsed =
+                scanner::parse::parse_block(block, 1024, txs, validators, &Default::default())?;
+            state.storage.save_block(parsed.block).await?;
+        }
+
+        let root: merkle::Hash = hex::decode(&data_hash).unwrap().try_into().unwrap();
+        for mode in [None, Some(PruneMode::Compact), Some(PruneMode::Drop)] {
+            if let Some(mode) = mode {
+                db::prune(0, 4, mode, 10, &pool).await?;
+            }
+            for index in [0, 2] {
+                let hash = &hashes[index];
+                let Json(res) = get_tx_proof(
+                    State(state.clone()),
+                    Query(GetTxByHashParams { hash: hash.clone() }),
+                )
+                .await?;
+                assert_eq!(
+                    (res.index, res.root_hash.as_str()),
+                    (index as i64, &*data_hash)
+                );
+                assert_eq!(
+                    engine::general_purpose::STANDARD.decode(&res.data).unwrap(),
+                    raw[index]
+                );
+                let proof = merkle::Proof {
+                    total: res.proof.total,
+                    index: res.proof.index,
+                    leaf_hash: hex::decode(&res.proof.leaf_hash)
+                        .unwrap()
+                        .try_into()
+                        .unwrap(),
+                    aunts: res
+                        .proof
+                        .aunts
+                        .iter()
+                        .map(|a| hex::decode(a).unwrap().try_into().unwrap())
+                        .collect(),
+                };
+                assert_eq!(proof.total, 3);
+                // the leaf of a tx is its hash.
+                assert!(proof.verify(&root, &hex::decode(hash).unwrap()));
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,269 @@
+use crate::service::error::Result;
+use crate::service::QueryResult;
+use crate::AppState;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use module::storage::{StorageError, UptimeBucket};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use storage::db::UPTIME_BUCKET;
+
+const DEFAULT_WINDOW: i64 = 1000;
+const MAX_WINDOW: i64 = 100000;
+
+#[derive(Serialize, Deserialize)]
+pub struct ValidatorUptime {
+    pub address: String,
+    pub window: i64,
+    pub signed: i64,
+    pub missed: i64,
+    pub proposed: i64,
+    pub uptime: f64,
+    pub current_missed_streak: i64,
+    pub longest_missed_streak: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DailyUptime {
+    pub day: String,
+    pub signed: i64,
+    pub missed: i64,
+    pub proposed: i64,
+    pub uptime: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ValidatorUptimeResponse {
+    pub window: ValidatorUptime,
+    pub daily: Vec<DailyUptime>,
+}
+
+fn uptime(signed: i64, missed: i64) -> f64 {
+    if signed + missed == 0 {
+        return 0.0;
+    }
+    signed as f64 / (signed + missed) as f64
+}
+
+/// Returns the first bucket of the last-`window`-blocks range and the window size. The
+/// scanner counts blocks in buckets of `UPTIME_BUCKET` heights, the range is rounded up to
+/// whole buckets.
+async fn window_start(state: &AppState, window: Option<i64>) -> Result<(i64, i64)> {
+    let window = window.unwrap_or(DEFAULT_WINDOW).clamp(1, MAX_WINDOW);
+    let (tip, _) = state.storage.blocks(0, 0).await?;
+    Ok(((tip - window + 1).max(0) / UPTIME_BUCKET, window))
+}
+
+/// Sum the buckets of a validator, in height order. A missed streak goes on into the next
+/// bucket if that one starts right after the last height of the streak.
+fn fold_buckets(address: String, window: i64, buckets: &[UptimeBucket]) -> ValidatorUptime {
+    let mut res = ValidatorUptime {
+        address,
+        window,
+        signed: 0,
+        missed: 0,
+        proposed: 0,
+        uptime: 0.0,
+        current_missed_streak: 0,
+        longest_missed_streak: 0,
+    };
+    let mut last_height = -1;
+    for b in buckets {
+        res.signed += b.signed;
+        res.missed += b.missed;
+        res.proposed += b.proposed;
+        let carried = if b.first_height == last_height + 1 {
+            res.current_missed_streak
+        } else {
+            0
+        };
+        if b.leading_missed == b.missed + b.signed {
+            res.current_missed_streak = carried + b.missed;
+        } else {
+            res.longest_missed_streak = res.longest_missed_streak.max(carried + b.leading_missed);
+            res.current_missed_streak = b.trailing_missed;
+        }
+        res.longest_missed_streak = res
+            .longest_missed_streak
+            .max(b.longest_missed)
+            .max(res.current_missed_streak);
+        last_height = b.last_height;
+    }
+    res.uptime = uptime(res.signed, res.missed);
+    res
+}
+
+/// Sum the buckets from `start` on of the validators at `addresses`, in address order.
+async fn query_window_uptime(
+    state: &AppState,
+    start: i64,
+    window: i64,
+    addresses: &[String],
+) -> Result<Vec<ValidatorUptime>> {
+    let mut buckets: BTreeMap<String, Vec<UptimeBucket>> = BTreeMap::new();
+    for bucket in state.storage.uptime_buckets(addresses, start).await? {
+        buckets
+            .entry(bucket.address.clone())
+            .or_default()
+            .push(bucket);
+    }
+
+    Ok(buckets
+        .into_iter()
+        .map(|(address, b)| fold_buckets(address, window, &b))
+        .collect())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetValidatorUptimeParams {
+    pub window: Option<i64>,
+    pub days: Option<i64>,
+}
+
+pub async fn get_validator_uptime(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(params): Query<GetValidatorUptimeParams>,
+) -> Result<Json<ValidatorUptimeResponse>> {
+    let address = address.to_uppercase();
+    let days = params.days.unwrap_or(30);
+
+    let (start, window) = window_start(&state, params.window).await?;
+    let mut stats =
+        query_window_uptime(&state, start, window, std::slice::from_ref(&address)).await?;
+    let days = state.storage.daily_uptime(&address, days).await?;
+
+    if stats.is_empty() && days.is_empty() {
+        return Err(StorageError::NotFound.into());
+    }
+
+    let daily = days
+        .into_iter()
+        .map(|d| DailyUptime {
+            uptime: uptime(d.signed, d.missed),
+            day: d.day,
+            signed: d.signed,
+            missed: d.missed,
+            proposed: d.proposed,
+        })
+        .collect();
+
+    let window = stats.pop().unwrap_or(ValidatorUptime {
+        address,
+        window,
+        signed: 0,
+        missed: 0,
+        proposed: 0,
+        uptime: 0.0,
+        current_missed_streak: 0,
+        longest_missed_streak: 0,
+    });
+
+    Ok(Json(ValidatorUptimeResponse { window, daily }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetValidatorsUptimeParams {
+    pub window: Option<i64>,
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+}
+
+pub async fn get_validators_uptime(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GetValidatorsUptimeParams>,
+) -> Result<Json<QueryResult<Vec<ValidatorUptime>>>> {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+
+    let (start, window) = window_start(&state, params.window).await?;
+    let (total, addresses) = state
+        .storage
+        .uptime_validators(start, page_size as i64, ((page - 1) * page_size) as i64)
+        .await?;
+    let data = query_window_uptime(&state, start, window, &addresses).await?;
+
+    Ok(Json(QueryResult {
+        total,
+        page,
+        page_size,
+        data,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::v2::test_state;
+
+    #[tokio::test]
+    async fn test_uptime() -> Result<()> {
+        let Some((state, pool)) = test_state("test_uptime").await else {
+            return Ok(());
+        };
+        let heights: Vec<i64> = (95..=104).collect();
+        storage::db::ensure_partitions(&heights, &pool).await?;
+        for &h in &heights {
+            sqlx::query("INSERT INTO block VALUES($1,$2,0,0,now(),'',$3,'{}')")
+                .bind(h.to_string())
+                .bind(h)
+                .bind(if h == 104 { "A" } else { "B" })
+                .execute(&pool)
+                .await?;
+            // A misses 97 to 103, across buckets, B misses the last two.
+            let signed_a = !(97..=103).contains(&h);
+            let signed_b = h < 103;
+            for (address, signed) in [("A", signed_a), ("B", signed_b)] {
+                sqlx::query("INSERT INTO block_generation VALUES($1,$2,1,0,$3,now())")
+                    .bind(h)
+                    .bind(address)
+                    .bind(signed.then_some("sig"))
+                    .execute(&pool)
+                    .await?;
+            }
+        }
+        // saving a height again recounts its bucket, it doesn't add up.
+        storage::db::save_uptime_buckets(&heights, &pool).await?;
+        storage::db::save_uptime_buckets(&heights[5..], &pool).await?;
+
+        let params = |window| GetValidatorsUptimeParams {
+            window: Some(window),
+            page: None,
+            page_size: None,
+        };
+        let res = get_validators_uptime(State(state.clone()), Query(params(10))).await?;
+        assert_eq!(res.total, 2);
+        let a = &res.data[0];
+        assert_eq!((a.signed, a.missed, a.proposed), (3, 7, 1));
+        assert_eq!((a.current_missed_streak, a.longest_missed_streak), (0, 7));
+        let b = &res.data[1];
+        assert_eq!((b.signed, b.missed, b.proposed), (8, 2, 9));
+        assert_eq!((b.current_missed_streak, b.longest_missed_streak), (2, 2));
+
+        // the last 5 blocks are the bucket from 100 on.
+        let res = get_validator_uptime(
+            State(state.clone()),
+            Path("a".to_string()),
+            Query(GetValidatorUptimeParams {
+                window: Some(5),
+                days: None,
+            }),
+        )
+        .await?;
+        assert_eq!((res.window.signed, res.window.missed), (1, 4));
+        assert_eq!(res.window.longest_missed_streak, 4);
+        // the address is bound, not spliced into the query.
+        let res = get_validator_uptime(
+            State(state),
+            Path("x' OR '1'='1".to_string()),
+            Query(GetValidatorUptimeParams {
+                window: None,
+                days: None,
+            }),
+        )
+        .await;
+        assert!(res.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use crate::service::error::Result;
+use crate::service::v2::slashing::SlashingResponse;
+use crate::service::QueryResult;
+use crate::AppState;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use module::schema::V2ValidatorEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize)]
+pub struct ValidatorDetailResponse {
+    pub address: String,
+    pub pubkey: Option<String>,
+    pub staker: Option<String>,
+    pub commission_rate: Option<Value>,
+    pub memo: Option<Value>,
+    pub updated_at: Option<i64>,
+    pub slashing: Vec<SlashingResponse>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ValidatorEventResponse {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub validator: String,
+    pub staker: String,
+    pub kind: String,
+    pub commission_rate: Option<Value>,
+    pub memo: Option<Value>,
+    pub new_staker: Option<String>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub value: Value,
+}
+
+impl From<V2ValidatorEvent> for ValidatorEventResponse {
+    fn from(event: V2ValidatorEvent) -> Self {
+        ValidatorEventResponse {
+            tx_hash: event.tx_hash,
+            block_hash: event.block_hash,
+            validator: event.validator,
+            staker: event.staker,
+            kind: event.kind,
+            commission_rate: event.commission_rate,
+            memo: event.memo,
+            new_staker: event.new_staker,
+            height: event.height,
+            timestamp: event.timestamp,
+            value: event.content,
+        }
+    }
+}
+
+pub async fn get_validator_detail(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<ValidatorDetailResponse>> {
+    let address = address.to_uppercase();
+    let validator = state.storage.validator(&address).await?;
+    let (_, slashing) = state
+        .storage
+        .slashing_events(Some(&address), i64::MAX, 0)
+        .await?;
+
+    Ok(Json(ValidatorDetailResponse {
+        address: validator.address,
+        pubkey: validator.pubkey,
+        staker: validator.staker,
+        commission_rate: validator.commission_rate,
+        memo: validator.memo,
+        updated_at: validator.updated_at,
+        slashing: slashing.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetValidatorHistoryParams {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+}
+
+pub async fn get_validator_history(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(params): Query<GetValidatorHistoryParams>,
+) -> Result<Json<QueryResult<Vec<ValidatorEventResponse>>>> {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+    let address = address.to_uppercase();
+
+    let (total, events) = state
+        .storage
+        .validator_events(&address, page_size as i64, ((page - 1) * page_size) as i64)
+        .await?;
+
+    Ok(Json(QueryResult {
+        total,
+        page,
+        page_size,
+        data: events.into_iter().map(Into::into).collect(),
+    }))
+}
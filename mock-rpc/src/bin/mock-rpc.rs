@@ -0,0 +1,52 @@
+extern crate mock_rpc;
+
+use clap::Parser;
+use mock_rpc::{MockNode, Result};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Serve tendermint rpc from fixture data.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+struct Args {
+    /// Fixture directory.
+    #[clap(long)]
+    fixtures: PathBuf,
+    /// Address to listen on, default is 127.0.0.1:26657.
+    #[clap(long)]
+    listen: Option<SocketAddr>,
+    /// Delay before every response, with milliseconds.
+    #[clap(long)]
+    latency: Option<u64>,
+    /// Answer every n-th request with an internal error.
+    #[clap(long)]
+    error_every: Option<u64>,
+    /// Comma separated heights answered as not found.
+    #[clap(long, value_delimiter = ',')]
+    missing: Vec<i64>,
+    /// Pretend the chain ends at this height.
+    #[clap(long)]
+    tip: Option<i64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::builder()
+        .target(env_logger::Target::Stdout)
+        .init();
+    let args = Args::parse();
+    let node = MockNode::load(&args.fixtures)?;
+    node.configure(|k| {
+        k.latency = Duration::from_millis(args.latency.unwrap_or(0));
+        k.error_every = args.error_every;
+        k.missing = args.missing.iter().copied().collect();
+        k.tip = args.tip;
+    });
+    let listen = args
+        .listen
+        .unwrap_or_else(|| ([127, 0, 0, 1], 26657).into());
+    let (_, handle) = node.serve(listen).await?;
+    handle.await?;
+    Ok(())
+}
@@ -0,0 +1,570 @@
+//! A local tendermint node answering from fixture data, for tests that must not depend on a
+//! live network.
+//!
+//! Fixtures use the layout of `module::source::FsSource`, documents being the `result` of
+//! the rpc responses:
+//!
+//! - `block/<height>.json`
+//! - `validators/<height>.json`, all validators, paged on request.
+//! - `commit/<height>.json`, optional, the `last_commit` of the next block otherwise.
+//! - `block_results/<height>.json` or `tx/<hash>.json`
+//! - `blockchain/<height>.json`, optional.
+//! - `abci_query/delegations.json`, optional.
+//!
+//! [`Knobs`] inject latency, errors, missing heights and forks while the node runs.
+#[macro_use]
+extern crate log;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use base64::{engine, Engine};
+use serde_json::{json, Value};
+use sha2::Digest;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+pub type Result<T> = core::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Faults injected into the responses, changeable while the node runs.
+#[derive(Debug, Clone, Default)]
+pub struct Knobs {
+    /// Delay before every response.
+    pub latency: Duration,
+    /// Answer every n-th request with an internal error.
+    pub error_every: Option<u64>,
+    /// Heights answered as not found while lower and higher ones are served.
+    pub missing: BTreeSet<i64>,
+    /// Block hash replacing the fixture one at a height, the next block links to it.
+    pub forks: HashMap<i64, String>,
+    /// Pretend the chain ends here, higher heights are beyond the tip.
+    pub tip: Option<i64>,
+    /// Interval between two `NewBlock` events on the websocket.
+    pub block_interval: Duration,
+}
+
+#[derive(Default)]
+struct Fixtures {
+    blocks: BTreeMap<i64, Value>,
+    validators: HashMap<i64, Value>,
+    blockchain: HashMap<i64, Value>,
+    commits: HashMap<i64, Value>,
+    /// Tx hash in upper case to the `/tx` result.
+    txs: HashMap<String, Value>,
+    delegations: Option<Value>,
+}
+
+struct Inner {
+    fixtures: Fixtures,
+    knobs: RwLock<Knobs>,
+    requests: AtomicU64,
+}
+
+#[derive(Clone)]
+pub struct MockNode {
+    inner: Arc<Inner>,
+}
+
+/// The fixtures of this crate, a chain of a few heights.
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// The document of `kind` at `height` among the fixtures of this crate, e.g.
+/// `fixture("block", 3)`. Panics if there is none.
+pub fn fixture(kind: &str, height: i64) -> Value {
+    let path = fixtures_dir().join(kind).join(format!("{height}.json"));
+    serde_json::from_slice(&fs::read(&path).unwrap()).unwrap()
+}
+
+/// Documents of a fixture directory by name, `None` if the directory does not exist.
+fn read_dir(dir: &Path) -> Result<Option<Vec<(String, Value)>>> {
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let mut docs = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            let mut doc: Value = serde_json::from_slice(&fs::read(&path)?)?;
+            if doc.get("jsonrpc").is_some() {
+                doc = doc["result"].take();
+            }
+            docs.push((name, doc));
+        }
+    }
+    Ok(Some(docs))
+}
+
+fn by_height(docs: Option<Vec<(String, Value)>>) -> Result<Vec<(i64, Value)>> {
+    docs.unwrap_or_default()
+        .into_iter()
+        .map(|(name, doc)| Ok((name.parse()?, doc)))
+        .collect()
+}
+
+impl MockNode {
+    /// Load the fixtures under `dir`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut fixtures = Fixtures {
+            blocks: by_height(read_dir(&dir.join("block"))?)?
+                .into_iter()
+                .collect(),
+            validators: by_height(read_dir(&dir.join("validators"))?)?
+                .into_iter()
+                .collect(),
+            blockchain: by_height(read_dir(&dir.join("blockchain"))?)?
+                .into_iter()
+                .collect(),
+            commits: by_height(read_dir(&dir.join("commit"))?)?
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+
+        for (height, mut results) in by_height(read_dir(&dir.join("block_results"))?)? {
+            let txs = fixtures
+                .blocks
+                .get(&height)
+                .and_then(|b| b["block"]["data"]["txs"].as_array())
+                .cloned()
+                .unwrap_or_default();
+            for (index, tx) in txs.into_iter().enumerate() {
+                let bytes =
+                    engine::general_purpose::STANDARD.decode(tx.as_str().unwrap_or_default())?;
+                let hash = hex::encode_upper(sha2::Sha256::digest(bytes));
+                let tx_result = results["txs_results"][index].take();
+                fixtures.txs.insert(
+                    hash.clone(),
+                    json!({
+                        "hash": hash,
+                        "height": height.to_string(),
+                        "index": index,
+                        "tx_result": tx_result,
+                        "tx": tx,
+                    }),
+                );
+            }
+        }
+        for (hash, tx) in read_dir(&dir.join("tx"))?.unwrap_or_default() {
+            fixtures.txs.insert(hash.to_uppercase(), tx);
+        }
+        if let Some(docs) = read_dir(&dir.join("abci_query"))? {
+            fixtures.delegations = docs
+                .into_iter()
+                .find(|(name, _)| name == "delegations")
+                .map(|(_, doc)| doc);
+        }
+
+        Ok(MockNode {
+            inner: Arc::new(Inner {
+                fixtures,
+                knobs: RwLock::new(Knobs {
+                    block_interval: Duration::from_secs(1),
+                    ..Default::default()
+                }),
+                requests: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Change the injected faults, applies to the requests that follow.
+    pub fn configure(&self, f: impl FnOnce(&mut Knobs)) {
+        f(&mut self.inner.knobs.write().unwrap())
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn router(&self) -> Router {
+        let router = Router::new()
+            .route("/status", get(status))
+            .route("/block", get(block))
+            .route("/blockchain", get(blockchain))
+            .route("/validators", get(validators))
+            .route("/commit", get(commit))
+            .route("/tx", get(tx))
+            .route("/abci_query", get(abci_query));
+        #[cfg(feature = "ws")]
+        let router = router.route("/websocket", get(ws::websocket));
+        router.with_state(self.clone())
+    }
+
+    /// Serve on `addr`, port 0 picks a free one. Returns the bound address.
+    pub async fn serve(&self, addr: SocketAddr) -> Result<(SocketAddr, JoinHandle<()>)> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let router = self.router();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                error!("Mock node error: {:?}", e);
+            }
+        });
+        info!("Mock node listening on {}.", addr);
+        Ok((addr, handle))
+    }
+
+    /// Serve on a free local port, returns the url to give to the scanner.
+    pub async fn spawn(&self) -> Result<String> {
+        let (addr, _) = self.serve(([127, 0, 0, 1], 0).into()).await?;
+        Ok(format!("http://{addr}/"))
+    }
+
+    fn knobs(&self) -> Knobs {
+        self.inner.knobs.read().unwrap().clone()
+    }
+
+    fn tip(&self) -> i64 {
+        let last = self
+            .inner
+            .fixtures
+            .blocks
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or_default();
+        self.knobs().tip.map_or(last, |tip| tip.min(last))
+    }
+
+    /// The block at `height` with the forks applied, `None` if it is not served.
+    fn block_at(&self, height: i64) -> Option<Value> {
+        let knobs = self.knobs();
+        if height > self.tip() || knobs.missing.contains(&height) {
+            return None;
+        }
+        let mut block = self.inner.fixtures.blocks.get(&height)?.clone();
+        if let Some(hash) = knobs.forks.get(&height) {
+            block["block_id"]["hash"] = json!(hash);
+        }
+        if let Some(hash) = knobs.forks.get(&(height - 1)) {
+            block["block"]["header"]["last_block_id"]["hash"] = json!(hash);
+            block["block"]["last_commit"]["block_id"]["hash"] = json!(hash);
+        }
+        Some(block)
+    }
+
+    /// Apply latency and injected errors, common to all requests.
+    async fn enter(&self) -> core::result::Result<(), Response> {
+        let n = self.inner.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        let knobs = self.knobs();
+        tokio::time::sleep(knobs.latency).await;
+        match knobs.error_every {
+            Some(every) if every > 0 && n.is_multiple_of(every) => Err(rpc_error("injected error")),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn rpc_result(result: Value) -> Response {
+    Json(json!({"jsonrpc": "2.0", "id": -1, "result": result})).into_response()
+}
+
+fn rpc_error(data: &str) -> Response {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": -1,
+        "error": {"code": -32603, "message": "Internal error", "data": data},
+    });
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+}
+
+/// The error tendermint gives for heights it does not have.
+fn beyond_tip(height: i64, tip: i64) -> Response {
+    rpc_error(&format!(
+        "height {height} must be less than or equal to the current blockchain height {tip}"
+    ))
+}
+
+/// Query values may be quoted, `height="5"` is the same as `height=5`.
+fn param<T: std::str::FromStr>(query: &HashMap<String, String>, name: &str) -> Option<T> {
+    query.get(name)?.trim_matches('"').parse().ok()
+}
+
+type Params = Query<HashMap<String, String>>;
+
+async fn status(State(node): State<MockNode>) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let tip = node.tip();
+    let hash = node.block_at(tip).map(|b| b["block_id"]["hash"].clone());
+    rpc_result(json!({
+        "node_info": {"network": "mock-chain", "moniker": "mock-rpc"},
+        "sync_info": {
+            "latest_block_hash": hash,
+            "latest_block_height": tip.to_string(),
+            "catching_up": false,
+        },
+    }))
+}
+
+async fn block(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let tip = node.tip();
+    let height = param(&query, "height").unwrap_or(tip);
+    match node.block_at(height) {
+        Some(block) => rpc_result(block),
+        None => beyond_tip(height, tip),
+    }
+}
+
+async fn blockchain(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let tip = node.tip();
+    let max = param(&query, "maxHeight").unwrap_or(tip).min(tip);
+    let min = param(&query, "minHeight").unwrap_or(1).max(max - 19);
+    let mut metas = vec![];
+    for height in (min..=max).rev() {
+        let Some(block) = node.block_at(height) else {
+            continue;
+        };
+        let meta = match node.inner.fixtures.blockchain.get(&height) {
+            Some(doc) => doc["block_metas"][0].clone(),
+            None => json!({
+                "block_id": block["block_id"],
+                "block_size": serde_json::to_vec(&block["block"]).unwrap().len().to_string(),
+                "header": block["block"]["header"],
+                "num_txs": block["block"]["data"]["txs"]
+                    .as_array()
+                    .map(|txs| txs.len())
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+        };
+        metas.push(meta);
+    }
+    rpc_result(json!({"last_height": tip.to_string(), "block_metas": metas}))
+}
+
+async fn validators(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let tip = node.tip();
+    let height = param(&query, "height").unwrap_or(tip);
+    let fixture = match node.block_at(height) {
+        Some(_) => node.inner.fixtures.validators.get(&height),
+        None => None,
+    };
+    let Some(fixture) = fixture else {
+        return beyond_tip(height, tip);
+    };
+    let all = fixture["validators"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let page = param(&query, "page").unwrap_or(1usize).max(1);
+    let per_page = param(&query, "per_page").unwrap_or(30usize).clamp(1, 100);
+    let validators: Vec<_> = all
+        .iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .cloned()
+        .collect();
+    rpc_result(json!({
+        "block_height": height.to_string(),
+        "count": validators.len().to_string(),
+        "total": all.len().to_string(),
+        "validators": validators,
+    }))
+}
+
+async fn commit(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let tip = node.tip();
+    let height = param(&query, "height").unwrap_or(tip);
+    let Some(block) = node.block_at(height) else {
+        return beyond_tip(height, tip);
+    };
+    // the commit of the tip is only seen by the node, not held by a block.
+    let (commit, canonical) = match node.block_at(height + 1) {
+        Some(mut next) => (next["block"]["last_commit"].take(), true),
+        None => match node.inner.fixtures.commits.get(&height) {
+            Some(doc) => {
+                let mut commit = doc["signed_header"]["commit"].clone();
+                commit["block_id"] = block["block_id"].clone();
+                (commit, false)
+            }
+            None => return rpc_error(&format!("commit of height {height} not found")),
+        },
+    };
+    rpc_result(json!({
+        "signed_header": {"header": block["block"]["header"], "commit": commit},
+        "canonical": canonical,
+    }))
+}
+
+async fn tx(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let hash = query
+        .get("hash")
+        .map(|h| h.trim_matches('"').trim_start_matches("0x").to_uppercase())
+        .unwrap_or_default();
+    let tx = node.inner.fixtures.txs.get(&hash).filter(|tx| {
+        tx["height"]
+            .as_str()
+            .and_then(|h| h.parse().ok())
+            .is_some_and(|h| node.block_at(h).is_some())
+    });
+    match tx {
+        Some(tx) => rpc_result(tx.clone()),
+        None => rpc_error(&format!("tx ({hash}) not found")),
+    }
+}
+
+async fn abci_query(State(node): State<MockNode>, Query(query): Params) -> Response {
+    if let Err(e) = node.enter().await {
+        return e;
+    }
+    let path = query.get("path").map(|p| p.trim_matches('"'));
+    let response = match (path, &node.inner.fixtures.delegations) {
+        (Some("/delegations"), Some(delegations)) => json!({
+            "code": 0,
+            "log": "",
+            "info": delegations.to_string(),
+            "height": node.tip().to_string(),
+        }),
+        _ => json!({
+            "code": 1,
+            "log": "",
+            "info": format!("unknown query path: {path:?}"),
+            "height": node.tip().to_string(),
+        }),
+    };
+    rpc_result(json!({ "response": response }))
+}
+
+#[cfg(feature = "ws")]
+mod ws {
+    use super::*;
+    use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+
+    /// `NewBlock` events for every served block, oldest first, then for blocks as the tip moves.
+    pub(crate) async fn websocket(State(node): State<MockNode>, ws: WebSocketUpgrade) -> Response {
+        ws.on_upgrade(move |socket| events(node, socket))
+    }
+
+    async fn events(node: MockNode, mut socket: WebSocket) {
+        // the subscription itself is not checked, NewBlock is all there is.
+        let id = match socket.recv().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text)
+                .map(|req| req["id"].clone())
+                .unwrap_or(json!(-1)),
+            _ => return,
+        };
+        let mut next = 1;
+        loop {
+            let tip = node.tip();
+            while next <= tip {
+                if let Some(block) = node.block_at(next) {
+                    let event = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": {
+                            "query": "tm.event='NewBlock'",
+                            "data": {
+                                "type": "tendermint/event/NewBlock",
+                                "value": {"block": block["block"]},
+                            },
+                        },
+                    });
+                    if socket.send(Message::Text(event.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+                next += 1;
+            }
+            tokio::time::sleep(node.knobs().block_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fixtures() -> MockNode {
+        MockNode::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap()
+    }
+
+    async fn get(url: &str, path: &str) -> (u16, Value) {
+        let resp = reqwest::get(format!("{url}{path}")).await.unwrap();
+        (resp.status().as_u16(), resp.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_serve_fixtures() {
+        let node = fixtures();
+        let url = node.spawn().await.unwrap();
+
+        let (_, status) = get(&url, "status").await;
+        assert_eq!(status["result"]["sync_info"]["latest_block_height"], "3");
+        let (_, block) = get(&url, "block?height=2").await;
+        let tx = block["result"]["block"]["data"]["txs"][0].as_str().unwrap();
+        let bytes = engine::general_purpose::STANDARD.decode(tx).unwrap();
+        let hash = hex::encode(sha2::Sha256::digest(bytes));
+        let (_, loaded) = get(&url, &format!("tx?hash=0x{hash}")).await;
+        assert_eq!(loaded["result"]["tx"], tx);
+        let (_, validators) = get(&url, "validators?height=2&per_page=1&page=2").await;
+        assert_eq!(validators["result"]["count"], "1");
+        assert_eq!(validators["result"]["total"], "2");
+        let (_, sizes) = get(&url, "blockchain?minHeight=2&maxHeight=2").await;
+        assert_eq!(sizes["result"]["block_metas"][0]["num_txs"], "2");
+        let (_, commit) = get(&url, "commit?height=2").await;
+        assert_eq!(
+            commit["result"]["signed_header"]["commit"]["block_id"],
+            block["result"]["block_id"]
+        );
+        let (_, commit) = get(&url, "commit?height=3").await;
+        assert_eq!(commit["result"]["canonical"], false);
+        let (code, _) = get(&url, "block?height=4").await;
+        assert_eq!(code, 500);
+    }
+
+    #[tokio::test]
+    async fn test_knobs() {
+        let node = fixtures();
+        let url = node.spawn().await.unwrap();
+
+        node.configure(|k| {
+            k.missing.insert(2);
+            k.forks.insert(1, "FORK".into());
+            k.tip = Some(2);
+        });
+        let (code, _) = get(&url, "block?height=2").await;
+        assert_eq!(code, 500);
+        let (_, block) = get(&url, "block?height=1").await;
+        assert_eq!(block["result"]["block_id"]["hash"], "FORK");
+        let (_, status) = get(&url, "status").await;
+        assert_eq!(status["result"]["sync_info"]["latest_block_height"], "2");
+
+        node.configure(|k| {
+            *k = Knobs::default();
+            k.error_every = Some(2);
+        });
+        let (code, _) = get(&url, "block?height=3").await;
+        assert_eq!(code, 500);
+        let (code, _) = get(&url, "block?height=3").await;
+        assert_eq!(code, 200);
+        assert_eq!(node.requests(), 5);
+    }
+}
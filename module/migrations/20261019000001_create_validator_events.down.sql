@@ -0,0 +1,2 @@
+drop index dl_new_validator_index;
+drop table validator_events;
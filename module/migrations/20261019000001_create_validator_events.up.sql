@@ -0,0 +1,20 @@
+create table validator_events(
+    tx varchar(64) not null,
+    op_index integer not null,
+    block varchar(64) not null,
+    validator varchar(64) not null,
+    staker varchar(64) not null,
+    kind varchar(32) not null,
+    commission_rate jsonb,
+    memo jsonb,
+    new_staker varchar(64),
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (tx, op_index, validator)
+);
+create index ve_validator_index on validator_events(validator);
+create index ve_staker_index on validator_events(staker);
+create index ve_height_index on validator_events(height);
+-- the validators of the stakers are read from the delegations registering one.
+create index dl_new_validator_index on delegations(sender) where new_validator <> '';
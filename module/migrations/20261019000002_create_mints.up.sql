@@ -0,0 +1,16 @@
+create table mints(
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    idx int not null,
+    receiver varchar(64) not null,
+    amount bigint not null,
+    kind varchar(32) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (tx, idx, kind)
+);
+create index mint_receiver_index on mints(receiver);
+create index mint_kind_index on mints(kind);
+create index mint_time_index on mints(timestamp);
+create index mint_height_index on mints(height);
@@ -0,0 +1 @@
+drop table slashing_events;
@@ -0,0 +1,23 @@
+-- a block may carry several punishments of a validator of the same kind, in different txs or
+-- evidence. rows are keyed by their position: the index of the tx in the block and of the op
+-- in the tx, or -1 and the index of the evidence. the penalty amount is the punished power
+-- times the penalty rate.
+create table slashing_events(
+    block varchar(64) not null,
+    tx varchar(64),
+    tx_index bigint not null,
+    op_index integer not null,
+    validator varchar(64) not null,
+    kind varchar(32) not null,
+    source varchar(16) not null,
+    evidence_height bigint,
+    power bigint,
+    penalty_rate jsonb,
+    penalty_amount bigint,
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (height, tx_index, op_index, validator)
+);
+create index se_validator_index on slashing_events(validator);
+create index se_time_index on slashing_events(timestamp);
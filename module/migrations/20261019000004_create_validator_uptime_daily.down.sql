@@ -0,0 +1,2 @@
+drop table validator_uptime_buckets;
+drop table validator_uptime_daily;
@@ -0,0 +1,64 @@
+create table validator_uptime_daily(
+    address varchar(64) not null,
+    day date not null,
+    signed bigint not null,
+    missed bigint not null,
+    proposed bigint not null,
+    primary key (address, day)
+);
+create index vud_day_index on validator_uptime_daily(day);
+
+insert into validator_uptime_daily
+select bg.address,
+       b.time::date,
+       count(bg.signature),
+       count(*) - count(bg.signature),
+       count(*) filter (where b.proposer = bg.address)
+from block_generation bg join block b on b.height = bg.height
+group by bg.address, b.time::date;
+
+-- per validator counters of buckets of 100 heights, the uptime of the last n blocks sums the
+-- buckets. the missed runs touching the bucket bounds are kept to join streaks across buckets.
+-- the scanner recounts the buckets of the heights it saves.
+create table validator_uptime_buckets(
+    address varchar(64) not null,
+    bucket bigint not null,
+    first_height bigint not null,
+    last_height bigint not null,
+    signed bigint not null,
+    missed bigint not null,
+    proposed bigint not null,
+    leading_missed bigint not null,
+    trailing_missed bigint not null,
+    longest_missed bigint not null,
+    primary key (address, bucket)
+);
+create index vub_bucket_index on validator_uptime_buckets(bucket);
+
+with g as (
+    select bg.height / 100 as bucket, bg.address, bg.height, bg.signature is null as miss,
+        coalesce(b.proposer = bg.address, false) as proposed
+    from block_generation bg left join block b on b.height = bg.height
+),
+runs as (
+    select bucket, address, min(height) as first, max(height) as last, count(*) as len
+    from (
+        select bucket, address, height,
+            height - row_number() over (partition by bucket, address order by height) as grp
+        from g where miss
+    ) m group by bucket, address, grp
+),
+s as (
+    select bucket, address, min(height) as first_height, max(height) as last_height,
+        count(*) filter (where not miss) as signed, count(*) filter (where miss) as missed,
+        count(*) filter (where proposed) as proposed
+    from g group by bucket, address
+)
+insert into validator_uptime_buckets
+select s.address, s.bucket, s.first_height, s.last_height, s.signed, s.missed, s.proposed,
+    coalesce((select len from runs r where r.bucket = s.bucket and r.address = s.address
+              and r.first = s.first_height), 0),
+    coalesce((select len from runs r where r.bucket = s.bucket and r.address = s.address
+              and r.last = s.last_height), 0),
+    coalesce((select max(len) from runs r where r.bucket = s.bucket and r.address = s.address), 0)
+from s;
@@ -0,0 +1,3 @@
+drop table operations;
+drop index tx_height_pos_index;
+alter table transaction drop column tx_index;
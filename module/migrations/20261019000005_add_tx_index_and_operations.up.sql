@@ -0,0 +1,26 @@
+alter table transaction add column tx_index bigint not null default 0;
+
+-- the origin is the base64 tx as it appears in the block data.
+update transaction t set tx_index = x.idx - 1
+from block b, jsonb_array_elements_text(b.block_data->'block'->'data'->'txs') with ordinality as x(tx, idx)
+where b.block_hash = t.block_hash and x.tx = t.origin;
+
+create index tx_height_pos_index on transaction(height, tx_index);
+
+create table operations(
+    tx varchar(64) not null,
+    op_index int not null,
+    kind varchar(32) not null,
+    signer varchar(64) not null,
+    counterparty varchar(64) not null,
+    asset varchar(64),
+    amount varchar(48),
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (tx, op_index)
+);
+create index op_kind_index on operations(kind);
+create index op_signer_index on operations(signer);
+create index op_counterparty_index on operations(counterparty);
+create index op_height_index on operations(height);
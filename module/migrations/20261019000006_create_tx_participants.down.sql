@@ -0,0 +1 @@
+drop table tx_participants;
@@ -0,0 +1,33 @@
+create table tx_participants(
+    tx varchar(64) not null,
+    address varchar(64) not null,
+    role varchar(16) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    primary key (tx, address, role)
+);
+create index tp_addr_time_index on tx_participants(address, timestamp desc);
+create index tp_addr_role_time_index on tx_participants(address, role, timestamp desc);
+-- the txs of an address are listed from its participant rows, latest block first.
+create index tp_addr_height_index on tx_participants(address, height desc);
+
+insert into tx_participants
+select tx_hash, sender, 'sender', height, timestamp from transaction where sender <> ''
+on conflict do nothing;
+
+insert into tx_participants
+select tx_hash, r.addr, 'receiver', height, timestamp
+from transaction, jsonb_array_elements_text(receiver->'addrs') as r(addr)
+where r.addr <> ''
+on conflict do nothing;
+
+insert into tx_participants
+select tx, signer, 'sender', height, timestamp from operations where signer <> ''
+on conflict do nothing;
+
+insert into tx_participants
+select tx, counterparty,
+       case when kind in ('Delegation', 'UnDelegation', 'UpdateStaker') then 'validator' else 'receiver' end,
+       height, timestamp
+from operations where counterparty <> ''
+on conflict do nothing;
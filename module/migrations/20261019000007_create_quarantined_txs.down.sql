@@ -0,0 +1 @@
+drop table quarantined_txs;
@@ -0,0 +1,14 @@
+-- a tx of unknown catalog may be sent again at another height, each height keeps its row so a
+-- pruned block is rebuilt with all its txs.
+create table quarantined_txs(
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    height bigint not null,
+    tx_index bigint not null,
+    origin text not null,
+    stage varchar(16) not null,
+    error text not null,
+    timestamp bigint not null,
+    primary key (tx, height)
+);
+create index qt_height_index on quarantined_txs(height);
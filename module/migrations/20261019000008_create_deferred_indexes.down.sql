@@ -0,0 +1 @@
+drop table deferred_indexes;
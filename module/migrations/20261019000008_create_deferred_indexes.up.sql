@@ -0,0 +1,6 @@
+-- secondary indexes dropped for an initial bulk load, restored once it completes.
+create table deferred_indexes(
+    name varchar(64) not null,
+    def text not null,
+    primary key (name)
+);
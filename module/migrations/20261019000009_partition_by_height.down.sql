@@ -0,0 +1,80 @@
+drop trigger block_hash_track on block;
+drop trigger tx_hash_release on transaction;
+drop trigger tx_hash_claim on transaction;
+drop function track_block_hash;
+drop function release_tx_hash;
+drop function claim_tx_hash;
+drop table block_heights;
+drop table tx_heights;
+
+alter table block rename to block_part;
+alter table transaction rename to transaction_part;
+alter table block_generation rename to block_generation_part;
+alter table block_part rename constraint block_pkey to block_part_pkey;
+alter table transaction_part rename constraint transaction_pkey to transaction_part_pkey;
+alter table block_generation_part rename constraint block_generation_pkey to block_generation_part_pkey;
+
+create table block (
+    block_hash varchar(64) not null,
+    height bigint not null,
+    size bigint not null,
+    tx_count bigint not null,
+    time timestamp not null,
+    app_hash varchar(64) not null,
+    proposer varchar(64) not null,
+    block_data jsonb not null,
+    primary key (block_hash)
+);
+
+create table transaction (
+     tx_hash varchar(64) not null,
+     block_hash varchar(64) not null,
+     height bigint not null,
+     timestamp bigint not null,
+     code bigint not null,
+     ty integer not null,
+     ty_sub integer not null,
+     sender varchar(64) not null,
+     receiver jsonb not null,
+     log text,
+     origin text not null,
+     result jsonb not null,
+     value jsonb not null,
+     tx_index bigint not null default 0,
+     primary key (tx_hash)
+);
+
+create table block_generation (
+    height bigint not null,
+    address varchar(64) not null,
+    power bigint not null,
+    priority bigint,
+    signature varchar(128),
+    time timestamp,
+    primary key (height, address)
+);
+
+insert into block select * from block_part;
+insert into transaction select * from transaction_part;
+insert into block_generation select * from block_generation_part;
+
+drop table block_part;
+drop table transaction_part;
+drop table block_generation_part;
+
+create index block_height_index on block (height);
+create index blk_time_index on block(time);
+create index block_proposer_index on block (proposer);
+
+create index tx_block_index on transaction (block_hash);
+create index tx_height_index on transaction (height);
+create index tx_time_index on transaction (timestamp);
+create index tx_sender_index on transaction(sender);
+create index tx_recv_index on transaction(receiver);
+create index tx_height_pos_index on transaction(height, tx_index);
+
+create index bg_height_addr_index on block_generation(height, address);
+create index bg_sig_index on block_generation(signature);
+
+drop function ensure_height_partition(bigint);
+drop table partition_settings;
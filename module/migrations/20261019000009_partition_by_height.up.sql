@@ -0,0 +1,164 @@
+-- block, transaction and block_generation are range partitioned by height.
+-- the partition size is read from `scanner.partition_size` when migrating, e.g.
+-- `alter database findora set scanner.partition_size = '500000'`, default is 1000000.
+-- it is fixed once partitions exist, the scanner reads it from `partition_settings`.
+create table partition_settings(
+    name varchar(16) not null,
+    size bigint not null,
+    primary key (name)
+);
+insert into partition_settings
+values ('height', coalesce(nullif(current_setting('scanner.partition_size', true), '')::bigint, 1000000));
+
+-- create the partitions of all height partitioned tables holding `h`.
+create function ensure_height_partition(h bigint) returns void as $$
+declare
+    sz bigint;
+    lo bigint;
+    t text;
+begin
+    select size into sz from partition_settings where name = 'height';
+    lo := (h / sz) * sz;
+    foreach t in array array['block', 'transaction', 'block_generation'] loop
+        begin
+            execute format('create table if not exists %I partition of %I for values from (%s) to (%s)',
+                           t || '_p' || (lo / sz), t, lo, lo + sz);
+        exception when duplicate_table then
+            null;
+        end;
+    end loop;
+end;
+$$ language plpgsql;
+
+alter table block rename to block_old;
+alter table transaction rename to transaction_old;
+alter table block_generation rename to block_generation_old;
+alter table block_old rename constraint block_pkey to block_old_pkey;
+alter table transaction_old rename constraint transaction_pkey to transaction_old_pkey;
+alter table block_generation_old rename constraint block_generation_pkey to block_generation_old_pkey;
+
+create table block (
+    block_hash varchar(64) not null,
+    height bigint not null,
+    size bigint not null,
+    tx_count bigint not null,
+    time timestamp not null,
+    app_hash varchar(64) not null,
+    proposer varchar(64) not null,
+    block_data jsonb not null,
+    primary key (height)
+) partition by range (height);
+
+create table transaction (
+     tx_hash varchar(64) not null,
+     block_hash varchar(64) not null,
+     height bigint not null,
+     timestamp bigint not null,
+     code bigint not null,
+     ty integer not null,
+     ty_sub integer not null,
+     sender varchar(64) not null,
+     receiver jsonb not null,
+     log text,
+     origin text not null,
+     result jsonb not null,
+     value jsonb not null,
+     tx_index bigint not null default 0,
+     primary key (tx_hash, height)
+) partition by range (height);
+
+create table block_generation (
+    height bigint not null,
+    address varchar(64) not null,
+    power bigint not null,
+    priority bigint,
+    signature varchar(128),
+    time timestamp,
+    primary key (height, address)
+) partition by range (height);
+
+select ensure_height_partition(h)
+from generate_series(0, coalesce((select max(height) from block_old), 0) + 1,
+                     (select size from partition_settings where name = 'height')) as h;
+
+insert into block select * from block_old;
+insert into transaction select * from transaction_old;
+insert into block_generation select * from block_generation_old;
+
+drop table block_old;
+drop table transaction_old;
+drop table block_generation_old;
+
+create index block_hash_index on block (block_hash);
+create index blk_time_index on block(time);
+create index block_proposer_index on block (proposer);
+
+create index tx_hash_index on transaction (tx_hash);
+create index tx_block_index on transaction (block_hash);
+create index tx_time_index on transaction (timestamp);
+create index tx_sender_index on transaction(sender);
+create index tx_recv_index on transaction(receiver);
+create index tx_height_pos_index on transaction(height, tx_index);
+
+create index bg_sig_index on block_generation(signature);
+
+-- the primary keys of the partitioned tables hold the height, so the hash of a block or a tx
+-- doesn't find its partition. the heights of the hashes are kept aside, a lookup by hash reads
+-- its height here first and then scans a single partition.
+create table tx_heights (
+    tx_hash varchar(64) not null,
+    height bigint not null,
+    primary key (tx_hash)
+);
+
+create table block_heights (
+    block_hash varchar(64) not null,
+    height bigint not null,
+    primary key (block_hash)
+);
+
+insert into tx_heights select tx_hash, height from transaction;
+insert into block_heights select block_hash, height from block;
+
+-- a tx hash stays unique, as it was before partitioning: saving a tx at another height moves
+-- it there.
+create function claim_tx_hash() returns trigger as $$
+declare
+    prev bigint;
+begin
+    select height into prev from tx_heights where tx_hash = new.tx_hash;
+    if prev is not null and prev <> new.height then
+        delete from transaction where tx_hash = new.tx_hash and height = prev;
+    end if;
+    insert into tx_heights values (new.tx_hash, new.height)
+    on conflict (tx_hash) do update set height = excluded.height;
+    return new;
+end;
+$$ language plpgsql;
+
+create function release_tx_hash() returns trigger as $$
+begin
+    delete from tx_heights where tx_hash = old.tx_hash and height = old.height;
+    return null;
+end;
+$$ language plpgsql;
+
+create function track_block_hash() returns trigger as $$
+begin
+    if tg_op <> 'INSERT' then
+        delete from block_heights where block_hash = old.block_hash and height = old.height;
+    end if;
+    if tg_op <> 'DELETE' then
+        insert into block_heights values (new.block_hash, new.height)
+        on conflict (block_hash) do update set height = excluded.height;
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger tx_hash_claim before insert on transaction
+    for each row execute function claim_tx_hash();
+create trigger tx_hash_release after delete on transaction
+    for each row execute function release_tx_hash();
+create trigger block_hash_track after insert or update of block_hash or delete on block
+    for each row execute function track_block_hash();
@@ -0,0 +1,3 @@
+drop table tx_types;
+drop table native_txs;
+drop table evm_txs;
@@ -0,0 +1,34 @@
+-- tables written by `scanner-cli migrate`, hand-applied on older deployments.
+create table if not exists evm_txs(
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    evm_tx varchar(66) not null,
+    sender varchar(64) not null,
+    receiver varchar(64) not null,
+    amount varchar(48) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (tx)
+);
+create index if not exists evm_txs_sender_index on evm_txs(sender);
+create index if not exists evm_txs_receiver_index on evm_txs(receiver);
+create index if not exists evm_txs_height_index on evm_txs(height);
+
+create table if not exists native_txs(
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    address varchar(64) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content jsonb not null,
+    primary key (tx)
+);
+create index if not exists native_txs_address_index on native_txs(address);
+create index if not exists native_txs_height_index on native_txs(height);
+
+create table if not exists tx_types(
+    tx varchar(64) not null,
+    ty integer not null,
+    primary key (tx)
+);
@@ -0,0 +1 @@
+drop table prune_heights;
@@ -0,0 +1,6 @@
+-- blocks below `height` are pruned with `mode`, a prune goes on from there.
+create table prune_heights (
+    mode varchar(16) not null,
+    height bigint not null,
+    primary key (mode)
+);
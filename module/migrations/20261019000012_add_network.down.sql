@@ -0,0 +1,78 @@
+create or replace function claim_tx_hash() returns trigger as $$
+declare
+    prev bigint;
+begin
+    select height into prev from tx_heights where tx_hash = new.tx_hash;
+    if prev is not null and prev <> new.height then
+        delete from transaction where tx_hash = new.tx_hash and height = prev;
+    end if;
+    insert into tx_heights values (new.tx_hash, new.height)
+    on conflict (tx_hash) do update set height = excluded.height;
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace function release_tx_hash() returns trigger as $$
+begin
+    delete from tx_heights where tx_hash = old.tx_hash and height = old.height;
+    return null;
+end;
+$$ language plpgsql;
+
+create or replace function track_block_hash() returns trigger as $$
+begin
+    if tg_op <> 'INSERT' then
+        delete from block_heights where block_hash = old.block_hash and height = old.height;
+    end if;
+    if tg_op <> 'DELETE' then
+        insert into block_heights values (new.block_hash, new.height)
+        on conflict (block_hash) do update set height = excluded.height;
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+-- fails on duplicate keys while the rows of several networks are kept, delete all but one first.
+do $$
+declare
+    t record;
+begin
+    for t in select * from (values
+        ('assets', 'asset, tx, ty'),
+        ('block', 'height'),
+        ('block_generation', 'height, address'),
+        ('block_heights', 'block_hash'),
+        ('claims', 'tx'),
+        ('delegations', 'tx'),
+        ('e2n', 'tx_hash'),
+        ('e2n_last_height', 'tip'),
+        ('evm_addrs', null),
+        ('evm_txs', 'tx'),
+        ('last_height', 'tip'),
+        ('mints', 'tx, idx, kind'),
+        ('n2e', 'tx'),
+        ('native_addrs', null),
+        ('native_txs', 'tx'),
+        ('operations', 'tx, op_index'),
+        ('prune_heights', 'mode'),
+        ('quarantined_txs', 'tx, height'),
+        ('slashing_events', 'height, tx_index, op_index, validator'),
+        ('transaction', 'tx_hash, height'),
+        ('tx_heights', 'tx_hash'),
+        ('tx_participants', 'tx, address, role'),
+        ('tx_types', 'tx'),
+        ('undelegations', 'tx'),
+        ('validator_events', 'tx, op_index, validator'),
+        ('validator_uptime_buckets', 'address, bucket'),
+        ('validator_uptime_daily', 'address, day'),
+        ('validators', 'address')
+    ) as v(name, pkey) loop
+        if t.pkey is not null then
+            execute format('alter table %I drop constraint %I, add primary key (%s)',
+                           t.name, t.name || '_pkey', t.pkey);
+        end if;
+        execute format('alter table %I drop column network', t.name);
+    end loop;
+end $$;
+
+drop function current_network();
@@ -0,0 +1,89 @@
+-- rows are tagged with the network they were scanned from, so mainnet, testnet and devnets
+-- can share a database. the scanner, the prismer and the explorer set `findora.network` on
+-- their connections, e.g. `select set_config('findora.network', 'testnet', false)`, and
+-- only read and write the rows of that network. it is mainnet if unset.
+create function current_network() returns varchar as $$
+    select coalesce(nullif(current_setting('findora.network', true), ''), 'mainnet')::varchar
+$$ language sql stable;
+
+-- the existing rows belong to the network of the migrating connection. the column is added
+-- with a constant default first, which doesn't rewrite the tables.
+do $$
+declare
+    t record;
+begin
+    for t in select * from (values
+        ('assets', 'asset, tx, ty'),
+        ('block', 'height'),
+        ('block_generation', 'height, address'),
+        ('block_heights', 'block_hash'),
+        ('claims', 'tx'),
+        ('delegations', 'tx'),
+        ('e2n', 'tx_hash'),
+        ('e2n_last_height', 'tip'),
+        ('evm_addrs', null),
+        ('evm_txs', 'tx'),
+        ('last_height', 'tip'),
+        ('mints', 'tx, idx, kind'),
+        ('n2e', 'tx'),
+        ('native_addrs', null),
+        ('native_txs', 'tx'),
+        ('operations', 'tx, op_index'),
+        ('prune_heights', 'mode'),
+        ('quarantined_txs', 'tx, height'),
+        ('slashing_events', 'height, tx_index, op_index, validator'),
+        ('transaction', 'tx_hash, height'),
+        ('tx_heights', 'tx_hash'),
+        ('tx_participants', 'tx, address, role'),
+        ('tx_types', 'tx'),
+        ('undelegations', 'tx'),
+        ('validator_events', 'tx, op_index, validator'),
+        ('validator_uptime_buckets', 'address, bucket'),
+        ('validator_uptime_daily', 'address, day'),
+        ('validators', 'address')
+    ) as v(name, pkey) loop
+        execute format('alter table %I add column network varchar(16) not null default %L',
+                       t.name, current_network());
+        execute format('alter table %I alter column network set default current_network()', t.name);
+        if t.pkey is not null then
+            execute format('alter table %I drop constraint %I, add primary key (network, %s)',
+                           t.name, t.name || '_pkey', t.pkey);
+        end if;
+    end loop;
+end $$;
+
+-- a hash is unique within its network.
+create or replace function claim_tx_hash() returns trigger as $$
+declare
+    prev bigint;
+begin
+    select height into prev from tx_heights where network = new.network and tx_hash = new.tx_hash;
+    if prev is not null and prev <> new.height then
+        delete from transaction where network = new.network and tx_hash = new.tx_hash and height = prev;
+    end if;
+    insert into tx_heights values (new.tx_hash, new.height, new.network)
+    on conflict (network, tx_hash) do update set height = excluded.height;
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace function release_tx_hash() returns trigger as $$
+begin
+    delete from tx_heights where network = old.network and tx_hash = old.tx_hash and height = old.height;
+    return null;
+end;
+$$ language plpgsql;
+
+create or replace function track_block_hash() returns trigger as $$
+begin
+    if tg_op <> 'INSERT' then
+        delete from block_heights
+        where network = old.network and block_hash = old.block_hash and height = old.height;
+    end if;
+    if tg_op <> 'DELETE' then
+        insert into block_heights values (new.block_hash, new.height, new.network)
+        on conflict (network, block_hash) do update set height = excluded.height;
+    end if;
+    return null;
+end;
+$$ language plpgsql;
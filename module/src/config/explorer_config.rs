@@ -1,30 +1,64 @@
-use anyhow::Result;
+use crate::network::Network;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::Read;
+use toml::Table;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub server: ServerConfig,
-    pub postgres: PostgresConfig,
+    pub postgres: Option<PostgresConfig>,
+    /// Served instead of `postgres` if set.
+    pub sqlite: Option<SqliteConfig>,
     pub rpc: TendermintConfig,
+    /// Networks served, each under `/api/<name>`, the first one also under `/api`.
+    /// Mainnet over `rpc` if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<NetworkConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ServerConfig {
     pub addr: String,
     pub port: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PostgresConfig {
     pub account: String,
     pub password: String,
     pub addr: String,
     pub database: String,
+    /// Connections of the pool of each network.
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+    /// Seconds to wait for a connection of the pool.
+    #[serde(default = "default_acquire_timeout")]
+    pub acquire_timeout: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+fn default_max_connections() -> u32 {
+    20
+}
+
+fn default_acquire_timeout() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SqliteConfig {
+    /// Database file, created if missing.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetworkConfig {
+    /// A preset or a devnet, see `module::network`.
+    pub name: String,
+    /// Tendermint rpc, the preset's if unset.
+    pub tendermint: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TendermintConfig {
     pub platform: String,
     pub platform_server: String,
@@ -32,13 +66,59 @@ pub struct TendermintConfig {
 }
 
 impl Config {
+    /// The file at `path`, overridden by the env.
     pub fn new(path: &str) -> Result<Self> {
-        let mut file = File::open(path)?;
+        let config: Config = super::load(Table::new(), Some(path), &[])?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.postgres.is_none() && self.sqlite.is_none() {
+            bail!("either `postgres` or `sqlite` must be configured.");
+        }
+        let networks = self.networks()?;
+        if self.sqlite.is_some() && networks.len() > 1 {
+            bail!("a SQLite database holds a single network, serve more from Postgres.");
+        }
+        for (i, n) in networks.iter().enumerate() {
+            if networks[..i].iter().any(|m| m.name == n.name) {
+                bail!("network `{}` is listed twice.", n.name);
+            }
+        }
+        Ok(())
+    }
 
-        let mut str = String::new();
-        file.read_to_string(&mut str)?;
+    /// The networks served, mainnet over `rpc` if none is listed.
+    pub fn networks(&self) -> Result<Vec<Network>> {
+        if self.networks.is_empty() {
+            return Ok(vec![Network {
+                tendermint: self.rpc.tendermint.clone(),
+                ..Network::mainnet()
+            }]);
+        }
 
-        let config: Config = toml::from_str(&str)?;
-        Ok(config)
+        let mut networks = vec![];
+        for n in &self.networks {
+            let mut network = Network::from_name(&n.name).map_err(anyhow::Error::msg)?;
+            if let Some(tendermint) = &n.tendermint {
+                network.tendermint = tendermint.clone();
+            }
+            networks.push(network);
+        }
+        Ok(networks)
+    }
+
+    /// The config without the database password, to be printed.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(postgres) = &mut config.postgres {
+            postgres.password = "***".to_string();
+        }
+        config
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
     }
 }
@@ -2,6 +2,9 @@ extern crate core;
 
 pub mod config;
 pub mod display;
+pub mod network;
 pub mod rpc;
 pub mod schema;
+pub mod source;
+pub mod storage;
 pub mod utils;
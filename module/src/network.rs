@@ -0,0 +1,100 @@
+//! The Findora networks one database can index.
+//!
+//! Every row is tagged with the name of the network it was scanned from, see the
+//! `add_network` migration. The presets hold what differs between the networks.
+use serde::{Deserialize, Serialize};
+
+/// Code of FRA, the native asset, on every network.
+pub const FRA_ASSET: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+/// Topic of `DepositAsset(bytes32,bytes,uint256,uint8,uint256)`, emitted by the prism bridge
+/// for an evm to native transfer.
+pub const DEPOSIT_ASSET_EVENT_HASH: &str =
+    "0xaae31ca36c1ef3c9daa9d5efff8c47306109c0f7cf997e61d766ba15d27e071e";
+
+pub const MAINNET: &str = "mainnet";
+pub const TESTNET: &str = "testnet";
+pub const DEVNET: &str = "devnet";
+
+/// Longest network name, the width of the `network` columns.
+pub const MAX_NAME_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub name: String,
+    /// Tendermint rpc of a public node.
+    pub tendermint: String,
+    /// Height the prismer starts from, the prism bridge isn't deployed below it.
+    pub prism_start: i64,
+}
+
+impl Network {
+    pub fn mainnet() -> Self {
+        Network {
+            name: MAINNET.to_string(),
+            tendermint: "https://prod-mainnet.prod.findora.org:26657/".to_string(),
+            prism_start: 4004430,
+        }
+    }
+
+    pub fn testnet() -> Self {
+        Network {
+            name: TESTNET.to_string(),
+            tendermint: "https://prod-testnet.prod.findora.org:26657/".to_string(),
+            prism_start: 1,
+        }
+    }
+
+    /// A local node.
+    pub fn devnet(name: &str) -> Self {
+        Network {
+            name: name.to_string(),
+            tendermint: "http://127.0.0.1:26657/".to_string(),
+            prism_start: 1,
+        }
+    }
+
+    /// The preset named `name`, any other valid name is a devnet.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(format!(
+                "Invalid network `{name}`, expected up to {MAX_NAME_LEN} of [a-z0-9_-]."
+            ));
+        }
+
+        Ok(match name {
+            MAINNET => Network::mainnet(),
+            TESTNET => Network::testnet(),
+            _ => Network::devnet(name),
+        })
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network::mainnet()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Network::from_name("mainnet").unwrap(), Network::mainnet());
+        assert_eq!(Network::from_name("testnet").unwrap().prism_start, 1);
+        let qa = Network::from_name("qa-02").unwrap();
+        assert_eq!(qa.name, "qa-02");
+        assert_eq!(qa.tendermint, Network::devnet(DEVNET).tendermint);
+        assert!(Network::from_name("").is_err());
+        assert!(Network::from_name("Mainnet").is_err());
+        assert!(Network::from_name("a/b").is_err());
+        assert!(Network::from_name("a-very-long-devnet").is_err());
+    }
+}
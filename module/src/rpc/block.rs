@@ -1,5 +1,6 @@
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Object)]
 pub struct Parts {
     pub total: String,
@@ -38,6 +39,8 @@ pub struct BlockHeader {
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Object)]
 pub struct Signature {
+    /// 1 absent, 2 for the block, 3 for nil.
+    pub block_id_flag: Option<i64>,
     pub validator_address: Option<String>,
     pub timestamp: Option<String>,
     pub signature: Option<String>,
@@ -56,10 +59,18 @@ pub struct Data {
     pub txs: Option<Vec<String>>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Object)]
+pub struct EvidenceData {
+    pub evidence: Option<Vec<Value>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default, Object)]
 pub struct Block {
     pub header: BlockHeader,
     pub data: Data,
+    #[serde(default)]
+    pub evidence: EvidenceData,
+    #[serde(default)]
     pub last_commit: LastCommit,
 }
 
@@ -69,6 +80,21 @@ pub struct BlockRPC {
     pub block: Block,
 }
 
+/// The `/commit` of a height: its header and the precommits for it, the `last_commit` of the
+/// next block once there is one.
+#[derive(Serialize, Deserialize, Debug, Clone, Object)]
+pub struct SignedHeader {
+    pub header: BlockHeader,
+    pub commit: LastCommit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Object)]
+pub struct CommitRPC {
+    pub signed_header: SignedHeader,
+    /// False for the commit of the tip, seen by the node but not yet in a block.
+    pub canonical: bool,
+}
+
 #[derive(Deserialize, Debug, Object)]
 pub struct BlockSizeRPC {
     pub last_height: String,
@@ -1,12 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct PubKey {
     pub r#type: String,
     pub value: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Validator {
     pub address: String,
     pub pub_key: PubKey,
@@ -14,7 +14,7 @@ pub struct Validator {
     pub proposer_priority: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ValidatorsRPC {
     pub validators: Vec<Validator>,
     pub count: String,
@@ -141,6 +141,12 @@ pub struct Block {
     pub v2_delegation_txs: Vec<V2DelegationTx>,
     pub v2_claim_txs: Vec<V2ClaimTx>,
     pub v2_asset_txs: Vec<V2AssetTx>,
+    pub v2_validator_events: Vec<V2ValidatorEvent>,
+    pub v2_mint_txs: Vec<V2MintTx>,
+    pub v2_slashing_events: Vec<V2SlashingEvent>,
+    pub v2_operations: Vec<V2Operation>,
+    pub tx_participants: Vec<TxParticipant>,
+    pub quarantined_txs: Vec<QuarantinedTx>,
     pub block_data: Value,
 }
 
@@ -253,6 +259,90 @@ pub struct V2ConvertAccountTx {
     pub content: Value,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct V2MintTx {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub idx: i32,
+    pub receiver: String,
+    pub amount: i64,
+    pub kind: String,
+    pub height: i64,
+    pub timestamp: i64,
+    pub content: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuarantinedTx {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub height: i64,
+    pub tx_index: i64,
+    pub origin: String,
+    pub stage: String,
+    pub error: String,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct TxParticipant {
+    pub tx_hash: String,
+    pub address: String,
+    pub role: String,
+    pub height: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct V2Operation {
+    pub tx_hash: String,
+    pub op_index: i32,
+    pub kind: String,
+    pub signer: String,
+    pub counterparty: String,
+    pub asset: Option<String>,
+    pub amount: Option<String>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub content: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct V2SlashingEvent {
+    pub block_hash: String,
+    pub tx_hash: Option<String>,
+    /// The index of the tx in the block, -1 for evidence.
+    pub tx_index: i64,
+    /// The index of the op in the tx, or of the evidence in the block.
+    pub op_index: i32,
+    pub validator: String,
+    pub kind: String,
+    pub source: String,
+    pub evidence_height: Option<i64>,
+    pub power: Option<i64>,
+    pub penalty_rate: Option<Value>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub content: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct V2ValidatorEvent {
+    pub tx_hash: String,
+    /// The index of the op in the tx.
+    pub op_index: i32,
+    pub block_hash: String,
+    pub validator: String,
+    pub staker: String,
+    pub kind: String,
+    pub commission_rate: Option<Value>,
+    pub memo: Option<Value>,
+    pub new_staker: Option<String>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub content: Value,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct V2EvmTx {
     pub tx_hash: String,
@@ -271,6 +361,7 @@ pub struct Transaction {
     pub tx_hash: String,
     pub block_hash: String,
     pub height: i64,
+    pub tx_index: i64,
     pub timestamp: i64,
     pub ty: i32,
     pub ty_sub: i32,
@@ -316,6 +407,7 @@ pub struct TransactionResponse {
     pub evm_tx_hash: String,
     pub block_hash: String,
     pub height: i64,
+    pub tx_index: i64,
     pub timestamp: i64,
     pub ty: i32,
     pub code: i64,
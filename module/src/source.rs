@@ -0,0 +1,511 @@
+//! Where blocks are ingested from: a live tendermint node or JSON documents captured on disk.
+use crate::rpc::block::{BlockRPC, BlockSizeRPC, CommitRPC};
+use crate::rpc::tx::Transaction;
+use crate::rpc::validator::ValidatorsRPC;
+use crate::rpc::{JsonRpcResponse, TdRpcResult};
+use crate::schema::DelegationInfo;
+use async_trait::async_trait;
+use base64::{engine, Engine};
+use log::debug;
+use reqwest::{Client, ClientBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sha2::Digest;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum SourceError {
+    /// No such block or tx, as a node answers for heights beyond its tip.
+    NotFound,
+    Backend(String),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::NotFound => write!(f, "not found"),
+            SourceError::Backend(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+macro_rules! backend_error {
+    ($($t:ty),*) => {
+        $(impl From<$t> for SourceError {
+            fn from(e: $t) -> Self {
+                SourceError::Backend(e.to_string())
+            }
+        })*
+    };
+}
+
+backend_error!(
+    reqwest::Error,
+    serde_json::Error,
+    std::io::Error,
+    std::num::ParseIntError,
+    base64::DecodeError,
+    String,
+    &str
+);
+
+pub type SourceResult<T> = Result<T, SourceError>;
+
+#[async_trait]
+pub trait BlockSource: Send + Sync {
+    async fn load_block(&self, height: i64) -> SourceResult<BlockRPC>;
+
+    async fn get_block_size(&self, height: i64) -> SourceResult<BlockSizeRPC>;
+
+    async fn load_validators(&self, height: i64) -> SourceResult<ValidatorsRPC>;
+
+    /// Header and precommits of `height`.
+    async fn load_commit(&self, height: i64) -> SourceResult<CommitRPC>;
+
+    async fn load_transaction(&self, hash: &str) -> SourceResult<Transaction>;
+
+    /// Height of the latest block the source has.
+    async fn latest_height(&self) -> SourceResult<i64>;
+}
+
+/// `file://` urls are read with [`FsSource`], others are tendermint rpc.
+pub fn open(url: Url, timeout: Duration) -> Box<dyn BlockSource> {
+    if url.scheme() == "file" {
+        let dir = url
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(url.path()));
+        Box::new(FsSource::new(dir))
+    } else {
+        Box::new(TendermintRPC::new(timeout, url))
+    }
+}
+
+pub struct TendermintRPC {
+    pub rpc: Url,
+    pub client: Client,
+}
+
+impl TendermintRPC {
+    pub fn new(timeout: Duration, rpc: Url) -> Self {
+        let client = ClientBuilder::new().timeout(timeout).build().unwrap();
+        TendermintRPC { client, rpc }
+    }
+
+    fn endpoint(&self, path: &str) -> SourceResult<Url> {
+        self.rpc
+            .join(path)
+            .map_err(|e| format!("invalid rpc url {}: {e}", self.rpc).into())
+    }
+
+    pub async fn load_block(&self, height: i64) -> SourceResult<BlockRPC> {
+        let mut url = self.endpoint("block")?;
+        url.set_query(Some(&format!("height={height}")));
+        debug!("{}", url.as_str());
+        let r: BlockRPC = self.client_get(url).await?;
+        Ok(r)
+    }
+
+    pub async fn get_block_size(&self, height: i64) -> SourceResult<BlockSizeRPC> {
+        let mut url = self.endpoint("blockchain")?;
+        url.set_query(Some(&format!("minHeight={height}&maxHeight={height}")));
+        debug!("{}", url.as_str());
+        let r: BlockSizeRPC = self.client_get(url).await?;
+        Ok(r)
+    }
+
+    fn validator_url(&self, height: i64, page: i32, per_page: i32) -> SourceResult<Url> {
+        let mut url = self.endpoint("validators")?;
+        url.set_query(Some(&format!(
+            "height={height}&per_page={per_page}&page={page}"
+        )));
+        Ok(url)
+    }
+
+    pub async fn load_validators(&self, height: i64) -> SourceResult<ValidatorsRPC> {
+        let mut page = 1;
+        let per_page = 100;
+
+        let url = self.validator_url(height, page, per_page)?;
+        let mut r: ValidatorsRPC = self.client_get(url).await?;
+        let mut count = r.count.parse::<i32>()?;
+        let mut total = r.total.parse::<i32>()?;
+
+        while total > count {
+            total -= count;
+            page += 1;
+            let tmp_url = self.validator_url(height, page, per_page)?;
+            let mut tmp_res: ValidatorsRPC = self.client_get(tmp_url).await?;
+            count = tmp_res.count.parse::<i32>()?;
+            if count == 0 {
+                return Err(format!("no validators on page {page} at height {height}").into());
+            }
+            r.validators.append(&mut tmp_res.validators)
+        }
+
+        Ok(r)
+    }
+
+    pub async fn load_commit(&self, height: i64) -> SourceResult<CommitRPC> {
+        let mut url = self.endpoint("commit")?;
+        url.set_query(Some(&format!("height={height}")));
+        debug!("{}", url.as_str());
+        let r: CommitRPC = self.client_get(url).await?;
+        Ok(r)
+    }
+
+    /// Height of the latest block known to the node.
+    pub async fn latest_height(&self) -> SourceResult<i64> {
+        let url = self.endpoint("status")?;
+        let r: Value = self.client_get(url).await?;
+        let h = r["sync_info"]["latest_block_height"]
+            .as_str()
+            .ok_or("missing latest_block_height")?
+            .parse()?;
+        Ok(h)
+    }
+
+    pub async fn load_transaction(&self, hash: &str) -> SourceResult<Transaction> {
+        let mut url = self.endpoint("tx")?;
+        url.set_query(Some(&format!("hash=0x{hash}")));
+
+        let r: Transaction = self.client_get(url).await?;
+        Ok(r)
+    }
+
+    pub async fn load_delegations(&self) -> SourceResult<(i64, DelegationInfo)> {
+        let mut url = self.endpoint("abci_query")?;
+        let mut queries = url.query_pairs_mut();
+        queries.append_pair("path", "\"/delegations\"");
+        queries.append_pair("data", "");
+        drop(queries);
+
+        let resp = self.client.get(url).send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(resp.text().await?.into());
+        }
+
+        let result: JsonRpcResponse<TdRpcResult> = resp.json().await?;
+
+        let response = result.result.response;
+
+        if response.code != 0 {
+            return Err(response.info.into());
+        }
+
+        let h = response.height.parse()?;
+
+        //let data = response.info.replace("\\\"", "\"");
+
+        let staking: DelegationInfo = serde_json::from_str(&response.info)?;
+
+        Ok((h, staking))
+    }
+
+    async fn client_get<T: DeserializeOwned>(&self, url: Url) -> SourceResult<T> {
+        let resp = self.client.get(url).send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let resp_text = resp.text().await?;
+            if resp_text.contains("less than or equal to") {
+                return Err(SourceError::NotFound);
+            }
+            return Err(resp_text.into());
+        }
+
+        let bytes = resp.bytes().await?;
+        if let Ok(r) = serde_json::from_slice::<'_, JsonRpcResponse<T>>(&bytes) {
+            Ok(r.result)
+        } else {
+            debug!("{}", String::from_utf8_lossy(&bytes));
+            Err(SourceError::NotFound)
+        }
+    }
+}
+
+#[async_trait]
+impl BlockSource for TendermintRPC {
+    async fn load_block(&self, height: i64) -> SourceResult<BlockRPC> {
+        TendermintRPC::load_block(self, height).await
+    }
+
+    async fn get_block_size(&self, height: i64) -> SourceResult<BlockSizeRPC> {
+        TendermintRPC::get_block_size(self, height).await
+    }
+
+    async fn load_validators(&self, height: i64) -> SourceResult<ValidatorsRPC> {
+        TendermintRPC::load_validators(self, height).await
+    }
+
+    async fn load_commit(&self, height: i64) -> SourceResult<CommitRPC> {
+        TendermintRPC::load_commit(self, height).await
+    }
+
+    async fn load_transaction(&self, hash: &str) -> SourceResult<Transaction> {
+        TendermintRPC::load_transaction(self, hash).await
+    }
+
+    async fn latest_height(&self) -> SourceResult<i64> {
+        TendermintRPC::latest_height(self).await
+    }
+}
+
+/// Tendermint rpc responses saved under a directory, one file per document:
+///
+/// - `block/<height>.json`
+/// - `validators/<height>.json`, all validators in one document.
+/// - `commit/<height>.json`, optional, the `last_commit` of the next block otherwise.
+/// - `blockchain/<height>.json`, optional, the size is that of the block document otherwise.
+/// - `tx/<hash>.json`, or `block_results/<height>.json` for all txs of a block.
+///
+/// Files hold either the whole json rpc response or its `result`. A missing block is
+/// [`SourceError::NotFound`], as a node answers for heights beyond its tip.
+pub struct FsSource {
+    dir: PathBuf,
+    /// Tx hash to (height, index) of the blocks indexed so far, to look txs up in `block_results`.
+    txs: Mutex<HashMap<String, (i64, usize)>>,
+    /// Heights whose txs are in `txs`.
+    indexed: Mutex<HashSet<i64>>,
+}
+
+impl FsSource {
+    pub fn new(dir: PathBuf) -> Self {
+        FsSource {
+            dir,
+            txs: Mutex::new(HashMap::new()),
+            indexed: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Heights of the block files.
+    fn heights(&self) -> SourceResult<Vec<i64>> {
+        let mut heights = vec![];
+        for entry in fs::read_dir(self.dir.join("block"))? {
+            let path = entry?.path();
+            if let Some(h) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<i64>().ok())
+            {
+                heights.push(h);
+            }
+        }
+        Ok(heights)
+    }
+
+    fn index_block(&self, height: i64, block: &BlockRPC) -> SourceResult<()> {
+        let mut txs = self.txs.lock().unwrap();
+        for (index, tx) in block.block.data.txs.iter().flatten().enumerate() {
+            let bytes = engine::general_purpose::STANDARD.decode(tx)?;
+            txs.insert(hex::encode(sha2::Sha256::digest(bytes)), (height, index));
+        }
+        self.indexed.lock().unwrap().insert(height);
+        Ok(())
+    }
+
+    /// Where the tx `hash` is, the blocks not indexed yet are read to find it.
+    fn locate_tx(&self, hash: &str) -> SourceResult<(i64, usize)> {
+        if let Some(at) = self.txs.lock().unwrap().get(hash) {
+            return Ok(*at);
+        }
+        for height in self.heights()? {
+            if self.indexed.lock().unwrap().contains(&height) {
+                continue;
+            }
+            let block: BlockRPC = self.read("block", &height.to_string())?;
+            self.index_block(height, &block)?;
+        }
+        self.txs
+            .lock()
+            .unwrap()
+            .get(hash)
+            .copied()
+            .ok_or(SourceError::NotFound)
+    }
+
+    fn read<T: DeserializeOwned>(&self, kind: &str, name: &str) -> SourceResult<T> {
+        Ok(serde_json::from_value(self.read_value(kind, name)?)?)
+    }
+
+    fn read_value(&self, kind: &str, name: &str) -> SourceResult<Value> {
+        let path = self.dir.join(kind).join(format!("{name}.json"));
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(SourceError::NotFound)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut doc: Value = serde_json::from_slice(&bytes)?;
+        if doc.get("jsonrpc").is_some() {
+            doc = doc["result"].take();
+        }
+        Ok(doc)
+    }
+}
+
+#[async_trait]
+impl BlockSource for FsSource {
+    async fn load_block(&self, height: i64) -> SourceResult<BlockRPC> {
+        let block: BlockRPC = self.read("block", &height.to_string())?;
+        self.index_block(height, &block)?;
+        Ok(block)
+    }
+
+    async fn get_block_size(&self, height: i64) -> SourceResult<BlockSizeRPC> {
+        match self.read("blockchain", &height.to_string()) {
+            Err(SourceError::NotFound) => {}
+            r => return r,
+        }
+        let block = self.read_value("block", &height.to_string())?;
+        let size = serde_json::to_vec(&block["block"])?.len();
+        let num_txs = block["block"]["data"]["txs"]
+            .as_array()
+            .map(|txs| txs.len())
+            .unwrap_or_default();
+        Ok(serde_json::from_value(json!({
+            "last_height": height.to_string(),
+            "block_metas": [{
+                "block_id": block["block_id"],
+                "block_size": size.to_string(),
+                "header": block["block"]["header"],
+                "num_txs": num_txs.to_string(),
+            }],
+        }))?)
+    }
+
+    async fn load_validators(&self, height: i64) -> SourceResult<ValidatorsRPC> {
+        self.read("validators", &height.to_string())
+    }
+
+    async fn load_commit(&self, height: i64) -> SourceResult<CommitRPC> {
+        match self.read("commit", &height.to_string()) {
+            Err(SourceError::NotFound) => {}
+            r => return r,
+        }
+        let block = self.read_value("block", &height.to_string())?;
+        let mut next = self.read_value("block", &(height + 1).to_string())?;
+        Ok(serde_json::from_value(json!({
+            "signed_header": {
+                "header": block["block"]["header"],
+                "commit": next["block"]["last_commit"].take(),
+            },
+            "canonical": true,
+        }))?)
+    }
+
+    async fn load_transaction(&self, hash: &str) -> SourceResult<Transaction> {
+        let hash = hash.to_lowercase();
+        match self.read("tx", &hash) {
+            Err(SourceError::NotFound) => {}
+            r => return r,
+        }
+        let (height, index) = self.locate_tx(&hash)?;
+        let block = self.read_value("block", &height.to_string())?;
+        let mut results = self.read_value("block_results", &height.to_string())?;
+        Ok(Transaction {
+            hash: hash.to_uppercase(),
+            height: height.to_string(),
+            index: index as i64,
+            tx_result: serde_json::from_value(results["txs_results"][index].take())?,
+            tx: block["block"]["data"]["txs"][index]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    async fn latest_height(&self) -> SourceResult<i64> {
+        self.heights()?
+            .into_iter()
+            .max()
+            .ok_or(SourceError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fs_source() {
+        let dir = std::env::temp_dir().join(format!("fs-source-{}", std::process::id()));
+        for kind in ["block", "block_results", "validators"] {
+            fs::create_dir_all(dir.join(kind)).unwrap();
+        }
+        let tx = engine::general_purpose::STANDARD.encode(b"tx");
+        let tx_hash = hex::encode(sha2::Sha256::digest(b"tx"));
+        let id = json!({"hash": "AB", "parts": {"total": "1", "hash": "CD"}});
+        let header = json!({
+            "version": {"block": "10", "app": "0"},
+            "chain_id": "test",
+            "height": "3",
+            "time": "2022-04-15T05:20:00.123456Z",
+            "last_block_id": id,
+            "last_commit_hash": "",
+            "data_hash": "",
+            "validators_hash": "",
+            "next_validators_hash": "",
+            "consensus_hash": "",
+            "app_hash": "",
+            "last_results_hash": "",
+            "evidence_hash": "",
+            "proposer_address": "",
+        });
+        let block = json!({
+            "block_id": id,
+            "block": {
+                "header": header,
+                "data": {"txs": [tx]},
+                "evidence": {"evidence": []},
+                "last_commit": {"height": "2", "round": "0", "block_id": id, "signatures": []},
+            },
+        });
+        let results = json!({"height": "3", "txs_results": [{
+            "code": 0, "data": null, "log": "[]", "info": "", "gasWanted": "0",
+            "gasUsed": "0", "events": [], "codespace": "",
+        }]});
+        let validators = json!({"jsonrpc": "2.0", "id": -1, "result": {
+            "block_height": "3", "validators": [], "count": "0", "total": "0",
+        }});
+        fs::write(dir.join("block/3.json"), block.to_string()).unwrap();
+        fs::write(dir.join("block_results/3.json"), results.to_string()).unwrap();
+        fs::write(dir.join("validators/3.json"), validators.to_string()).unwrap();
+
+        // a tx is found without its block loaded first.
+        let source = FsSource::new(dir.clone());
+        let loaded = source.load_transaction(&tx_hash).await.unwrap();
+        assert_eq!((loaded.height.as_str(), loaded.index), ("3", 0));
+        assert!(matches!(
+            source.load_transaction(&"00".repeat(32)).await,
+            Err(SourceError::NotFound)
+        ));
+
+        let source = FsSource::new(dir.clone());
+        assert_eq!(source.latest_height().await.unwrap(), 3);
+        assert!(matches!(
+            source.load_block(4).await,
+            Err(SourceError::NotFound)
+        ));
+        let block = source.load_block(3).await.unwrap();
+        assert_eq!(block.block.header.height, "3");
+        let size = source.get_block_size(3).await.unwrap();
+        assert_eq!(size.block_metas.unwrap()[0].num_txs, "1");
+        assert_eq!(source.load_validators(3).await.unwrap().total, "0");
+        assert!(matches!(
+            source.load_commit(3).await,
+            Err(SourceError::NotFound)
+        ));
+        let loaded = source.load_transaction(&tx_hash).await.unwrap();
+        assert_eq!((loaded.index, loaded.tx), (0, tx));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
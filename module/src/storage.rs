@@ -0,0 +1,335 @@
+//! Storage the indexer writes to and the explorer reads from.
+//!
+//! The `storage` crate implements it for Postgres, which backs production deployments, and
+//! for SQLite, which backs tests and small deployments. It covers every record a scan writes
+//! and every route of the explorer reads: blocks, txs, operations, the staking, validator,
+//! uptime, mint, slashing and asset records and the chain statistics.
+use crate::schema::{
+    Block, PrismTxResult, Transaction, V2AssetTx, V2ClaimTx, V2ConvertAccountTx, V2DelegationTx,
+    V2MintTx, V2Operation, V2UndelegationTx, V2ValidatorEvent,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound,
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "not found"),
+            StorageError::Backend(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// A row of the `block` table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockRecord {
+    pub block_hash: String,
+    pub height: i64,
+    pub size: i64,
+    pub tx_count: i64,
+    pub time: NaiveDateTime,
+    pub app_hash: String,
+    pub proposer: String,
+    pub block_data: Value,
+}
+
+/// Conditions of a tx listing, `None` matches everything.
+#[derive(Debug, Default, Clone)]
+pub struct TxFilter {
+    /// Txs sent by this address.
+    pub from: Option<String>,
+    /// Txs received by this address.
+    pub to: Option<String>,
+    /// Txs this address took part in.
+    pub address: Option<String>,
+    pub ty: Option<i32>,
+    pub height: Option<i64>,
+    /// Unix time bounds, both included.
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+/// A row of `slashing_events`, the penalty amount is the punished power times the rate.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlashingRecord {
+    pub block_hash: String,
+    pub tx_hash: Option<String>,
+    pub validator: String,
+    pub kind: String,
+    pub source: String,
+    pub evidence_height: Option<i64>,
+    pub power: Option<i64>,
+    pub penalty_rate: Option<Value>,
+    pub penalty_amount: Option<i64>,
+    pub height: i64,
+    pub timestamp: i64,
+    pub content: Value,
+}
+
+/// What is known of a validator: its key and the metadata of its latest event carrying some.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidatorRecord {
+    pub address: String,
+    pub pubkey: Option<String>,
+    /// The staker the metadata was set by, or the one that replaced it since.
+    pub staker: Option<String>,
+    pub commission_rate: Option<Value>,
+    pub memo: Option<Value>,
+    /// Unix time of the metadata.
+    pub updated_at: Option<i64>,
+}
+
+/// The counters of a validator over a bucket of `UPTIME_BUCKET` heights.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UptimeBucket {
+    pub address: String,
+    pub first_height: i64,
+    pub last_height: i64,
+    pub signed: i64,
+    pub missed: i64,
+    pub proposed: i64,
+    /// Missed runs starting at the first height and ending at the last one.
+    pub leading_missed: i64,
+    pub trailing_missed: i64,
+    pub longest_missed: i64,
+}
+
+/// The counters of a validator over a day, `YYYY-MM-DD` in UTC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyUptime {
+    pub day: String,
+    pub signed: i64,
+    pub missed: i64,
+    pub proposed: i64,
+}
+
+/// Minted over a day, `YYYY-MM-DD` in UTC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyMint {
+    pub date: String,
+    pub amount: i64,
+    pub count: i64,
+}
+
+/// Tx counts by kind.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TxDistribution {
+    pub native: i64,
+    /// The native txs hiding their asset type or amount.
+    pub privacy: i64,
+    pub evm: i64,
+    pub n2e: i64,
+    pub e2n: i64,
+}
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // writes, by the scanner and the prismer.
+
+    /// Save a block and the records derived from it, replacing what was saved at its height.
+    async fn save_block(&self, block: Block) -> StorageResult<()>;
+
+    /// Save several blocks at once, either all of them are saved or none is.
+    async fn save_blocks(&self, blocks: &[Block]) -> StorageResult<()>;
+
+    async fn save_last_height(&self, height: i64) -> StorageResult<()>;
+
+    /// `NotFound` until the first block is saved.
+    async fn load_last_height(&self) -> StorageResult<i64>;
+
+    /// Heights holding quarantined txs, ascending.
+    async fn load_quarantined_heights(&self) -> StorageResult<Vec<i64>>;
+
+    /// Validators of the stakers by staker address, as the blocks below `height` registered,
+    /// updated or replaced them.
+    async fn stakers(&self, height: i64) -> StorageResult<HashMap<String, String>>;
+
+    /// Save the evm to native transfers found by the prismer.
+    async fn save_prism_txs(&self, txs: Vec<PrismTxResult>) -> StorageResult<()>;
+
+    async fn save_prism_last_height(&self, height: i64) -> StorageResult<()>;
+
+    async fn load_prism_last_height(&self) -> StorageResult<i64>;
+
+    // reads, by the explorer.
+
+    async fn block_by_height(&self, height: i64) -> StorageResult<BlockRecord>;
+
+    /// `hash` is matched as saved, upper case.
+    async fn block_by_hash(&self, hash: &str) -> StorageResult<BlockRecord>;
+
+    /// The latest blocks first, along with the highest height.
+    async fn blocks(&self, limit: i64, offset: i64) -> StorageResult<(i64, Vec<BlockRecord>)>;
+
+    /// Origins of the txs at `height`, the quarantined ones included, in block order.
+    async fn tx_origins(&self, height: i64) -> StorageResult<Vec<String>>;
+
+    /// Signatures of the last commit of the block at `height` by validator address, the ones
+    /// a dropped block is rebuilt with.
+    async fn commit_signatures(&self, height: i64) -> StorageResult<Vec<(String, String)>>;
+
+    async fn tx_by_hash(&self, hash: &str) -> StorageResult<Transaction>;
+
+    /// Txs matching `filter` along with their count. The txs of a single height are listed
+    /// in block order, the latest first otherwise.
+    async fn txs(
+        &self,
+        filter: &TxFilter,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<Transaction>)>;
+
+    /// Operations of a tx in op order, empty for an unknown tx.
+    async fn tx_operations(&self, hash: &str) -> StorageResult<Vec<V2Operation>>;
+
+    async fn prism_tx_by_hash(&self, hash: &str) -> StorageResult<PrismTxResult>;
+
+    /// Evm to native transfers the latest first, along with their count.
+    async fn prism_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<PrismTxResult>)>;
+
+    /// Native to evm transfers the latest first, along with their count.
+    async fn n2e_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ConvertAccountTx>)>;
+
+    async fn n2e_tx_by_hash(&self, hash: &str) -> StorageResult<V2ConvertAccountTx>;
+
+    /// Claims the latest first, along with their count.
+    async fn claims(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ClaimTx>)>;
+
+    async fn claim_by_hash(&self, hash: &str) -> StorageResult<V2ClaimTx>;
+
+    /// Delegations the latest first, along with their count.
+    async fn delegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2DelegationTx>)>;
+
+    async fn delegation_by_hash(&self, hash: &str) -> StorageResult<V2DelegationTx>;
+
+    /// Undelegations the latest first, along with their count.
+    async fn undelegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2UndelegationTx>)>;
+
+    async fn undelegation_by_hash(&self, hash: &str) -> StorageResult<V2UndelegationTx>;
+
+    /// Definitions and issuances of assets the latest first, along with their count.
+    async fn assets(
+        &self,
+        asset: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2AssetTx>)>;
+
+    /// Mints the latest first, in tx order within a block, along with their count.
+    async fn mints(
+        &self,
+        receiver: Option<&str>,
+        kind: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2MintTx>)>;
+
+    /// Minted within the unix time bounds, both included, and by day the latest first.
+    async fn daily_mints(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, Vec<DailyMint>)>;
+
+    /// `NotFound` when the address has neither a key nor metadata.
+    async fn validator(&self, address: &str) -> StorageResult<ValidatorRecord>;
+
+    /// Events of a validator the latest first, along with their count.
+    async fn validator_events(
+        &self,
+        address: &str,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ValidatorEvent>)>;
+
+    /// Slashing events the latest first, along with their count. The events of a validator
+    /// include the governance punishments of its stakers.
+    async fn slashing_events(
+        &self,
+        validator: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<SlashingRecord>)>;
+
+    /// Uptime buckets from `start` on of the validators at `addresses`, by address then height.
+    async fn uptime_buckets(
+        &self,
+        addresses: &[String],
+        start: i64,
+    ) -> StorageResult<Vec<UptimeBucket>>;
+
+    /// Validators having uptime buckets from `start` on in address order, along with their
+    /// count.
+    async fn uptime_validators(
+        &self,
+        start: i64,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<String>)>;
+
+    /// The last `days` days of a validator, the latest first.
+    async fn daily_uptime(&self, address: &str, days: i64) -> StorageResult<Vec<DailyUptime>>;
+
+    /// Count of the txs matching `filter`.
+    async fn tx_count(&self, filter: &TxFilter) -> StorageResult<i64>;
+
+    async fn tx_distribution(&self) -> StorageResult<TxDistribution>;
+
+    /// Distinct native and evm addresses seen within the unix time bounds, both included.
+    async fn address_counts(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, i64)>;
+
+    // the last answers of the price feeds, served when they can't be reached.
+
+    async fn load_price(&self, name: &str) -> StorageResult<String>;
+
+    async fn save_price(&self, name: &str, price: &str) -> StorageResult<()>;
+
+    async fn load_market(&self, name: &str) -> StorageResult<Value>;
+
+    async fn save_market(&self, name: &str, market: &Value) -> StorageResult<()>;
+}
@@ -0,0 +1,222 @@
+//! Merkle trees of tendermint, RFC 6962 with SHA-256. The `data_hash` of a block header is
+//! the root of its txs, the hash of each tx being a leaf, see `txs_root`.
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+pub fn leaf_hash(leaf: &[u8]) -> Hash {
+    Sha256::new()
+        .chain_update([0u8])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+pub fn inner_hash(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([1u8])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Largest power of 2 less than `n`, `n` > 1.
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// Root of the tree of `items`, the hash of nothing for no item.
+pub fn root<T: AsRef<[u8]>>(items: &[T]) -> Hash {
+    match items.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaf_hash(items[0].as_ref()),
+        n => {
+            let k = split_point(n);
+            inner_hash(&root(&items[..k]), &root(&items[k..]))
+        }
+    }
+}
+
+/// Leaves of the txs of a block, each the SHA-256 of a tx as tendermint's `Txs.Hash`.
+pub fn tx_leaves<T: AsRef<[u8]>>(txs: &[T]) -> Vec<Hash> {
+    txs.iter()
+        .map(|tx| Sha256::digest(tx.as_ref()).into())
+        .collect()
+}
+
+/// `data_hash` of a block holding `txs`, i.e. the root of `tx_leaves`.
+pub fn txs_root<T: AsRef<[u8]>>(txs: &[T]) -> Hash {
+    root(&tx_leaves(txs))
+}
+
+/// Inclusion proof of the leaf at `index` among `total`, as tendermint's `merkle.Proof`:
+/// the `aunts` are the sibling hashes from the leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub total: usize,
+    pub index: usize,
+    pub leaf_hash: Hash,
+    pub aunts: Vec<Hash>,
+}
+
+impl Proof {
+    /// Root the proof leads to, none if the aunts don't fit `total` and `index`.
+    pub fn root(&self) -> Option<Hash> {
+        root_from_aunts(self.index, self.total, self.leaf_hash, &self.aunts)
+    }
+
+    /// Whether `item` is the leaf of the proof and the proof leads to `root`.
+    pub fn verify(&self, root: &Hash, item: &[u8]) -> bool {
+        leaf_hash(item) == self.leaf_hash && self.root().as_ref() == Some(root)
+    }
+}
+
+/// Proof of the item at `index`, none if out of range.
+pub fn proof<T: AsRef<[u8]>>(items: &[T], index: usize) -> Option<Proof> {
+    if index >= items.len() {
+        return None;
+    }
+    let mut aunts = vec![];
+    let leaf_hash = collect_aunts(items, index, &mut aunts);
+    Some(Proof {
+        total: items.len(),
+        index,
+        leaf_hash,
+        aunts,
+    })
+}
+
+/// Pushes the aunts of `index` deepest first and returns its leaf hash.
+fn collect_aunts<T: AsRef<[u8]>>(items: &[T], index: usize, aunts: &mut Vec<Hash>) -> Hash {
+    if items.len() == 1 {
+        return leaf_hash(items[0].as_ref());
+    }
+    let k = split_point(items.len());
+    if index < k {
+        let leaf = collect_aunts(&items[..k], index, aunts);
+        aunts.push(root(&items[k..]));
+        leaf
+    } else {
+        let leaf = collect_aunts(&items[k..], index - k, aunts);
+        aunts.push(root(&items[..k]));
+        leaf
+    }
+}
+
+/// tendermint's `computeHashFromAunts`.
+fn root_from_aunts(index: usize, total: usize, leaf: Hash, aunts: &[Hash]) -> Option<Hash> {
+    if index >= total {
+        return None;
+    }
+    match total {
+        0 => None,
+        1 => aunts.is_empty().then_some(leaf),
+        _ => {
+            let (last, rest) = aunts.split_last()?;
+            let k = split_point(total);
+            if index < k {
+                Some(inner_hash(&root_from_aunts(index, k, leaf, rest)?, last))
+            } else {
+                Some(inner_hash(
+                    last,
+                    &root_from_aunts(index - k, total - k, leaf, rest)?,
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use base64::{engine, Engine};
+
+    #[test]
+    fn test_root() {
+        // vectors of tendermint's `merkle.HashFromByteSlices`.
+        let cases: [(Vec<&[u8]>, &str); 5] = [
+            (
+                vec![],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                vec![&[1, 2, 3]],
+                "054edec1d0211f624fed0cbca9d4f9400b0e491c43742af2c5b0abebf0c990d8",
+            ),
+            (
+                vec![&[]],
+                "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            ),
+            (
+                vec![&[1, 2, 3], &[4, 5, 6]],
+                "82e6cfce00453804379b53962939eaa7906b39904be0813fcadd31b100773c4b",
+            ),
+            (
+                vec![&[1, 2], &[3, 4], &[5, 6], &[7, 8], &[9, 10]],
+                "f326493eceab4f2d9ffbc78c59432a0a005d6ea98392045c74df5d14a113be18",
+            ),
+        ];
+        for (items, expected) in cases {
+            assert_eq!(hex::encode(root(&items)), expected);
+        }
+    }
+
+    #[test]
+    fn test_txs_root() {
+        let tx: &[u8] = b"tx";
+        assert_eq!(txs_root(&[tx]), leaf_hash(&Sha256::digest(tx)));
+        assert_eq!(txs_root::<&[u8]>(&[]), root::<&[u8]>(&[]));
+
+        // block 3 of the mock node, an evm tx and a native one.
+        let block: serde_json::Value =
+            serde_json::from_str(include_str!("../../../mock-rpc/fixtures/block/3.json")).unwrap();
+        let txs: Vec<Vec<u8>> = block["block"]["data"]["txs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tx| {
+                engine::general_purpose::STANDARD
+                    .decode(tx.as_str().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let data_hash = block["block"]["header"]["data_hash"].as_str().unwrap();
+        assert_eq!(hex::encode_upper(txs_root(&txs)), data_hash);
+        assert_ne!(hex::encode_upper(root(&txs)), data_hash);
+    }
+
+    #[test]
+    fn test_proof() {
+        for n in 1..=9u8 {
+            let items: Vec<Vec<u8>> = (0..n).map(|i| vec![i, i + 1]).collect();
+            let root = root(&items);
+            for (i, item) in items.iter().enumerate() {
+                let proof = proof(&items, i).unwrap();
+                assert_eq!((proof.total, proof.index), (items.len(), i));
+                assert!(proof.verify(&root, item));
+                assert!(!proof.verify(&root, &[0xff]));
+
+                let mut forged = proof.clone();
+                if let Some(aunt) = forged.aunts.first_mut() {
+                    aunt[0] ^= 1;
+                    assert!(!forged.verify(&root, item));
+                }
+                let mut moved = proof.clone();
+                moved.index = (i + 1) % items.len();
+                if items.len() > 1 {
+                    assert!(!moved.verify(&root, item));
+                }
+                let mut short = proof;
+                if short.aunts.pop().is_some() {
+                    assert_eq!(short.root(), None);
+                }
+            }
+            assert_eq!(proof(&items, items.len()), None);
+        }
+    }
+}
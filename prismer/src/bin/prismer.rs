@@ -15,5 +15,6 @@ async fn main() -> Result<()> {
         PrismerCmd::Load(load) => load.execute().await,
         PrismerCmd::Scan(batch_scan) => batch_scan.execute().await,
         PrismerCmd::Subscribe(subscribe) => subscribe.run().await,
+        PrismerCmd::Config(config) => config.execute(),
     }
 }
@@ -1,59 +1,60 @@
-use crate::{db, prismer::RangeScanner, rpc::RPCCaller};
+use crate::{prismer::RangeScanner, rpc::RPCCaller};
 use clap::Parser;
+use module::config::scanner_config::Config;
 use reqwest::Url;
-use sqlx::PgPool;
-use std::env;
+use std::sync::Arc;
 use std::time::Duration;
+use storage::Storage;
 
 #[derive(Parser)]
 pub enum PrismerCmd {
     Scan(RangeScan),
     Load(Load),
     Subscribe(Subscribe),
+    #[clap(subcommand)]
+    Config(ConfigCmd),
 }
 use crate::{Error, Result};
 
-const DEFAULT_TIMEOUT_SECS: u64 = 32;
-const DEFAULT_RETIES: usize = 3;
-const DEFAULT_CONCURRENCY: usize = 8;
-
 /// load block at specific height.
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 pub struct Load {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     /// Target block height.
     #[clap(long)]
     height: Option<i64>,
-    ///Rpc timeout with seconds.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
 }
 
 impl Load {
     pub async fn execute(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
+        let (config, rpc, storage) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+        let retries = self.retries.unwrap_or(config.rpc.retries);
 
         let target = if let Some(h) = self.height {
             if h <= 0 {
                 return Err(format!("Invalid height: {h}.").into());
             }
             h
-        } else if let Ok(h) = db::load_last_height(&pool).await {
+        } else if let Ok(h) = storage.load_prism_last_height().await {
             h + 1
         } else {
             1
         };
 
         info!("Got header {}", target);
-        let caller = RPCCaller::new(retries, 1, timeout, rpc, pool);
+        let caller = RPCCaller::new(retries, 1, timeout, rpc, storage)
+            .with_backoff(config.rpc.retry_backoff());
         caller.load_and_save_block(target).await?;
 
         info!("Load block at height {} succeed.", target);
@@ -65,34 +66,37 @@ impl Load {
 #[derive(Parser)]
 #[clap(about, version, author)]
 pub struct RangeScan {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     ///Start height
     #[clap(long)]
     start: u64,
     ///End height, included.
     #[clap(long)]
     end: u64,
-    ///Rpc timeout with seconds, default is 32 seconds.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block, default is 3.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
-    ///How many concurrency would be used to call rpc, default is 8.
+    ///How many concurrency would be used to call rpc, default is `scan.concurrency` of the config.
     #[clap(long)]
     concurrency: Option<usize>,
 }
 
 impl RangeScan {
     pub async fn execute(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
-        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        let (config, rpc, storage) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+        let retries = self.retries.unwrap_or(config.rpc.retries);
+        let concurrency = self.concurrency.unwrap_or(config.scan.concurrency);
 
-        let range_scanner = RangeScanner::new(timeout, rpc, retries, concurrency, pool);
+        let caller = RPCCaller::new(retries, concurrency, timeout, rpc, storage)
+            .with_backoff(config.rpc.retry_backoff());
+        let range_scanner = RangeScanner::from_caller(caller);
 
         if self.start < 1 {
             return Err("`start` must >= 1.".into());
@@ -113,55 +117,56 @@ impl RangeScan {
 #[derive(Parser)]
 #[clap(about, version, author)]
 pub struct Subscribe {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     ///Start height
     #[clap(long)]
     start: Option<i64>,
-    ///Rpc timeout with seconds, default is 10.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block, default is 3.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
     #[clap(long)]
-    ///block generation interval, with seconds.
+    ///block generation interval, with seconds, default is `prism.interval` of the config.
     interval: Option<u64>,
-    ///How many concurrency would be used when scanning, default is 8.
+    ///How many concurrency would be used when scanning, default is `scan.concurrency` of the config.
     #[clap(long)]
     concurrency: Option<usize>,
 }
 
 impl Subscribe {
     pub async fn run(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-
-        let itv = env::var("INTERVAL")
-            .ok()
-            .unwrap_or("10".to_string())
-            .parse::<u64>()?;
-        let interval = Duration::from_secs(itv);
+        let (config, rpc, storage) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+
+        let interval = Duration::from_secs(self.interval.unwrap_or(config.prism.interval));
         info!("interval={:?}", interval);
 
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
+        let retries = self.retries.unwrap_or(config.rpc.retries);
 
         let mut cursor = if let Some(h) = self.start {
             if h <= 0 {
                 return Err(format!("Invalid height: {h}.").into());
             }
             h
-        } else if let Ok(h) = db::load_last_height(&pool).await {
+        } else if let Ok(h) = storage.load_prism_last_height().await {
             h + 1
         } else {
-            let prism_start = env::var("PRISM_START").unwrap_or("4004430".to_string());
-            prism_start.parse::<i64>().unwrap()
+            config
+                .network()
+                .map_err(|e| Error::from(e.to_string()))?
+                .prism_start
         };
 
-        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        let concurrency = self.concurrency.unwrap_or(config.scan.concurrency);
         assert!(concurrency >= 1);
-        let range_scanner = RangeScanner::new(timeout, rpc, retries, concurrency, pool.clone());
+        let caller = RPCCaller::new(retries, concurrency, timeout, rpc, storage.clone())
+            .with_backoff(config.rpc.retry_backoff());
+        let range_scanner = RangeScanner::from_caller(caller);
         let batch_size = 4 * concurrency as i64;
 
         info!("Subscribing start from {}, try fast sync ...", cursor);
@@ -178,7 +183,7 @@ impl Subscribe {
         info!("Fast sync complete.");
         let caller = range_scanner.caller().clone();
         loop {
-            if let Ok(h) = db::load_last_height(&pool).await {
+            if let Ok(h) = storage.load_prism_last_height().await {
                 cursor = h + 1;
             }
             match caller.load_and_save_block(cursor).await {
@@ -196,9 +201,42 @@ impl Subscribe {
     }
 }
 
-async fn prepare(rpc: &str) -> Result<(Url, PgPool)> {
-    let pool = db::connect().await?;
+/// The config, the rpc url and the configured storage.
+async fn prepare(rpc: Option<&str>) -> Result<(Config, Url, Arc<dyn Storage>)> {
+    let config = crate::config()?;
+    let (storage, _) = storage::connect().await?;
+    let rpc = match rpc {
+        Some(rpc) => rpc.to_string(),
+        None => {
+            config
+                .network()
+                .map_err(|e| Error::from(e.to_string()))?
+                .tendermint
+        }
+    };
     let rpc: Url = rpc.parse().map_err(|e| Error::from(format!("{e}")))?;
 
-    Ok((rpc, pool))
+    Ok((config, rpc, storage))
+}
+
+/// Config of the file at `CONFIG_FILE_PATH` and the env.
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCmd {
+    /// Validate the config and print the effective one, without the database password.
+    Check,
+}
+
+impl ConfigCmd {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            ConfigCmd::Check => {
+                let toml = crate::config()?
+                    .redacted()
+                    .to_toml()
+                    .map_err(|e| Error::from(e.to_string()))?;
+                println!("{toml}");
+                Ok(())
+            }
+        }
+    }
 }
@@ -61,6 +61,24 @@ impl From<sqlx::Error> for Error {
     }
 }
 
+impl From<module::storage::StorageError> for Error {
+    fn from(e: module::storage::StorageError) -> Self {
+        match e {
+            module::storage::StorageError::NotFound => Error::NotFound,
+            e => Error::CustomError(e.to_string()),
+        }
+    }
+}
+
+impl From<module::source::SourceError> for Error {
+    fn from(e: module::source::SourceError) -> Self {
+        match e {
+            module::source::SourceError::NotFound => Error::NotFound,
+            e => Error::CustomError(e.to_string()),
+        }
+    }
+}
+
 impl From<String> for Error {
     fn from(e: String) -> Self {
         Error::CustomError(e)
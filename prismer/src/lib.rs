@@ -2,7 +2,6 @@
 extern crate log;
 
 pub mod commands;
-pub mod db;
 pub mod error;
 pub mod prismer;
 pub mod rpc;
@@ -11,6 +10,12 @@ pub mod utils;
 
 pub use error::{Error, Result};
 
+/// The config of the file at `CONFIG_FILE_PATH` and the env, validated.
+pub fn config() -> Result<module::config::scanner_config::Config> {
+    module::config::scanner_config::Config::load()
+        .map_err(|e| Error::from(format!("Invalid config: {e}")))
+}
+
 pub mod schema {
     pub use module::schema::*;
 }
@@ -1,11 +1,11 @@
-use crate::{db, rpc::RPCCaller, Error};
+use crate::{rpc::RPCCaller, Error};
 
 use crossbeam::channel::bounded;
 use reqwest::Url;
 
-use sqlx::PgPool;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::{sync::Arc, time::Duration};
+use storage::Storage;
 
 pub struct RangeScanner {
     caller: Arc<RPCCaller>,
@@ -17,16 +17,21 @@ impl RangeScanner {
         tendermint_rpc: Url,
         retries: usize,
         concurrency: usize,
-        pool: PgPool,
+        storage: Arc<dyn Storage>,
     ) -> Self {
+        Self::from_caller(RPCCaller::new(
+            retries,
+            concurrency,
+            timeout,
+            tendermint_rpc,
+            storage,
+        ))
+    }
+
+    /// Scan with `caller`, its backoff set beforehand.
+    pub fn from_caller(caller: RPCCaller) -> Self {
         RangeScanner {
-            caller: Arc::new(RPCCaller::new(
-                retries,
-                concurrency,
-                timeout,
-                tendermint_rpc,
-                pool,
-            )),
+            caller: Arc::new(caller),
         }
     }
 
@@ -98,13 +103,13 @@ async fn task(
     succeed_cnt: Arc<AtomicI64>,
 ) {
     match caller.load_height_retried(h).await {
-        Ok(block) => match db::save(block, &caller.pool).await {
+        Ok(block) => match caller.storage.save_prism_txs(block).await {
             Ok(_) => {
                 let h_old = last_height.load(Ordering::Acquire);
                 if h > h_old {
                     last_height.store(h, Ordering::Release);
                     //write the last height to database.
-                    if let Err(e) = db::save_last_height(h, &caller.pool).await {
+                    if let Err(e) = caller.storage.save_prism_last_height(h).await {
                         error!("Database error: {:?}", e);
                     }
                 }
@@ -122,3 +127,37 @@ async fn task(
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mock_rpc::MockNode;
+    use storage::SqliteStorage;
+
+    #[tokio::test]
+    async fn test_range_scan() {
+        let dir = mock_rpc::fixtures_dir();
+        let node = MockNode::load(&dir).unwrap();
+        let url = node.spawn().await.unwrap().parse().unwrap();
+        let storage = Arc::new(SqliteStorage::memory().await.unwrap());
+        let scanner = RangeScanner::new(Duration::from_secs(5), url, 0, 2, storage.clone());
+
+        assert_eq!(scanner.range_scan(1, 4).await.unwrap(), 3);
+        assert_eq!(storage.load_prism_last_height().await.unwrap(), 3);
+        // the evm tx is the only deposit.
+        let (total, txs) = storage.prism_txs(None, None, 10, 0).await.unwrap();
+        assert_eq!(total, 1);
+        let deposit = &txs[0];
+        assert_eq!(
+            deposit.tx_hash,
+            "263d57cd0d924b81ab820c1ba73aab47aaba3bb7152623a1da4da178a0221f99"
+        );
+        assert_eq!(deposit.height, 3);
+        assert_eq!(
+            deposit.receiver,
+            "fra18fnyetvs2kc035xz78kyfcygmej8pk5h2kwczy03w6uewdphzfxsk74dym"
+        );
+        assert_eq!((deposit.amount.as_str(), deposit.decimal), ("1000000", 6));
+        assert_eq!(deposit.asset, "A".repeat(43) + "=");
+    }
+}
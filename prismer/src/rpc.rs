@@ -1,113 +1,35 @@
+use crate::tx;
 use crate::utils::bech32_encode;
-use crate::{db, tx};
 use crate::{Error, Result};
 use base64::{engine, Engine};
 use chrono::NaiveDateTime;
 use ethabi::{Event as EthEvent, EventParam, Hash, ParamType, RawLog};
 use ethereum::LegacyTransaction;
-use module::rpc::{
-    block::BlockRPC as ModuleBlockRPC, tx::Transaction as ModuleTx, JsonRpcResponse, TdRpcResult,
-};
-use module::schema::{DelegationInfo, PrismTxResult};
+use module::config::scanner_config::Backoff;
+use module::network::DEPOSIT_ASSET_EVENT_HASH;
+use module::schema::PrismTxResult;
+use module::source::{self, BlockSource};
 use module::utils::crypto::recover_signer;
-use reqwest::{Client, ClientBuilder, Url};
+use reqwest::Url;
 use rlp::{Encodable, RlpStream};
-use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::Digest;
-use sqlx::PgPool;
 use std::str::FromStr;
 use std::string::ToString;
+use std::sync::Arc;
 use std::time::Duration;
+use storage::Storage;
 
 const DEPOSIT_ASSET: &str = "DepositAsset";
-// DepositAsset(bytes32,bytes,uint256,uint8,uint256);
-const DEPOSIT_ASSET_EVENT_HASH: &str =
-    "0xaae31ca36c1ef3c9daa9d5efff8c47306109c0f7cf997e61d766ba15d27e071e";
 
-pub struct TendermintRPC {
-    pub rpc: Url,
-    pub client: Client,
-}
-
-impl TendermintRPC {
-    pub fn new(timeout: Duration, rpc: Url) -> Self {
-        let client = ClientBuilder::new().timeout(timeout).build().unwrap();
-        TendermintRPC { client, rpc }
-    }
-
-    pub async fn load_block(&self, height: i64) -> Result<ModuleBlockRPC> {
-        let mut url = self.rpc.join("block").unwrap();
-        url.set_query(Some(&format!("height={height}")));
-        debug!("{}", url.as_str());
-        let r: ModuleBlockRPC = self.client_get(url).await?;
-        Ok(r)
-    }
-
-    pub async fn load_transaction(&self, hash: &str) -> Result<ModuleTx> {
-        let mut url = self.rpc.join("tx").unwrap();
-        url.set_query(Some(&format!("hash=0x{hash}")));
-
-        let r: ModuleTx = self.client_get(url).await?;
-        Ok(r)
-    }
-
-    pub async fn load_delegations(&self) -> Result<(i64, DelegationInfo)> {
-        let mut url = self.rpc.join("abci_query").unwrap();
-        let mut queries = url.query_pairs_mut();
-        queries.append_pair("path", "\"/delegations\"");
-        queries.append_pair("data", "");
-        drop(queries);
-
-        let resp = self.client.get(url).send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            return Err(resp.text().await?.into());
-        }
-
-        let result: JsonRpcResponse<TdRpcResult> = resp.json().await?;
-
-        let response = result.result.response;
-
-        if response.code != 0 {
-            return Err(response.info.into());
-        }
-
-        let h = response.height.parse()?;
-
-        //let data = response.info.replace("\\\"", "\"");
-
-        let staking: DelegationInfo = serde_json::from_str(&response.info)?;
-
-        Ok((h, staking))
-    }
-
-    async fn client_get<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
-        let resp = self.client.get(url).send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            let resp_text = resp.text().await?;
-            if resp_text.contains("less than or equal to") {
-                return Err(Error::NotFound);
-            }
-            return Err(resp_text.into());
-        }
-
-        let bytes = resp.bytes().await?;
-        if let Ok(r) = serde_json::from_slice::<'_, JsonRpcResponse<T>>(&bytes) {
-            Ok(r.result)
-        } else {
-            debug!("{}", String::from_utf8_lossy(&bytes));
-            Err(Error::NotFound)
-        }
-    }
-}
 pub struct RPCCaller {
     pub(crate) retries: usize,
+    /// Waits between the retries of a block.
+    pub(crate) backoff: Backoff,
     pub(crate) concurrency: usize,
-    pub(crate) rpc: TendermintRPC,
-    pub(crate) pool: PgPool,
+    pub(crate) rpc: Box<dyn BlockSource>,
+    pub(crate) storage: Arc<dyn Storage>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -167,17 +89,24 @@ impl RPCCaller {
         concurrency: usize,
         timeout: Duration,
         tendermint_rpc: Url,
-        pool: PgPool,
+        storage: Arc<dyn Storage>,
     ) -> Self {
-        let rpc = TendermintRPC::new(timeout, tendermint_rpc);
+        let rpc = source::open(tendermint_rpc, timeout);
         RPCCaller {
             retries,
+            backoff: Backoff::default(),
             concurrency,
             rpc,
-            pool,
+            storage,
         }
     }
 
+    /// Set the waits between the retries of a block.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
     pub async fn load_height(&self, height: i64) -> Result<Vec<PrismTxResult>> {
         let block = self.rpc.load_block(height).await?;
         let block_hash = block.block_id.hash;
@@ -309,13 +238,15 @@ impl RPCCaller {
                     if i == self.retries {
                         return Err(e);
                     }
+                    let delay = self.backoff.delay(i);
                     info!(
-                        "Load height {} failed, error: `{:?}`\nRetry {} ...",
+                        "Load height {} failed, error: `{:?}`\nRetry {} in {:?} ...",
                         height,
                         e,
-                        i + 1
+                        i + 1,
+                        delay
                     );
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    tokio::time::sleep(delay).await;
                 }
             };
         }
@@ -324,8 +255,8 @@ impl RPCCaller {
 
     pub async fn load_and_save_block(&self, target: i64) -> Result<()> {
         let block = self.load_height_retried(target).await?;
-        db::save(block, &self.pool).await?;
-        db::save_last_height(target, &self.pool).await?;
+        self.storage.save_prism_txs(block).await?;
+        self.storage.save_prism_last_height(target).await?;
         Ok(())
     }
 }
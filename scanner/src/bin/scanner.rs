@@ -16,5 +16,12 @@ async fn main() -> Result<()> {
         ScannerCmd::Scan(batch_scan) => batch_scan.execute().await,
         ScannerCmd::Subscribe(subscribe) => subscribe.run().await,
         ScannerCmd::Migrate(migrate) => migrate.execute().await,
+        ScannerCmd::Retry(retry) => retry.execute().await,
+        ScannerCmd::Verify(verify) => verify.execute().await,
+        ScannerCmd::Prune(prune) => prune.execute().await,
+        ScannerCmd::Export(export) => export.execute().await,
+        ScannerCmd::Import(import) => import.execute().await,
+        ScannerCmd::Schema(schema) => schema.execute().await,
+        ScannerCmd::Config(config) => config.execute(),
     }
 }
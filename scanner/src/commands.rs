@@ -1,33 +1,45 @@
-use crate::db::{
-    save_asset_tx, save_claim_tx, save_delegation_tx, save_evm_tx, save_n2e_tx, save_native_tx,
-    save_tx_type, save_undelegation_tx,
-};
 use crate::types::{
     ClaimOpt, ConvertAccountOpt, DefineAssetOpt, DelegationOpt, FindoraEVMTx, FindoraTxType,
     IssueAssetOpt, TransferAssetOpt, TxValue, UnDelegationOpt,
 };
 use crate::util::pubkey_to_fra_address;
-use crate::{db, rpc::RPCCaller, scanner::RangeScanner};
+use crate::{
+    export::{self, Dataset, ExportFormat, DATASETS},
+    import,
+    light::Trust,
+    rpc::{
+        decode_governance_op, decode_mint_op, decode_operation, decode_validator_op, RPCCaller,
+        QUARANTINED_TXS,
+    },
+    scanner::RangeScanner,
+    status::ServerState,
+    verify,
+};
 use crate::{Error, Result};
 use base64::{engine, Engine};
 use clap::Parser;
 use ethereum::TransactionAction;
 use ethereum_types::H256;
 use futures::TryStreamExt;
+use module::config::scanner_config::Config;
+use module::network::FRA_ASSET;
+use module::storage::TxFilter;
 use module::utils::crypto::recover_signer;
 use reqwest::Url;
 use serde_json::Value;
 use sha3::{Digest, Keccak256};
 use sqlx::{PgPool, Row};
-use std::env;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
-
-const DEFAULT_TIMEOUT_SECS: u64 = 32;
-const DEFAULT_RETIES: usize = 3;
-const DEFAULT_CONCURRENCY: usize = 8;
-//const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
-
-pub const FRA_ASSET: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+use storage::db::{
+    self, save_asset_tx, save_claim_tx, save_delegation_tx, save_evm_tx, save_mint_tx, save_n2e_tx,
+    save_native_tx, save_operation, save_quarantined_tx, save_slashing_event, save_tx_type,
+    save_undelegation_tx, save_validator_event, PruneMode,
+};
+use storage::Storage;
 
 #[derive(Parser)]
 pub enum ScannerCmd {
@@ -35,45 +47,82 @@ pub enum ScannerCmd {
     Load(Load),
     Subscribe(Subscribe),
     Migrate(Migrate),
+    Retry(Retry),
+    Verify(Verify),
+    Prune(Prune),
+    Export(Export),
+    Import(Import),
+    Schema(Schema),
+    #[clap(subcommand)]
+    Config(ConfigCmd),
+}
+
+/// Config of the file at `CONFIG_FILE_PATH` and the env.
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCmd {
+    /// Validate the config and print the effective one, without the database password.
+    Check,
+}
+
+impl ConfigCmd {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            ConfigCmd::Check => {
+                let toml = crate::config()?
+                    .redacted()
+                    .to_toml()
+                    .map_err(|e| Error::from(e.to_string()))?;
+                println!("{toml}");
+                Ok(())
+            }
+        }
+    }
 }
 
 /// load block at specific height.
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 pub struct Load {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     /// Target block height.
     #[clap(long)]
     height: Option<i64>,
-    ///Rpc timeout with seconds.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
+    ///Verify each block against its header and its tx results, default is `scan.verify` of the config.
+    #[clap(long)]
+    verify: bool,
 }
 
 impl Load {
     pub async fn execute(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
+        let (config, rpc, storage, _) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+        let retries = self.retries.unwrap_or(config.rpc.retries);
 
         let target = if let Some(h) = self.height {
             if h <= 0 {
                 return Err(format!("Invalid height: {h}.").into());
             }
             h
-        } else if let Ok(h) = db::load_last_height(&pool).await {
+        } else if let Ok(h) = storage.load_last_height().await {
             h + 1
         } else {
             1
         };
 
         info!("Got header {}", target);
-        let caller = RPCCaller::new(retries, 1, timeout, rpc, pool);
+        let caller = RPCCaller::new(retries, 1, timeout, rpc, storage)
+            .with_backoff(config.rpc.retry_backoff())
+            .with_verify(self.verify || config.scan.verify)
+            .with_trust(Trust::from_config(&config.light_client));
         caller.load_and_save_block(target).await?;
 
         info!("Load block at height {} succeed.", target);
@@ -85,34 +134,53 @@ impl Load {
 #[derive(Parser)]
 #[clap(about, version, author)]
 pub struct RangeScan {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     ///Start height
     #[clap(long)]
     start: u64,
     ///End height, included.
     #[clap(long)]
     end: u64,
-    ///Rpc timeout with seconds, default is 32 seconds.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block, default is 3.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
-    ///How many concurrency would be used to call rpc, default is 8.
+    ///How many concurrency would be used to call rpc, default is `scan.concurrency` of the config.
     #[clap(long)]
     concurrency: Option<usize>,
+    ///Upper bound the adaptive concurrency may grow to, default is `scan.max_concurrency` of the config.
+    #[clap(long)]
+    max_concurrency: Option<usize>,
+    ///How many blocks to save in one bulk write, default is `scan.bulk_blocks` of the config.
+    #[clap(long)]
+    bulk_blocks: Option<usize>,
+    ///Drop secondary indexes during the scan and rebuild them once it completes, for initial loads.
+    #[clap(long)]
+    defer_indexes: bool,
+    ///Verify each block against its header and its tx results, default is `scan.verify` of the config.
+    #[clap(long)]
+    verify: bool,
 }
 
 impl RangeScan {
     pub async fn execute(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
-        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        let (config, rpc, storage, pool) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+        let retries = self.retries.unwrap_or(config.rpc.retries);
+        let concurrency = self.concurrency.unwrap_or(config.scan.concurrency);
 
-        let range_scanner = RangeScanner::new(timeout, rpc, retries, concurrency, pool);
+        let caller = RPCCaller::new(retries, concurrency, timeout, rpc, storage)
+            .with_backoff(config.rpc.retry_backoff())
+            .with_verify(self.verify || config.scan.verify)
+            .with_trust(Trust::from_config(&config.light_client));
+        let range_scanner = RangeScanner::from_caller(caller)
+            .with_max_concurrency(self.max_concurrency.unwrap_or(config.scan.max_concurrency))
+            .with_bulk_blocks(self.bulk_blocks.unwrap_or(config.scan.bulk_blocks));
 
         if self.start < 1 {
             return Err("`start` must >= 1.".into());
@@ -122,10 +190,31 @@ impl RangeScan {
             return Err("`end` must large than `start`.".into());
         }
 
-        let _ = range_scanner
-            .range_scan(self.start as i64, self.end as i64 + 1)
+        range_scanner
+            .caller()
+            .check_start(self.start as i64)
             .await?;
-        Ok(())
+
+        if self.defer_indexes {
+            let Some(pool) = &pool else {
+                return Err("`defer-indexes` requires Postgres.".into());
+            };
+            let n = db::defer_indexes(pool).await?;
+            info!("Deferred {} indexes.", n);
+        }
+
+        let res = range_scanner
+            .range_scan(self.start as i64, self.end as i64 + 1)
+            .await;
+
+        //also picks up the indexes left over by an interrupted load.
+        if let Some(pool) = &pool {
+            let n = db::restore_indexes(pool).await?;
+            if n > 0 {
+                info!("Restored {} indexes.", n);
+            }
+        }
+        res.map(|_| ())
     }
 }
 
@@ -133,93 +222,554 @@ impl RangeScan {
 #[derive(Parser)]
 #[clap(about, version, author)]
 pub struct Subscribe {
-    /// Server to tendermint.
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
     #[clap(short, long)]
-    server: String,
+    server: Option<String>,
     ///Start height
     #[clap(long)]
     start: Option<i64>,
-    ///Rpc timeout with seconds, default is 10.
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
     #[clap(long)]
     timeout: Option<u64>,
-    ///Times to retry to pull a block, default is 3.
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
     #[clap(long)]
     retries: Option<usize>,
     #[clap(long)]
-    ///block generation interval, with seconds.
+    ///block generation interval, with seconds, default is `scan.interval` of the config.
     interval: Option<u64>,
-    ///How many concurrency would be used when scanning, default is 8.
+    ///How many concurrency would be used when scanning, default is `scan.concurrency` of the config.
     #[clap(long)]
     concurrency: Option<usize>,
+    ///Upper bound the adaptive concurrency may grow to, default is `scan.max_concurrency` of the config.
+    #[clap(long)]
+    max_concurrency: Option<usize>,
+    ///How many blocks to save in one bulk write during fast sync, default is `scan.bulk_blocks` of the config.
+    #[clap(long)]
+    bulk_blocks: Option<usize>,
+    ///How many blocks fast sync scans before looking at the tip again, default is `scan.sync_chunk` of the config.
+    #[clap(long)]
+    sync_chunk: Option<usize>,
+    ///Address to serve the status and control api at, default is `status.listen` of the config.
+    #[clap(long)]
+    status: Option<String>,
+    ///Verify each block against its header and its tx results, default is `scan.verify` of the config.
+    #[clap(long)]
+    verify: bool,
 }
 
 impl Subscribe {
     pub async fn run(&self) -> Result<()> {
-        let (rpc, pool) = prepare(&self.server).await?;
-        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
-
-        let itv = env::var("INTERVAL")
-            .ok()
-            .unwrap_or(String::from("15"))
-            .parse::<u64>()?;
-        let interval = Duration::from_secs(itv);
+        let (config, rpc, storage, pool) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+
+        let interval = Duration::from_secs(self.interval.unwrap_or(config.scan.interval));
         info!("interval={:?}", interval);
 
-        let retries = self.retries.unwrap_or(DEFAULT_RETIES);
+        let retries = self.retries.unwrap_or(config.rpc.retries);
 
         let mut cursor = if let Some(h) = self.start {
             if h <= 0 {
                 return Err(format!("Invalid height: {h}.").into());
             }
             h
-        } else if let Ok(h) = db::load_last_height(&pool).await {
+        } else if let Ok(h) = storage.load_last_height().await {
             h + 1
         } else {
             1
         };
 
-        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        let concurrency = self.concurrency.unwrap_or(config.scan.concurrency);
         assert!(concurrency >= 1);
-        let range_scanner = RangeScanner::new(timeout, rpc, retries, concurrency, pool.clone());
-        let batch_size = 4 * concurrency as i64;
+        let caller = RPCCaller::new(retries, concurrency, timeout, rpc, storage.clone())
+            .with_backoff(config.rpc.retry_backoff())
+            .with_verify(self.verify || config.scan.verify)
+            .with_trust(Trust::from_config(&config.light_client));
+        let range_scanner = RangeScanner::from_caller(caller)
+            .with_max_concurrency(self.max_concurrency.unwrap_or(config.scan.max_concurrency))
+            .with_bulk_blocks(self.bulk_blocks.unwrap_or(config.scan.bulk_blocks));
+        let caller = range_scanner.caller().clone();
+        caller.check_start(cursor).await?;
+        let status = caller.status().clone();
+        status.set_cursor(cursor - 1);
+        if let Some(addr) = self.status.as_ref().or(config.status.listen.as_ref()) {
+            let state = ServerState {
+                status: status.clone(),
+                network: config.network.clone(),
+                token: config.status.token.clone(),
+                max_lag: config.status.max_lag,
+            };
+            crate::status::serve(addr, state).await?;
+        }
+
+        let retention = match (config.prune.keep_blocks, pool) {
+            (None, _) => None,
+            (Some(keep_blocks), Some(pool)) => Some((keep_blocks, prune_mode(&config)?, pool)),
+            (Some(_), None) => return Err("`prune.keep_blocks` needs Postgres.".into()),
+        };
+        // blocks out of the window are pruned as they leave it, from where the last prune stopped.
+        let retain = |last: i64| {
+            let retention = retention.clone();
+            let status = status.clone();
+            let batch = config.prune.batch;
+            async move {
+                let Some((keep_blocks, mode, pool)) = retention else {
+                    return;
+                };
+                let pruned = async {
+                    let from = db::load_prune_height(mode, &pool).await?;
+                    db::prune(from, last + 1 - keep_blocks, mode, batch, &pool).await
+                };
+                if let Err(e) = pruned.await {
+                    status.record_error(format!("prune: {e:?}"));
+                }
+            }
+        };
 
+        let chunk = self.sync_chunk.unwrap_or(config.scan.sync_chunk) as i64;
         info!("Subscribing start from {}, try fast sync ...", cursor);
         loop {
-            let succeed_cnt = range_scanner
-                .range_scan(cursor, cursor + batch_size)
-                .await?;
-            if succeed_cnt == batch_size {
-                cursor += batch_size;
-            } else {
+            status.wait_running().await;
+            caller.refetch_scheduled().await;
+            //the tip moves while syncing, repeat until caught up.
+            let tip = caller.wait_latest_height(interval).await;
+            if cursor > tip {
+                break;
+            }
+            let end = tip.min(cursor + chunk - 1);
+            let report = range_scanner.range_scan(cursor, end + 1).await?;
+            retain(report.watermark).await;
+            if report.watermark < end {
                 break;
             }
+            cursor = end + 1;
         }
         info!("Fast sync complete.");
-        let caller = range_scanner.caller().clone();
+        let backoff = config.rpc.retry_backoff();
+        let mut failures = 0;
         loop {
-            if let Ok(h) = db::load_last_height(&pool).await {
+            if status.is_paused() {
+                info!("Paused at height {}.", cursor);
+                status.wait_running().await;
+                info!("Resumed at height {}.", cursor);
+            }
+            caller.refetch_scheduled().await;
+            if let Ok(h) = storage.load_last_height().await {
                 cursor = h + 1;
+                status.set_cursor(h);
+            }
+            if let Err(e) = caller.latest_height().await {
+                status.record_error(format!("latest height: {e:?}"));
             }
-            match caller.load_and_save_block(cursor).await {
+            // a height failing its retries is asked again after a backoff, the subscriber goes on.
+            let wait = match caller.load_and_save_block(cursor).await {
                 Ok(_) => {
                     info!("Block at {} loaded.", cursor);
+                    retain(cursor).await;
+                    failures = 0;
+                    interval
                 }
                 Err(Error::NotFound) => {
                     error!("Block {} not found.", cursor);
+                    interval
+                }
+                Err(Error::Pending) => {
+                    info!("Block {} waits for the one above.", cursor);
+                    interval
+                }
+                Err(e) => {
+                    error!("Load height {} error: {:?}", cursor, e);
+                    status.record_error(format!("load height {cursor}: {e:?}"));
+                    failures += 1;
+                    interval.max(backoff.delay(failures - 1))
                 }
-                Err(e) => return Err(e),
             };
-            tokio::time::sleep(interval).await;
+            tokio::time::sleep(wait).await;
         }
         //may handle signal here.
     }
 }
 
-async fn prepare(rpc: &str) -> Result<(Url, PgPool)> {
-    let pool = db::connect().await?;
+/// re-decode the heights holding quarantined txs.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Retry {
+    /// Server to tendermint, or a `file://` directory of captured rpc responses. Default is
+    /// `rpc.tendermint` of the config, or the one of the network.
+    #[clap(short, long)]
+    server: Option<String>,
+    /// Only retry the quarantined txs at this height.
+    #[clap(long)]
+    height: Option<i64>,
+    ///Rpc timeout with seconds, default is `rpc.timeout` of the config.
+    #[clap(long)]
+    timeout: Option<u64>,
+    ///Times to retry to pull a block, default is `rpc.retries` of the config.
+    #[clap(long)]
+    retries: Option<usize>,
+}
+
+impl Retry {
+    pub async fn execute(&self) -> Result<()> {
+        let (config, rpc, storage, _) = prepare(self.server.as_deref()).await?;
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(config.rpc.timeout));
+        let retries = self.retries.unwrap_or(config.rpc.retries);
+
+        let heights = if let Some(h) = self.height {
+            vec![h]
+        } else {
+            storage.load_quarantined_heights().await?
+        };
+
+        let caller = RPCCaller::new(retries, 1, timeout, rpc, storage.clone())
+            .with_backoff(config.rpc.retry_backoff())
+            .with_verify(config.scan.verify)
+            .with_trust(Trust::from_config(&config.light_client));
+        let mut remaining = 0;
+        for h in &heights {
+            // the last height is left untouched, only the records of this height are rewritten.
+            let block = caller.load_height_retried(*h).await?;
+            caller.check_link(&block).await?;
+            remaining += block.quarantined_txs.len();
+            storage.save_block(block).await?;
+        }
+
+        info!(
+            "Retried {} heights, {} txs still quarantined.",
+            heights.len(),
+            remaining
+        );
+        Ok(())
+    }
+}
+
+/// Re-verify the stored blocks of a range against their headers and report the mismatches.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Verify {
+    ///Start height
+    #[clap(long)]
+    start: i64,
+    ///End height, included, default is the last height.
+    #[clap(long)]
+    end: Option<i64>,
+}
+
+impl Verify {
+    pub async fn execute(&self) -> Result<()> {
+        let (storage, _) = storage::connect().await?;
+        let end = match self.end {
+            Some(end) => end,
+            None => storage.load_last_height().await?,
+        };
+        if self.start < 1 || end < self.start {
+            return Err(format!("Invalid range: [{}, {}].", self.start, end).into());
+        }
+
+        let (mut verified, mut mismatches) = (0, 0);
+        for h in self.start..=end {
+            let record = match storage.block_by_height(h).await {
+                Ok(record) => record,
+                Err(module::storage::StorageError::NotFound) => {
+                    println!("height {h}, block: not saved");
+                    mismatches += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let origins = storage.tx_origins(h).await?;
+            let filter = TxFilter {
+                height: Some(h),
+                ..Default::default()
+            };
+            let (_, txs) = storage.txs(&filter, i64::MAX, 0).await?;
+            let found = verify::verify_stored(&record, &origins, &txs);
+            for m in &found {
+                println!("{m}");
+            }
+            mismatches += found.len();
+            verified += 1;
+        }
+
+        info!("Verified {} blocks, {} mismatches.", verified, mismatches);
+        if mismatches > 0 {
+            return Err(format!("{mismatches} mismatches found.").into());
+        }
+        Ok(())
+    }
+}
+
+/// Prune the block data out of the retention window.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Prune {
+    /// Keep the full block data of the latest N blocks, default is `prune.keep_blocks` of the config.
+    #[clap(long)]
+    keep_blocks: Option<i64>,
+    /// `compact` strips the txs, `drop` also the commit signatures, default is `prune.mode` of the config.
+    #[clap(long, value_enum)]
+    mode: Option<PruneMode>,
+    /// Only report what would be pruned.
+    #[clap(long)]
+    dry_run: bool,
+    /// Heights pruned per statement, default is `prune.batch` of the config.
+    #[clap(long)]
+    batch: Option<i64>,
+    /// Height to prune from, default is where the last prune stopped.
+    #[clap(long)]
+    from: Option<i64>,
+}
+
+impl Prune {
+    pub async fn execute(&self) -> Result<()> {
+        let config = crate::config()?;
+        let keep_blocks = self
+            .keep_blocks
+            .or(config.prune.keep_blocks)
+            .ok_or("`keep-blocks` is required, or `prune.keep_blocks` of the config.")?;
+        if keep_blocks < 0 {
+            return Err("`keep-blocks` must >= 0.".into());
+        }
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => prune_mode(&config)?,
+        };
+        let pool = db::connect().await?;
+        let before = db::load_last_height(&pool).await? + 1 - keep_blocks;
+        let from = match self.from {
+            Some(from) => from,
+            None => db::load_prune_height(mode, &pool).await?,
+        };
+        let (blocks, bytes) = db::prune_estimate(from, before, mode, &pool).await?;
+        info!(
+            "{} blocks in [{}, {}) to prune ({:?}), about {} bytes to reclaim.",
+            blocks, from, before, mode, bytes
+        );
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let batch = self.batch.unwrap_or(config.prune.batch);
+        let pruned = db::prune(from, before, mode, batch, &pool).await?;
+        info!(
+            "Pruned {} blocks, run VACUUM to return the space to the system.",
+            pruned
+        );
+        Ok(())
+    }
+}
+
+/// `prune.mode` of the config.
+fn prune_mode(config: &Config) -> Result<PruneMode> {
+    <PruneMode as clap::ValueEnum>::from_str(&config.prune.mode, false).map_err(Error::from)
+}
+
+/// Export indexed data by height range, one file per chunk of heights.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Export {
+    /// Output directory, a sub directory is created per dataset.
+    #[clap(long)]
+    out: PathBuf,
+    /// Start height.
+    #[clap(long)]
+    start: i64,
+    /// End height, included, default is the last indexed height.
+    #[clap(long)]
+    end: Option<i64>,
+    #[clap(long, value_enum, default_value = "jsonl")]
+    format: ExportFormat,
+    /// Comma separated datasets, default is all of them.
+    #[clap(long, value_delimiter = ',')]
+    datasets: Vec<String>,
+    /// Heights per file, starting at multiples of it, default is 100000.
+    #[clap(long)]
+    chunk: Option<i64>,
+}
+
+impl Export {
+    pub async fn execute(&self) -> Result<()> {
+        let pool = db::connect().await?;
+        // a chunk is never rewritten once complete, so stop at what is indexed.
+        let last_height = db::load_last_height(&pool).await?;
+        let end = self.end.unwrap_or(last_height).min(last_height);
+        if self.start < 0 || end < self.start {
+            return Err(format!("Invalid range [{}, {}].", self.start, end).into());
+        }
+        let chunk = self.chunk.unwrap_or(100000).max(1);
+
+        let datasets = if self.datasets.is_empty() {
+            DATASETS.iter().collect()
+        } else {
+            self.datasets
+                .iter()
+                .map(|name| {
+                    Dataset::find(name)
+                        .ok_or_else(|| Error::from(format!("Unknown dataset: {name}.")))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        for dataset in datasets {
+            let rows = export::export(
+                dataset,
+                self.start,
+                end + 1,
+                chunk,
+                &self.out,
+                self.format,
+                &pool,
+            )
+            .await?;
+            info!("Exported {} rows of {}.", rows, dataset.name);
+        }
+        Ok(())
+    }
+}
+
+/// Bootstrap an empty database from the blocks and transactions exported by `export`.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Import {
+    /// Directory given as `--out` to `export`.
+    #[clap(long)]
+    dir: PathBuf,
+    /// Import even if blocks are already indexed, the imported ones must follow them without a gap.
+    #[clap(long)]
+    force: bool,
+    /// Do not rebuild the tx tables from the imported transactions.
+    #[clap(long)]
+    skip_migrate: bool,
+    /// Server to tendermint, subscribe from the imported height when given.
+    #[clap(short, long)]
+    server: Option<String>,
+}
+
+impl Import {
+    pub async fn execute(&self) -> Result<()> {
+        let pool = db::connect().await?;
+        let row = sqlx::query(
+            "SELECT EXISTS (SELECT 1 FROM block WHERE network=current_network()) AS indexed",
+        )
+        .fetch_one(&pool)
+        .await?;
+        let indexed: bool = row.try_get("indexed")?;
+        if indexed && !self.force {
+            return Err("Blocks are already indexed, use --force to import anyway.".into());
+        }
+
+        let stats = import::import(&self.dir, &pool).await?;
+        info!(
+            "Imported {} blocks and {} txs in [{}, {}].",
+            stats.blocks, stats.txs, stats.first, stats.last
+        );
+        // validators and block generation are not exported, they fill in from subscribe on.
+        if !self.skip_migrate {
+            Migrate {}.execute().await?;
+        }
+
+        match &self.server {
+            Some(server) => {
+                Subscribe {
+                    server: Some(server.clone()),
+                    start: None,
+                    timeout: None,
+                    retries: None,
+                    interval: None,
+                    concurrency: None,
+                    max_concurrency: None,
+                    bulk_blocks: None,
+                    sync_chunk: None,
+                    status: None,
+                    verify: false,
+                }
+                .run()
+                .await
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Apply the embedded schema migrations. Unlike `migrate`, which rebuilds the tx tables.
+#[derive(Parser, Debug)]
+#[clap(about, version, author)]
+pub struct Schema {
+    /// Log applied and pending migrations without changing anything.
+    #[clap(long)]
+    status: bool,
+    /// Revert the migrations above this version.
+    #[clap(long)]
+    undo: Option<i64>,
+    /// Record the migrations up to this version as applied without running them, for a
+    /// database migrated by hand. The pending ones are left to a later `schema`.
+    #[clap(long, conflicts_with = "undo")]
+    baseline: Option<i64>,
+}
+
+impl Schema {
+    pub async fn execute(&self) -> Result<()> {
+        let pool = db::connect_unchecked().await?;
+        if self.status {
+            let applied: HashMap<i64, bool> = db::applied_migrations(&pool)
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            for m in db::MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+            {
+                let state = match applied.get(&m.version) {
+                    Some(true) => "applied",
+                    Some(false) => "failed",
+                    None => "pending",
+                };
+                info!("{} {}: {}", m.version, m.description, state);
+            }
+            return Ok(());
+        }
+
+        if let Some(version) = self.baseline {
+            let recorded = db::baseline_schema(version, &pool).await?;
+            info!(
+                "Recorded {} migrations as applied, baseline at version {}.",
+                recorded.len(),
+                version
+            );
+        } else if let Some(target) = self.undo {
+            db::MIGRATOR
+                .undo(&pool, target)
+                .await
+                .map_err(|e| Error::from(format!("{e}")))?;
+            info!("Schema reverted to version {}.", target);
+        } else {
+            db::MIGRATOR
+                .run(&pool)
+                .await
+                .map_err(|e| Error::from(format!("{e}")))?;
+            info!("Schema at version {}.", db::expected_schema_version());
+        }
+        Ok(())
+    }
+}
+
+/// The rpc url and the storage at `DATABASE_URL`, along with the pool if it is Postgres.
+async fn prepare(rpc: Option<&str>) -> Result<(Config, Url, Arc<dyn Storage>, Option<PgPool>)> {
+    let config = crate::config()?;
+    let (storage, pool) = storage::connect().await?;
+    let rpc = match rpc {
+        Some(rpc) => rpc.to_string(),
+        None => {
+            config
+                .network()
+                .map_err(|e| Error::from(e.to_string()))?
+                .tendermint
+        }
+    };
     let rpc: Url = rpc.parse().map_err(|e| Error::from(format!("{e}")))?;
 
-    Ok((rpc, pool))
+    Ok((config, rpc, storage, pool))
 }
 
 #[derive(Parser)]
@@ -231,207 +781,383 @@ impl Migrate {
         let pool = db::connect().await?;
         let mut conn = pool.acquire().await?;
 
-        let mut cursor =
-            sqlx::query("SELECT tx_hash,block_hash,height,timestamp,ty,value FROM transaction")
-                .fetch(&mut *conn);
+        let mut cursor = sqlx::query(
+            "SELECT tx_hash,block_hash,height,tx_index,timestamp,ty,origin,value FROM transaction WHERE network=current_network()",
+        )
+        .fetch(&mut *conn);
         while let Some(row) = cursor.try_next().await? {
             let tx: String = row.try_get("tx_hash")?;
             let block: String = row.try_get("block_hash")?;
             let height: i64 = row.try_get("height")?;
+            let tx_index: i64 = row.try_get("tx_index")?;
             let timestamp: i64 = row.try_get("timestamp")?;
             let ty: i32 = row.try_get("ty")?;
+            let origin: String = row.try_get("origin")?;
             let v = row.try_get("value")?;
-            if ty == 1 {
-                let evm_tx: FindoraEVMTx = serde_json::from_value(v).unwrap();
-                let evm_tx_hash =
-                    H256::from_slice(Keccak256::digest(&rlp::encode(&evm_tx)).as_slice());
-                let signer = recover_signer(&evm_tx.function.ethereum.transact).unwrap();
-                let receiver = match evm_tx.function.ethereum.transact.action {
-                    TransactionAction::Call(to) => {
-                        format!("{to:?}")
-                    }
-                    _ => "".to_string(),
-                };
 
-                let v: Value = serde_json::to_value(&evm_tx).unwrap();
-                let evm_tx_hash = format!("{evm_tx_hash:?}");
-                let sender = format!("{signer:?}");
-                let amount = evm_tx.function.ethereum.transact.value.to_string();
-                save_evm_tx(
+            match migrate_tx(&tx, &block, height, tx_index, timestamp, ty, v, &pool).await {
+                Ok(()) => {}
+                Err(Error::DBError(e)) => return Err(e.into()),
+                Err(e) => {
+                    let error = format!("{e:?}");
+                    warn!("Quarantined tx {} at height {}: {}", tx, height, error);
+                    QUARANTINED_TXS.fetch_add(1, Ordering::Relaxed);
+                    save_quarantined_tx(
+                        &tx, &block, height, tx_index, &origin, "migrate", &error, timestamp, &pool,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn migrate_tx(
+    tx: &str,
+    block: &str,
+    height: i64,
+    tx_index: i64,
+    timestamp: i64,
+    ty: i32,
+    v: Value,
+    pool: &PgPool,
+) -> Result<()> {
+    if ty == 1 {
+        let evm_tx: FindoraEVMTx = serde_json::from_value(v)?;
+        let evm_tx_hash = H256::from_slice(Keccak256::digest(&rlp::encode(&evm_tx)).as_slice());
+        let signer =
+            recover_signer(&evm_tx.function.ethereum.transact).ok_or(Error::EvmTxParseError)?;
+        let receiver = match evm_tx.function.ethereum.transact.action {
+            TransactionAction::Call(to) => {
+                format!("{to:?}")
+            }
+            _ => "".to_string(),
+        };
+
+        let v: Value = serde_json::to_value(&evm_tx)?;
+        let evm_tx_hash = format!("{evm_tx_hash:?}");
+        let sender = format!("{signer:?}");
+        let amount = evm_tx.function.ethereum.transact.value.to_string();
+        save_evm_tx(
+            &tx.to_lowercase(),
+            &block.to_lowercase(),
+            &evm_tx_hash.to_lowercase(),
+            &sender.to_lowercase(),
+            &receiver.to_lowercase(),
+            &amount,
+            height,
+            timestamp,
+            v,
+            pool,
+        )
+        .await?;
+        save_tx_type(tx, FindoraTxType::Evm as i32, pool).await?;
+    } else {
+        let tx_val: TxValue = serde_json::from_value(v)?;
+        for (op_index, op) in tx_val.body.operations.into_iter().enumerate() {
+            let o = decode_operation(&op, tx, op_index as i32, height, timestamp);
+            save_operation(
+                &o.tx_hash,
+                o.op_index,
+                &o.kind,
+                &o.signer,
+                &o.counterparty,
+                o.asset.as_deref(),
+                o.amount.as_deref(),
+                o.height,
+                o.timestamp,
+                &o.content,
+                pool,
+            )
+            .await?;
+
+            // a ReplaceStaker keeps the validator its staker got from the txs saved below.
+            let stakers = match op.get("ReplaceStaker") {
+                Some(_) => db::load_stakers(height, pool).await?,
+                None => HashMap::new(),
+            };
+            if let Some((_, events)) =
+                decode_validator_op(&op, tx, op_index as i32, block, height, timestamp, &stakers)?
+            {
+                for ev in events {
+                    save_validator_event(
+                        &ev.tx_hash,
+                        ev.op_index,
+                        &ev.block_hash,
+                        &ev.validator,
+                        &ev.staker,
+                        &ev.kind,
+                        ev.commission_rate.as_ref(),
+                        ev.memo.as_ref(),
+                        ev.new_staker.as_deref(),
+                        ev.height,
+                        ev.timestamp,
+                        &ev.content,
+                        pool,
+                    )
+                    .await?;
+                }
+                continue;
+            }
+            if let Some((_, mints)) = decode_mint_op(&op, tx, block, height, timestamp)? {
+                for m in mints {
+                    save_mint_tx(
+                        &m.tx_hash,
+                        &m.block_hash,
+                        m.idx,
+                        &m.receiver,
+                        m.amount,
+                        &m.kind,
+                        m.height,
+                        m.timestamp,
+                        &m.content,
+                        pool,
+                    )
+                    .await?;
+                }
+                continue;
+            }
+            if let Some(ev) =
+                decode_governance_op(&op, tx, tx_index, op_index as i32, block, height, timestamp)?
+            {
+                save_slashing_event(
+                    &ev.block_hash,
+                    ev.tx_hash.as_deref(),
+                    ev.tx_index,
+                    ev.op_index,
+                    &ev.validator,
+                    &ev.kind,
+                    &ev.source,
+                    ev.evidence_height,
+                    ev.power,
+                    ev.penalty_rate.as_ref(),
+                    ev.height,
+                    ev.timestamp,
+                    &ev.content,
+                    pool,
+                )
+                .await?;
+                continue;
+            }
+
+            let op_str = serde_json::to_string(&op)?;
+            if op_str.contains("ConvertAccount") {
+                debug!("ConvertAccount, height: {}", height);
+                let op_copy = op.clone();
+                let opt: ConvertAccountOpt = serde_json::from_value(op)?;
+                let asset: String;
+                if let Some(asset_bin) = &opt.convert_account.asset_type {
+                    asset = engine::general_purpose::URL_SAFE.encode(asset_bin);
+                } else {
+                    asset = FRA_ASSET.to_string();
+                }
+                let signer = pubkey_to_fra_address(&opt.convert_account.signer)?;
+                save_n2e_tx(
                     &tx.to_lowercase(),
                     &block.to_lowercase(),
-                    &evm_tx_hash.to_lowercase(),
-                    &sender.to_lowercase(),
-                    &receiver.to_lowercase(),
-                    &amount,
+                    &signer,
+                    &opt.convert_account.receiver.ethereum,
+                    &asset,
+                    &opt.convert_account.value,
                     height,
                     timestamp,
-                    v,
-                    &pool,
+                    &op_copy,
+                    pool,
                 )
                 .await?;
-                save_tx_type(&tx, FindoraTxType::Evm as i32, &pool).await?;
-            } else {
-                let tx_val: TxValue = serde_json::from_value(v).unwrap();
-                for op in tx_val.body.operations {
-                    let op_str = serde_json::to_string(&op).unwrap();
-                    if op_str.contains("ConvertAccount") {
-                        debug!("ConvertAccount, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: ConvertAccountOpt = serde_json::from_value(op).unwrap();
-                        let asset: String;
-                        if let Some(asset_bin) = &opt.convert_account.asset_type {
-                            asset = engine::general_purpose::URL_SAFE.encode(asset_bin);
-                        } else {
-                            asset = FRA_ASSET.to_string();
-                        }
-                        let signer = pubkey_to_fra_address(&opt.convert_account.signer).unwrap();
-                        save_n2e_tx(
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &signer,
-                            &opt.convert_account.receiver.ethereum,
-                            &asset,
-                            &opt.convert_account.value,
-                            height,
-                            timestamp,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::NativeToEVM as i32, &pool).await?;
-                    } else if op_str.contains("UnDelegation") {
-                        debug!("UnDelegation, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: UnDelegationOpt = serde_json::from_value(op).unwrap();
-                        let sender = pubkey_to_fra_address(&opt.undelegation.pubkey).unwrap();
-                        let (amount, new_delegator, target_validator) =
-                            match opt.undelegation.body.pu {
-                                Some(pu) => {
-                                    let target_validator_addr = hex::encode(pu.target_validator);
-                                    (
-                                        pu.am,
-                                        pu.new_delegator_id,
-                                        target_validator_addr.to_uppercase(),
-                                    )
-                                }
-                                _ => (0, "".to_string(), "".to_string()),
-                            };
-
-                        save_undelegation_tx(
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &sender,
-                            amount,
-                            &target_validator,
-                            &new_delegator,
-                            height,
-                            timestamp,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::Undelegation as i32, &pool).await?;
-                    } else if op_str.contains("Delegation") {
-                        debug!("Delegation, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: DelegationOpt = serde_json::from_value(op).unwrap();
-                        let sender = pubkey_to_fra_address(&opt.delegation.pubkey).unwrap();
-                        let new_validator =
-                            opt.delegation.body.new_validator.unwrap_or("".to_string());
-
-                        save_delegation_tx(
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &sender,
-                            opt.delegation.body.amount,
-                            &opt.delegation.body.validator,
-                            &new_validator,
-                            height,
-                            timestamp,
-                            &op_copy,
-                            &pool,
+                save_tx_type(tx, FindoraTxType::NativeToEVM as i32, pool).await?;
+            } else if op_str.contains("UnDelegation") {
+                debug!("UnDelegation, height: {}", height);
+                let op_copy = op.clone();
+                let opt: UnDelegationOpt = serde_json::from_value(op)?;
+                let sender = pubkey_to_fra_address(&opt.undelegation.pubkey)?;
+                let (amount, new_delegator, target_validator) = match opt.undelegation.body.pu {
+                    Some(pu) => {
+                        let target_validator_addr = hex::encode(pu.target_validator);
+                        (
+                            pu.am,
+                            pu.new_delegator_id,
+                            target_validator_addr.to_uppercase(),
                         )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::Claim as i32, &pool).await?;
-                    } else if op_str.contains("Claim") {
-                        debug!("Claim, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: ClaimOpt = serde_json::from_value(op).unwrap();
-                        let sender = pubkey_to_fra_address(&opt.claim.pubkey).unwrap();
-                        save_claim_tx(
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &sender,
-                            opt.claim.body.amount.unwrap_or(0),
-                            height,
-                            timestamp,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::Claim as i32, &pool).await?;
-                    } else if op_str.contains("DefineAsset") {
-                        debug!("DefineAsset, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: DefineAssetOpt = serde_json::from_value(op).unwrap();
-                        let issuer = pubkey_to_fra_address(&opt.define_asset.pubkey.key).unwrap();
-                        let asset = engine::general_purpose::URL_SAFE
-                            .encode(opt.define_asset.body.asset.code.val);
-                        save_asset_tx(
-                            &asset,
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &issuer,
-                            height,
-                            timestamp,
-                            0,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::DefineOrIssueAsset as i32, &pool).await?;
-                    } else if op_str.contains("IssueAsset") {
-                        debug!("IssueAsset, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: IssueAssetOpt = serde_json::from_value(op).unwrap();
-                        let issuer = pubkey_to_fra_address(&opt.issue_asset.pubkey.key).unwrap();
-                        let asset =
-                            engine::general_purpose::URL_SAFE.encode(opt.issue_asset.body.code.val);
-                        save_asset_tx(
-                            &asset,
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &issuer,
-                            height,
-                            timestamp,
-                            1,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::DefineOrIssueAsset as i32, &pool).await?;
-                    } else if op_str.contains("TransferAsset") {
-                        debug!("TransferAsset, height: {}", height);
-                        let op_copy = op.clone();
-                        let opt: TransferAssetOpt = serde_json::from_value(op).unwrap();
-                        let key = &opt.transfer_asset.body_signatures[0].address.key;
-                        let addr = pubkey_to_fra_address(key).unwrap();
-                        save_native_tx(
-                            &tx.to_lowercase(),
-                            &block.to_lowercase(),
-                            &addr,
-                            height,
-                            timestamp,
-                            &op_copy,
-                            &pool,
-                        )
-                        .await?;
-                        save_tx_type(&tx, FindoraTxType::Native as i32, &pool).await?;
                     }
-                }
+                    _ => (0, "".to_string(), "".to_string()),
+                };
+
+                save_undelegation_tx(
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &sender,
+                    amount,
+                    &target_validator,
+                    &new_delegator,
+                    height,
+                    timestamp,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::Undelegation as i32, pool).await?;
+            } else if op_str.contains("Delegation") {
+                debug!("Delegation, height: {}", height);
+                let op_copy = op.clone();
+                let opt: DelegationOpt = serde_json::from_value(op)?;
+                let sender = pubkey_to_fra_address(&opt.delegation.pubkey)?;
+                let new_validator = opt.delegation.body.new_validator.unwrap_or("".to_string());
+
+                save_delegation_tx(
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &sender,
+                    opt.delegation.body.amount,
+                    &opt.delegation.body.validator,
+                    &new_validator,
+                    height,
+                    timestamp,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::Claim as i32, pool).await?;
+            } else if op_str.contains("Claim") {
+                debug!("Claim, height: {}", height);
+                let op_copy = op.clone();
+                let opt: ClaimOpt = serde_json::from_value(op)?;
+                let sender = pubkey_to_fra_address(&opt.claim.pubkey)?;
+                save_claim_tx(
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &sender,
+                    opt.claim.body.amount.unwrap_or(0),
+                    height,
+                    timestamp,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::Claim as i32, pool).await?;
+            } else if op_str.contains("DefineAsset") {
+                debug!("DefineAsset, height: {}", height);
+                let op_copy = op.clone();
+                let opt: DefineAssetOpt = serde_json::from_value(op)?;
+                let issuer = pubkey_to_fra_address(&opt.define_asset.pubkey.key)?;
+                let asset =
+                    engine::general_purpose::URL_SAFE.encode(opt.define_asset.body.asset.code.val);
+                save_asset_tx(
+                    &asset,
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &issuer,
+                    height,
+                    timestamp,
+                    0,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::DefineOrIssueAsset as i32, pool).await?;
+            } else if op_str.contains("IssueAsset") {
+                debug!("IssueAsset, height: {}", height);
+                let op_copy = op.clone();
+                let opt: IssueAssetOpt = serde_json::from_value(op)?;
+                let issuer = pubkey_to_fra_address(&opt.issue_asset.pubkey.key)?;
+                let asset = engine::general_purpose::URL_SAFE.encode(opt.issue_asset.body.code.val);
+                save_asset_tx(
+                    &asset,
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &issuer,
+                    height,
+                    timestamp,
+                    1,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::DefineOrIssueAsset as i32, pool).await?;
+            } else if op_str.contains("TransferAsset") {
+                debug!("TransferAsset, height: {}", height);
+                let op_copy = op.clone();
+                let opt: TransferAssetOpt = serde_json::from_value(op)?;
+                let key = &opt
+                    .transfer_asset
+                    .body_signatures
+                    .first()
+                    .ok_or("TransferAsset without body signatures")?
+                    .address
+                    .key;
+                let addr = pubkey_to_fra_address(key)?;
+                save_native_tx(
+                    &tx.to_lowercase(),
+                    &block.to_lowercase(),
+                    &addr,
+                    height,
+                    timestamp,
+                    &op_copy,
+                    pool,
+                )
+                .await?;
+                save_tx_type(tx, FindoraTxType::Native as i32, pool).await?;
             }
         }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use storage::db::{commit_signatures, load_prune_height, prune, save_bulk, test_pool};
+
+    #[tokio::test]
+    async fn test_prune() -> Result<()> {
+        let Some(pool) = test_pool("test_prune").await else {
+            return Ok(());
+        };
+        let mut blocks = vec![];
+        for h in 1..=3 {
+            let (block, txs, validators) = parse::test::inputs(h);
+            blocks
+                .push(parse::parse_block(block, 1024, txs, validators, &Default::default())?.block);
+        }
+        save_bulk(&blocks, &pool).await?;
+        let data = |h: i64| {
+            let pool = pool.clone();
+            async move {
+                let row = sqlx::query("SELECT block_data FROM block WHERE height=$1")
+                    .bind(h)
+                    .fetch_one(&pool)
+                    .await?;
+                row.try_get::<Value, _>("block_data")
+            }
+        };
+
+        // the compact prune goes on where it stopped.
+        assert_eq!(prune(0, 3, PruneMode::Compact, 1, &pool).await?, 2);
+        assert_eq!(load_prune_height(PruneMode::Compact, &pool).await?, 3);
+        assert_eq!(load_prune_height(PruneMode::Drop, &pool).await?, 0);
+        assert_eq!(data(2).await?["pruned"], "compact");
+        assert!(data(3).await?.get("pruned").is_none());
+
+        // a dropped block keeps the signatures block_generation doesn't, and the tx origins.
+        assert_eq!(prune(0, 4, PruneMode::Drop, 10, &pool).await?, 3);
+        assert_eq!(load_prune_height(PruneMode::Compact, &pool).await?, 4);
+        let block = data(3).await?;
+        assert_eq!(block["pruned"], "drop");
+        assert!(block["block"]["data"].get("txs").is_none());
+        let signatures = block["block"]["last_commit"]["signatures"]
+            .as_array()
+            .unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert!(signatures.iter().all(|s| s.get("signature").is_none()));
+        assert_eq!(commit_signatures(3, &pool).await?.len(), 2);
+        let blanked: i64 = sqlx::query_scalar("SELECT count(*) FROM transaction WHERE origin=''")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(blanked, 0);
         Ok(())
     }
 }
@@ -8,9 +8,16 @@ pub enum Error {
     SerdeJsonError(serde_json::Error),
     JoinError(tokio::task::JoinError),
     DBError(sqlx::Error),
+    StorageError(module::storage::StorageError),
+    SourceError(module::source::SourceError),
     TryIntoError(core::num::TryFromIntError),
+    IOError(std::io::Error),
     EvmTxParseError,
+    /// The rpc returned data failing its verification.
+    IntegrityError(String),
     NotFound,
+    /// The block is loaded but not verified yet, its commit comes with the block above.
+    Pending,
 }
 
 impl From<core::num::TryFromIntError> for Error {
@@ -19,6 +26,12 @@ impl From<core::num::TryFromIntError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::IOError(e)
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Self {
         Error::RequestError(e)
@@ -61,6 +74,24 @@ impl From<sqlx::Error> for Error {
     }
 }
 
+impl From<module::storage::StorageError> for Error {
+    fn from(e: module::storage::StorageError) -> Self {
+        match e {
+            module::storage::StorageError::NotFound => Error::NotFound,
+            e => Error::StorageError(e),
+        }
+    }
+}
+
+impl From<module::source::SourceError> for Error {
+    fn from(e: module::source::SourceError) -> Self {
+        match e {
+            module::source::SourceError::NotFound => Error::NotFound,
+            e => Error::SourceError(e),
+        }
+    }
+}
+
 impl From<String> for Error {
     fn from(e: String) -> Self {
         Error::CustomError(e)
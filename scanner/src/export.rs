@@ -0,0 +1,595 @@
+//! Export of the indexed tables to files, chunked by height, see `scanner-cli export`.
+use crate::Result;
+use futures::TryStreamExt;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Type of an exported column. Json columns are nested in JSONL and serialized text otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Text,
+    Json,
+}
+
+impl ColumnType {
+    fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Int => "int64",
+            ColumnType::Text => "string",
+            ColumnType::Json => "json",
+        }
+    }
+}
+
+/// An exported column, `expr` is evaluated against the source table.
+pub struct Column {
+    pub name: &'static str,
+    pub ty: ColumnType,
+    expr: &'static str,
+}
+
+const fn col(name: &'static str, ty: ColumnType, expr: &'static str) -> Column {
+    Column { name, ty, expr }
+}
+
+/// A set of rows exported with a fixed schema.
+pub struct Dataset {
+    pub name: &'static str,
+    table: &'static str,
+    filter: &'static str,
+    order: &'static str,
+    pub columns: &'static [Column],
+}
+
+use ColumnType::{Int, Json, Text};
+
+/// `block_data` as the rpc returned it, the parts `scanner-cli prune` stripped taken back from
+/// the tables, so an exported block holds its txs whatever was pruned.
+const UNPRUNED_BLOCK_DATA: &str = "CASE WHEN block_data ? 'pruned' THEN jsonb_set(jsonb_set(block_data, '{block,data,txs}', \
+    COALESCE((SELECT jsonb_agg(origin ORDER BY tx_index) FROM (SELECT origin,tx_index FROM transaction x \
+    WHERE x.network=block.network AND x.height=block.height UNION ALL SELECT origin,tx_index FROM quarantined_txs q \
+    WHERE q.network=block.network AND q.height=block.height) o), 'null')), '{block,last_commit,signatures}', \
+    COALESCE((SELECT jsonb_agg(CASE WHEN s ? 'signature' OR g.signature IS NULL THEN s ELSE s || jsonb_build_object('signature', g.signature) END ORDER BY i) \
+    FROM jsonb_array_elements(CASE WHEN jsonb_typeof(block_data #> '{block,last_commit,signatures}')='array' \
+    THEN block_data #> '{block,last_commit,signatures}' ELSE '[]' END) WITH ORDINALITY AS e(s, i) \
+    LEFT JOIN block_generation g ON g.network=block.network AND g.height=block.height AND g.address=s->>'validator_address'), \
+    block_data #> '{block,last_commit,signatures}', 'null')) - 'pruned' ELSE block_data END";
+
+pub const DATASETS: &[Dataset] = &[
+    Dataset {
+        name: "blocks",
+        table: "block",
+        filter: "",
+        order: "height",
+        columns: &[
+            col("block_hash", Text, "block_hash"),
+            col("height", Int, "height"),
+            col("size", Int, "size"),
+            col("tx_count", Int, "tx_count"),
+            col("time", Int, "extract(epoch from time)::bigint"),
+            col("app_hash", Text, "app_hash"),
+            col("proposer", Text, "proposer"),
+            col("block_data", Json, UNPRUNED_BLOCK_DATA),
+        ],
+    },
+    Dataset {
+        name: "transactions",
+        table: "transaction",
+        filter: "",
+        order: "height,tx_index",
+        columns: &[
+            col("tx_hash", Text, "tx_hash"),
+            col("block_hash", Text, "block_hash"),
+            col("height", Int, "height"),
+            col("tx_index", Int, "tx_index"),
+            col("timestamp", Int, "timestamp"),
+            col("code", Int, "code"),
+            col("ty", Int, "ty"),
+            col("ty_sub", Int, "ty_sub"),
+            col("sender", Text, "sender"),
+            col("receiver", Json, "receiver"),
+            col("log", Text, "log"),
+            col("origin", Text, "origin"),
+            col("result", Json, "result"),
+            col("value", Json, "value"),
+        ],
+    },
+    Dataset {
+        name: "transfers",
+        table: "operations",
+        filter: "AND kind='TransferAsset'",
+        order: "height,tx,op_index",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("op_index", Int, "op_index"),
+            col("sender", Text, "signer"),
+            col("receiver", Text, "counterparty"),
+            col("asset", Text, "asset"),
+            col("amount", Text, "amount"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+        ],
+    },
+    Dataset {
+        name: "delegations",
+        table: "delegations",
+        filter: "",
+        order: "height,tx",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("block_hash", Text, "block"),
+            col("sender", Text, "sender"),
+            col("amount", Int, "amount"),
+            col("validator", Text, "validator"),
+            col("new_validator", Text, "new_validator"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "content"),
+        ],
+    },
+    Dataset {
+        name: "undelegations",
+        table: "undelegations",
+        filter: "",
+        order: "height,tx",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("block_hash", Text, "block"),
+            col("sender", Text, "sender"),
+            col("amount", Int, "amount"),
+            col("target_validator", Text, "target_validator"),
+            col("new_delegator", Text, "new_delegator"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "content"),
+        ],
+    },
+    Dataset {
+        name: "claims",
+        table: "claims",
+        filter: "",
+        order: "height,tx",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("block_hash", Text, "block"),
+            col("sender", Text, "sender"),
+            col("amount", Int, "amount"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "content"),
+        ],
+    },
+    Dataset {
+        name: "validator_events",
+        table: "validator_events",
+        filter: "",
+        order: "height,tx,op_index",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("op_index", Int, "op_index"),
+            col("block_hash", Text, "block"),
+            col("validator", Text, "validator"),
+            col("staker", Text, "staker"),
+            col("kind", Text, "kind"),
+            col("commission_rate", Json, "commission_rate"),
+            col("memo", Json, "memo"),
+            col("new_staker", Text, "new_staker"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "content"),
+        ],
+    },
+    Dataset {
+        name: "n2e",
+        table: "n2e",
+        filter: "",
+        order: "height,tx",
+        columns: &[
+            col("tx_hash", Text, "tx"),
+            col("block_hash", Text, "block"),
+            col("sender", Text, "sender"),
+            col("receiver", Text, "receiver"),
+            col("asset", Text, "asset"),
+            col("amount", Text, "amount"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "content"),
+        ],
+    },
+    Dataset {
+        name: "e2n",
+        table: "e2n",
+        filter: "",
+        order: "height,tx_hash",
+        columns: &[
+            col("tx_hash", Text, "tx_hash"),
+            col("block_hash", Text, "block_hash"),
+            col("sender", Text, "sender"),
+            col("receiver", Text, "receiver"),
+            col("asset", Text, "asset"),
+            col("amount", Text, "amount"),
+            col("decimal", Int, "decimal"),
+            col("height", Int, "height"),
+            col("timestamp", Int, "timestamp"),
+            col("content", Json, "value"),
+        ],
+    },
+];
+
+impl Dataset {
+    pub fn find(name: &str) -> Option<&'static Dataset> {
+        DATASETS.iter().find(|d| d.name == name)
+    }
+
+    fn query(&self) -> String {
+        let list: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| format!("{} AS {}", c.expr, c.name))
+            .collect();
+        format!(
+            "SELECT to_jsonb(t) AS row FROM (SELECT {} FROM {} WHERE network=current_network() AND height>=$1 AND height<$2 {} ORDER BY {}) t",
+            list.join(","),
+            self.table,
+            self.filter,
+            self.order
+        )
+    }
+
+    fn schema(&self) -> Value {
+        let columns: Vec<Value> = self
+            .columns
+            .iter()
+            .map(|c| json!({"name": c.name, "type": c.ty.name()}))
+            .collect();
+        json!({ "dataset": self.name, "columns": columns })
+    }
+}
+
+/// Path of the chunk holding heights `[from, to)`, a partial one is marked in its extension.
+pub fn chunk_path(dir: &Path, dataset: &str, from: i64, to: i64, ext: &str, partial: bool) -> PathBuf {
+    let ext = if partial {
+        format!("partial.{ext}")
+    } else {
+        ext.to_string()
+    };
+    dir.join(dataset)
+        .join(format!("{:012}-{:012}.{}", from, to - 1, ext))
+}
+
+/// The chunks of `[start, end)` as `(from, to, complete)`, aligned to multiples of `chunk`.
+///
+/// The first and the last chunk are partial unless the range starts and ends on a boundary.
+pub fn chunks(start: i64, end: i64, chunk: i64) -> Vec<(i64, i64, bool)> {
+    let mut chunks = vec![];
+    let mut from = start;
+    while from < end {
+        let lo = from / chunk * chunk;
+        let to = (lo + chunk).min(end);
+        chunks.push((from, to, from == lo && to == lo + chunk));
+        from = to;
+    }
+    chunks
+}
+
+/// Remove the partial files of the chunk starting at `lo` but `keep`, the heights they hold
+/// are written again.
+fn remove_partials(dir: &Path, lo: i64, chunk: i64, keep: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let from = name.get(..12).and_then(|f| f.parse::<i64>().ok());
+        if name.contains(".partial.")
+            && from.is_some_and(|f| f / chunk * chunk == lo)
+            && path != keep
+        {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Export `dataset` for heights `[start, end)`, one file per `chunk` heights, see [`chunks`].
+///
+/// A chunk is written to a temporary file and renamed when complete, existing complete chunks
+/// are skipped so an interrupted export resumes where it stopped. Partial chunks are written
+/// again by every export, until the chunk is complete. Returns the rows written.
+pub async fn export(
+    dataset: &Dataset,
+    start: i64,
+    end: i64,
+    chunk: i64,
+    dir: &Path,
+    format: ExportFormat,
+    pool: &PgPool,
+) -> Result<u64> {
+    let dataset_dir = dir.join(dataset.name);
+    fs::create_dir_all(&dataset_dir)?;
+    let schema = serde_json::to_vec_pretty(&dataset.schema())?;
+    fs::write(dataset_dir.join("schema.json"), schema)?;
+
+    let sql = dataset.query();
+    let mut rows = 0;
+    for (from, to, complete) in chunks(start, end, chunk) {
+        let path = chunk_path(dir, dataset.name, from, to, format.extension(), !complete);
+        if complete && path.exists() {
+            debug!("{} exists, skipped.", path.display());
+            continue;
+        }
+
+        let tmp = path.with_extension(format!("{}.tmp", format.extension()));
+        let mut writer = ChunkWriter::create(&tmp, format, dataset.columns)?;
+        let mut cursor = sqlx::query(&sql).bind(from).bind(to).fetch(pool);
+        while let Some(row) = cursor.try_next().await? {
+            let row: Value = row.try_get("row")?;
+            writer.write(&row)?;
+            rows += 1;
+        }
+        writer.finish()?;
+        fs::rename(&tmp, &path)?;
+        remove_partials(&dataset_dir, from / chunk * chunk, chunk, &path)?;
+        info!("Exported {}.", path.display());
+    }
+
+    Ok(rows)
+}
+
+enum ChunkWriter {
+    Jsonl(BufWriter<File>),
+    Csv(BufWriter<File>, &'static [Column]),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet_chunk::ParquetChunk),
+}
+
+impl ChunkWriter {
+    fn create(path: &Path, format: ExportFormat, columns: &'static [Column]) -> Result<Self> {
+        let file = || File::create(path).map(BufWriter::new);
+        match format {
+            ExportFormat::Jsonl => Ok(ChunkWriter::Jsonl(file()?)),
+            ExportFormat::Csv => {
+                let mut w = file()?;
+                let header: Vec<&str> = columns.iter().map(|c| c.name).collect();
+                writeln!(w, "{}", header.join(","))?;
+                Ok(ChunkWriter::Csv(w, columns))
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Ok(ChunkWriter::Parquet(parquet_chunk::ParquetChunk::new(
+                File::create(path)?,
+                columns,
+            )?)),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => Err("built without the `parquet` feature".into()),
+        }
+    }
+
+    fn write(&mut self, row: &Value) -> Result<()> {
+        match self {
+            ChunkWriter::Jsonl(w) => {
+                serde_json::to_writer(&mut *w, row)?;
+                Ok(writeln!(w)?)
+            }
+            ChunkWriter::Csv(w, columns) => {
+                let fields: Vec<String> = columns.iter().map(|c| csv_field(&row[c.name])).collect();
+                Ok(writeln!(w, "{}", fields.join(","))?)
+            }
+            #[cfg(feature = "parquet")]
+            ChunkWriter::Parquet(p) => p.write(row),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            ChunkWriter::Jsonl(mut w) | ChunkWriter::Csv(mut w, _) => Ok(w.flush()?),
+            #[cfg(feature = "parquet")]
+            ChunkWriter::Parquet(p) => p.finish(),
+        }
+    }
+}
+
+/// A CSV field, quoted when needed. Json values are written as their compact text.
+fn csv_field(v: &Value) -> String {
+    let s = match v {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    };
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_chunk {
+    use super::{Column, ColumnType};
+    use crate::{Error, Result};
+    use arrow_array::builder::{Int64Builder, StringBuilder};
+    use arrow_array::{ArrayRef, RecordBatch};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use serde_json::Value;
+    use std::fs::File;
+    use std::sync::Arc;
+
+    const ROW_GROUP_ROWS: usize = 65536;
+
+    enum Builder {
+        Int(Int64Builder),
+        Text(StringBuilder),
+    }
+
+    pub(super) struct ParquetChunk {
+        writer: ArrowWriter<File>,
+        schema: Arc<Schema>,
+        columns: &'static [Column],
+        builders: Vec<Builder>,
+        rows: usize,
+    }
+
+    fn builders(columns: &[Column]) -> Vec<Builder> {
+        columns
+            .iter()
+            .map(|c| match c.ty {
+                ColumnType::Int => Builder::Int(Int64Builder::new()),
+                ColumnType::Text | ColumnType::Json => Builder::Text(StringBuilder::new()),
+            })
+            .collect()
+    }
+
+    impl ParquetChunk {
+        pub(super) fn new(file: File, columns: &'static [Column]) -> Result<Self> {
+            let fields: Vec<Field> = columns
+                .iter()
+                .map(|c| match c.ty {
+                    ColumnType::Int => Field::new(c.name, DataType::Int64, true),
+                    ColumnType::Text | ColumnType::Json => Field::new(c.name, DataType::Utf8, true),
+                })
+                .collect();
+            let schema = Arc::new(Schema::new(fields));
+            let writer = ArrowWriter::try_new(file, schema.clone(), None)
+                .map_err(|e| Error::from(e.to_string()))?;
+            Ok(ParquetChunk {
+                writer,
+                schema,
+                columns,
+                builders: builders(columns),
+                rows: 0,
+            })
+        }
+
+        pub(super) fn write(&mut self, row: &Value) -> Result<()> {
+            for (c, b) in self.columns.iter().zip(self.builders.iter_mut()) {
+                let v = &row[c.name];
+                match b {
+                    Builder::Int(b) => b.append_option(v.as_i64()),
+                    Builder::Text(b) => match v {
+                        Value::Null => b.append_null(),
+                        Value::String(s) if c.ty == ColumnType::Text => b.append_value(s),
+                        v => b.append_value(v.to_string()),
+                    },
+                }
+            }
+            self.rows += 1;
+            if self.rows >= ROW_GROUP_ROWS {
+                self.flush()?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            if self.rows == 0 {
+                return Ok(());
+            }
+            let arrays: Vec<ArrayRef> = self
+                .builders
+                .iter_mut()
+                .map(|b| match b {
+                    Builder::Int(b) => Arc::new(b.finish()) as ArrayRef,
+                    Builder::Text(b) => Arc::new(b.finish()) as ArrayRef,
+                })
+                .collect();
+            let batch = RecordBatch::try_new(self.schema.clone(), arrays)
+                .map_err(|e| Error::from(e.to_string()))?;
+            self.writer
+                .write(&batch)
+                .map_err(|e| Error::from(e.to_string()))?;
+            self.rows = 0;
+            Ok(())
+        }
+
+        pub(super) fn finish(mut self) -> Result<()> {
+            self.flush()?;
+            self.writer
+                .close()
+                .map_err(|e| Error::from(e.to_string()))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field(&Value::Null), "");
+        assert_eq!(csv_field(&json!(42)), "42");
+        assert_eq!(csv_field(&json!("fra1abc")), "fra1abc");
+        assert_eq!(csv_field(&json!("a,\"b\"")), "\"a,\"\"b\"\"\"");
+        assert_eq!(
+            csv_field(&json!({"addrs": ["x"]})),
+            "\"{\"\"addrs\"\":[\"\"x\"\"]}\""
+        );
+    }
+
+    #[test]
+    fn test_chunk_path() {
+        let p = chunk_path(Path::new("out"), "blocks", 100, 200, "jsonl", false);
+        assert_eq!(p, Path::new("out/blocks/000000000100-000000000199.jsonl"));
+        let p = chunk_path(Path::new("out"), "blocks", 100, 150, "jsonl", true);
+        assert_eq!(p, Path::new("out/blocks/000000000100-000000000149.partial.jsonl"));
+    }
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(
+            chunks(150, 420, 100),
+            [
+                (150, 200, false),
+                (200, 300, true),
+                (300, 400, true),
+                (400, 420, false)
+            ]
+        );
+        assert_eq!(chunks(0, 200, 100), [(0, 100, true), (100, 200, true)]);
+        assert_eq!(chunks(5, 5, 100), []);
+    }
+
+    #[test]
+    fn test_remove_partials() {
+        let dir = std::env::temp_dir().join(format!("export-partials-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let names = [
+            "000000000100-000000000149.partial.jsonl",
+            "000000000120-000000000179.partial.jsonl",
+            "000000000200-000000000249.partial.jsonl",
+            "000000000100-000000000199.jsonl",
+        ];
+        for name in names {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        remove_partials(&dir, 100, 100, &dir.join(names[3])).unwrap();
+        let mut left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, [names[3], names[2]]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
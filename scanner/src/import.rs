@@ -0,0 +1,285 @@
+//! Bootstrap of a database from the `blocks` and `transactions` files of `scanner-cli export`.
+use crate::{verify, Result};
+use base64::{engine, Engine};
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sha2::Digest;
+use sqlx::{PgPool, Row};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use storage::db;
+
+/// Rows inserted per statement.
+const IMPORT_BATCH: usize = 1000;
+
+#[derive(Debug, Default)]
+pub struct ImportStats {
+    pub blocks: u64,
+    pub txs: u64,
+    pub first: i64,
+    pub last: i64,
+}
+
+/// JSONL chunks of `dataset` in height order.
+fn chunk_files(dir: &Path, dataset: &str) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir.join(dataset))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "jsonl") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Check a block row against its block data and its txs against the `data_hash` of its
+/// header, returns its height.
+///
+/// `time` is taken from the header, the exported one is truncated to seconds.
+pub(crate) fn verify_block(row: &mut Value) -> Result<i64> {
+    let height = row["height"].as_i64().ok_or("block without height")?;
+    let header = &row["block_data"]["block"]["header"];
+    if row["block_data"]["block_id"]["hash"] != row["block_hash"] {
+        return Err(format!("Block hash mismatch at height {height}.").into());
+    }
+    if header["height"].as_str() != Some(height.to_string().as_str()) {
+        return Err(format!("Header height mismatch at height {height}.").into());
+    }
+    if row["block_data"].get("pruned").is_some() {
+        return Err(format!("Block at height {height} is pruned, export it again.").into());
+    }
+    let txs: Option<Vec<String>> =
+        serde_json::from_value(row["block_data"]["block"]["data"]["txs"].clone())?;
+    let data_hash = header["data_hash"].as_str().unwrap_or_default();
+    if let Some(m) = verify::verify_txs(height, data_hash, &txs.unwrap_or_default()).first() {
+        return Err(format!("Block does not match its header, {m}.").into());
+    }
+    let time = header["time"].as_str().ok_or("block without time")?;
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.fZ")?;
+    row["time"] = Value::String(time.format("%Y-%m-%dT%H:%M:%S%.f").to_string());
+    Ok(height)
+}
+
+/// Check a tx row against its raw bytes, returns its height.
+pub(crate) fn verify_tx(row: &Value) -> Result<i64> {
+    let height = row["height"].as_i64().ok_or("tx without height")?;
+    let tx_hash = row["tx_hash"].as_str().ok_or("tx without hash")?;
+    let origin = row["origin"].as_str().unwrap_or_default();
+    let bytes = engine::general_purpose::STANDARD.decode(origin)?;
+    if hex::encode(sha2::Sha256::digest(bytes)) != tx_hash {
+        return Err(format!("Tx hash mismatch: {tx_hash}.").into());
+    }
+    Ok(height)
+}
+
+async fn insert(table: &str, rows: &mut Vec<Value>, pool: &PgPool) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let heights: Vec<i64> = rows.iter().filter_map(|r| r["height"].as_i64()).collect();
+    db::ensure_partitions(&heights, pool).await?;
+    // exported rows carry no network, they are imported into the one of the pool.
+    sqlx::query(&format!(
+        "INSERT INTO {table} SELECT * FROM jsonb_populate_recordset(jsonb_populate_record(NULL::{table}, jsonb_build_object('network', current_network())), $1) ON CONFLICT DO NOTHING"
+    ))
+    .bind(Value::Array(std::mem::take(rows)))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Load the exported blocks and transactions under `dir`.
+///
+/// Blocks must be contiguous, and follow the indexed ones without a gap, every tx must be
+/// one of the txs of an imported block. Rows already present are kept, so an interrupted
+/// import can be run again. `last_height` is moved to the last imported block, never lowered.
+pub async fn import(dir: &Path, pool: &PgPool) -> Result<ImportStats> {
+    let indexed = match db::load_last_height(pool).await {
+        Ok(h) => Some(h),
+        Err(sqlx::Error::RowNotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let mut stats = ImportStats::default();
+    let mut expected: Option<i64> = None;
+    let mut rows = vec![];
+    for file in chunk_files(dir, "blocks")? {
+        for line in BufReader::new(File::open(&file)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut row: Value = serde_json::from_str(&line)?;
+            let h = verify_block(&mut row)?;
+            match expected {
+                Some(e) if h != e => {
+                    return Err(format!("Heights [{e}, {h}) missing in {}.", file.display()).into())
+                }
+                None => {
+                    if let Some(last) = indexed.filter(|last| h > last + 1) {
+                        return Err(format!(
+                            "Heights [{}, {h}) between the indexed blocks and {} missing.",
+                            last + 1,
+                            file.display()
+                        )
+                        .into());
+                    }
+                    stats.first = h
+                }
+                _ => {}
+            }
+            expected = Some(h + 1);
+            stats.last = h;
+            stats.blocks += 1;
+            rows.push(row);
+            if rows.len() >= IMPORT_BATCH {
+                insert("block", &mut rows, pool).await?;
+            }
+        }
+        insert("block", &mut rows, pool).await?;
+        info!("Imported {}.", file.display());
+    }
+    if stats.blocks == 0 {
+        return Err(format!("No block found under {}.", dir.display()).into());
+    }
+
+    for file in chunk_files(dir, "transactions")? {
+        for line in BufReader::new(File::open(&file)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row: Value = serde_json::from_str(&line)?;
+            let h = verify_tx(&row)?;
+            if h < stats.first || h > stats.last {
+                return Err(format!("Tx at height {h} out of the imported blocks.").into());
+            }
+            stats.txs += 1;
+            rows.push(row);
+            if rows.len() >= IMPORT_BATCH {
+                insert("transaction", &mut rows, pool).await?;
+            }
+        }
+        insert("transaction", &mut rows, pool).await?;
+        info!("Imported {}.", file.display());
+    }
+
+    let row = sqlx::query("SELECT count(*) AS orphans FROM transaction t WHERE t.network=current_network() AND t.height>=$1 AND t.height<=$2 AND NOT EXISTS (SELECT 1 FROM block b WHERE b.network=t.network AND b.height=t.height AND b.block_hash=t.block_hash AND b.block_data#>>ARRAY['block','data','txs',t.tx_index::text]=t.origin)")
+        .bind(stats.first)
+        .bind(stats.last)
+        .fetch_one(pool)
+        .await?;
+    let orphans: i64 = row.try_get("orphans")?;
+    if orphans > 0 {
+        return Err(format!("{orphans} txs do not match the block at their height.").into());
+    }
+
+    db::save_last_height(stats.last.max(indexed.unwrap_or_default()), pool).await?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_block() {
+        let mut row = json!({
+            "block_hash": "AB",
+            "height": 7,
+            "time": 1650000000,
+            "block_data": {
+                "block_id": {"hash": "AB"},
+                "block": {"header": {"height": "7", "time": "2022-04-15T05:20:00.123456Z"}}
+            }
+        });
+        assert_eq!(verify_block(&mut row).unwrap(), 7);
+        assert_eq!(row["time"], "2022-04-15T05:20:00.123456");
+
+        row["block_hash"] = json!("CD");
+        assert!(verify_block(&mut row).is_err());
+
+        // the txs must hash to the data hash.
+        row["block_hash"] = json!("AB");
+        row["block_data"]["block"]["data"] = json!({"txs": ["eHg="]});
+        assert!(verify_block(&mut row).is_err());
+        row["block_data"]["block"]["data"] = json!({"txs": null});
+        row["block_data"]["pruned"] = json!("compact");
+        assert!(verify_block(&mut row).is_err());
+    }
+
+    #[test]
+    fn test_verify_tx() {
+        let origin = engine::general_purpose::STANDARD.encode(b"tx");
+        let tx_hash = hex::encode(sha2::Sha256::digest(b"tx"));
+        let row = json!({"tx_hash": tx_hash, "height": 7, "origin": origin});
+        assert_eq!(verify_tx(&row).unwrap(), 7);
+
+        let row = json!({"tx_hash": "00", "height": 7, "origin": origin});
+        assert!(verify_tx(&row).is_err());
+
+        let row = json!({"tx_hash": "00", "height": 7, "origin": ""});
+        assert!(verify_tx(&row).is_err());
+    }
+
+    /// An export directory holding the fixture blocks at `heights`.
+    fn export_dir(name: &str, heights: &[i64], tamper: Option<i64>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("blocks")).unwrap();
+        fs::create_dir_all(dir.join("transactions")).unwrap();
+        let fixtures = mock_rpc::fixtures_dir().join("block");
+        let mut lines = vec![];
+        for h in heights {
+            let mut data: Value =
+                serde_json::from_slice(&fs::read(fixtures.join(format!("{h}.json"))).unwrap())
+                    .unwrap();
+            if tamper == Some(*h) {
+                data["block"]["header"]["data_hash"] = json!("00".repeat(32));
+            }
+            let header = &data["block"]["header"];
+            lines.push(
+                json!({
+                    "block_hash": data["block_id"]["hash"], "height": h, "size": 1,
+                    "tx_count": 0, "time": 0, "app_hash": header["app_hash"],
+                    "proposer": header["proposer_address"], "block_data": data
+                })
+                .to_string(),
+            );
+        }
+        fs::write(
+            dir.join("blocks/000000000000-000000000099.partial.jsonl"),
+            lines.join("\n"),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_import() -> Result<()> {
+        let Some(pool) = db::test_pool("test_import").await else {
+            return Ok(());
+        };
+        db::save_last_height(1, &pool).await?;
+        let gap = export_dir("import-gap", &[3], None);
+        assert!(import(&gap, &pool).await.is_err());
+        let tampered = export_dir("import-tampered", &[1, 2, 3], Some(2));
+        assert!(import(&tampered, &pool).await.is_err());
+
+        let full = export_dir("import-full", &[1, 2, 3], None);
+        let stats = import(&full, &pool).await?;
+        assert_eq!((stats.first, stats.last, stats.blocks), (1, 3, 3));
+        assert_eq!(db::load_last_height(&pool).await?, 3);
+
+        // an import below the indexed blocks keeps the last height.
+        db::save_last_height(10, &pool).await?;
+        import(&full, &pool).await?;
+        assert_eq!(db::load_last_height(&pool).await?, 10);
+        for dir in [gap, tampered, full] {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
@@ -2,16 +2,27 @@
 extern crate log;
 
 pub mod commands;
-pub mod db;
 pub mod error;
+pub mod export;
+pub mod import;
+pub mod light;
+pub mod parse;
 pub mod rpc;
 pub mod scanner;
+pub mod status;
 pub mod tx;
 pub mod types;
 mod util;
+pub mod verify;
 
 pub use error::{Error, Result};
 
+/// The config of the file at `CONFIG_FILE_PATH` and the env, validated.
+pub fn config() -> Result<module::config::scanner_config::Config> {
+    module::config::scanner_config::Config::load()
+        .map_err(|e| Error::from(format!("Invalid config: {e}")))
+}
+
 pub mod schema {
     pub use module::schema::*;
 }
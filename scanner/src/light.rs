@@ -0,0 +1,697 @@
+//! Light client checks of the blocks an rpc returns: a block is the chain's if more than 2/3 of
+//! the voting power of its validators signed it, and its validators are the ones named by the
+//! block below, back to a trusted block.
+//!
+//! Headers, validator sets and votes are hashed and signed as tendermint encodes them, amino
+//! for block protocol 10 (tendermint 0.33) and protobuf for 11 (tendermint 0.34).
+use crate::verify::Mismatch;
+use base64::{engine, Engine};
+use chrono::DateTime;
+use module::config::scanner_config::LightClientConfig;
+use module::rpc::block::{BlockHeader, BlockId, BlockRPC as ModuleBlockRPC, LastCommit};
+use module::rpc::validator::{Validator, ValidatorsRPC};
+use module::utils::merkle::{self, Hash};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::Digest;
+
+/// `block_id_flag` of a signature for the committed block.
+const BLOCK_ID_FLAG_COMMIT: i64 = 2;
+/// `SignedMsgType` of a precommit.
+const PRECOMMIT: u64 = 2;
+/// Amino prefix of `tendermint/PubKeyEd25519` and the length of the key.
+const AMINO_ED25519_PREFIX: [u8; 5] = [0x16, 0x24, 0xDE, 0x64, 0x20];
+
+/// The block the checks start from, its height and hash taken on trust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trust {
+    pub height: i64,
+    pub hash: String,
+}
+
+impl Trust {
+    /// The trusted block of the config, `None` if it sets none.
+    pub fn from_config(config: &LightClientConfig) -> Option<Self> {
+        Some(Trust {
+            height: config.trusted_height?,
+            hash: config.trusted_hash.clone()?,
+        })
+    }
+
+    /// Whether the block at `height` is checked, those below the trusted one are not.
+    pub fn covers(&self, height: i64) -> bool {
+        height >= self.height
+    }
+}
+
+/// A verified block, the one above it must link to.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub height: i64,
+    pub hash: String,
+    pub header: BlockHeader,
+}
+
+impl Link {
+    pub fn of(block: &ModuleBlockRPC) -> Self {
+        Link {
+            height: block.block.header.height.parse().unwrap_or_default(),
+            hash: block.block_id.hash.clone(),
+            header: block.block.header.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Tendermint 0.33.
+    Amino,
+    /// Tendermint 0.34.
+    Proto,
+}
+
+impl Encoding {
+    pub fn of(header: &BlockHeader) -> Result<Self, String> {
+        match header.version.block.as_str() {
+            "10" => Ok(Encoding::Amino),
+            "11" => Ok(Encoding::Proto),
+            v => Err(format!("unsupported block protocol {v}")),
+        }
+    }
+}
+
+fn uvarint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// Fields at their zero value are left out, as both encodings do.
+fn field_varint(buf: &mut Vec<u8>, field: u64, n: u64) {
+    if n != 0 {
+        uvarint(buf, field << 3);
+        uvarint(buf, n);
+    }
+}
+
+fn field_fixed64(buf: &mut Vec<u8>, field: u64, n: i64) {
+    if n != 0 {
+        uvarint(buf, field << 3 | 1);
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+fn field_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        field_message(buf, field, bytes);
+    }
+}
+
+/// A length delimited field written even if empty, as are embedded structs.
+fn field_message(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    uvarint(buf, field << 3 | 2);
+    uvarint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+/// Bytes prefixed with their length, empty if there are none.
+fn length_prefixed(bytes: &[u8]) -> Vec<u8> {
+    let mut buf = vec![];
+    if !bytes.is_empty() {
+        uvarint(&mut buf, bytes.len() as u64);
+        buf.extend_from_slice(bytes);
+    }
+    buf
+}
+
+fn decode_hex(name: &str, s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s).map_err(|e| format!("{name} is not hex: {e}"))
+}
+
+fn parse_u64(name: &str, s: &str) -> Result<u64, String> {
+    s.parse().map_err(|e| format!("{name} {s}: {e}"))
+}
+
+fn timestamp(time: &str) -> Result<Vec<u8>, String> {
+    let t = DateTime::parse_from_rfc3339(time).map_err(|e| format!("time {time}: {e}"))?;
+    let mut buf = vec![];
+    field_varint(&mut buf, 1, t.timestamp() as u64);
+    field_varint(&mut buf, 2, t.timestamp_subsec_nanos() as u64);
+    Ok(buf)
+}
+
+/// `BlockID` of a header, with its part set header.
+fn block_id(id: &BlockId) -> Result<Vec<u8>, String> {
+    let mut parts = vec![];
+    field_varint(&mut parts, 1, parse_u64("parts total", &id.parts.total)?);
+    field_bytes(&mut parts, 2, &decode_hex("parts hash", &id.parts.hash)?);
+    let mut buf = vec![];
+    field_bytes(&mut buf, 1, &decode_hex("block hash", &id.hash)?);
+    field_message(&mut buf, 2, &parts);
+    Ok(buf)
+}
+
+/// `CanonicalBlockID` of a vote, empty for a vote for nil.
+fn canonical_block_id(encoding: Encoding, id: &BlockId) -> Result<Vec<u8>, String> {
+    let hash = decode_hex("block hash", &id.hash)?;
+    let parts_hash = decode_hex("parts hash", &id.parts.hash)?;
+    if hash.is_empty() && parts_hash.is_empty() {
+        return Ok(vec![]);
+    }
+    let total = parse_u64("parts total", &id.parts.total)?;
+    let mut parts = vec![];
+    let mut buf = vec![];
+    field_bytes(&mut buf, 1, &hash);
+    match encoding {
+        Encoding::Amino => {
+            field_bytes(&mut parts, 1, &parts_hash);
+            field_varint(&mut parts, 2, total);
+            field_bytes(&mut buf, 2, &parts);
+        }
+        Encoding::Proto => {
+            field_varint(&mut parts, 1, total);
+            field_bytes(&mut parts, 2, &parts_hash);
+            field_message(&mut buf, 2, &parts);
+        }
+    }
+    Ok(buf)
+}
+
+/// Hash of a header, its block hash.
+pub fn header_hash(encoding: Encoding, header: &BlockHeader) -> Result<Hash, String> {
+    let mut version = vec![];
+    field_varint(
+        &mut version,
+        1,
+        parse_u64("block version", &header.version.block)?,
+    );
+    field_varint(
+        &mut version,
+        2,
+        parse_u64("app version", &header.version.app)?,
+    );
+    let height = parse_u64("height", &header.height)?;
+
+    let hashes = [
+        ("last_commit_hash", &header.last_commit_hash),
+        ("data_hash", &header.data_hash),
+        ("validators_hash", &header.validators_hash),
+        ("next_validators_hash", &header.next_validators_hash),
+        ("consensus_hash", &header.consensus_hash),
+        ("app_hash", &header.app_hash),
+        ("last_results_hash", &header.last_results_hash),
+        ("evidence_hash", &header.evidence_hash),
+        ("proposer_address", &header.proposer_address),
+    ];
+    let mut leaves = vec![
+        version,
+        vec![],
+        vec![],
+        timestamp(&header.time)?,
+        block_id(&header.last_block_id)?,
+    ];
+    // amino encodes the scalars bare, protobuf wraps them in a message.
+    match encoding {
+        Encoding::Amino => {
+            leaves[1] = length_prefixed(header.chain_id.as_bytes());
+            uvarint(&mut leaves[2], height);
+            for (name, hash) in hashes {
+                leaves.push(length_prefixed(&decode_hex(name, hash)?));
+            }
+        }
+        Encoding::Proto => {
+            field_bytes(&mut leaves[1], 1, header.chain_id.as_bytes());
+            field_varint(&mut leaves[2], 1, height);
+            for (name, hash) in hashes {
+                let mut leaf = vec![];
+                field_bytes(&mut leaf, 1, &decode_hex(name, hash)?);
+                leaves.push(leaf);
+            }
+        }
+    }
+    Ok(merkle::root(&leaves))
+}
+
+fn ed25519_key(validator: &Validator) -> Result<Vec<u8>, String> {
+    if validator.pub_key.r#type != "tendermint/PubKeyEd25519" {
+        return Err(format!(
+            "validator {} has a {} key",
+            validator.address, validator.pub_key.r#type
+        ));
+    }
+    let key = engine::general_purpose::STANDARD
+        .decode(&validator.pub_key.value)
+        .map_err(|e| format!("key of validator {}: {e}", validator.address))?;
+    if key.len() != 32 {
+        return Err(format!(
+            "key of validator {} is not 32 bytes",
+            validator.address
+        ));
+    }
+    Ok(key)
+}
+
+/// Hash of a validator set, in the order the rpc gives it.
+pub fn validators_hash(encoding: Encoding, validators: &[Validator]) -> Result<Hash, String> {
+    let mut leaves = vec![];
+    for v in validators {
+        let key = ed25519_key(v)?;
+        let mut pub_key = vec![];
+        match encoding {
+            Encoding::Amino => {
+                pub_key.extend_from_slice(&AMINO_ED25519_PREFIX);
+                pub_key.extend_from_slice(&key);
+            }
+            Encoding::Proto => field_bytes(&mut pub_key, 1, &key),
+        }
+        let mut leaf = vec![];
+        field_bytes(&mut leaf, 1, &pub_key);
+        field_varint(&mut leaf, 2, parse_u64("voting power", &v.voting_power)?);
+        leaves.push(leaf);
+    }
+    Ok(merkle::root(&leaves))
+}
+
+/// Bytes a validator signs to precommit `block_id`, the length prefixed `CanonicalVote`.
+pub fn vote_sign_bytes(
+    encoding: Encoding,
+    chain_id: &str,
+    height: i64,
+    round: i64,
+    block_id: &BlockId,
+    time: &str,
+) -> Result<Vec<u8>, String> {
+    let mut vote = vec![];
+    field_varint(&mut vote, 1, PRECOMMIT);
+    field_fixed64(&mut vote, 2, height);
+    field_fixed64(&mut vote, 3, round);
+    field_bytes(&mut vote, 4, &canonical_block_id(encoding, block_id)?);
+    field_message(&mut vote, 5, &timestamp(time)?);
+    field_bytes(&mut vote, 6, chain_id.as_bytes());
+
+    let mut buf = vec![];
+    uvarint(&mut buf, vote.len() as u64);
+    buf.extend(vote);
+    Ok(buf)
+}
+
+/// Check more than 2/3 of the voting power of `validators` signed `commit`, its signatures
+/// being in the order of the validators.
+pub fn verify_commit(
+    encoding: Encoding,
+    chain_id: &str,
+    commit: &LastCommit,
+    validators: &[Validator],
+) -> Result<(), String> {
+    let signatures = commit.signatures.as_deref().unwrap_or_default();
+    if signatures.len() != validators.len() {
+        return Err(format!(
+            "{} signatures for {} validators",
+            signatures.len(),
+            validators.len()
+        ));
+    }
+    let height = commit.height.parse().map_err(|e| format!("height: {e}"))?;
+    let round = commit.round.parse().map_err(|e| format!("round: {e}"))?;
+
+    let mut total: u128 = 0;
+    let mut signed: u128 = 0;
+    for (sig, v) in signatures.iter().zip(validators) {
+        let power = parse_u64("voting power", &v.voting_power)? as u128;
+        total += power;
+        if sig.block_id_flag != Some(BLOCK_ID_FLAG_COMMIT) {
+            continue;
+        }
+        let key = ed25519_key(v)?;
+        let address = hex::encode_upper(&sha2::Sha256::digest(&key)[..20]);
+        if !address.eq_ignore_ascii_case(&v.address) {
+            return Err(format!("validator {} has the key of {address}", v.address));
+        }
+        if !sig
+            .validator_address
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case(&v.address))
+        {
+            return Err(format!(
+                "signature of {:?} in place of {}",
+                sig.validator_address, v.address
+            ));
+        }
+        let time = sig.timestamp.as_deref().unwrap_or_default();
+        let message = vote_sign_bytes(encoding, chain_id, height, round, &commit.block_id, time)?;
+        let signature = engine::general_purpose::STANDARD
+            .decode(sig.signature.as_deref().unwrap_or_default())
+            .map_err(|e| format!("signature of {}: {e}", v.address))?;
+        UnparsedPublicKey::new(&ED25519, &key)
+            .verify(&message, &signature)
+            .map_err(|_| format!("invalid signature of {}", v.address))?;
+        signed += power;
+    }
+    if signed * 3 <= total * 2 {
+        return Err(format!("signed by {signed} of {total} voting power"));
+    }
+    Ok(())
+}
+
+/// Check a block against its commit, the `last_commit` of the block above, and its validators.
+///
+/// The header must hash to the block hash, the validators to the `validators_hash` of the
+/// header, and they must have signed the commit for the block. The block at the trusted height
+/// must have the trusted hash, the ones above link to it, see [`verify_link`].
+pub fn verify_block(
+    block: &Link,
+    commit: &LastCommit,
+    validators: &ValidatorsRPC,
+    trust: &Trust,
+) -> Vec<Mismatch> {
+    let header = &block.header;
+    let height = block.height;
+    let mut mismatches = vec![];
+    let fail = |check, detail| Mismatch {
+        height,
+        check,
+        detail,
+    };
+
+    let encoding = match Encoding::of(header) {
+        Ok(e) => e,
+        Err(e) => return vec![fail("header_hash", e)],
+    };
+    match header_hash(encoding, header) {
+        Ok(hash) if hex::encode_upper(hash).eq_ignore_ascii_case(&block.hash) => {}
+        Ok(hash) => mismatches.push(fail(
+            "header_hash",
+            format!(
+                "block {}, the header hashes to {}",
+                block.hash,
+                hex::encode_upper(hash)
+            ),
+        )),
+        Err(e) => mismatches.push(fail("header_hash", e)),
+    }
+    if height == trust.height && !block.hash.eq_ignore_ascii_case(&trust.hash) {
+        mismatches.push(fail(
+            "trusted_hash",
+            format!("block {}, trusted {}", block.hash, trust.hash),
+        ));
+    }
+    match validators_hash(encoding, &validators.validators) {
+        Ok(hash) if hex::encode_upper(hash).eq_ignore_ascii_case(&header.validators_hash) => {}
+        Ok(hash) => mismatches.push(fail(
+            "validators_hash",
+            format!(
+                "header has {}, the validators hash to {}",
+                header.validators_hash,
+                hex::encode_upper(hash)
+            ),
+        )),
+        Err(e) => mismatches.push(fail("validators_hash", e)),
+    }
+
+    if commit.height != header.height || !commit.block_id.hash.eq_ignore_ascii_case(&block.hash) {
+        mismatches.push(fail(
+            "commit",
+            format!("commit of {} at {}", commit.block_id.hash, commit.height),
+        ));
+    } else if let Err(e) = verify_commit(encoding, &header.chain_id, commit, &validators.validators)
+    {
+        mismatches.push(fail("commit", e));
+    }
+    mismatches
+}
+
+/// Check `next` follows `prev`, the verified block of the height below: it names `prev` as its
+/// last block, and its validators are the next ones of `prev`.
+pub fn verify_link(prev: &Link, next: &Link) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+    let last = &next.header.last_block_id.hash;
+    if next.height != prev.height + 1 || !last.eq_ignore_ascii_case(&prev.hash) {
+        mismatches.push(Mismatch {
+            height: next.height,
+            check: "last_block_id",
+            detail: format!("last block {last}, block {} at {}", prev.hash, prev.height),
+        });
+    }
+    let validators = &next.header.validators_hash;
+    if !validators.eq_ignore_ascii_case(&prev.header.next_validators_hash) {
+        mismatches.push(Mismatch {
+            height: next.height,
+            check: "next_validators_hash",
+            detail: format!(
+                "validators {validators}, {} named by the block below",
+                prev.header.next_validators_hash
+            ),
+        });
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mock_rpc::fixture;
+    use module::rpc::block::{Parts, Version};
+    use serde_json::{json, Value};
+
+    /// Block, commit and validators of a fixture height, the commit of the top block coming
+    /// from `/commit` in place of the block above.
+    fn inputs(height: i64) -> (ModuleBlockRPC, LastCommit, ValidatorsRPC) {
+        let block = serde_json::from_value(fixture("block", height)).unwrap();
+        let commit = if height < 3 {
+            fixture("block", height + 1)["block"]["last_commit"].clone()
+        } else {
+            fixture("commit", height)["signed_header"]["commit"].clone()
+        };
+        let validators = serde_json::from_value(fixture("validators", height)).unwrap();
+        (block, serde_json::from_value(commit).unwrap(), validators)
+    }
+
+    fn checks(mismatches: Vec<Mismatch>) -> Vec<&'static str> {
+        mismatches.into_iter().map(|m| m.check).collect()
+    }
+
+    fn sha256_hex(s: &str) -> String {
+        hex::encode_upper(sha2::Sha256::digest(s))
+    }
+
+    #[test]
+    fn test_header_hash() {
+        // the header of `TestHeaderHash` of tendermint.
+        let header = BlockHeader {
+            version: Version {
+                block: "1".to_string(),
+                app: "2".to_string(),
+            },
+            chain_id: "chainId".to_string(),
+            height: "3".to_string(),
+            time: "2019-10-13T16:14:44Z".to_string(),
+            last_block_id: BlockId {
+                hash: "00".repeat(32),
+                parts: Parts {
+                    total: "6".to_string(),
+                    hash: "00".repeat(32),
+                },
+            },
+            last_commit_hash: sha256_hex("last_commit_hash"),
+            data_hash: sha256_hex("data_hash"),
+            validators_hash: sha256_hex("validators_hash"),
+            next_validators_hash: sha256_hex("next_validators_hash"),
+            consensus_hash: sha256_hex("consensus_hash"),
+            app_hash: sha256_hex("app_hash"),
+            last_results_hash: sha256_hex("last_results_hash"),
+            evidence_hash: sha256_hex("evidence_hash"),
+            proposer_address: sha256_hex("proposer_address")[..40].to_string(),
+        };
+        assert_eq!(
+            hex::encode_upper(header_hash(Encoding::Proto, &header).unwrap()),
+            "F740121F553B5418C3EFBD343C2DBFE9E007BB67B0D020A0741374BAB65242A4"
+        );
+        assert_eq!(
+            hex::encode_upper(header_hash(Encoding::Amino, &header).unwrap()),
+            "ABDC78921B18A47EE6BEF5E31637BADB0F3E587E3C0F4DB2D1E93E9FF0533862"
+        );
+    }
+
+    #[test]
+    fn test_vote_sign_bytes() {
+        let zero_time = "0001-01-01T00:00:00Z";
+        let nil = BlockId::default();
+        let time = [
+            0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff, 0xff, 0xff, 0x1,
+        ];
+        for encoding in [Encoding::Amino, Encoding::Proto] {
+            let bytes = vote_sign_bytes(encoding, "", 1, 1, &nil, zero_time).unwrap();
+            let mut expected = vec![0x21, 0x8, 0x2, 0x11, 0x1, 0, 0, 0, 0, 0, 0, 0, 0x19, 0x1];
+            expected.extend([0; 7]);
+            expected.extend(time);
+            assert_eq!(bytes, expected);
+
+            let bytes = vote_sign_bytes(encoding, "test_chain_id", 1, 1, &nil, zero_time).unwrap();
+            assert_eq!(bytes[0], 0x30);
+            assert!(bytes.ends_with(b"\x32\x0dtest_chain_id"));
+        }
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let (first, _, _) = inputs(1);
+        let trust = Trust {
+            height: 1,
+            hash: first.block_id.hash.clone(),
+        };
+        for h in 1..=3 {
+            let (block, commit, validators) = inputs(h);
+            assert_eq!(
+                verify_block(&Link::of(&block), &commit, &validators, &trust),
+                vec![]
+            );
+        }
+
+        let (block, commit, validators) = inputs(2);
+        let block = Link::of(&block);
+        let mut tampered = block.clone();
+        tampered.header.app_hash = "00".repeat(32);
+        assert_eq!(
+            checks(verify_block(&tampered, &commit, &validators, &trust)),
+            vec!["header_hash"]
+        );
+        let other = Trust {
+            height: 2,
+            hash: "00".repeat(32),
+        };
+        assert_eq!(
+            checks(verify_block(&block, &commit, &validators, &other)),
+            vec!["trusted_hash"]
+        );
+
+        // not the set the header names, though it signed.
+        let mut forged = serde_json::to_value(&validators).unwrap();
+        forged["validators"][1]["voting_power"] = json!("800000");
+        let forged = serde_json::from_value(forged).unwrap();
+        assert_eq!(
+            checks(verify_block(&block, &commit, &forged, &trust)),
+            vec!["validators_hash"]
+        );
+
+        // 80000 of 180000 is not enough.
+        let mut absent = commit.clone();
+        absent.signatures.as_mut().unwrap()[0].block_id_flag = Some(1);
+        let e = verify_commit(
+            Encoding::Amino,
+            "mock-chain",
+            &absent,
+            &validators.validators,
+        )
+        .unwrap_err();
+        assert_eq!(e, "signed by 80000 of 180000 voting power");
+        let mut swapped = commit.clone();
+        swapped.signatures.as_mut().unwrap()[1].signature =
+            commit.signatures.as_ref().unwrap()[0].signature.clone();
+        assert_eq!(
+            checks(verify_block(&block, &swapped, &validators, &trust)),
+            vec!["commit"]
+        );
+        let (_, previous, _) = inputs(1);
+        assert_eq!(
+            checks(verify_block(&block, &previous, &validators, &trust)),
+            vec!["commit"]
+        );
+    }
+
+    #[test]
+    fn test_verify_link() {
+        let link = |h| Link::of(&inputs(h).0);
+        assert_eq!(verify_link(&link(1), &link(2)), vec![]);
+        assert_eq!(verify_link(&link(2), &link(3)), vec![]);
+        assert_eq!(
+            checks(verify_link(&link(1), &link(3))),
+            vec!["last_block_id"]
+        );
+
+        let mut next = link(3);
+        next.header.validators_hash = "00".repeat(32);
+        assert_eq!(
+            checks(verify_link(&link(2), &next)),
+            vec!["next_validators_hash"]
+        );
+    }
+
+    #[test]
+    fn test_mainnet_headers() {
+        // blocks 5812560 and 5812561 of the mainnet, as served in `docs/api.md`.
+        let link = |height, hash: &str, header: Value| Link {
+            height,
+            hash: hash.to_string(),
+            header: serde_json::from_value(header).unwrap(),
+        };
+        let prev = link(
+            5812560,
+            "E25975AF1C35E24DE22649969FB36BF7BD1E4C3E8143510FB194E74B716EED54",
+            json!({
+                "version": {
+                    "block": "10",
+                    "app": "0"
+                },
+                "chain_id": "chain-qILMsV",
+                "height": "5812560",
+                "time": "2024-06-25T05:57:08.278720595Z",
+                "last_block_id": {
+                    "hash": "4E81593DB5A09DA42A9E4514476C9FBC6C5255F431E361A3B8117E6F46A479A8",
+                    "parts": {
+                        "total": "1",
+                        "hash": "4CCD089EF35C506527DCFCDC253E6E6AA3B8AB74F1F00360531CB1F792AB0820"
+                    }
+                },
+                "last_commit_hash": "5F013BD68C8A63C7A7C313377D6DC3FF3C9FA3744F24D94691987CE6DBE9733D",
+                "data_hash": "B1D2CFD492580F5755026B390C3B13E49A83D8DAD1630C42208E875F754A74CC",
+                "validators_hash": "F0A2F1BB46A9C113FF1236BD837F2FFDF2D1F4EDA3BDBE06AC5378F76BD96930",
+                "next_validators_hash": "F0A2F1BB46A9C113FF1236BD837F2FFDF2D1F4EDA3BDBE06AC5378F76BD96930",
+                "consensus_hash": "048091BC7DDC283F77BFBF91D73C44DA58C3DF8A9CBC867405D8B7F3DAADA22F",
+                "app_hash": "EAA672B0BD8B5F7A8708BCC3A9C6EB3989A69120F3B280DA62F8A89FAE1FF120",
+                "last_results_hash": "352E083368129F73731739BBD04F7964DD3CA9FB4665BC703C305CEEFE5B9A69",
+                "evidence_hash": "",
+                "proposer_address": "09EF1DB6B67D1CBF7EBA6BD9B204611848993DF7"
+            }),
+        );
+        let next = link(
+            5812561,
+            "3D088AFEE34E9164C06A85DF7C2F0433645C7875A127B820115303404809B3B6",
+            json!({
+                "version": {
+                    "block": "10",
+                    "app": "0"
+                },
+                "chain_id": "chain-qILMsV",
+                "height": "5812561",
+                "time": "2024-06-25T05:57:42.742413604Z",
+                "last_block_id": {
+                    "hash": "E25975AF1C35E24DE22649969FB36BF7BD1E4C3E8143510FB194E74B716EED54",
+                    "parts": {
+                        "total": "1",
+                        "hash": "87B8CE2C2EE639DC47DC58F11FFBEFCF8B97F018D30D2A0F143E6D874DCC3696"
+                    }
+                },
+                "last_commit_hash": "10A55872C66C92C3738A51DA55D2142B15C0EFAF455F62FD5AAAC28E99F01319",
+                "data_hash": "35A93BA81CF8DA26802C103AD141147820EE9975F667AA6DFA56795198768480",
+                "validators_hash": "F0A2F1BB46A9C113FF1236BD837F2FFDF2D1F4EDA3BDBE06AC5378F76BD96930",
+                "next_validators_hash": "F0A2F1BB46A9C113FF1236BD837F2FFDF2D1F4EDA3BDBE06AC5378F76BD96930",
+                "consensus_hash": "048091BC7DDC283F77BFBF91D73C44DA58C3DF8A9CBC867405D8B7F3DAADA22F",
+                "app_hash": "C42510F6D4B9BBA3BE08E19EF3E36A1E31701AC9A510A314EB5E2F2DCA032180",
+                "last_results_hash": "352E083368129F73731739BBD04F7964DD3CA9FB4665BC703C305CEEFE5B9A69",
+                "evidence_hash": "",
+                "proposer_address": "09EF1DB6B67D1CBF7EBA6BD9B204611848993DF7"
+            }),
+        );
+        for block in [&prev, &next] {
+            let encoding = Encoding::of(&block.header).unwrap();
+            assert_eq!(encoding, Encoding::Amino);
+            assert_eq!(
+                hex::encode_upper(header_hash(encoding, &block.header).unwrap()),
+                block.hash
+            );
+        }
+        assert_eq!(verify_link(&prev, &next), vec![]);
+        assert_eq!(checks(verify_link(&next, &prev)), vec!["last_block_id"]);
+    }
+}
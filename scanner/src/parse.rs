@@ -0,0 +1,339 @@
+//! Derivation of the indexed records of a block from tendermint data, without network or
+//! database access.
+use crate::rpc::{collect_participants, decode_evidence, decode_tx, DecodedTxs};
+use crate::{tx, Result};
+use base64::{engine, Engine};
+use chrono::NaiveDateTime;
+use module::rpc::{
+    block::BlockRPC as ModuleBlockRPC, tx::Transaction as ModuleTx,
+    validator::ValidatorsRPC as ModuleValidatorsRPC,
+};
+use module::schema::{Block as ModuleBlock, QuarantinedTx, Validator};
+use sha2::Digest;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Why a tx was quarantined instead of indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub tx_hash: String,
+    pub tx_index: usize,
+    /// `result` if its tx result was not given, `catalog`, `decode` or `panic` otherwise.
+    pub stage: String,
+    pub error: String,
+}
+
+/// Validators of the stakers, by staker address.
+pub type Stakers = HashMap<String, String>;
+
+#[derive(Debug)]
+pub struct ParsedBlock {
+    pub block: ModuleBlock,
+    /// One per quarantined tx, in block order.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Hashes of the txs of `block` in block order, as `/tx` expects them.
+pub fn tx_hashes(block: &ModuleBlockRPC) -> Result<Vec<String>> {
+    block
+        .block
+        .data
+        .txs
+        .iter()
+        .flatten()
+        .map(|tx| {
+            let bytes = engine::general_purpose::STANDARD.decode(tx)?;
+            Ok(hex::encode(sha2::Sha256::digest(bytes)))
+        })
+        .collect()
+}
+
+/// Build the block to save from a block, its size, the results of its txs, the validator set
+/// at its height and the validators of the stakers known below it.
+///
+/// Tx results are matched by hash, a tx without one is quarantined. So is a tx failing to
+/// decode, the error being reported in the diagnostics. Errors are only returned for a
+/// malformed block.
+pub fn parse_block(
+    block: ModuleBlockRPC,
+    block_size: i64,
+    tx_results: Vec<ModuleTx>,
+    validator_info: ModuleValidatorsRPC,
+    stakers: &Stakers,
+) -> Result<ParsedBlock> {
+    let block_data = serde_json::to_value(block.clone())?;
+    let block_hash = block.block_id.hash;
+    let height = block.block.header.height.parse::<i64>()?;
+    let timestamp =
+        NaiveDateTime::parse_from_str(&block.block.header.time, "%Y-%m-%dT%H:%M:%S%.fZ")?;
+    let app_hash = block.block.header.app_hash;
+    let proposer = block.block.header.proposer_address;
+    let mut validators = Vec::new();
+    let mut decoded = DecodedTxs {
+        v2_slashing_events: decode_evidence(
+            block.block.evidence.evidence.as_deref().unwrap_or_default(),
+            &block_hash,
+            height,
+            timestamp.and_utc().timestamp(),
+        ),
+        ..Default::default()
+    };
+    let mut quarantined_txs: Vec<QuarantinedTx> = vec![];
+    let mut diagnostics = vec![];
+    // the txs of the block may register or replace stakers for the ones after them.
+    let mut stakers = stakers.clone();
+
+    let mut tx_results: HashMap<String, ModuleTx> = tx_results
+        .into_iter()
+        .map(|tx| (tx.hash.to_lowercase(), tx))
+        .collect();
+    for (tx_index, tx_string) in block
+        .block
+        .data
+        .txs
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        let bytes = engine::general_purpose::STANDARD.decode(&tx_string)?;
+        let tx_hash = hex::encode(sha2::Sha256::digest(&bytes));
+
+        let (stage, error) = match tx_results.remove(&tx_hash) {
+            None => ("result", "tx result not found".to_string()),
+            Some(tx) => {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    decode_tx(
+                        &bytes,
+                        tx_string.clone(),
+                        tx_hash.clone(),
+                        tx,
+                        block_hash.clone(),
+                        height,
+                        timestamp,
+                        tx_index,
+                        &stakers,
+                    )
+                }));
+                match res {
+                    Ok(Ok(d)) => {
+                        learn_stakers(&d, &mut stakers);
+                        decoded.append(d);
+                        continue;
+                    }
+                    Ok(Err(e)) => match tx::try_tx_catalog(&bytes) {
+                        tx::TxCatalog::Unknown => ("catalog", format!("{e:?}")),
+                        _ => ("decode", format!("{e:?}")),
+                    },
+                    Err(e) => {
+                        let msg = e
+                            .downcast_ref::<String>()
+                            .cloned()
+                            .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+                            .unwrap_or_default();
+                        ("panic", msg)
+                    }
+                }
+            }
+        };
+
+        diagnostics.push(Diagnostic {
+            tx_hash: tx_hash.clone(),
+            tx_index,
+            stage: stage.to_string(),
+            error: error.clone(),
+        });
+        quarantined_txs.push(QuarantinedTx {
+            tx_hash,
+            block_hash: block_hash.clone(),
+            height,
+            tx_index: tx_index as i64,
+            origin: tx_string,
+            stage: stage.to_string(),
+            error,
+            timestamp: timestamp.and_utc().timestamp(),
+        });
+    }
+
+    for vv in validator_info.validators {
+        let address = vv.address;
+        let power = vv.voting_power.parse::<u64>()?;
+        let pub_key = vv.pub_key;
+        let priority = vv.proposer_priority.parse::<i64>()?;
+        if block.block.last_commit.signatures.is_none() {
+            break;
+        }
+        let sign_info = block
+            .block
+            .last_commit
+            .signatures
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|v| Some(&address) == v.validator_address.as_ref());
+
+        let (signature, timestamp) = if let Some(s) = sign_info {
+            let signature = s.signature.clone();
+            let timestamp = if let Some(s) = &s.timestamp {
+                Some(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.fZ")?)
+            } else {
+                None
+            };
+
+            (signature, timestamp)
+        } else {
+            (None, None)
+        };
+
+        let validator = Validator {
+            address,
+            power,
+            pub_key,
+            priority,
+            signature,
+            timestamp,
+        };
+
+        validators.push(validator);
+    }
+
+    let mut tx_participants = collect_participants(&decoded.txs, &decoded.v2_operations);
+    tx_participants.append(&mut collect_participants(&decoded.evm_txs, &[]));
+
+    let block = ModuleBlock {
+        block_hash,
+        height,
+        size: block_size,
+        tx_count: (decoded.evm_txs.len() + decoded.txs.len()) as i64,
+        timestamp,
+        app_hash,
+        proposer,
+        evm_addrs: decoded.evm_addrs,
+        native_addrs: decoded.native_addrs,
+        txs: decoded.txs,
+        evm_txs: decoded.evm_txs,
+        validators,
+        v2_convert_account_txs: decoded.v2_convert_account_txs,
+        v2_undelegation_txs: decoded.v2_undelegation_txs,
+        v2_delegation_txs: decoded.v2_delegation_txs,
+        v2_claim_txs: decoded.v2_claim_txs,
+        v2_asset_txs: decoded.v2_asset_txs,
+        v2_validator_events: decoded.v2_validator_events,
+        v2_mint_txs: decoded.v2_mint_txs,
+        v2_slashing_events: decoded.v2_slashing_events,
+        v2_operations: decoded.v2_operations,
+        tx_participants,
+        quarantined_txs,
+        block_data,
+    };
+    Ok(ParsedBlock { block, diagnostics })
+}
+
+/// Record the validators of the stakers `d` registers, updates or replaces.
+fn learn_stakers(d: &DecodedTxs, stakers: &mut Stakers) {
+    for tx in &d.v2_delegation_txs {
+        if !tx.new_validator.is_empty() {
+            stakers.insert(tx.sender.clone(), tx.new_validator.clone());
+        }
+    }
+    for ev in &d.v2_validator_events {
+        stakers.insert(ev.staker.clone(), ev.validator.clone());
+        if let Some(new_staker) = &ev.new_staker {
+            stakers.insert(new_staker.clone(), ev.validator.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::types::FindoraTxType;
+    use mock_rpc::fixture;
+
+    /// Block, tx results and validators of a fixture height.
+    pub(crate) fn inputs(height: i64) -> (ModuleBlockRPC, Vec<ModuleTx>, ModuleValidatorsRPC) {
+        let block: ModuleBlockRPC = serde_json::from_value(fixture("block", height)).unwrap();
+        let results = fixture("block_results", height);
+        let txs = tx_hashes(&block)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, hash)| ModuleTx {
+                hash: hash.to_uppercase(),
+                height: height.to_string(),
+                index: i as i64,
+                tx_result: serde_json::from_value(results["txs_results"][i].clone()).unwrap(),
+                tx: block.block.data.txs.as_ref().unwrap()[i].clone(),
+            })
+            .collect();
+        let validators = serde_json::from_value(fixture("validators", height)).unwrap();
+        (block, txs, validators)
+    }
+
+    #[test]
+    fn test_parse_evm_tx() {
+        let (block, txs, validators) = inputs(3);
+        let parsed = parse_block(block, 1024, txs, validators, &Stakers::new()).unwrap();
+        assert!(parsed.diagnostics.is_empty());
+        let block = parsed.block;
+        assert_eq!((block.height, block.size, block.tx_count), (3, 1024, 2));
+        assert_eq!(
+            block.evm_txs[0].tx_hash,
+            "263d57cd0d924b81ab820c1ba73aab47aaba3bb7152623a1da4da178a0221f99"
+        );
+        assert_eq!(block.evm_txs[0].ty_sub, FindoraTxType::Evm as i32);
+        assert_eq!(block.v2_operations[0].kind, "Ethereum");
+        assert_eq!(block.validators.len(), 2);
+        assert!(block.validators.iter().all(|v| v.signature.is_some()));
+    }
+
+    #[test]
+    fn test_parse_native_tx() {
+        // the delegation of mainnet height 2799430 in a tx of its own.
+        let (block, txs, validators) = inputs(3);
+        let block = parse_block(block, 1024, txs, validators, &Stakers::new())
+            .unwrap()
+            .block;
+        assert_eq!(block.txs.len(), 1);
+        assert_eq!(block.txs[0].tx_index, 1);
+        assert_eq!(block.txs[0].ty, FindoraTxType::Native as i32);
+        assert_eq!(block.txs[0].ty_sub, FindoraTxType::Delegation as i32);
+        let delegation = &block.v2_delegation_txs[0];
+        assert_eq!(delegation.tx_hash, block.txs[0].tx_hash);
+        assert_eq!(
+            delegation.sender,
+            "fra18fnyetvs2kc035xz78kyfcygmej8pk5h2kwczy03w6uewdphzfxsk74dym"
+        );
+        assert_eq!(
+            (delegation.validator.as_str(), delegation.amount),
+            ("9E6717392EFDCFA101E33449A7C2A238251315B1", 1000000)
+        );
+        assert_eq!(block.v2_operations[1].kind, "Delegation");
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        // a native tx that doesn't decode and a tx of no known catalog.
+        let (block, txs, validators) = inputs(2);
+        let parsed = parse_block(block.clone(), 1024, txs, validators, &Stakers::new()).unwrap();
+        assert_eq!(parsed.block.tx_count, 0);
+        let quarantined: Vec<_> = parsed
+            .block
+            .quarantined_txs
+            .iter()
+            .map(|q| (q.tx_index, q.origin.as_str(), q.stage.as_str()))
+            .collect();
+        assert_eq!(
+            quarantined,
+            [
+                (0, "eyJtb2NrIjoidHggYXQgaGVpZ2h0IDIifQ==", "decode"),
+                (1, "YWJj", "catalog")
+            ]
+        );
+        assert_eq!(parsed.diagnostics[1].stage, "catalog");
+        assert!(parsed.diagnostics[1].error.contains("unknown tx catalog"));
+
+        let parsed = parse_block(block, 1024, vec![], Default::default(), &Stakers::new()).unwrap();
+        assert_eq!(parsed.diagnostics[0].stage, "result");
+        assert!(parsed.block.validators.is_empty());
+    }
+}
@@ -1,703 +1,1689 @@
-use crate::commands::FRA_ASSET;
+use crate::light::{self, Link, Trust};
+use crate::parse::Stakers;
+use crate::status::Status;
 use crate::types::{
     ClaimOpt, ConvertAccountOpt, DefineAssetOpt, DelegationOpt, EthereumWrap, FindoraEVMTx,
-    FindoraEVMTxWrap, FindoraTxType, IssueAssetOpt, OutputTypeHideAmountHide,
-    OutputTypeHideAmountShow, OutputTypeShowAmountHide, OutputTypeShowAmountShow, TransactWrap,
-    TransactWrapData, TransferAssetOpt, TxValue, UnDelegationOpt, XHubOpt,
+    FindoraEVMTxWrap, FindoraTxType, FraDistributionOpt, GovernanceOpt, IssueAssetOpt, MintFraOpt,
+    OutputTypeHideAmountHide, OutputTypeHideAmountShow, OutputTypeShowAmountHide,
+    OutputTypeShowAmountShow, ReplaceStakerOpt, TransactWrap, TransactWrapData, TransferAssetOpt,
+    TxValue, UnDelegationOpt, UpdateStakerOpt, UpdateValidatorOpt, XHubOpt,
 };
 use crate::util::pubkey_to_fra_address;
-use crate::{db, tx};
+use crate::{parse, tx, verify};
 use crate::{Error, Result};
 use base64::{engine, Engine};
 use chrono::NaiveDateTime;
 use ethereum::TransactionAction;
-use module::rpc::block::BlockSizeRPC;
-use module::rpc::{
-    block::BlockRPC as ModuleBlockRPC, tx::Transaction as ModuleTx,
-    validator::ValidatorsRPC as ModuleValidatorsRPC, JsonRpcResponse, TdRpcResult,
-};
+use futures::TryFutureExt;
+use module::config::scanner_config::Backoff;
+use module::network::FRA_ASSET;
+use module::rpc::block::LastCommit;
+use module::rpc::tx::Transaction as ModuleTx;
+use module::rpc::validator::ValidatorsRPC;
 use module::schema::{
-    Address, Block as ModuleBlock, DelegationInfo, Transaction, V2AssetTx, V2ClaimTx,
-    V2ConvertAccountTx, V2DelegationTx, V2UndelegationTx, Validator,
+    Address, Block as ModuleBlock, Transaction, TxParticipant, V2AssetTx, V2ClaimTx,
+    V2ConvertAccountTx, V2DelegationTx, V2MintTx, V2Operation, V2SlashingEvent, V2UndelegationTx,
+    V2ValidatorEvent,
 };
+use module::source::{self, BlockSource};
 use module::utils::crypto::recover_signer;
-use reqwest::{Client, ClientBuilder, Url};
-use serde::de::DeserializeOwned;
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sha2::Digest;
-use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use storage::Storage;
+
+/// Verified blocks kept for the blocks above to link to.
+const MAX_LINKS: usize = 1024;
+
+/// Number of txs quarantined since the scanner started.
+pub static QUARANTINED_TXS: AtomicU64 = AtomicU64::new(0);
+
+/// The top block the light client checks, kept until the block above carries its commit.
+struct PendingBlock {
+    link: Link,
+    validators: ValidatorsRPC,
+    block: ModuleBlock,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Receivers {
     pub addrs: Vec<String>,
 }
 
-pub struct TendermintRPC {
-    pub rpc: Url,
-    pub client: Client,
+pub struct RPCCaller {
+    pub(crate) retries: usize,
+    /// Waits between the retries of a block.
+    pub(crate) backoff: Backoff,
+    pub(crate) concurrency: usize,
+    pub(crate) rpc: Box<dyn BlockSource>,
+    pub(crate) storage: Arc<dyn Storage>,
+    pub(crate) status: Arc<Status>,
+    /// Verify each block against its header and tx results before parsing it.
+    pub(crate) verify: bool,
+    /// Verify the commits of the blocks from this one on, see `light`.
+    pub(crate) trust: Option<Trust>,
+    /// The blocks `check_link` passed by height, none above a block that failed.
+    links: Mutex<BTreeMap<i64, Link>>,
+    pending: Mutex<Option<PendingBlock>>,
 }
 
-impl TendermintRPC {
-    pub fn new(timeout: Duration, rpc: Url) -> Self {
-        let client = ClientBuilder::new().timeout(timeout).build().unwrap();
-        TendermintRPC { client, rpc }
+impl RPCCaller {
+    pub fn new(
+        retries: usize,
+        concurrency: usize,
+        timeout: Duration,
+        tendermint_rpc: Url,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
+        let status = Arc::new(Status::new(tendermint_rpc.as_str()));
+        let rpc = source::open(tendermint_rpc, timeout);
+        RPCCaller {
+            retries,
+            backoff: Backoff::default(),
+            concurrency,
+            rpc,
+            storage,
+            status,
+            verify: false,
+            trust: None,
+            links: Mutex::new(BTreeMap::new()),
+            pending: Mutex::new(None),
+        }
     }
 
-    pub async fn load_block(&self, height: i64) -> Result<ModuleBlockRPC> {
-        let mut url = self.rpc.join("block").unwrap();
-        url.set_query(Some(&format!("height={height}")));
-        debug!("{}", url.as_str());
-        let r: ModuleBlockRPC = self.client_get(url).await?;
-        Ok(r)
+    /// Set the waits between the retries of a block.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
     }
 
-    pub async fn get_block_size(&self, height: i64) -> Result<BlockSizeRPC> {
-        let mut url = self.rpc.join("blockchain").unwrap();
-        url.set_query(Some(&format!("minHeight={height}&maxHeight={height}")));
-        debug!("{}", url.as_str());
-        let r: BlockSizeRPC = self.client_get(url).await?;
-        Ok(r)
+    /// Set whether blocks are verified, see `verify::verify_block`.
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
     }
 
-    fn validator_url(&self, height: i64, page: i32, per_page: i32) -> Result<Url> {
-        let mut url = self.rpc.join("validators").unwrap();
-        url.set_query(Some(&format!(
-            "height={height}&per_page={per_page}&page={page}"
-        )));
-        Ok(url)
+    /// Set the block the light client checks start from, off if `None`.
+    pub fn with_trust(mut self, trust: Option<Trust>) -> Self {
+        self.trust = trust;
+        self
     }
 
-    pub async fn load_validators(&self, height: i64) -> Result<ModuleValidatorsRPC> {
-        let mut page = 1;
-        let per_page = 100;
-
-        let url = self.validator_url(height, page, per_page).unwrap();
-        let mut r: ModuleValidatorsRPC = self.client_get(url).await?;
-        let mut count = r.count.clone().parse::<i32>().unwrap();
-        let mut total = r.total.clone().parse::<i32>().unwrap();
+    pub fn status(&self) -> &Arc<Status> {
+        &self.status
+    }
 
-        while total > count {
-            total -= count;
-            page += 1;
-            let tmp_url = self.validator_url(height, page, per_page).unwrap();
-            let mut tmp_res: ModuleValidatorsRPC = self.client_get(tmp_url).await?;
-            count = tmp_res.count.clone().parse::<i32>().unwrap();
-            r.validators.append(&mut tmp_res.validators)
+    /// Height of the latest block known to the node, kept as the tip of the status.
+    pub async fn latest_height(&self) -> Result<i64> {
+        match self.rpc.latest_height().await {
+            Ok(h) => {
+                self.status.rpc_ok();
+                self.status.set_tip(h);
+                Ok(h)
+            }
+            Err(e) => {
+                self.status.rpc_failed(format!("{e:?}"));
+                Err(e.into())
+            }
         }
-
-        Ok(r)
     }
 
-    pub async fn load_transaction(&self, hash: &str) -> Result<ModuleTx> {
-        let mut url = self.rpc.join("tx").unwrap();
-        url.set_query(Some(&format!("hash=0x{hash}")));
-
-        let r: ModuleTx = self.client_get(url).await?;
-        Ok(r)
+    /// Height of the latest block, asking a failing rpc again after a backoff doubling from a
+    /// second up to `max`.
+    pub async fn wait_latest_height(&self, max: Duration) -> i64 {
+        let mut backoff = Duration::from_secs(1).min(max);
+        loop {
+            match self.latest_height().await {
+                Ok(h) => return h,
+                Err(e) => {
+                    warn!("Latest height: {:?}, retrying in {:?}.", e, backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(max);
+                }
+            }
+        }
     }
 
-    pub async fn load_delegations(&self) -> Result<(i64, DelegationInfo)> {
-        let mut url = self.rpc.join("abci_query").unwrap();
-        let mut queries = url.query_pairs_mut();
-        queries.append_pair("path", "\"/delegations\"");
-        queries.append_pair("data", "");
-        drop(queries);
+    /// Load and parse the block at `height`. `Pending` if the light client checks it but the
+    /// block above, which carries its commit, is not out yet; the block is then kept and only
+    /// the one above is asked for next time.
+    pub async fn load_height(&self, height: i64) -> Result<ModuleBlock> {
+        let trust = self.trust.as_ref().filter(|t| t.covers(height));
+        if let Some(trust) = trust {
+            let pending = self.pending.lock().unwrap().take();
+            if let Some(pending) = pending.filter(|p| p.block.height == height) {
+                return self.verify_pending(pending, trust).await;
+            }
+        }
+        let (block, block_size_rpc, validator_info, stakers, above) = tokio::try_join!(
+            self.rpc.load_block(height).map_err(Error::from),
+            self.rpc.get_block_size(height).map_err(Error::from),
+            self.rpc.load_validators(height).map_err(Error::from),
+            self.storage.stakers(height).map_err(Error::from),
+            async {
+                match trust {
+                    Some(_) => match self.rpc.load_block(height + 1).await {
+                        Ok(above) => Ok(Some(above)),
+                        Err(module::source::SourceError::NotFound) => Ok(None),
+                        Err(e) => Err(Error::from(e)),
+                    },
+                    None => Ok(None),
+                }
+            },
+        )?;
+        let tx_hashes = parse::tx_hashes(&block)?;
+        let tx_results = futures::future::try_join_all(
+            tx_hashes
+                .iter()
+                .map(|tx_hash| self.rpc.load_transaction(tx_hash)),
+        )
+        .await?;
+        if self.verify {
+            integrity(verify::verify_block(&block, &block_size_rpc, &tx_results))?;
+        }
+        let link = Link::of(&block);
+        if let (Some(trust), Some(above)) = (trust, &above) {
+            integrity(light::verify_block(
+                &link,
+                &above.block.last_commit,
+                &validator_info,
+                trust,
+            ))?;
+        }
+        let block_size = block_size_rpc
+            .block_metas
+            .as_deref()
+            .and_then(|metas| metas.first())
+            .ok_or_else(|| format!("no block meta at height {height}"))?
+            .block_size
+            .parse::<i64>()?;
 
-        let resp = self.client.get(url).send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            return Err(resp.text().await?.into());
+        let validators = (trust.is_some() && above.is_none()).then(|| validator_info.clone());
+        let parsed = parse::parse_block(block, block_size, tx_results, validator_info, &stakers)?;
+        for d in parsed.diagnostics {
+            warn!(
+                "Quarantined tx {} at height {}, stage: {}, error: {}",
+                d.tx_hash, parsed.block.height, d.stage, d.error
+            );
+            QUARANTINED_TXS.fetch_add(1, Ordering::Relaxed);
         }
+        if let Some(validators) = validators {
+            *self.pending.lock().unwrap() = Some(PendingBlock {
+                link,
+                validators,
+                block: parsed.block,
+            });
+            return Err(Error::Pending);
+        }
+        Ok(parsed.block)
+    }
 
-        let result: JsonRpcResponse<TdRpcResult> = resp.json().await?;
+    /// Check the pending block against the commit of the block above, kept pending while that
+    /// one is not out.
+    async fn verify_pending(&self, pending: PendingBlock, trust: &Trust) -> Result<ModuleBlock> {
+        let above = match self.rpc.load_block(pending.block.height + 1).await {
+            Ok(above) => above,
+            Err(e) => {
+                *self.pending.lock().unwrap() = Some(pending);
+                return Err(match e {
+                    module::source::SourceError::NotFound => Error::Pending,
+                    e => e.into(),
+                });
+            }
+        };
+        integrity(light::verify_block(
+            &pending.link,
+            &above.block.last_commit,
+            &pending.validators,
+            trust,
+        ))?;
+        Ok(pending.block)
+    }
 
-        let response = result.result.response;
+    pub async fn load_height_retried(&self, height: i64) -> Result<ModuleBlock> {
+        for i in 0..self.retries + 1 {
+            match self.load_height(height).await {
+                Ok(r) => {
+                    self.status.rpc_ok();
+                    return Ok(r);
+                }
+                Err(e @ (Error::NotFound | Error::Pending)) => {
+                    self.status.rpc_ok();
+                    return Err(e);
+                }
+                Err(e) => {
+                    self.status.rpc_failed(format!("{e:?}"));
+                    if i == self.retries {
+                        return Err(e);
+                    }
+                    let delay = self.backoff.delay(i);
+                    info!(
+                        "Load height {} failed, error: `{:?}`\nRetry {} in {:?} ...",
+                        height,
+                        e,
+                        i + 1,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            };
+        }
+        unreachable!()
+    }
 
-        if response.code != 0 {
-            return Err(response.info.into());
+    /// Check a loaded block follows the verified one below it and keep it for the block above.
+    ///
+    /// The block below is the one checked last at its height, or else the saved one, checked
+    /// against the commit the block carries for it and the validators it names. Nothing is
+    /// checked without a trusted block.
+    pub async fn check_link(&self, block: &ModuleBlock) -> Result<()> {
+        let Some(trust) = self.trust.as_ref().filter(|t| t.covers(block.height)) else {
+            return Ok(());
+        };
+        let next = Link {
+            height: block.height,
+            hash: block.block_hash.clone(),
+            header: serde_json::from_value(block.block_data["block"]["header"].clone())?,
+        };
+        if block.height > trust.height {
+            let below = self.links.lock().unwrap().get(&(block.height - 1)).cloned();
+            let linked = match below {
+                Some(prev) => integrity(light::verify_link(&prev, &next)),
+                None => match self.saved_link(block, trust).await {
+                    Ok(prev) => integrity(light::verify_link(&prev, &next)),
+                    Err(e) => Err(e),
+                },
+            };
+            if let Err(e) = linked {
+                self.forget_links(block.height);
+                return Err(e);
+            }
+        }
+        let mut links = self.links.lock().unwrap();
+        // the blocks above were checked against another block of this height.
+        if links
+            .get(&block.height)
+            .is_some_and(|l| l.hash != next.hash)
+        {
+            links.split_off(&block.height);
         }
+        links.insert(block.height, next);
+        while links.len() > MAX_LINKS {
+            links.pop_first();
+        }
+        Ok(())
+    }
 
-        let h = response.height.parse()?;
+    /// Check the blocks from `start` on can be followed: from the trusted block or below it,
+    /// or from above a saved block, the one the first block links to.
+    pub async fn check_start(&self, start: i64) -> Result<()> {
+        let Some(trust) = self.trust.as_ref().filter(|t| start > t.height) else {
+            return Ok(());
+        };
+        match self.storage.block_by_height(start - 1).await {
+            Ok(_) => Ok(()),
+            Err(module::storage::StorageError::NotFound) => Err(format!(
+                "height {start} is above the trusted height {} with no block saved below it, \
+                start at {} or right above a saved block.",
+                trust.height, trust.height
+            )
+            .into()),
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        //let data = response.info.replace("\\\"", "\"");
+    /// The saved block below `block`, checked against the `last_commit` of `block`.
+    async fn saved_link(&self, block: &ModuleBlock, trust: &Trust) -> Result<Link> {
+        let height = block.height - 1;
+        let record = match self.storage.block_by_height(height).await {
+            Ok(record) => record,
+            Err(module::storage::StorageError::NotFound) => {
+                return Err(Error::IntegrityError(format!(
+                    "height {}, no verified block below to follow",
+                    block.height
+                )))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let prev = Link {
+            height,
+            hash: record.block_hash,
+            header: serde_json::from_value(record.block_data["block"]["header"].clone())?,
+        };
+        let commit: LastCommit =
+            serde_json::from_value(block.block_data["block"]["last_commit"].clone())?;
+        let validators = self.rpc.load_validators(height).await?;
+        integrity(light::verify_block(&prev, &commit, &validators, trust))?;
+        Ok(prev)
+    }
 
-        let staking: DelegationInfo = serde_json::from_str(&response.info)?;
+    /// Forget the blocks checked from `height` on, one of them failed to be saved.
+    pub(crate) fn forget_links(&self, height: i64) {
+        self.links.lock().unwrap().split_off(&height);
+    }
 
-        Ok((h, staking))
+    pub async fn load_and_save_block(&self, target: i64) -> Result<()> {
+        let block = self.load_height_retried(target).await?;
+        self.check_link(&block).await?;
+        if let Err(e) = self.storage.save_block(block).await {
+            self.forget_links(target);
+            return Err(e.into());
+        }
+        self.storage.save_last_height(target).await?;
+        self.status.set_cursor(target);
+        Ok(())
     }
 
-    async fn client_get<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
-        let resp = self.client.get(url).send().await?;
-        let status = resp.status();
-        if !status.is_success() {
-            let resp_text = resp.text().await?;
-            if resp_text.contains("less than or equal to") {
-                return Err(Error::NotFound);
+    /// Load and save the heights scheduled through the status api, the last height is left untouched.
+    pub async fn refetch_scheduled(&self) {
+        for h in self.status.take_refetch() {
+            let res = match self.load_height_retried(h).await {
+                Ok(block) => match self.check_link(&block).await {
+                    Ok(_) => self.storage.save_block(block).await.map_err(|e| {
+                        self.forget_links(h);
+                        Error::from(e)
+                    }),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok(_) => info!("Height {} refetched.", h),
+                Err(e) => {
+                    error!("Refetch height {} error: {:?}", h, e);
+                    self.status
+                        .record_error(format!("refetch height {h}: {e:?}"));
+                }
             }
-            return Err(resp_text.into());
         }
+    }
+}
 
-        let bytes = resp.bytes().await?;
-        if let Ok(r) = serde_json::from_slice::<'_, JsonRpcResponse<T>>(&bytes) {
-            Ok(r.result)
-        } else {
-            debug!("{}", String::from_utf8_lossy(&bytes));
-            Err(Error::NotFound)
-        }
+/// `IntegrityError` of the mismatches, if any.
+fn integrity(mismatches: Vec<verify::Mismatch>) -> Result<()> {
+    if mismatches.is_empty() {
+        return Ok(());
     }
+    let detail: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+    Err(Error::IntegrityError(detail.join("; ")))
 }
-pub struct RPCCaller {
-    pub(crate) retries: usize,
-    pub(crate) concurrency: usize,
-    pub(crate) rpc: TendermintRPC,
-    pub(crate) pool: PgPool,
+
+/// Records decoded from the transactions of a block.
+#[derive(Default)]
+pub(crate) struct DecodedTxs {
+    pub(crate) txs: Vec<Transaction>,
+    pub(crate) evm_txs: Vec<Transaction>,
+    pub(crate) v2_convert_account_txs: Vec<V2ConvertAccountTx>,
+    pub(crate) v2_undelegation_txs: Vec<V2UndelegationTx>,
+    pub(crate) v2_delegation_txs: Vec<V2DelegationTx>,
+    pub(crate) v2_claim_txs: Vec<V2ClaimTx>,
+    pub(crate) v2_asset_txs: Vec<V2AssetTx>,
+    pub(crate) v2_validator_events: Vec<V2ValidatorEvent>,
+    pub(crate) v2_mint_txs: Vec<V2MintTx>,
+    pub(crate) v2_slashing_events: Vec<V2SlashingEvent>,
+    pub(crate) v2_operations: Vec<V2Operation>,
+    pub(crate) evm_addrs: Vec<Address>,
+    pub(crate) native_addrs: Vec<Address>,
 }
 
-impl RPCCaller {
-    pub fn new(
-        retries: usize,
-        concurrency: usize,
-        timeout: Duration,
-        tendermint_rpc: Url,
-        pool: PgPool,
-    ) -> Self {
-        let rpc = TendermintRPC::new(timeout, tendermint_rpc);
-        RPCCaller {
-            retries,
-            concurrency,
-            rpc,
-            pool,
+impl DecodedTxs {
+    pub(crate) fn append(&mut self, mut other: DecodedTxs) {
+        self.txs.append(&mut other.txs);
+        self.evm_txs.append(&mut other.evm_txs);
+        self.v2_convert_account_txs
+            .append(&mut other.v2_convert_account_txs);
+        self.v2_undelegation_txs
+            .append(&mut other.v2_undelegation_txs);
+        self.v2_delegation_txs.append(&mut other.v2_delegation_txs);
+        self.v2_claim_txs.append(&mut other.v2_claim_txs);
+        self.v2_asset_txs.append(&mut other.v2_asset_txs);
+        self.v2_validator_events
+            .append(&mut other.v2_validator_events);
+        self.v2_mint_txs.append(&mut other.v2_mint_txs);
+        self.v2_slashing_events
+            .append(&mut other.v2_slashing_events);
+        self.v2_operations.append(&mut other.v2_operations);
+        self.evm_addrs.append(&mut other.evm_addrs);
+        self.native_addrs.append(&mut other.native_addrs);
+    }
+}
+
+/// Decode one transaction of a block.
+///
+/// Nothing is recorded for the tx if any part of it fails to decode, the error is returned so
+/// the caller can quarantine it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_tx(
+    bytes: &[u8],
+    origin: String,
+    tx_hash: String,
+    tx: ModuleTx,
+    block_hash: String,
+    height: i64,
+    timestamp: NaiveDateTime,
+    tx_index: usize,
+    stakers: &Stakers,
+) -> Result<DecodedTxs> {
+    let mut d = DecodedTxs::default();
+    let mut result_tmp = tx.tx_result.clone();
+
+    if !result_tmp.log.is_empty() {
+        let mut trim_log: Vec<u8> = vec![];
+        let log_bytes = result_tmp.log.as_bytes();
+        for b in log_bytes {
+            if *b > 31 {
+                trim_log.push(*b);
+            }
         }
+        result_tmp.log = String::from_utf8_lossy(&trim_log).to_string();
     }
+    let result = serde_json::to_value(&result_tmp)?;
 
-    pub async fn load_height(&self, height: i64) -> Result<ModuleBlock> {
-        let block = self.rpc.load_block(height).await?;
-        let block_data = serde_json::to_value(block.clone()).unwrap();
-        let block_size_rpc = self.rpc.get_block_size(height).await?;
-        let block_size = block_size_rpc.block_metas.unwrap().as_slice()[0]
-            .block_size
-            .parse::<i64>()
-            .unwrap();
-        let validator_info = self.rpc.load_validators(height).await?;
-
-        let block_hash = block.block_id.hash;
-        let height = block.block.header.height.parse::<i64>()?;
-        let timestamp =
-            NaiveDateTime::parse_from_str(&block.block.header.time, "%Y-%m-%dT%H:%M:%S%.fZ")?;
-        let app_hash = block.block.header.app_hash;
-        let proposer = block.block.header.proposer_address;
-        let mut txs = Vec::new();
-        let mut evm_txs = Vec::new();
-        let mut validators = Vec::new();
-        let mut v2_convert_account_txs = Vec::new();
-        let mut v2_undelegation_txs = Vec::new();
-        let mut v2_delegation_txs = Vec::new();
-        let mut v2_claim_txs = Vec::new();
-        let mut v2_asset_txs = Vec::new();
-        let mut evm_addrs: Vec<Address> = vec![];
-        let mut native_addrs: Vec<Address> = vec![];
-
-        for tx_string in block.block.data.txs.unwrap_or_default() {
-            let bytes = engine::general_purpose::STANDARD.decode(&tx_string)?;
-            let origin = tx_string;
-            let hasher = sha2::Sha256::digest(&bytes);
-            let tx_hash = hex::encode(hasher);
-            let tx = self.rpc.load_transaction(&tx_hash).await?;
-            let mut result_tmp = tx.tx_result.clone();
-            if !result_tmp.log.is_empty() {
-                let mut trim_log: Vec<u8> = vec![];
-                let log_bytes = result_tmp.log.as_bytes();
-                for b in log_bytes {
-                    if *b > 31 {
-                        trim_log.push(*b);
+    match tx::try_tx_catalog(bytes) {
+        tx::TxCatalog::EvmTx => {
+            let value: Value = serde_json::from_slice(tx::unwrap(bytes)?)?;
+            let sender: String;
+            let ty_sub: i32;
+            let mut addrs: Vec<String> = vec![];
+            let mut v: Value = value.clone();
+            let evm_tx_str = serde_json::to_string(&value)?;
+            if evm_tx_str.contains("XHub") {
+                debug!("[EVM] XHub, height: {}, tx: {}", height, tx_hash);
+                let xhub_opt: XHubOpt = serde_json::from_value(value)?;
+                for xo in &xhub_opt.function.xhub.nonconfidential_transfer.outputs {
+                    let to = pubkey_to_fra_address(&xo.target)?;
+                    d.native_addrs.push(Address {
+                        tx: tx_hash.clone(),
+                        address: to,
+                        timestamp: timestamp.and_utc().timestamp(),
+                    });
+                }
+                sender = "".to_string();
+                ty_sub = FindoraTxType::EVMToNative as i32;
+            } else {
+                debug!("[EVM] Ethereum, height: {}, tx: {}", height, tx_hash);
+                let evm_tx: FindoraEVMTx = serde_json::from_value(value)?;
+                let signer = recover_signer(&evm_tx.function.ethereum.transact)
+                    .ok_or(Error::EvmTxParseError)?;
+                let to = match evm_tx.function.ethereum.transact.action {
+                    TransactionAction::Call(to) => {
+                        format!("{to:?}")
                     }
+                    _ => "".to_string(),
+                };
+                addrs.push(to);
+                sender = format!("{signer:?}");
+                ty_sub = FindoraTxType::Evm as i32;
+                let wrap_evm_tx = FindoraEVMTxWrap {
+                    function: EthereumWrap {
+                        ethereum: TransactWrap {
+                            transact: TransactWrapData {
+                                from: sender.clone(),
+                                nonce: evm_tx.function.ethereum.transact.nonce,
+                                gas_price: evm_tx.function.ethereum.transact.gas_price,
+                                gas_limit: evm_tx.function.ethereum.transact.gas_limit,
+                                action: evm_tx.function.ethereum.transact.action,
+                                value: evm_tx.function.ethereum.transact.value,
+                                input: evm_tx.function.ethereum.transact.input,
+                                signature: evm_tx.function.ethereum.transact.signature,
+                            },
+                        },
+                    },
+                };
+
+                v = serde_json::to_value(&wrap_evm_tx)?;
+            }
+            let r = Receivers {
+                addrs: addrs.clone(),
+            };
+            let receivers_val = serde_json::to_value(&r)?;
+            d.v2_operations.push(V2Operation {
+                tx_hash: tx_hash.clone(),
+                op_index: 0,
+                kind: if ty_sub == FindoraTxType::Evm as i32 {
+                    "Ethereum".to_string()
+                } else {
+                    "XHub".to_string()
+                },
+                signer: sender.clone(),
+                counterparty: addrs.first().cloned().unwrap_or_default(),
+                asset: Some(FRA_ASSET.to_string()),
+                amount: v["function"]["Ethereum"]["Transact"]["value"]
+                    .as_str()
+                    .map(|a| a.to_string()),
+                height,
+                timestamp: timestamp.and_utc().timestamp(),
+                content: v.clone(),
+            });
+            d.evm_txs.push(Transaction {
+                tx_hash: tx_hash.clone(),
+                block_hash: block_hash.clone(),
+                height,
+                tx_index: tx_index as i64,
+                timestamp: timestamp.and_utc().timestamp(),
+                code: tx.tx_result.code,
+                ty: FindoraTxType::Evm as i32,
+                ty_sub,
+                sender: sender.clone(),
+                receiver: receivers_val,
+                log: result_tmp.log,
+                origin,
+                result,
+                value: v,
+            });
+
+            addrs.push(sender);
+            addrs.dedup();
+            for a in addrs {
+                if a.is_empty() {
+                    continue;
                 }
-                result_tmp.log = String::from_utf8_lossy(&trim_log).parse().unwrap();
+                d.evm_addrs.push(Address {
+                    tx: tx_hash.clone(),
+                    address: a,
+                    timestamp: timestamp.and_utc().timestamp(),
+                });
             }
-            let result = serde_json::to_value(&result_tmp).unwrap();
-
-            match tx::try_tx_catalog(&bytes) {
-                tx::TxCatalog::EvmTx => {
-                    let value: Value = serde_json::from_slice(tx::unwrap(&bytes)?)?;
-                    let sender: String;
-                    let ty_sub: i32;
-                    let mut addrs: Vec<String> = vec![];
-                    let mut v: Value = value.clone();
-                    let evm_tx_str = serde_json::to_string(&value).unwrap();
-                    if evm_tx_str.contains("XHub") {
-                        debug!("[EVM] XHub, height: {}, tx: {}", height, tx_hash);
-                        let xhub_opt: XHubOpt = serde_json::from_value(value).unwrap();
-                        for xo in &xhub_opt.function.xhub.nonconfidential_transfer.outputs {
-                            let to = pubkey_to_fra_address(&xo.target).unwrap();
-                            native_addrs.push(Address {
-                                tx: tx_hash.clone(),
-                                address: to,
-                                timestamp: timestamp.and_utc().timestamp(),
-                            });
-                        }
-                        sender = "".to_string();
-                        ty_sub = FindoraTxType::EVMToNative as i32;
-                    } else {
-                        debug!("[EVM] Ethereum, height: {}, tx: {}", height, tx_hash);
-                        let evm_tx: FindoraEVMTx = serde_json::from_value(value).unwrap();
-                        let signer = recover_signer(&evm_tx.function.ethereum.transact).unwrap();
-                        let to = match evm_tx.function.ethereum.transact.action {
-                            TransactionAction::Call(to) => {
-                                format!("{to:?}")
-                            }
-                            _ => "".to_string(),
-                        };
-                        addrs.push(to);
-                        sender = format!("{signer:?}");
-                        ty_sub = FindoraTxType::Evm as i32;
-                        let wrap_evm_tx = FindoraEVMTxWrap {
-                            function: EthereumWrap {
-                                ethereum: TransactWrap {
-                                    transact: TransactWrapData {
-                                        from: sender.clone(),
-                                        nonce: evm_tx.function.ethereum.transact.nonce,
-                                        gas_price: evm_tx.function.ethereum.transact.gas_price,
-                                        gas_limit: evm_tx.function.ethereum.transact.gas_limit,
-                                        action: evm_tx.function.ethereum.transact.action,
-                                        value: evm_tx.function.ethereum.transact.value,
-                                        input: evm_tx.function.ethereum.transact.input,
-                                        signature: evm_tx.function.ethereum.transact.signature,
-                                    },
-                                },
-                            },
-                        };
+        }
+
+        tx::TxCatalog::FindoraTx => {
+            let value: Value = serde_json::from_slice(bytes)?;
+            let v: Value = value.clone();
+            let mut sender: String = "".to_string();
+            let mut ty_sub = 0;
+            let mut addrs: Vec<String> = vec![];
+            let tx_val: TxValue = serde_json::from_value(v)?;
+
+            for (op_index, op) in tx_val.body.operations.into_iter().enumerate() {
+                d.v2_operations.push(decode_operation(
+                    &op,
+                    &tx_hash,
+                    op_index as i32,
+                    height,
+                    timestamp.and_utc().timestamp(),
+                ));
+                if let Some((signer, mut events)) = decode_validator_op(
+                    &op,
+                    &tx_hash,
+                    op_index as i32,
+                    &block_hash,
+                    height,
+                    timestamp.and_utc().timestamp(),
+                    stakers,
+                )? {
+                    sender = signer;
+                    d.v2_validator_events.append(&mut events);
+                    continue;
+                }
+                if let Some((signer, mut mints)) = decode_mint_op(
+                    &op,
+                    &tx_hash,
+                    &block_hash,
+                    height,
+                    timestamp.and_utc().timestamp(),
+                )? {
+                    if let Some(signer) = signer {
+                        sender = signer;
+                    }
+                    d.v2_mint_txs.append(&mut mints);
+                    continue;
+                }
+                if let Some(ev) = decode_governance_op(
+                    &op,
+                    &tx_hash,
+                    tx_index as i64,
+                    op_index as i32,
+                    &block_hash,
+                    height,
+                    timestamp.and_utc().timestamp(),
+                )? {
+                    d.v2_slashing_events.push(ev);
+                    continue;
+                }
 
-                        v = serde_json::to_value(&wrap_evm_tx).unwrap();
+                let op_str = serde_json::to_string(&op)?;
+                if op_str.contains("ConvertAccount") {
+                    debug!("[Native] ConvertAccount, height: {}", height);
+                    let op_copy = op.clone();
+                    let opt: ConvertAccountOpt = serde_json::from_value(op)?;
+                    let asset: String;
+                    if let Some(asset_bin) = &opt.convert_account.asset_type {
+                        asset = engine::general_purpose::URL_SAFE.encode(asset_bin);
+                    } else {
+                        asset = FRA_ASSET.to_string();
                     }
-                    let r = Receivers {
-                        addrs: addrs.clone(),
+                    let signer = pubkey_to_fra_address(&opt.convert_account.signer)?;
+                    let receiver = opt.convert_account.receiver.ethereum;
+                    d.evm_addrs.push(Address {
+                        tx: tx_hash.clone(),
+                        address: receiver.clone(),
+                        timestamp: timestamp.and_utc().timestamp(),
+                    });
+                    sender.clone_from(&signer);
+                    ty_sub = FindoraTxType::NativeToEVM as i32;
+                    d.v2_convert_account_txs.push(V2ConvertAccountTx {
+                        tx_hash: tx_hash.clone(),
+                        block_hash: block_hash.clone(),
+                        sender: signer,
+                        receiver,
+                        asset,
+                        amount: opt.convert_account.value,
+                        height,
+                        timestamp: timestamp.and_utc().timestamp(),
+                        content: op_copy,
+                    });
+                } else if op_str.contains("UnDelegation") {
+                    debug!("[Native] UnDelegation, height: {}, tx: {}", height, tx_hash);
+                    let op_copy = op.clone();
+                    let opt: UnDelegationOpt = serde_json::from_value(op)?;
+                    let signer = pubkey_to_fra_address(&opt.undelegation.pubkey)?;
+                    let (amount, new_delegator, target_validator) = match opt.undelegation.body.pu {
+                        Some(pu) => {
+                            let target_validator_addr = hex::encode(pu.target_validator);
+                            (
+                                pu.am,
+                                pu.new_delegator_id,
+                                target_validator_addr.to_uppercase(),
+                            )
+                        }
+                        _ => (0, "".to_string(), "".to_string()),
                     };
-                    let receivers_val = serde_json::to_value(&r).unwrap();
-                    evm_txs.push(Transaction {
+
+                    sender.clone_from(&signer);
+                    ty_sub = FindoraTxType::Undelegation as i32;
+                    d.v2_undelegation_txs.push(V2UndelegationTx {
+                        tx_hash: tx_hash.clone(),
+                        block_hash: block_hash.clone(),
+                        sender: signer,
+                        amount,
+                        target_validator,
+                        new_delegator,
+                        height,
+                        timestamp: timestamp.and_utc().timestamp(),
+                        content: op_copy,
+                    });
+                } else if op_str.contains("Delegation") {
+                    debug!("[Native] Delegation, height: {}, tx: {}", height, tx_hash);
+                    let op_copy = op.clone();
+                    let opt: DelegationOpt = serde_json::from_value(op)?;
+                    let signer = pubkey_to_fra_address(&opt.delegation.pubkey)?;
+                    let new_validator = opt.delegation.body.new_validator.unwrap_or("".to_string());
+                    sender.clone_from(&signer);
+                    ty_sub = FindoraTxType::Delegation as i32;
+                    d.v2_delegation_txs.push(V2DelegationTx {
+                        tx_hash: tx_hash.clone(),
+                        block_hash: block_hash.clone(),
+                        sender: signer,
+                        amount: opt.delegation.body.amount,
+                        validator: opt.delegation.body.validator,
+                        new_validator,
+                        height,
+                        timestamp: timestamp.and_utc().timestamp(),
+                        content: op_copy,
+                    });
+                } else if op_str.contains("Claim") {
+                    debug!("[Native] Claim, height: {}, tx: {}", height, tx_hash);
+                    let op_copy = op.clone();
+                    let opt: ClaimOpt = serde_json::from_value(op)?;
+                    let signer = pubkey_to_fra_address(&opt.claim.pubkey)?;
+                    sender.clone_from(&signer);
+                    ty_sub = FindoraTxType::Claim as i32;
+                    d.v2_claim_txs.push(V2ClaimTx {
+                        tx_hash: tx_hash.clone(),
+                        block_hash: block_hash.clone(),
+                        sender: signer,
+                        amount: opt.claim.body.amount.unwrap_or(0),
+                        height,
+                        timestamp: timestamp.and_utc().timestamp(),
+                        content: op_copy,
+                    });
+                } else if op_str.contains("DefineAsset") {
+                    debug!("[Native] DefineAsset, height: {}, tx: {}", height, tx_hash);
+                    let op_copy = op.clone();
+                    let opt: DefineAssetOpt = serde_json::from_value(op)?;
+                    let issuer = pubkey_to_fra_address(&opt.define_asset.pubkey.key)?;
+                    let asset = engine::general_purpose::URL_SAFE
+                        .encode(opt.define_asset.body.asset.code.val);
+                    sender.clone_from(&issuer);
+                    ty_sub = FindoraTxType::DefineOrIssueAsset as i32;
+                    d.v2_asset_txs.push(V2AssetTx {
+                        asset,
+                        tx_hash: tx_hash.clone(),
+                        block_hash: block_hash.clone(),
+                        issuer,
+                        height,
+                        timestamp: timestamp.and_utc().timestamp(),
+                        issued: 0,
+                        content: op_copy,
+                    });
+                } else if op_str.contains("IssueAsset") {
+                    debug!("[Native] IssueAsset, height: {}, tx: {}", height, tx_hash);
+                    let op_copy = op.clone();
+                    let opt: IssueAssetOpt = serde_json::from_value(op)?;
+                    let issuer = pubkey_to_fra_address(&opt.issue_asset.pubkey.key)?;
+                    let asset =
+                        engine::general_purpose::URL_SAFE.encode(opt.issue_asset.body.code.val);
+                    sender.clone_from(&issuer);
+                    ty_sub = FindoraTxType::DefineOrIssueAsset as i32;
+                    d.v2_asset_txs.push(V2AssetTx {
+                        asset,
                         tx_hash: tx_hash.clone(),
                         block_hash: block_hash.clone(),
+                        issuer,
                         height,
                         timestamp: timestamp.and_utc().timestamp(),
-                        code: tx.tx_result.code,
-                        ty: FindoraTxType::Evm as i32,
-                        ty_sub,
-                        sender: sender.clone(),
-                        receiver: receivers_val,
-                        log: result_tmp.log,
-                        origin,
-                        result,
-                        value: v,
+                        issued: 1,
+                        content: op_copy,
                     });
+                } else if op_str.contains("TransferAsset") {
+                    debug!(
+                        "[Native] TransferAsset, height: {}, tx: {}",
+                        height, tx_hash
+                    );
+                    let opt: TransferAssetOpt = serde_json::from_value(op)?;
+                    let pk = &opt
+                        .transfer_asset
+                        .body_signatures
+                        .first()
+                        .ok_or("TransferAsset without body signatures")?
+                        .address
+                        .key;
+                    let signer = pubkey_to_fra_address(pk)?;
+                    for o in opt.transfer_asset.body.transfer.outputs {
+                        let mut receiver: String = "".to_string();
+                        let type_show_amount_show: core::result::Result<
+                            OutputTypeShowAmountShow,
+                            _,
+                        > = serde_json::from_value(o.clone());
 
-                    addrs.push(sender);
-                    addrs.dedup();
-                    for a in addrs {
-                        if a.is_empty() {
-                            continue;
-                        }
-                        evm_addrs.push(Address {
-                            tx: tx_hash.clone(),
-                            address: a,
-                            timestamp: timestamp.and_utc().timestamp(),
-                        });
-                    }
-                }
+                        if let Ok(tsas) = type_show_amount_show {
+                            // type show, amount show
+                            let pk = tsas.public_key;
+                            if pk.eq(&FRA_ASSET) {
+                                continue;
+                            }
+                            receiver = pubkey_to_fra_address(&pk)?;
+                        } else {
+                            // type show, amount hide
+                            let type_show_amount_hide: core::result::Result<
+                                OutputTypeShowAmountHide,
+                                _,
+                            > = serde_json::from_value(o.clone());
 
-                tx::TxCatalog::FindoraTx => {
-                    let value: Value = serde_json::from_slice(&bytes)?;
-                    let v: Value = value.clone();
-                    let mut sender: String = "".to_string();
-                    let mut ty_sub = 0;
-                    let mut addrs: Vec<String> = vec![];
-                    let tx_val: TxValue = serde_json::from_value(v).unwrap();
-
-                    for op in tx_val.body.operations {
-                        let op_str = serde_json::to_string(&op).unwrap();
-                        if op_str.contains("ConvertAccount") {
-                            debug!("[Native] ConvertAccount, height: {}", height);
-                            let op_copy = op.clone();
-                            let opt: ConvertAccountOpt = serde_json::from_value(op).unwrap();
-                            let asset: String;
-                            if let Some(asset_bin) = &opt.convert_account.asset_type {
-                                asset = engine::general_purpose::URL_SAFE.encode(asset_bin);
+                            if let Ok(tsah) = type_show_amount_hide {
+                                let pk = tsah.public_key;
+                                if pk.eq(&FRA_ASSET) {
+                                    continue;
+                                }
+                                ty_sub = FindoraTxType::TypeShowAmountHide as i32;
+                                receiver = pubkey_to_fra_address(&pk)?;
                             } else {
-                                asset = FRA_ASSET.to_string();
-                            }
-                            let signer =
-                                pubkey_to_fra_address(&opt.convert_account.signer).unwrap();
-                            let receiver = opt.convert_account.receiver.ethereum;
-                            evm_addrs.push(Address {
-                                tx: tx_hash.clone(),
-                                address: receiver.clone(),
-                                timestamp: timestamp.and_utc().timestamp(),
-                            });
-                            sender.clone_from(&signer);
-                            ty_sub = FindoraTxType::NativeToEVM as i32;
-                            v2_convert_account_txs.push(V2ConvertAccountTx {
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                sender: signer,
-                                receiver,
-                                asset,
-                                amount: opt.convert_account.value,
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("UnDelegation") {
-                            debug!("[Native] UnDelegation, height: {}, tx: {}", height, tx_hash);
-                            let op_copy = op.clone();
-                            let opt: UnDelegationOpt = serde_json::from_value(op).unwrap();
-                            let signer = pubkey_to_fra_address(&opt.undelegation.pubkey).unwrap();
-                            let (amount, new_delegator, target_validator) =
-                                match opt.undelegation.body.pu {
-                                    Some(pu) => {
-                                        let target_validator_addr =
-                                            hex::encode(pu.target_validator);
-                                        (
-                                            pu.am,
-                                            pu.new_delegator_id,
-                                            target_validator_addr.to_uppercase(),
-                                        )
-                                    }
-                                    _ => (0, "".to_string(), "".to_string()),
-                                };
-
-                            sender.clone_from(&signer);
-                            ty_sub = FindoraTxType::Undelegation as i32;
-                            v2_undelegation_txs.push(V2UndelegationTx {
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                sender: signer,
-                                amount,
-                                target_validator,
-                                new_delegator,
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("Delegation") {
-                            debug!("[Native] Delegation, height: {}, tx: {}", height, tx_hash);
-                            let op_copy = op.clone();
-                            let opt: DelegationOpt = serde_json::from_value(op).unwrap();
-                            let signer = pubkey_to_fra_address(&opt.delegation.pubkey).unwrap();
-                            let new_validator =
-                                opt.delegation.body.new_validator.unwrap_or("".to_string());
-                            sender.clone_from(&signer);
-                            ty_sub = FindoraTxType::Delegation as i32;
-                            v2_delegation_txs.push(V2DelegationTx {
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                sender: signer,
-                                amount: opt.delegation.body.amount,
-                                validator: opt.delegation.body.validator,
-                                new_validator,
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("Claim") {
-                            debug!("[Native] Claim, height: {}, tx: {}", height, tx_hash);
-                            let op_copy = op.clone();
-                            let opt: ClaimOpt = serde_json::from_value(op).unwrap();
-                            let signer = pubkey_to_fra_address(&opt.claim.pubkey).unwrap();
-                            sender.clone_from(&signer);
-                            ty_sub = FindoraTxType::Claim as i32;
-                            v2_claim_txs.push(V2ClaimTx {
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                sender: signer,
-                                amount: opt.claim.body.amount.unwrap_or(0),
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("DefineAsset") {
-                            debug!("[Native] DefineAsset, height: {}, tx: {}", height, tx_hash);
-                            let op_copy = op.clone();
-                            let opt: DefineAssetOpt = serde_json::from_value(op).unwrap();
-                            let issuer =
-                                pubkey_to_fra_address(&opt.define_asset.pubkey.key).unwrap();
-                            let asset = engine::general_purpose::URL_SAFE
-                                .encode(opt.define_asset.body.asset.code.val);
-                            sender.clone_from(&issuer);
-                            ty_sub = FindoraTxType::DefineOrIssueAsset as i32;
-                            v2_asset_txs.push(V2AssetTx {
-                                asset,
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                issuer,
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                issued: 0,
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("IssueAsset") {
-                            debug!("[Native] IssueAsset, height: {}, tx: {}", height, tx_hash);
-                            let op_copy = op.clone();
-                            let opt: IssueAssetOpt = serde_json::from_value(op).unwrap();
-                            let issuer =
-                                pubkey_to_fra_address(&opt.issue_asset.pubkey.key).unwrap();
-                            let asset = engine::general_purpose::URL_SAFE
-                                .encode(opt.issue_asset.body.code.val);
-                            sender.clone_from(&issuer);
-                            ty_sub = FindoraTxType::DefineOrIssueAsset as i32;
-                            v2_asset_txs.push(V2AssetTx {
-                                asset,
-                                tx_hash: tx_hash.clone(),
-                                block_hash: block_hash.clone(),
-                                issuer,
-                                height,
-                                timestamp: timestamp.and_utc().timestamp(),
-                                issued: 1,
-                                content: op_copy,
-                            });
-                        } else if op_str.contains("TransferAsset") {
-                            debug!(
-                                "[Native] TransferAsset, height: {}, tx: {}",
-                                height, tx_hash
-                            );
-                            let opt: TransferAssetOpt = serde_json::from_value(op).unwrap();
-                            let pk = &opt.transfer_asset.body_signatures[0].address.key;
-                            let signer = pubkey_to_fra_address(pk).unwrap();
-                            for o in opt.transfer_asset.body.transfer.outputs {
-                                let mut receiver: String = "".to_string();
-                                let type_show_amount_show: core::result::Result<
-                                    OutputTypeShowAmountShow,
+                                // type hide, amount show
+                                let type_hide_amount_show: core::result::Result<
+                                    OutputTypeHideAmountShow,
                                     _,
                                 > = serde_json::from_value(o.clone());
 
-                                if let Ok(tsas) = type_show_amount_show {
-                                    // type show, amount show
-                                    let pk = tsas.public_key;
+                                if let Ok(thas) = type_hide_amount_show {
+                                    let pk = thas.public_key;
                                     if pk.eq(&FRA_ASSET) {
                                         continue;
                                     }
-                                    receiver = pubkey_to_fra_address(&pk).unwrap();
+                                    ty_sub = FindoraTxType::TypeHideAmountShow as i32;
+                                    receiver = pubkey_to_fra_address(&pk)?;
                                 } else {
-                                    // type show, amount hide
-                                    let type_show_amount_hide: core::result::Result<
-                                        OutputTypeShowAmountHide,
+                                    // type hide, amount hide
+                                    let type_hide_amount_hide: core::result::Result<
+                                        OutputTypeHideAmountHide,
                                         _,
                                     > = serde_json::from_value(o.clone());
 
-                                    if let Ok(tsah) = type_show_amount_hide {
-                                        let pk = tsah.public_key;
-                                        if pk.eq(&FRA_ASSET) {
+                                    if let Ok(thah) = type_hide_amount_hide {
+                                        if thah.public_key.eq(&FRA_ASSET) {
                                             continue;
                                         }
-                                        ty_sub = FindoraTxType::TypeShowAmountHide as i32;
-                                        receiver = pubkey_to_fra_address(&pk).unwrap();
-                                    } else {
-                                        // type hide, amount show
-                                        let type_hide_amount_show: core::result::Result<
-                                            OutputTypeHideAmountShow,
-                                            _,
-                                        > = serde_json::from_value(o.clone());
-
-                                        if let Ok(thas) = type_hide_amount_show {
-                                            let pk = thas.public_key;
-                                            if pk.eq(&FRA_ASSET) {
-                                                continue;
-                                            }
-                                            ty_sub = FindoraTxType::TypeHideAmountShow as i32;
-                                            receiver = pubkey_to_fra_address(&pk).unwrap();
-                                        } else {
-                                            // type hide, amount hide
-                                            let type_hide_amount_hide: core::result::Result<
-                                                OutputTypeHideAmountHide,
-                                                _,
-                                            > = serde_json::from_value(o.clone());
-
-                                            if let Ok(thah) = type_hide_amount_hide {
-                                                if thah.public_key.eq(&FRA_ASSET) {
-                                                    continue;
-                                                }
-                                                ty_sub = FindoraTxType::TypeHideAmountHide as i32;
-                                                receiver = pubkey_to_fra_address(&thah.public_key)
-                                                    .unwrap();
-                                            }
-                                        }
+                                        ty_sub = FindoraTxType::TypeHideAmountHide as i32;
+                                        receiver = pubkey_to_fra_address(&thah.public_key)?;
                                     }
                                 }
-
-                                addrs.push(receiver);
                             }
-                            sender = signer;
-                        } else {
-                            debug!("[Native], height: {}, tx: {}", height, tx_hash);
                         }
-                    }
-
-                    let r = Receivers {
-                        addrs: addrs.clone(),
-                    };
-                    let receivers_val = serde_json::to_value(&r).unwrap();
-                    txs.push(Transaction {
-                        tx_hash: tx_hash.clone(),
-                        block_hash: block_hash.clone(),
-                        height,
-                        timestamp: timestamp.and_utc().timestamp(),
-                        code: tx.tx_result.code,
-                        ty: FindoraTxType::Native as i32,
-                        ty_sub,
-                        sender: sender.clone(),
-                        receiver: receivers_val,
-                        log: result_tmp.log,
-                        origin,
-                        result,
-                        value,
-                    });
 
-                    addrs.push(sender);
-                    addrs.dedup();
-                    for a in addrs {
-                        if a.is_empty() {
-                            continue;
-                        }
-                        native_addrs.push(Address {
-                            tx: tx_hash.clone(),
-                            address: a,
-                            timestamp: timestamp.and_utc().timestamp(),
-                        });
+                        addrs.push(receiver);
                     }
+                    sender = signer;
+                } else {
+                    debug!("[Native], height: {}, tx: {}", height, tx_hash);
                 }
+            }
+
+            let r = Receivers {
+                addrs: addrs.clone(),
+            };
+            let receivers_val = serde_json::to_value(&r)?;
+            d.txs.push(Transaction {
+                tx_hash: tx_hash.clone(),
+                block_hash: block_hash.clone(),
+                height,
+                tx_index: tx_index as i64,
+                timestamp: timestamp.and_utc().timestamp(),
+                code: tx.tx_result.code,
+                ty: FindoraTxType::Native as i32,
+                ty_sub,
+                sender: sender.clone(),
+                receiver: receivers_val,
+                log: result_tmp.log,
+                origin,
+                result,
+                value,
+            });
 
-                tx::TxCatalog::Unknown => {
-                    info!("Unknown tx: {}", tx_hash);
+            addrs.push(sender);
+            addrs.dedup();
+            for a in addrs {
+                if a.is_empty() {
+                    continue;
                 }
+                d.native_addrs.push(Address {
+                    tx: tx_hash.clone(),
+                    address: a,
+                    timestamp: timestamp.and_utc().timestamp(),
+                });
             }
         }
 
-        for vv in validator_info.validators {
-            let address = vv.address;
-            let power = vv.voting_power.parse::<u64>()?;
-            let pub_key = vv.pub_key;
-            let priority = vv.proposer_priority.parse::<i64>()?;
-            if block.block.last_commit.signatures.is_none() {
-                break;
-            }
-            let sign_info = block
-                .block
-                .last_commit
-                .signatures
-                .as_ref()
-                .unwrap()
-                .iter()
-                .find(|v| Some(&address) == v.validator_address.as_ref());
+        tx::TxCatalog::Unknown => {
+            return Err(format!("unknown tx catalog: {tx_hash}").into());
+        }
+    }
 
-            let (signature, timestamp) = if let Some(s) = sign_info {
-                let signature = s.signature.clone();
-                let timestamp = if let Some(s) = &s.timestamp {
-                    Some(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.fZ")?)
-                } else {
-                    None
-                };
+    Ok(d)
+}
 
-                (signature, timestamp)
-            } else {
-                (None, None)
-            };
+fn td_addr_to_string(addr: &[i64]) -> String {
+    let bytes: Vec<u8> = addr.iter().map(|b| *b as u8).collect();
+    hex::encode_upper(bytes)
+}
 
-            let validator = Validator {
-                address,
-                power,
-                pub_key,
-                priority,
-                signature,
-                timestamp,
-            };
+/// Decode the validator lifecycle operations (UpdateValidator, UpdateStaker and ReplaceStaker).
+///
+/// Returns the signer and the decoded events, or `None` if `op` is not one of them. A
+/// ReplaceStaker without new tendermint params keeps the validator of its staker, taken from
+/// `stakers`, it fails to decode if the staker is not known.
+pub(crate) fn decode_validator_op(
+    op: &Value,
+    tx_hash: &str,
+    op_index: i32,
+    block_hash: &str,
+    height: i64,
+    timestamp: i64,
+    stakers: &Stakers,
+) -> Result<Option<(String, Vec<V2ValidatorEvent>)>> {
+    let mut events: Vec<V2ValidatorEvent> = vec![];
 
-            validators.push(validator);
+    let signer = if op.get("UpdateValidator").is_some() {
+        debug!(
+            "[Native] UpdateValidator, height: {}, tx: {}",
+            height, tx_hash
+        );
+        let opt: UpdateValidatorOpt = serde_json::from_value(op.clone())?;
+        for v in opt.update_validator.body.vs {
+            events.push(V2ValidatorEvent {
+                tx_hash: tx_hash.to_string(),
+                op_index,
+                block_hash: block_hash.to_string(),
+                validator: td_addr_to_string(&v.td_addr),
+                staker: pubkey_to_fra_address(&v.id)?,
+                kind: "UpdateValidator".to_string(),
+                commission_rate: Some(serde_json::to_value(&v.commission_rate)?),
+                memo: Some(serde_json::to_value(&v.memo)?),
+                new_staker: None,
+                height,
+                timestamp,
+                content: serde_json::to_value(&v)?,
+            });
         }
-
-        Ok(ModuleBlock {
-            block_hash,
+        pubkey_to_fra_address(&opt.update_validator.pubkey)?
+    } else if op.get("UpdateStaker").is_some() {
+        debug!("[Native] UpdateStaker, height: {}, tx: {}", height, tx_hash);
+        let opt: UpdateStakerOpt = serde_json::from_value(op.clone())?;
+        let signer = pubkey_to_fra_address(&opt.update_staker.pubkey)?;
+        let new_validator = opt.update_staker.body.new_validator;
+        events.push(V2ValidatorEvent {
+            tx_hash: tx_hash.to_string(),
+            op_index,
+            block_hash: block_hash.to_string(),
+            validator: opt.update_staker.body.validator.to_uppercase(),
+            staker: signer.clone(),
+            kind: "UpdateStaker".to_string(),
+            commission_rate: Some(serde_json::to_value(&new_validator.commission_rate)?),
+            memo: Some(serde_json::to_value(&new_validator.memo)?),
+            new_staker: None,
+            height,
+            timestamp,
+            content: op.clone(),
+        });
+        signer
+    } else if op.get("ReplaceStaker").is_some() {
+        debug!(
+            "[Native] ReplaceStaker, height: {}, tx: {}",
+            height, tx_hash
+        );
+        let opt: ReplaceStakerOpt = serde_json::from_value(op.clone())?;
+        let signer = pubkey_to_fra_address(&opt.replace_staker.pubkey)?;
+        let new_staker = pubkey_to_fra_address(&opt.replace_staker.body.new_public_key)?;
+        let validator = match &opt.replace_staker.body.new_tendermint_params {
+            Some(params) => hex::encode_upper(&params.address),
+            None => stakers
+                .get(&signer)
+                .cloned()
+                .ok_or_else(|| format!("no validator of staker {signer}"))?,
+        };
+        events.push(V2ValidatorEvent {
+            tx_hash: tx_hash.to_string(),
+            op_index,
+            block_hash: block_hash.to_string(),
+            validator,
+            staker: signer.clone(),
+            kind: "ReplaceStaker".to_string(),
+            commission_rate: None,
+            memo: None,
+            new_staker: Some(new_staker),
             height,
-            size: block_size,
-            tx_count: (evm_txs.len() + txs.len()) as i64,
             timestamp,
-            app_hash,
-            proposer,
-            evm_addrs,
-            native_addrs,
-            txs,
-            evm_txs,
-            validators,
-            v2_convert_account_txs,
-            v2_undelegation_txs,
-            v2_delegation_txs,
-            v2_claim_txs,
-            v2_asset_txs,
-            block_data,
-        })
+            content: op.clone(),
+        });
+        signer
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some((signer, events)))
+}
+
+/// Decode the coinbase operations (MintFra and FraDistribution) into mint records.
+///
+/// Returns the signer (MintFra has none) and the records, or `None` if `op` is not one of them.
+pub(crate) fn decode_mint_op(
+    op: &Value,
+    tx_hash: &str,
+    block_hash: &str,
+    height: i64,
+    timestamp: i64,
+) -> Result<Option<(Option<String>, Vec<V2MintTx>)>> {
+    let mut mints: Vec<V2MintTx> = vec![];
+
+    let signer = if op.get("MintFra").is_some() {
+        debug!("[Native] MintFra, height: {}, tx: {}", height, tx_hash);
+        let opt: MintFraOpt = serde_json::from_value(op.clone())?;
+        for (idx, entry) in opt.mint_fra.entries.iter().enumerate() {
+            mints.push(V2MintTx {
+                tx_hash: tx_hash.to_string(),
+                block_hash: block_hash.to_string(),
+                idx: idx as i32,
+                receiver: pubkey_to_fra_address(&entry.target_pk)?,
+                amount: entry.amount,
+                kind: entry.kind.clone(),
+                height,
+                timestamp,
+                content: serde_json::to_value(entry)?,
+            });
+        }
+        None
+    } else if op.get("FraDistribution").is_some() {
+        debug!(
+            "[Native] FraDistribution, height: {}, tx: {}",
+            height, tx_hash
+        );
+        let opt: FraDistributionOpt = serde_json::from_value(op.clone())?;
+        for (idx, (pk, amount)) in opt.fra_distribution.body.alloc_table.iter().enumerate() {
+            mints.push(V2MintTx {
+                tx_hash: tx_hash.to_string(),
+                block_hash: block_hash.to_string(),
+                idx: idx as i32,
+                receiver: pubkey_to_fra_address(pk)?,
+                amount: *amount,
+                kind: "FraDistribution".to_string(),
+                height,
+                timestamp,
+                content: op.clone(),
+            });
+        }
+        Some(pubkey_to_fra_address(&opt.fra_distribution.pubkey)?)
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some((signer, mints)))
+}
+
+/// The penalty rate, `[numerator, denominator]`, findora applies to a kind of byzantine
+/// behavior unless a governance op sets its own.
+fn default_penalty_rate(kind: &str) -> Option<[i64; 2]> {
+    match kind {
+        "DuplicateVote" => Some([5, 100]),
+        "LightClientAttack" => Some([1, 100]),
+        "Unknown" => Some([30, 100]),
+        _ => None,
     }
+}
 
-    pub async fn load_height_retried(&self, height: i64) -> Result<ModuleBlock> {
-        for i in 0..self.retries + 1 {
-            match self.load_height(height).await {
-                Ok(r) => return Ok(r),
-                Err(Error::NotFound) => {
-                    return Err(Error::NotFound);
+/// Decode the evidence of misbehavior committed in a block.
+///
+/// Both the 0.33 (`VoteA`) and the 0.34 (`vote_a`) json layouts of duplicate vote evidence are
+/// accepted, light client attacks produce one event per byzantine validator.
+pub(crate) fn decode_evidence(
+    evidence: &[Value],
+    block_hash: &str,
+    height: i64,
+    timestamp: i64,
+) -> Vec<V2SlashingEvent> {
+    let mut events: Vec<V2SlashingEvent> = vec![];
+
+    for (index, ev) in evidence.iter().enumerate() {
+        let ty = ev["type"].as_str().unwrap_or_default();
+        let kind = ty
+            .trim_start_matches("tendermint/")
+            .trim_end_matches("Evidence")
+            .to_string();
+        let value = &ev["value"];
+        let parse_i64 = |v: &Value| v.as_str().and_then(|s| s.parse::<i64>().ok());
+
+        let mut offenders: Vec<(String, Option<i64>, Option<i64>)> = vec![];
+        if let Some(vote) = value.get("vote_a").or_else(|| value.get("VoteA")) {
+            offenders.push((
+                vote["validator_address"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                parse_i64(&vote["height"]),
+                parse_i64(&value["validator_power"]),
+            ));
+        } else if let Some(vals) = value["byzantine_validators"].as_array() {
+            for v in vals {
+                offenders.push((
+                    v["address"].as_str().unwrap_or_default().to_string(),
+                    parse_i64(&value["common_height"]),
+                    parse_i64(&v["voting_power"]),
+                ));
+            }
+        }
+
+        if offenders.is_empty() {
+            debug!("Unknown evidence at height {}: {}", height, ev);
+            continue;
+        }
+
+        for (validator, evidence_height, power) in offenders {
+            events.push(V2SlashingEvent {
+                block_hash: block_hash.to_string(),
+                tx_hash: None,
+                tx_index: -1,
+                op_index: index as i32,
+                validator: validator.to_uppercase(),
+                kind: kind.clone(),
+                source: "evidence".to_string(),
+                evidence_height,
+                power,
+                penalty_rate: default_penalty_rate(&kind).map(|r| serde_json::json!(r)),
+                height,
+                timestamp,
+                content: ev.clone(),
+            });
+        }
+    }
+
+    events
+}
+
+/// Decode a Governance (punishment) operation.
+///
+/// The byzantine id is the staker key, so the event is recorded under its fra address.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_governance_op(
+    op: &Value,
+    tx_hash: &str,
+    tx_index: i64,
+    op_index: i32,
+    block_hash: &str,
+    height: i64,
+    timestamp: i64,
+) -> Result<Option<V2SlashingEvent>> {
+    if op.get("Governance").is_none() {
+        return Ok(None);
+    }
+    debug!("[Native] Governance, height: {}, tx: {}", height, tx_hash);
+    let opt: GovernanceOpt = serde_json::from_value(op.clone())?;
+    let body = opt.governance.body;
+    let penalty_rate = match body.custom_rate {
+        Some(rate) => Some(serde_json::to_value(rate)?),
+        None => default_penalty_rate(&body.kind).map(|r| serde_json::json!(r)),
+    };
+
+    Ok(Some(V2SlashingEvent {
+        block_hash: block_hash.to_string(),
+        tx_hash: Some(tx_hash.to_string()),
+        tx_index,
+        op_index,
+        validator: pubkey_to_fra_address(&body.byzantine_id)?,
+        kind: body.kind,
+        source: "governance".to_string(),
+        evidence_height: None,
+        power: None,
+        penalty_rate,
+        height,
+        timestamp,
+        content: op.clone(),
+    }))
+}
+
+/// Collect the addresses taking part in `txs`: the sender and receivers of every transaction
+/// plus the signer and counterparty of every operation. Staking counterparties get the
+/// `validator` role.
+pub(crate) fn collect_participants(txs: &[Transaction], ops: &[V2Operation]) -> Vec<TxParticipant> {
+    let mut tx_ops: HashMap<&str, Vec<&V2Operation>> = HashMap::new();
+    for op in ops {
+        tx_ops.entry(op.tx_hash.as_str()).or_default().push(op);
+    }
+
+    let mut participants: Vec<TxParticipant> = vec![];
+    for tx in txs {
+        let mut seen: HashSet<(String, &str)> = HashSet::new();
+        let mut push = |address: &str, role: &'static str| {
+            if !address.is_empty() && seen.insert((address.to_string(), role)) {
+                participants.push(TxParticipant {
+                    tx_hash: tx.tx_hash.clone(),
+                    address: address.to_string(),
+                    role: role.to_string(),
+                    height: tx.height,
+                    timestamp: tx.timestamp,
+                });
+            }
+        };
+
+        push(&tx.sender, "sender");
+        for addr in tx.receiver["addrs"].as_array().into_iter().flatten() {
+            push(addr.as_str().unwrap_or_default(), "receiver");
+        }
+        for op in tx_ops.get(tx.tx_hash.as_str()).into_iter().flatten() {
+            push(&op.signer, "sender");
+            let role = match op.kind.as_str() {
+                "Delegation" | "UnDelegation" | "UpdateStaker" => "validator",
+                _ => "receiver",
+            };
+            push(&op.counterparty, role);
+        }
+    }
+
+    participants
+}
+
+/// Summarise a native operation as its kind, signer, primary counterparty and, when they are
+/// public, the asset and amount moved. Unknown or undecodable operations only carry their kind.
+pub(crate) fn decode_operation(
+    op: &Value,
+    tx_hash: &str,
+    op_index: i32,
+    height: i64,
+    timestamp: i64,
+) -> V2Operation {
+    let kind = match op {
+        Value::Object(m) => m.keys().next().cloned().unwrap_or_default(),
+        Value::String(s) => s.clone(),
+        _ => "".to_string(),
+    };
+    let fra_address = |pk: &Value| {
+        pk.as_str()
+            .and_then(|pk| pubkey_to_fra_address(pk).ok())
+            .unwrap_or_default()
+    };
+    let body = &op[&kind];
+
+    let mut signer = fra_address(&body["pubkey"]);
+    let mut counterparty = "".to_string();
+    let mut asset: Option<String> = None;
+    let mut amount: Option<String> = None;
+
+    match kind.as_str() {
+        "ConvertAccount" => {
+            if let Ok(opt) = serde_json::from_value::<ConvertAccountOpt>(op.clone()) {
+                signer = pubkey_to_fra_address(&opt.convert_account.signer).unwrap_or_default();
+                counterparty = opt.convert_account.receiver.ethereum;
+                asset = Some(match &opt.convert_account.asset_type {
+                    Some(asset_bin) => engine::general_purpose::URL_SAFE.encode(asset_bin),
+                    None => FRA_ASSET.to_string(),
+                });
+                amount = Some(opt.convert_account.value);
+            }
+        }
+        "Delegation" => {
+            if let Ok(opt) = serde_json::from_value::<DelegationOpt>(op.clone()) {
+                counterparty = opt.delegation.body.validator;
+                asset = Some(FRA_ASSET.to_string());
+                amount = Some(opt.delegation.body.amount.to_string());
+            }
+        }
+        "UnDelegation" => {
+            if let Ok(opt) = serde_json::from_value::<UnDelegationOpt>(op.clone()) {
+                if let Some(pu) = opt.undelegation.body.pu {
+                    counterparty = hex::encode_upper(pu.target_validator);
+                    asset = Some(FRA_ASSET.to_string());
+                    amount = Some(pu.am.to_string());
                 }
-                Err(e) => {
-                    if i == self.retries {
-                        return Err(e);
-                    }
-                    info!(
-                        "Load height {} failed, error: `{:?}`\nRetry {} ...",
-                        height,
-                        e,
-                        i + 1
-                    );
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+        "Claim" => {
+            if let Ok(opt) = serde_json::from_value::<ClaimOpt>(op.clone()) {
+                asset = Some(FRA_ASSET.to_string());
+                amount = opt.claim.body.amount.map(|a| a.to_string());
+            }
+        }
+        "DefineAsset" => {
+            if let Ok(opt) = serde_json::from_value::<DefineAssetOpt>(op.clone()) {
+                signer = pubkey_to_fra_address(&opt.define_asset.pubkey.key).unwrap_or_default();
+                asset = Some(
+                    engine::general_purpose::URL_SAFE.encode(opt.define_asset.body.asset.code.val),
+                );
+            }
+        }
+        "IssueAsset" => {
+            if let Ok(opt) = serde_json::from_value::<IssueAssetOpt>(op.clone()) {
+                signer = pubkey_to_fra_address(&opt.issue_asset.pubkey.key).unwrap_or_default();
+                asset =
+                    Some(engine::general_purpose::URL_SAFE.encode(opt.issue_asset.body.code.val));
+            }
+        }
+        "TransferAsset" => {
+            signer = fra_address(&body["body_signatures"][0]["address"]["key"]);
+            let outputs = body["body"]["transfer"]["outputs"].as_array();
+            // the first output paid to someone else than the signer or the fee sink.
+            for o in outputs.into_iter().flatten() {
+                let receiver = fra_address(&o["public_key"]);
+                if o["public_key"] == FRA_ASSET || receiver.is_empty() || receiver == signer {
+                    continue;
                 }
-            };
+                counterparty = receiver;
+                asset = o["asset_type"]["NonConfidential"]
+                    .as_array()
+                    .map(|code| {
+                        code.iter()
+                            .map(|b| b.as_u64().unwrap_or_default() as u8)
+                            .collect::<Vec<u8>>()
+                    })
+                    .map(|code| engine::general_purpose::URL_SAFE.encode(code));
+                amount = o["amount"]["NonConfidential"]
+                    .as_str()
+                    .map(|a| a.to_string());
+                break;
+            }
         }
-        unreachable!()
+        "UpdateStaker" => {
+            counterparty = body["body"]["validator"]
+                .as_str()
+                .unwrap_or_default()
+                .to_uppercase();
+        }
+        "ReplaceStaker" => {
+            counterparty = fra_address(&body["body"]["new_public_key"]);
+        }
+        "Governance" => {
+            counterparty = fra_address(&body["body"]["byzantine_id"]);
+        }
+        _ => {}
     }
 
-    pub async fn load_and_save_block(&self, target: i64) -> Result<()> {
-        let block = self.load_height_retried(target).await?;
-        db::save(block, &self.pool).await?;
-        db::save_last_height(target, &self.pool).await?;
-        Ok(())
+    V2Operation {
+        tx_hash: tx_hash.to_string(),
+        op_index,
+        kind,
+        signer,
+        counterparty,
+        asset,
+        amount,
+        height,
+        timestamp,
+        content: op.clone(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mock_rpc::MockNode;
+    use module::source::{SourceError, TendermintRPC};
+    use storage::SqliteStorage;
+
+    async fn mock_node() -> (MockNode, Url) {
+        let dir = mock_rpc::fixtures_dir();
+        let node = MockNode::load(&dir).unwrap();
+        let url = node.spawn().await.unwrap().parse().unwrap();
+        (node, url)
+    }
+
+    #[test]
+    fn test_decode_validator_op() -> Result<()> {
+        // keys of a mainnet undelegation.
+        let staker = "OmZMrZBVsPjQwvHsROCI3mRw2pdVnYER8Xa5lzQ3Ek0=";
+        let new_staker = "Vp_Ph2OobZuAOQ7vS7uXyjYcToAVoDGpo-hX55yAKLQ=";
+        let staker_addr = "fra18fnyetvs2kc035xz78kyfcygmej8pk5h2kwczy03w6uewdphzfxsk74dym";
+        let stakers = Stakers::from([(
+            staker_addr.to_string(),
+            "9E6717392EFDCFA101E33449A7C2A238251315B1".to_string(),
+        )]);
+        let decode = |op: Value| decode_validator_op(&op, "tx", 1, "block", 7, 70, &stakers);
+
+        let update_staker = serde_json::json!({"UpdateStaker": {
+            "body": {"validator": "9e6717392efdcfa101e33449a7c2a238251315b1", "new_validator": {
+                "id": staker, "td_pubkey": [], "td_addr": [], "td_power": 0,
+                "commission_rate": [1, 100], "memo": {"name": "v", "desc": "", "website": "", "logo": ""},
+                "kind": "", "signed_last_block": false, "signed_cnt": 0, "delegators": {}}},
+            "pubkey": staker}});
+        let (signer, events) = decode(update_staker)?.unwrap();
+        assert_eq!(signer, staker_addr);
+        assert_eq!(
+            events[0].validator,
+            "9E6717392EFDCFA101E33449A7C2A238251315B1"
+        );
+        assert_eq!(events[0].commission_rate, Some(serde_json::json!([1, 100])));
+
+        let replace = |params: Value| {
+            serde_json::json!({"ReplaceStaker": {
+                "body": {"new_public_key": new_staker, "new_tendermint_params": params},
+                "pubkey": staker}})
+        };
+        let (_, events) = decode(replace(Value::Null))?.unwrap();
+        assert_eq!(
+            (events[0].kind.as_str(), events[0].op_index),
+            ("ReplaceStaker", 1)
+        );
+        // the validator of the staker.
+        assert_eq!(
+            events[0].validator,
+            "9E6717392EFDCFA101E33449A7C2A238251315B1"
+        );
+        assert_eq!(
+            events[0].new_staker.as_deref(),
+            Some("fra1260ulpmr4pkehqpepmh5hwuhegmpcn5qzksrr2drapt708yq9z6qlpv9k9")
+        );
+        let params = serde_json::json!({"address": vec![171; 20], "pubkey": []});
+        let (_, events) = decode(replace(params))?.unwrap();
+        assert_eq!(events[0].validator, "AB".repeat(20));
+
+        let unknown =
+            |op: Value| decode_validator_op(&op, "tx", 1, "block", 7, 70, &Stakers::new());
+        assert!(unknown(replace(Value::Null)).is_err());
+        assert!(decode(serde_json::json!({"TransferAsset": {}}))?.is_none());
+        let mut bad = replace(Value::Null);
+        bad["ReplaceStaker"]["pubkey"] = "not a key".into();
+        assert!(decode(bad).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_participants() {
+        let tx = |hash: &str| -> Transaction {
+            serde_json::from_value(serde_json::json!({
+                "tx_hash": hash, "block_hash": "B", "height": 1, "tx_index": 0, "timestamp": 10,
+                "code": 0, "ty": 0, "ty_sub": 0, "sender": "a",
+                "receiver": {"addrs": ["b", "a", "b", ""]}, "log": "", "origin": "",
+                "result": {}, "value": {}
+            }))
+            .unwrap()
+        };
+        let op = |hash: &str, kind: &str, counterparty: &str| -> V2Operation {
+            serde_json::from_value(serde_json::json!({
+                "tx_hash": hash, "op_index": 0, "kind": kind, "signer": "a",
+                "counterparty": counterparty, "asset": null, "amount": null, "height": 1,
+                "timestamp": 10, "content": {}
+            }))
+            .unwrap()
+        };
+        let participants = collect_participants(
+            &[tx("t1"), tx("t2")],
+            &[op("t1", "Delegation", "v"), op("t2", "TransferAsset", "c")],
+        );
+        let roles: Vec<_> = participants
+            .iter()
+            .map(|p| (p.tx_hash.as_str(), p.address.as_str(), p.role.as_str()))
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                ("t1", "a", "sender"),
+                ("t1", "b", "receiver"),
+                ("t1", "a", "receiver"),
+                ("t1", "v", "validator"),
+                ("t2", "a", "sender"),
+                ("t2", "b", "receiver"),
+                ("t2", "a", "receiver"),
+                ("t2", "c", "receiver"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_slashing() -> Result<()> {
+        let vote = serde_json::json!({"type": "tendermint/DuplicateVoteEvidence", "value": {
+            "vote_a": {"validator_address": "ab".repeat(20), "height": "9"},
+            "validator_power": "1000"}});
+        let events = decode_evidence(&[vote.clone(), vote], "block", 10, 100);
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.tx_index, e.op_index))
+                .collect::<Vec<_>>(),
+            [(-1, 0), (-1, 1)]
+        );
+        assert_eq!(events[0].validator, "AB".repeat(20));
+        assert_eq!(events[0].kind, "DuplicateVote");
+        assert_eq!(events[0].power, Some(1000));
+        assert_eq!(events[0].penalty_rate, Some(serde_json::json!([5, 100])));
+
+        let governance = |rate: Value| {
+            serde_json::json!({"Governance": {"body": {"kind": "DuplicateVote", "custom_rate": rate,
+                "byzantine_id": "OmZMrZBVsPjQwvHsROCI3mRw2pdVnYER8Xa5lzQ3Ek0="}}})
+        };
+        let ev =
+            decode_governance_op(&governance(Value::Null), "tx", 3, 1, "block", 10, 100)?.unwrap();
+        assert_eq!((ev.tx_index, ev.op_index), (3, 1));
+        assert_eq!(
+            ev.validator,
+            "fra18fnyetvs2kc035xz78kyfcygmej8pk5h2kwczy03w6uewdphzfxsk74dym"
+        );
+        assert_eq!(ev.power, None);
+        assert_eq!(ev.penalty_rate, Some(serde_json::json!([5, 100])));
+        let ev = decode_governance_op(
+            &governance(serde_json::json!([1, 2])),
+            "tx",
+            3,
+            1,
+            "block",
+            10,
+            100,
+        )?
+        .unwrap();
+        assert_eq!(ev.penalty_rate, Some(serde_json::json!([1, 2])));
+        Ok(())
+    }
 
     #[tokio::test]
     async fn test_rpc() -> Result<()> {
-        // let rpc = TendermintRPC::new(
-        //     Duration::from_secs(10),
-        //     "https://prod-mainnet.prod.findora.org:26657"
-        //         .to_string()
-        //         .parse()
-        //         .unwrap(),
-        // );
-        // let _ = rpc.load_block(1550667).await?;
-        // let _ = rpc.load_validators(1550667).await?;
-        // let _ = rpc
-        //     .load_transaction("c19fc22beb61030607367b42d4898a26ede1e6aa6b400330804c95b241f29bd0")
-        //     .await?;
+        let (_node, url) = mock_node().await;
+        let rpc = TendermintRPC::new(Duration::from_secs(10), url);
+        let block = rpc.load_block(2).await?;
+        let tx_hash = &parse::tx_hashes(&block)?[0];
+        let tx = &block.block.data.txs.unwrap()[0];
+        assert_eq!(&rpc.load_transaction(tx_hash).await?.tx, tx);
+        assert_eq!(rpc.latest_height().await?, 3);
+        assert!(matches!(
+            rpc.load_block(4).await,
+            Err(SourceError::NotFound)
+        ));
+        let _ = rpc.load_delegations().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_latest_height() -> Result<()> {
+        let (node, url) = mock_node().await;
+        let storage = Arc::new(SqliteStorage::memory().await?);
+        let caller = RPCCaller::new(0, 1, Duration::from_secs(10), url, storage);
+        // every request fails until healed.
+        node.configure(|k| k.error_every = Some(1));
+        let waiting = caller.wait_latest_height(Duration::from_millis(20));
+        let healed = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            node.configure(|k| k.error_every = None);
+        };
+        let (tip, _) = tokio::join!(waiting, healed);
+        assert_eq!(tip, 3);
+        assert_eq!(caller.status().report("mainnet").tip, 3);
         Ok(())
     }
 
     #[tokio::test]
     async fn test_load_validators() -> Result<()> {
-        // let rpc = TendermintRPC::new(
-        //     Duration::from_secs(10),
-        //     "https://prod-mainnet.prod.findora.org:26657"
-        //         .to_string()
-        //         .parse()
-        //         .unwrap(),
-        // );
-        // let _ = rpc.load_validators(2360073).await?;
+        let (_node, url) = mock_node().await;
+        let rpc = TendermintRPC::new(Duration::from_secs(10), url);
+        let r = rpc.load_validators(2).await?;
+        assert_eq!(r.validators.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_height() -> Result<()> {
+        let (node, url) = mock_node().await;
+        let storage = Arc::new(SqliteStorage::memory().await?);
+        let caller = RPCCaller::new(0, 1, Duration::from_secs(10), url, storage.clone());
+
+        let block = caller.load_height(2).await?;
+        assert_eq!(block.validators.len(), 2);
+        assert!(block.validators.iter().all(|v| v.signature.is_some()));
+        // the fixture txs are not findora txs.
+        assert_eq!(block.quarantined_txs.len(), 2);
+
+        caller.load_and_save_block(3).await?;
+        assert_eq!(storage.load_last_height().await?, 3);
+        let evm_tx = "263d57cd0d924b81ab820c1ba73aab47aaba3bb7152623a1da4da178a0221f99";
+        assert_eq!(storage.tx_by_hash(evm_tx).await?.height, 3);
+
+        node.configure(|k| {
+            k.missing.insert(3);
+        });
+        assert!(matches!(
+            caller.load_height_retried(3).await,
+            Err(Error::NotFound)
+        ));
+        node.configure(|k| k.error_every = Some(1));
+        assert!(caller.load_height_retried(1).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_height_backoff() -> Result<()> {
+        let (node, url) = mock_node().await;
+        let storage = Arc::new(SqliteStorage::memory().await?);
+        let backoff = Backoff {
+            initial: Duration::from_millis(40),
+            max: Duration::from_millis(60),
+        };
+        let caller =
+            RPCCaller::new(3, 1, Duration::from_secs(10), url, storage).with_backoff(backoff);
+
+        // waits 40 then 60ms, healed during the second wait.
+        node.configure(|k| k.error_every = Some(1));
+        let begin = std::time::Instant::now();
+        let healed = async {
+            tokio::time::sleep(Duration::from_millis(70)).await;
+            node.configure(|k| k.error_every = None);
+        };
+        let (block, _) = tokio::join!(caller.load_height_retried(1), healed);
+        assert_eq!(block?.height, 1);
+        assert!(begin.elapsed() >= Duration::from_millis(100));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_light_client() -> Result<()> {
+        let (node, url) = mock_node().await;
+        let storage = Arc::new(SqliteStorage::memory().await?);
+        let plain = RPCCaller::new(0, 1, Duration::from_secs(10), url.clone(), storage.clone());
+        let first = plain.load_height(1).await?;
+        let trust = Trust {
+            height: 1,
+            hash: first.block_hash.clone(),
+        };
+        let light = || {
+            RPCCaller::new(0, 1, Duration::from_secs(10), url.clone(), storage.clone())
+                .with_trust(Some(trust.clone()))
+        };
+        let caller = light();
+
+        // nothing to follow below the trusted block.
+        assert!(matches!(
+            caller.load_and_save_block(2).await,
+            Err(Error::IntegrityError(_))
+        ));
+        assert!(caller.check_start(2).await.is_err());
+        caller.check_start(1).await?;
+        for h in 1..=2 {
+            caller.load_and_save_block(h).await?;
+        }
+        caller.check_start(3).await?;
+        // the commit of the top block comes with the block above, it waits for it.
+        assert!(matches!(caller.load_height(3).await, Err(Error::Pending)));
+        assert!(matches!(caller.load_height(3).await, Err(Error::Pending)));
+
+        let pending = light();
+        node.configure(|k| {
+            k.missing.insert(3);
+        });
+        assert!(matches!(pending.load_height(2).await, Err(Error::Pending)));
+        node.configure(|k| k.missing.clear());
+        assert_eq!(pending.load_height(2).await?.height, 2);
+
+        // a block failing its link is not followed, the saved one below is checked in its place.
+        let second = plain.load_height(2).await?;
+        let third = plain.load_height(3).await?;
+        let mut forged = plain.load_height(2).await?;
+        forged.block_data["block"]["header"]["last_block_id"]["hash"] =
+            serde_json::json!("00".repeat(32));
+        assert!(matches!(
+            caller.check_link(&forged).await,
+            Err(Error::IntegrityError(_))
+        ));
+        caller.check_link(&third).await?;
+
+        // a saved block is not taken as verified.
+        let mut tampered = second;
+        tampered.block_data["block"]["header"]["app_hash"] = serde_json::json!("00".repeat(32));
+        storage.save_block(tampered).await?;
+        assert!(matches!(
+            light().check_link(&third).await,
+            Err(Error::IntegrityError(_))
+        ));
+
+        node.configure(|k| {
+            k.forks.insert(2, "00".repeat(32));
+        });
+        assert!(matches!(
+            caller.load_height(2).await,
+            Err(Error::IntegrityError(_))
+        ));
+        let wrong = Trust {
+            height: 2,
+            hash: "00".repeat(32),
+        };
+        node.configure(|k| k.forks.clear());
+        let caller = caller.with_trust(Some(wrong));
+        assert!(matches!(
+            caller.load_height(2).await,
+            Err(Error::IntegrityError(_))
+        ));
         Ok(())
     }
 }
@@ -1,14 +1,126 @@
-use crate::{db, rpc::RPCCaller, Error};
+use crate::{rpc::RPCCaller, Error, Result};
 
-use crossbeam::channel::bounded;
+use futures::stream::{FuturesUnordered, StreamExt};
+use module::schema::Block as ModuleBlock;
 use reqwest::Url;
 
-use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::atomic::{AtomicI64, Ordering};
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use storage::Storage;
+use tokio::sync::mpsc;
+
+/// Upper bound of in-flight fetches unless set by `with_max_concurrency`.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 64;
+/// A fetch slower than this multiple of the baseline latency shrinks the window.
+const LATENCY_TOLERANCE: u32 = 2;
+/// Blocks written per bulk statement unless set by `with_bulk_blocks`.
+pub const DEFAULT_BULK_BLOCKS: usize = 32;
+/// How often the throughput is logged.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Result of a range scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanReport {
+    /// Number of heights loaded and saved.
+    pub succeed: i64,
+    /// Highest height `h` such that every height in `[start, h]` is committed,
+    /// `start - 1` if the first height failed.
+    pub watermark: i64,
+    /// Heights committed above the watermark, i.e. after a lower height failed.
+    pub out_of_order: Vec<i64>,
+}
+
+/// Tracks committed heights and advances only over a contiguous prefix.
+#[derive(Debug)]
+pub(crate) struct Watermark {
+    height: i64,
+    pending: BTreeSet<i64>,
+}
+
+impl Watermark {
+    /// `start` is the first height expected to be committed.
+    pub(crate) fn new(start: i64) -> Self {
+        Watermark {
+            height: start - 1,
+            pending: BTreeSet::new(),
+        }
+    }
+
+    /// Mark `h` as committed, returns the new watermark if it advanced.
+    pub(crate) fn commit(&mut self, h: i64) -> Option<i64> {
+        if h <= self.height {
+            return None;
+        }
+        self.pending.insert(h);
+        let old = self.height;
+        while self.pending.remove(&(self.height + 1)) {
+            self.height += 1;
+        }
+        (self.height > old).then_some(self.height)
+    }
+
+    pub(crate) fn height(&self) -> i64 {
+        self.height
+    }
+
+    /// Committed heights that are still waiting for a lower one.
+    pub(crate) fn out_of_order(&self) -> Vec<i64> {
+        self.pending.iter().copied().collect()
+    }
+}
+
+/// AIMD limit of in-flight RPC fetches.
+///
+/// Grows by about one per round trip while latency stays near the baseline,
+/// shrinks by 10% on slow responses and halves on errors.
+#[derive(Debug)]
+pub(crate) struct AdaptiveConcurrency {
+    limit: f64,
+    max: usize,
+    baseline: Option<Duration>,
+}
+
+impl AdaptiveConcurrency {
+    pub(crate) fn new(initial: usize, max: usize) -> Self {
+        let max = max.max(1);
+        AdaptiveConcurrency {
+            limit: initial.clamp(1, max) as f64,
+            max,
+            baseline: None,
+        }
+    }
+
+    pub(crate) fn current(&self) -> usize {
+        self.limit as usize
+    }
+
+    pub(crate) fn on_success(&mut self, latency: Duration) {
+        let baseline = match self.baseline {
+            Some(b) if latency >= b => b + (latency - b) / 64, // follow a node that got slower for good.
+            _ => latency,
+        };
+        self.baseline = Some(baseline);
+
+        if latency > baseline * LATENCY_TOLERANCE {
+            self.limit = (self.limit * 0.9).max(1.0);
+        } else {
+            self.limit = (self.limit + 1.0 / self.limit).min(self.max as f64);
+        }
+    }
+
+    pub(crate) fn on_error(&mut self) {
+        self.limit = (self.limit / 2.0).max(1.0);
+    }
+}
 
 pub struct RangeScanner {
     caller: Arc<RPCCaller>,
+    max_concurrency: usize,
+    bulk_blocks: usize,
 }
 
 impl RangeScanner {
@@ -17,73 +129,149 @@ impl RangeScanner {
         tendermint_rpc: Url,
         retries: usize,
         concurrency: usize,
-        pool: PgPool,
+        storage: Arc<dyn Storage>,
     ) -> Self {
+        Self::from_caller(RPCCaller::new(
+            retries,
+            concurrency,
+            timeout,
+            tendermint_rpc,
+            storage,
+        ))
+    }
+
+    /// Scan with `caller`, its backoff, verify and trust set beforehand.
+    pub fn from_caller(caller: RPCCaller) -> Self {
         RangeScanner {
-            caller: Arc::new(RPCCaller::new(
-                retries,
-                concurrency,
-                timeout,
-                tendermint_rpc,
-                pool,
-            )),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY.max(caller.concurrency),
+            bulk_blocks: DEFAULT_BULK_BLOCKS,
+            caller: Arc::new(caller),
         }
     }
 
+    /// Set how many fetched blocks the writer may save in one bulk write, 1 saves them one by one.
+    pub fn with_bulk_blocks(mut self, bulk_blocks: usize) -> Self {
+        self.bulk_blocks = bulk_blocks.max(1);
+        self
+    }
+
+    /// Set the upper bound the fetch window may grow to.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(self.caller.concurrency);
+        self
+    }
+
     ///scan block in [start..end].
-    pub async fn range_scan(&self, start: i64, end: i64) -> Result<i64, Error> {
+    ///
+    /// Blocks are fetched ahead within an adaptive window and handed to a single writer
+    /// in height order. `last_height` only follows the contiguous prefix of committed
+    /// heights, so resuming from `last_height + 1` never skips a block that failed.
+    pub async fn range_scan(&self, start: i64, end: i64) -> Result<ScanReport> {
         info!("Scanning [{},{}) ...", start, end);
-        let concurrency = self.caller.concurrency; //how many spawned.
-
-        let (sender, rev) = bounded(concurrency);
-
-        //Store the max height.
-        let last_height = Arc::new(AtomicI64::new(0));
-        //counter of successful tasks.
-        let succeed_cnt = Arc::new(AtomicI64::new(0));
-
-        let inner_p = self.caller.clone();
-        let last_height_p = last_height.clone();
-
-        //start producer.
-        let succeed_cnt_cloned = succeed_cnt.clone();
-        let handle_producer = tokio::task::spawn_blocking(move || {
-            for h in start..end {
-                let fut = task(
-                    inner_p.clone(),
-                    h,
-                    last_height_p.clone(),
-                    succeed_cnt_cloned.clone(),
-                );
-                //build a future that have not been executed.
-                sender.send(Some(fut)).unwrap();
+        let begin = Instant::now();
+        let window = 2 * self.max_concurrency as i64;
+
+        let (sender, receiver) = mpsc::channel(self.max_concurrency);
+        let saved = Arc::new(AtomicI64::new(0));
+        let writer = tokio::spawn(write_blocks(
+            receiver,
+            start,
+            self.bulk_blocks,
+            saved.clone(),
+            self.caller.clone(),
+        ));
+
+        let mut limit = AdaptiveConcurrency::new(self.caller.concurrency, self.max_concurrency);
+        let mut in_flight = FuturesUnordered::new();
+        //fetched blocks waiting for a lower height.
+        let mut reorder: BTreeMap<i64, Result<ModuleBlock>> = BTreeMap::new();
+        let mut next = start; //next height to fetch.
+        let mut send_cursor = start; //next height to hand to the writer.
+        let mut fetched = 0;
+        let mut last_report = Instant::now();
+
+        let status = &self.caller.status;
+        loop {
+            // a long sync would hold back the refetches until its end.
+            if status.has_refetch() && !status.is_paused() {
+                self.caller.refetch_scheduled().await;
+            }
+            if status.is_paused() && in_flight.is_empty() && next < end {
+                info!("Paused at height {}.", next);
+                status.wait_running().await;
+                info!("Resumed at height {}.", next);
+            }
+            while next < end
+                && in_flight.len() < limit.current()
+                && next - send_cursor < window
+                && !status.is_paused()
+            {
+                let caller = self.caller.clone();
+                let h = next;
+                in_flight.push(tokio::spawn(async move {
+                    let t = Instant::now();
+                    let r = caller.load_height_retried(h).await;
+                    (h, r, t.elapsed())
+                }));
+                next += 1;
+            }
+
+            let Some(joined) = in_flight.next().await else {
+                break;
+            };
+            let (h, res, latency) = joined?;
+            match &res {
+                Ok(_) => limit.on_success(latency),
+                Err(Error::NotFound | Error::Pending) => {}
+                Err(_) => limit.on_error(),
             }
+            fetched += 1;
+            reorder.insert(h, res);
 
-            //make them exit.
-            for _ in 0..concurrency {
-                sender.send(None).unwrap();
+            while let Some(res) = reorder.remove(&send_cursor) {
+                if sender.send((send_cursor, res)).await.is_err() {
+                    return Err("block writer exited".into());
+                }
+                send_cursor += 1;
+            }
+
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                let secs = begin.elapsed().as_secs_f64();
+                info!(
+                    "Fetched {} blocks ({:.1}/s), saved {} ({:.1}/s), concurrency {}, at height {}.",
+                    fetched,
+                    fetched as f64 / secs,
+                    saved.load(Ordering::Acquire),
+                    saved.load(Ordering::Acquire) as f64 / secs,
+                    limit.current(),
+                    send_cursor
+                );
+                last_report = Instant::now();
             }
-        });
-
-        //spawn consumers.
-        let spawned_handles: Vec<_> = (0..concurrency)
-            .map(move |_| {
-                let rev_cloned = rev.clone();
-                tokio::spawn(async move {
-                    while let Ok(Some(fut)) = rev_cloned.recv() {
-                        //handle error within.
-                        fut.await;
-                    }
-                })
-            })
-            .collect();
-
-        for h in spawned_handles {
-            h.await?;
         }
-        handle_producer.await?;
-        info!("Scanning [{},{}) complete.", start, end);
-        Ok(succeed_cnt.load(Ordering::Acquire))
+        drop(sender);
+
+        let watermark = writer.await?;
+        let report = ScanReport {
+            succeed: saved.load(Ordering::Acquire),
+            watermark: watermark.height(),
+            out_of_order: watermark.out_of_order(),
+        };
+        if !report.out_of_order.is_empty() {
+            warn!(
+                "Heights {:?} committed out of order, last height stays at {}.",
+                report.out_of_order, report.watermark
+            );
+        }
+        info!(
+            "Scanning [{},{}) complete, {} blocks saved in {:?} ({:.1}/s).",
+            start,
+            end,
+            report.succeed,
+            begin.elapsed(),
+            report.succeed as f64 / begin.elapsed().as_secs_f64()
+        );
+        Ok(report)
     }
 
     pub fn caller(&self) -> &Arc<RPCCaller> {
@@ -91,34 +279,150 @@ impl RangeScanner {
     }
 }
 
-async fn task(
+/// Save blocks in the order received and persist the watermark as it advances.
+///
+/// Blocks already waiting in the channel are saved together with `Storage::save_blocks`,
+/// a batch that fails is retried block by block so one bad block does not hold back the others.
+/// Each block must follow the one below it, see `RPCCaller::check_link`.
+async fn write_blocks(
+    mut receiver: mpsc::Receiver<(i64, Result<ModuleBlock>)>,
+    start: i64,
+    bulk_blocks: usize,
+    saved: Arc<AtomicI64>,
     caller: Arc<RPCCaller>,
-    h: i64,
-    last_height: Arc<AtomicI64>,
-    succeed_cnt: Arc<AtomicI64>,
-) {
-    match caller.load_height_retried(h).await {
-        Ok(block) => match db::save(block, &caller.pool).await {
-            Ok(_) => {
-                let h_old = last_height.load(Ordering::Acquire);
-                if h > h_old {
-                    last_height.store(h, Ordering::Release);
-                    //write the last height to database.
-                    if let Err(e) = db::save_last_height(h, &caller.pool).await {
-                        error!("Database error: {:?}", e);
-                    }
+) -> Watermark {
+    let storage = &caller.storage;
+    let status = &caller.status;
+    let mut watermark = Watermark::new(start);
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        while batch.len() < bulk_blocks {
+            match receiver.try_recv() {
+                Ok(next) => batch.push(next),
+                Err(_) => break,
+            }
+        }
+
+        let mut blocks = vec![];
+        for (h, res) in batch {
+            let res = match res {
+                Ok(block) => caller.check_link(&block).await.map(|_| block),
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok(block) => blocks.push((h, block)),
+                Err(Error::NotFound) => info!("Block not found at height {}.", h),
+                Err(Error::Pending) => info!("Block at height {} waits for the one above.", h),
+                Err(e) => {
+                    error!("Load height error: {:?}", e);
+                    status.record_error(format!("load height {h}: {e:?}"));
+                }
+            }
+        }
+
+        let mut committed = vec![];
+        let blocks = if blocks.len() > 1 {
+            let (heights, bulk): (Vec<_>, Vec<_>) = blocks.into_iter().unzip();
+            match storage.save_blocks(&bulk).await {
+                Ok(_) => {
+                    committed = heights;
+                    vec![]
+                }
+                Err(e) => {
+                    warn!("Bulk save error: {:?}, falling back to single saves.", e);
+                    heights.into_iter().zip(bulk).collect()
+                }
+            }
+        } else {
+            blocks
+        };
+        for (h, block) in blocks {
+            match storage.save_block(block).await {
+                Ok(_) => committed.push(h),
+                Err(e) => {
+                    caller.forget_links(h);
+                    error!("Database error: {:?}", e);
+                    status.record_error(format!("save height {h}: {e:?}"));
                 }
-                succeed_cnt.fetch_add(1, Ordering::Release);
-                debug!("Height at {} succeed.", h);
             }
-            Err(e) => error!("Database error: {:?}", e),
-        },
-        Err(e) => {
-            if let Error::NotFound = e {
-                info!("Block not found at height {}.", h);
-            } else {
-                error!("Load height error: {:?}", e);
+        }
+
+        let mut advanced = None;
+        for h in committed {
+            advanced = watermark.commit(h).or(advanced);
+            saved.fetch_add(1, Ordering::Release);
+            debug!("Height at {} succeed.", h);
+        }
+        if let Some(last) = advanced {
+            //write the last height to database.
+            match storage.save_last_height(last).await {
+                Ok(_) => status.set_cursor(last),
+                Err(e) => {
+                    error!("Database error: {:?}", e);
+                    status.record_error(format!("save last height {last}: {e:?}"));
+                }
             }
         }
-    };
+    }
+    watermark
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AdaptiveConcurrency, RangeScanner, Watermark};
+    use crate::Result;
+    use mock_rpc::MockNode;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use storage::{SqliteStorage, Storage};
+
+    #[test]
+    fn test_watermark_contiguous() {
+        let mut w = Watermark::new(10);
+        assert_eq!(w.commit(12), None);
+        assert_eq!(w.commit(11), None);
+        assert_eq!(w.out_of_order(), vec![11, 12]);
+        assert_eq!(w.commit(10), Some(12));
+        assert_eq!(w.commit(14), None);
+        assert_eq!(w.height(), 12);
+        assert_eq!(w.out_of_order(), vec![14]);
+        assert_eq!(w.commit(13), Some(14));
+        assert!(w.out_of_order().is_empty());
+    }
+
+    #[test]
+    fn test_adaptive_concurrency() {
+        let mut c = AdaptiveConcurrency::new(4, 8);
+        for _ in 0..64 {
+            c.on_success(Duration::from_millis(100));
+        }
+        assert_eq!(c.current(), 8);
+        c.on_error();
+        assert_eq!(c.current(), 4);
+        c.on_success(Duration::from_millis(500));
+        assert_eq!(c.current(), 3);
+        for _ in 0..8 {
+            c.on_error();
+        }
+        assert_eq!(c.current(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refetch_during_scan() -> Result<()> {
+        let dir = mock_rpc::fixtures_dir();
+        let node = MockNode::load(&dir).unwrap();
+        let url = node.spawn().await.unwrap().parse().unwrap();
+        let storage = Arc::new(SqliteStorage::memory().await?);
+        let scanner = RangeScanner::new(Duration::from_secs(10), url, 0, 1, storage.clone());
+
+        // height 3 is out of the scanned range.
+        scanner.caller().status().schedule_refetch(&[3]).unwrap();
+        let report = scanner.range_scan(1, 3).await?;
+        assert_eq!(report.watermark, 2);
+        assert!(!scanner.caller().status().has_refetch());
+        let evm_tx = "263d57cd0d924b81ab820c1ba73aab47aaba3bb7152623a1da4da178a0221f99";
+        assert_eq!(storage.tx_by_hash(evm_tx).await?.height, 3);
+        assert_eq!(storage.load_last_height().await?, 2);
+        Ok(())
+    }
 }
@@ -0,0 +1,428 @@
+//! State of a running scanner and the HTTP api serving it:
+//!
+//! - `GET /health`, the process is alive.
+//! - `GET /ready`, the cursor lags the tip by at most `status.max_lag` blocks.
+//! - `GET /status`, cursor, tip, lag, last error and the state of the rpc endpoint.
+//! - `POST /control/pause`, `POST /control/resume`, stop and restart the ingestion.
+//! - `POST /control/refetch` with `{"heights": [..]}`, load and save these heights again.
+//!
+//! The control actions require `Authorization: Bearer <status.token>`.
+use crate::rpc::QUARANTINED_TXS;
+use crate::Result;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Heights scheduled by one refetch request at most.
+const MAX_REFETCH: usize = 1000;
+/// Heights waiting to be refetched at most, further requests are refused until they are served.
+const MAX_REFETCH_PENDING: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorRecord {
+    /// Unix timestamp.
+    pub at: i64,
+    pub message: String,
+}
+
+impl ErrorRecord {
+    fn now(message: String) -> Self {
+        ErrorRecord {
+            at: chrono::Utc::now().timestamp(),
+            message,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EndpointStatus {
+    pub url: String,
+    /// The last call got an answer.
+    pub healthy: bool,
+    /// Unix timestamp of the last answer.
+    pub last_ok: Option<i64>,
+    pub last_error: Option<ErrorRecord>,
+    /// Calls failed since the last answer.
+    pub failures: u64,
+}
+
+/// Shared by the ingestion and the status api.
+pub struct Status {
+    started_at: i64,
+    /// Highest height saved along with all the heights below, 0 if none.
+    cursor: AtomicI64,
+    /// Latest height of the node, 0 until known.
+    tip: AtomicI64,
+    last_error: Mutex<Option<ErrorRecord>>,
+    endpoint: Mutex<EndpointStatus>,
+    paused: watch::Sender<bool>,
+    refetch: Mutex<BTreeSet<i64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatusReport {
+    pub network: String,
+    pub started_at: i64,
+    pub cursor: i64,
+    pub tip: i64,
+    pub lag: i64,
+    pub paused: bool,
+    pub refetch_pending: Vec<i64>,
+    pub quarantined_txs: u64,
+    pub last_error: Option<ErrorRecord>,
+    pub rpc: Vec<EndpointStatus>,
+}
+
+impl Status {
+    pub fn new(rpc: &str) -> Self {
+        Status {
+            started_at: chrono::Utc::now().timestamp(),
+            cursor: AtomicI64::new(0),
+            tip: AtomicI64::new(0),
+            last_error: Mutex::new(None),
+            endpoint: Mutex::new(EndpointStatus {
+                url: rpc.to_string(),
+                healthy: true,
+                last_ok: None,
+                last_error: None,
+                failures: 0,
+            }),
+            paused: watch::Sender::new(false),
+            refetch: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub fn set_cursor(&self, height: i64) {
+        self.cursor.store(height, Ordering::Release);
+    }
+
+    pub fn set_tip(&self, height: i64) {
+        self.tip.fetch_max(height, Ordering::AcqRel);
+    }
+
+    /// Blocks the cursor is behind the tip, `None` until the tip is known.
+    pub fn lag(&self) -> Option<i64> {
+        let tip = self.tip.load(Ordering::Acquire);
+        (tip > 0).then(|| (tip - self.cursor.load(Ordering::Acquire)).max(0))
+    }
+
+    pub fn record_error(&self, message: String) {
+        *self.last_error.lock().unwrap() = Some(ErrorRecord::now(message));
+    }
+
+    /// The rpc endpoint answered, a block not found included.
+    pub fn rpc_ok(&self) {
+        let mut endpoint = self.endpoint.lock().unwrap();
+        endpoint.healthy = true;
+        endpoint.last_ok = Some(chrono::Utc::now().timestamp());
+        endpoint.failures = 0;
+    }
+
+    pub fn rpc_failed(&self, message: String) {
+        let mut endpoint = self.endpoint.lock().unwrap();
+        endpoint.healthy = false;
+        endpoint.last_error = Some(ErrorRecord::now(message));
+        endpoint.failures += 1;
+    }
+
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Return once the ingestion is not paused.
+    pub async fn wait_running(&self) {
+        let mut rx = self.paused.subscribe();
+        // the sender lives as long as `self`.
+        let _ = rx.wait_for(|paused| !paused).await;
+    }
+
+    /// Schedule `heights` to be loaded and saved again, returns the heights pending,
+    /// `None` if more than `MAX_REFETCH_PENDING` heights would be pending.
+    pub fn schedule_refetch(&self, heights: &[i64]) -> Option<Vec<i64>> {
+        let mut refetch = self.refetch.lock().unwrap();
+        let added = heights.iter().filter(|h| !refetch.contains(h)).count();
+        if refetch.len() + added > MAX_REFETCH_PENDING {
+            return None;
+        }
+        refetch.extend(heights);
+        Some(refetch.iter().copied().collect())
+    }
+
+    pub fn has_refetch(&self) -> bool {
+        !self.refetch.lock().unwrap().is_empty()
+    }
+
+    /// Heights to refetch, in order, emptying the schedule.
+    pub fn take_refetch(&self) -> Vec<i64> {
+        std::mem::take(&mut *self.refetch.lock().unwrap())
+            .into_iter()
+            .collect()
+    }
+
+    pub fn report(&self, network: &str) -> StatusReport {
+        let cursor = self.cursor.load(Ordering::Acquire);
+        let tip = self.tip.load(Ordering::Acquire);
+        StatusReport {
+            network: network.to_string(),
+            started_at: self.started_at,
+            cursor,
+            tip,
+            lag: self.lag().unwrap_or(0),
+            paused: self.is_paused(),
+            refetch_pending: self.refetch.lock().unwrap().iter().copied().collect(),
+            quarantined_txs: QUARANTINED_TXS.load(Ordering::Relaxed),
+            last_error: self.last_error.lock().unwrap().clone(),
+            rpc: vec![self.endpoint.lock().unwrap().clone()],
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerState {
+    pub status: Arc<Status>,
+    pub network: String,
+    /// Control actions are refused if unset.
+    pub token: Option<String>,
+    pub max_lag: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RefetchParams {
+    pub heights: Vec<i64>,
+}
+
+type Response = (StatusCode, Json<Value>);
+
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/status", get(status))
+        .route("/control/pause", post(pause))
+        .route("/control/resume", post(resume))
+        .route("/control/refetch", post(refetch))
+        .with_state(Arc::new(state))
+}
+
+/// Bind `addr` and serve the status api in the background.
+pub async fn serve(addr: &str, state: ServerState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving the status at {}.", addr);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router(state)).await {
+            error!("Status server error: {:?}", e);
+        }
+    });
+    Ok(())
+}
+
+async fn health() -> Response {
+    (StatusCode::OK, Json(json!({"status": "ok"})))
+}
+
+async fn ready(State(state): State<Arc<ServerState>>) -> Response {
+    let lag = state.status.lag();
+    let code = match lag {
+        Some(lag) if lag <= state.max_lag => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        code,
+        Json(json!({"ready": code == StatusCode::OK, "lag": lag, "max_lag": state.max_lag})),
+    )
+}
+
+async fn status(State(state): State<Arc<ServerState>>) -> Json<StatusReport> {
+    Json(state.status.report(&state.network))
+}
+
+/// `Err` is the response to a request without the token.
+fn authorize(state: &ServerState, headers: &HeaderMap) -> std::result::Result<(), Response> {
+    let Some(token) = &state.token else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "control actions are disabled, set `status.token`"})),
+        ));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match given {
+        Some(given) if constant_time_eq(given.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "invalid token"})),
+        )),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn pause(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Err(r) = authorize(&state, &headers) {
+        return r;
+    }
+    state.status.pause();
+    info!("Ingestion paused.");
+    (StatusCode::OK, Json(json!({"paused": true})))
+}
+
+async fn resume(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Err(r) = authorize(&state, &headers) {
+        return r;
+    }
+    state.status.resume();
+    info!("Ingestion resumed.");
+    (StatusCode::OK, Json(json!({"paused": false})))
+}
+
+async fn refetch(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(params): Json<RefetchParams>,
+) -> Response {
+    if let Err(r) = authorize(&state, &headers) {
+        return r;
+    }
+    if params.heights.is_empty() || params.heights.len() > MAX_REFETCH {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("between 1 and {MAX_REFETCH} heights are expected")})),
+        );
+    }
+    if let Some(h) = params.heights.iter().find(|h| **h < 1) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("invalid height: {h}")})),
+        );
+    }
+    let Some(pending) = state.status.schedule_refetch(&params.heights) else {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({"error": format!("{MAX_REFETCH_PENDING} heights are already pending")})),
+        );
+    };
+    info!("Refetch of {:?} scheduled.", params.heights);
+    (StatusCode::ACCEPTED, Json(json!({"pending": pending})))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn state(token: Option<&str>) -> Arc<ServerState> {
+        Arc::new(ServerState {
+            status: Arc::new(Status::new("http://127.0.0.1:26657/")),
+            network: "mainnet".to_string(),
+            token: token.map(str::to_string),
+            max_lag: 2,
+        })
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_ready() {
+        let state = state(None);
+        let (code, _) = ready(State(state.clone())).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.status.set_cursor(7);
+        state.status.set_tip(10);
+        let (code, _) = ready(State(state.clone())).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.status.set_cursor(8);
+        let (code, Json(body)) = ready(State(state.clone())).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["lag"], 2);
+
+        state.status.rpc_failed("timeout".to_string());
+        let Json(report) = status(State(state)).await;
+        assert_eq!((report.cursor, report.tip, report.lag), (8, 10, 2));
+        assert!(!report.rpc[0].healthy);
+        assert_eq!(report.rpc[0].failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_control() {
+        let disabled = state(None);
+        let (code, _) = pause(State(disabled.clone()), bearer("")).await;
+        assert_eq!(code, StatusCode::FORBIDDEN);
+        assert!(!disabled.status.is_paused());
+
+        let state = state(Some("secret"));
+        let (code, _) = pause(State(state.clone()), bearer("wrong")).await;
+        assert_eq!(code, StatusCode::UNAUTHORIZED);
+        let (code, _) = pause(State(state.clone()), HeaderMap::new()).await;
+        assert_eq!(code, StatusCode::UNAUTHORIZED);
+        let (code, _) = pause(State(state.clone()), bearer("secret")).await;
+        assert_eq!(code, StatusCode::OK);
+        assert!(state.status.is_paused());
+
+        let status = state.status.clone();
+        let waiting = tokio::spawn(async move { status.wait_running().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        let (code, _) = resume(State(state.clone()), bearer("secret")).await;
+        assert_eq!(code, StatusCode::OK);
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let params = |heights: Vec<i64>| Json(RefetchParams { heights });
+        let (code, _) = refetch(State(state.clone()), bearer("secret"), params(vec![0])).await;
+        assert_eq!(code, StatusCode::BAD_REQUEST);
+        let (code, _) = refetch(State(state.clone()), bearer("secret"), params(vec![5, 3])).await;
+        assert_eq!(code, StatusCode::ACCEPTED);
+        let (_, Json(body)) =
+            refetch(State(state.clone()), bearer("secret"), params(vec![3])).await;
+        assert_eq!(body["pending"], json!([3, 5]));
+        assert_eq!(state.status.take_refetch(), vec![3, 5]);
+        assert!(state.status.take_refetch().is_empty());
+
+        let heights: Vec<i64> = (1..=MAX_REFETCH_PENDING as i64).collect();
+        for chunk in heights.chunks(MAX_REFETCH) {
+            let (code, _) = refetch(
+                State(state.clone()),
+                bearer("secret"),
+                params(chunk.to_vec()),
+            )
+            .await;
+            assert_eq!(code, StatusCode::ACCEPTED);
+        }
+        // already pending heights are still accepted.
+        let (code, _) = refetch(State(state.clone()), bearer("secret"), params(vec![1])).await;
+        assert_eq!(code, StatusCode::ACCEPTED);
+        let (code, _) = refetch(State(state.clone()), bearer("secret"), params(vec![20_000])).await;
+        assert_eq!(code, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(state.status.take_refetch().len(), MAX_REFETCH_PENDING);
+    }
+}
@@ -1,9 +1,11 @@
 use ethereum::{LegacyTransaction, TransactionAction, TransactionSignature};
 use ethereum_types::{H256, U256};
+use module::schema::NewValidator;
 use rlp::{Encodable, RlpStream};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
 
 #[allow(dead_code)]
 pub enum FindoraTxType {
@@ -396,3 +398,128 @@ pub struct IssueAssetBody {
     pub records: Value,
     pub seq_num: i64,
 }
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// update validator
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateValidatorOpt {
+    #[serde(rename = "UpdateValidator")]
+    pub update_validator: UpdateValidator,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateValidator {
+    pub body: UpdateValidatorBody,
+    pub pubkey: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateValidatorBody {
+    pub height: i64,
+    pub vs: Vec<NewValidator>,
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// update staker
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateStakerOpt {
+    #[serde(rename = "UpdateStaker")]
+    pub update_staker: UpdateStaker,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateStaker {
+    pub body: UpdateStakerBody,
+    pub pubkey: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateStakerBody {
+    pub validator: String,
+    pub new_validator: NewValidator,
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// replace staker
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplaceStakerOpt {
+    #[serde(rename = "ReplaceStaker")]
+    pub replace_staker: ReplaceStaker,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplaceStaker {
+    pub body: ReplaceStakerBody,
+    pub pubkey: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReplaceStakerBody {
+    pub new_public_key: String,
+    pub new_tendermint_params: Option<TendermintParams>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TendermintParams {
+    pub address: Vec<u8>,
+    pub pubkey: Vec<u8>,
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// mint fra
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintFraOpt {
+    #[serde(rename = "MintFra")]
+    pub mint_fra: MintFra,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintFra {
+    pub entries: Vec<MintEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintEntry {
+    pub kind: String,
+    pub target_pk: String,
+    pub amount: i64,
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// fra distribution
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FraDistributionOpt {
+    #[serde(rename = "FraDistribution")]
+    pub fra_distribution: FraDistribution,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FraDistribution {
+    pub body: FraDistributionBody,
+    pub pubkey: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FraDistributionBody {
+    pub alloc_table: BTreeMap<String, i64>,
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// governance
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GovernanceOpt {
+    #[serde(rename = "Governance")]
+    pub governance: Governance,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Governance {
+    pub body: GovernanceBody,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GovernanceBody {
+    pub kind: String,
+    pub custom_rate: Option<Vec<i64>>,
+    pub byzantine_id: String,
+}
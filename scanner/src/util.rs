@@ -48,8 +48,9 @@ pub fn public_key_from_bech32(addr: &str) -> Result<XfrPublicKey> {
         .and_then(|bytes| XfrPublicKey::zei_from_bytes(&bytes).c(d!()))
 }
 
-pub fn pubkey_to_fra_address(pubkey: &str) -> Result<String> {
-    let pk = public_key_from_base64(pubkey).unwrap();
+pub fn pubkey_to_fra_address(pubkey: &str) -> crate::Result<String> {
+    let pk = public_key_from_base64(pubkey)
+        .map_err(|e| crate::Error::CustomError(format!("invalid pubkey {pubkey}: {e:?}")))?;
     let address = public_key_to_bech32(&pk);
 
     Ok(address)
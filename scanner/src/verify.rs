@@ -0,0 +1,351 @@
+//! Integrity checks of the data an rpc returns, and of what was saved from it: the txs of a
+//! block against the `data_hash` of its header, the tx results against the txs.
+use base64::{engine, Engine};
+use module::rpc::{
+    block::BlockRPC as ModuleBlockRPC, block::BlockSizeRPC, tx::Transaction as ModuleTx,
+};
+use module::schema::Transaction;
+use module::storage::BlockRecord;
+use module::utils::merkle;
+use sha2::Digest;
+use std::fmt;
+
+/// A check a block failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub height: i64,
+    /// `tx_bytes`, `data_hash`, `block_meta`, `num_txs`, `tx_results`, `tx_hash`, `tx_index`,
+    /// `tx_height`, `block_data`, `block_hash`, `stored_txs` or `tx_count`, and those of
+    /// `light`: `header_hash`, `trusted_hash`, `validators_hash`, `commit`, `last_block_id` or
+    /// `next_validators_hash`.
+    pub check: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "height {}, {}: {}", self.height, self.check, self.detail)
+    }
+}
+
+struct Checks {
+    height: i64,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Checks {
+    fn fail(&mut self, check: &'static str, detail: String) {
+        self.mismatches.push(Mismatch {
+            height: self.height,
+            check,
+            detail,
+        });
+    }
+
+    /// Decoded txs, `None` if one is not base64.
+    fn decode_txs(&mut self, txs: &[String]) -> Option<Vec<Vec<u8>>> {
+        let mut decoded = vec![];
+        for (i, tx) in txs.iter().enumerate() {
+            match engine::general_purpose::STANDARD.decode(tx) {
+                Ok(bytes) => decoded.push(bytes),
+                Err(e) => {
+                    self.fail("tx_bytes", format!("tx {i} is not base64: {e}"));
+                    return None;
+                }
+            }
+        }
+        Some(decoded)
+    }
+
+    fn data_hash(&mut self, data_hash: &str, txs: &[Vec<u8>]) {
+        // tendermint before 0.34 leaves the hash of no tx empty.
+        if txs.is_empty() && data_hash.is_empty() {
+            return;
+        }
+        let root = hex::encode_upper(merkle::txs_root(txs));
+        if !root.eq_ignore_ascii_case(data_hash) {
+            self.fail(
+                "data_hash",
+                format!("header has {data_hash}, the txs hash to {root}"),
+            );
+        }
+    }
+}
+
+fn tx_hash(bytes: &[u8]) -> String {
+    hex::encode(sha2::Sha256::digest(bytes))
+}
+
+/// Check the txs of a block, base64 as in its data, against the `data_hash` of its header.
+pub fn verify_txs(height: i64, data_hash: &str, txs: &[String]) -> Vec<Mismatch> {
+    let mut checks = Checks {
+        height,
+        mismatches: vec![],
+    };
+    if let Some(bytes) = checks.decode_txs(txs) {
+        checks.data_hash(data_hash, &bytes);
+    }
+    checks.mismatches
+}
+
+/// Check a block against its meta and the results of its txs, as returned by an rpc.
+///
+/// Each tx result must match a tx of the block by hash and index, its bytes hashing to its
+/// hash, and every tx must have one.
+pub fn verify_block(
+    block: &ModuleBlockRPC,
+    meta: &BlockSizeRPC,
+    tx_results: &[ModuleTx],
+) -> Vec<Mismatch> {
+    let height = block.block.header.height.parse().unwrap_or_default();
+    let mut checks = Checks {
+        height,
+        mismatches: vec![],
+    };
+    let txs = block.block.data.txs.as_deref().unwrap_or_default();
+
+    match meta.block_metas.as_deref() {
+        Some([m]) => {
+            if m.block_id.hash != block.block_id.hash
+                || m.header.height != block.block.header.height
+            {
+                checks.fail(
+                    "block_meta",
+                    format!(
+                        "meta of {} at {}, block {}",
+                        m.block_id.hash, m.header.height, block.block_id.hash
+                    ),
+                );
+            }
+            if m.num_txs.parse() != Ok(txs.len()) {
+                checks.fail(
+                    "num_txs",
+                    format!("meta has {}, block has {}", m.num_txs, txs.len()),
+                );
+            }
+        }
+        _ => checks.fail("block_meta", "expected one block meta".to_string()),
+    }
+
+    let Some(bytes) = checks.decode_txs(txs) else {
+        return checks.mismatches;
+    };
+    checks.data_hash(&block.block.header.data_hash, &bytes);
+
+    let hashes: Vec<String> = bytes.iter().map(|b| tx_hash(b)).collect();
+    if tx_results.len() != hashes.len() {
+        checks.fail(
+            "tx_results",
+            format!("{} results for {} txs", tx_results.len(), hashes.len()),
+        );
+    }
+    for r in tx_results {
+        let hash = r.hash.to_lowercase();
+        match engine::general_purpose::STANDARD.decode(&r.tx) {
+            Ok(b) if tx_hash(&b) == hash => {}
+            _ => checks.fail("tx_hash", format!("result of {hash} holds other bytes")),
+        }
+        match hashes.iter().position(|h| *h == hash) {
+            Some(i) if i as i64 == r.index => {}
+            Some(i) => checks.fail(
+                "tx_index",
+                format!("result of {hash} at {}, tx at {i}", r.index),
+            ),
+            None => checks.fail("tx_results", format!("{hash} is not a tx of the block")),
+        }
+        if r.height != block.block.header.height {
+            checks.fail(
+                "tx_height",
+                format!("result of {hash} at height {}", r.height),
+            );
+        }
+    }
+    checks.mismatches
+}
+
+/// Check a saved block against its header: `origins` are the origins of all its saved txs,
+/// the quarantined ones included, in block order, and `txs` its indexed txs.
+pub fn verify_stored(record: &BlockRecord, origins: &[String], txs: &[Transaction]) -> Vec<Mismatch> {
+    let mut checks = Checks {
+        height: record.height,
+        mismatches: vec![],
+    };
+    let block: ModuleBlockRPC = match serde_json::from_value(record.block_data.clone()) {
+        Ok(b) => b,
+        Err(e) => {
+            checks.fail("block_data", e.to_string());
+            return checks.mismatches;
+        }
+    };
+    if block.block_id.hash != record.block_hash
+        || block.block.header.height != record.height.to_string()
+    {
+        checks.fail(
+            "block_hash",
+            format!(
+                "block data of {} at {}",
+                block.block_id.hash, block.block.header.height
+            ),
+        );
+    }
+
+    // a pruned block keeps its txs in the tx tables only.
+    let block_txs = block.block.data.txs.as_deref().unwrap_or(origins);
+    if block_txs != origins {
+        checks.fail(
+            "stored_txs",
+            format!(
+                "{} txs saved for {} in the block",
+                origins.len(),
+                block_txs.len()
+            ),
+        );
+    }
+    let Some(bytes) = checks.decode_txs(block_txs) else {
+        return checks.mismatches;
+    };
+    checks.data_hash(&block.block.header.data_hash, &bytes);
+
+    if txs.len() as i64 != record.tx_count {
+        checks.fail(
+            "tx_count",
+            format!("{} txs saved, block counts {}", txs.len(), record.tx_count),
+        );
+    }
+    for tx in txs {
+        let at_index = usize::try_from(tx.tx_index)
+            .ok()
+            .and_then(|i| block_txs.get(i));
+        if at_index != Some(&tx.origin) {
+            checks.fail(
+                "tx_index",
+                format!("{} is not the tx at {}", tx.tx_hash, tx.tx_index),
+            );
+        }
+        match engine::general_purpose::STANDARD.decode(&tx.origin) {
+            Ok(b) if tx_hash(&b) == tx.tx_hash => {}
+            _ => checks.fail("tx_hash", format!("{} holds other bytes", tx.tx_hash)),
+        }
+        if tx.block_hash != record.block_hash || tx.height != record.height {
+            checks.fail(
+                "block_hash",
+                format!("{} saved in {} at {}", tx.tx_hash, tx.block_hash, tx.height),
+            );
+        }
+    }
+    checks.mismatches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mock_rpc::fixture;
+    use crate::parse::tx_hashes;
+    use serde_json::{json, Value};
+
+    fn inputs(height: i64) -> (ModuleBlockRPC, BlockSizeRPC, Vec<ModuleTx>) {
+        let block: ModuleBlockRPC = serde_json::from_value(fixture("block", height)).unwrap();
+        let txs = block.block.data.txs.clone().unwrap_or_default();
+        let meta = serde_json::from_value(json!({
+            "last_height": "3",
+            "block_metas": [{
+                "block_id": block.block_id,
+                "block_size": "1024",
+                "header": block.block.header,
+                "num_txs": txs.len().to_string(),
+            }],
+        }))
+        .unwrap();
+        let results = tx_hashes(&block)
+            .unwrap()
+            .into_iter()
+            .zip(txs)
+            .enumerate()
+            .map(|(i, (hash, tx))| ModuleTx {
+                hash: hash.to_uppercase(),
+                height: height.to_string(),
+                index: i as i64,
+                tx_result: Default::default(),
+                tx,
+            })
+            .collect();
+        (block, meta, results)
+    }
+
+    #[test]
+    fn test_verify_block() {
+        for h in 1..=3 {
+            let (block, meta, results) = inputs(h);
+            assert_eq!(verify_block(&block, &meta, &results), vec![]);
+        }
+
+        let (block, meta, results) = inputs(3);
+        let mut tampered = block.clone();
+        tampered.block.header.data_hash = "00".repeat(32);
+        let checks: Vec<_> = verify_block(&tampered, &meta, &results)
+            .into_iter()
+            .map(|m| m.check)
+            .collect();
+        assert_eq!(checks, vec!["data_hash"]);
+
+        // the results of another block.
+        let (_, _, other) = inputs(2);
+        let checks: Vec<_> = verify_block(&block, &meta, &other)
+            .into_iter()
+            .map(|m| m.check)
+            .collect();
+        assert_eq!(
+            checks,
+            vec!["tx_results", "tx_height", "tx_results", "tx_height"]
+        );
+
+        let mut swapped = results.clone();
+        swapped[0].tx = other[0].tx.clone();
+        assert_eq!(verify_block(&block, &meta, &swapped)[0].check, "tx_hash");
+        assert_eq!(verify_block(&block, &meta, &[])[0].check, "tx_results");
+    }
+
+    #[test]
+    fn test_verify_stored() {
+        let (block, _, _) = inputs(3);
+        let origins = block.block.data.txs.clone().unwrap();
+        let hash = tx_hashes(&block).unwrap().remove(0);
+        let mut record = BlockRecord {
+            block_hash: block.block_id.hash.clone(),
+            height: 3,
+            size: 1024,
+            tx_count: 1,
+            time: Default::default(),
+            app_hash: String::new(),
+            proposer: String::new(),
+            block_data: serde_json::to_value(&block).unwrap(),
+        };
+        let mut txs = vec![Transaction {
+            tx_hash: hash,
+            block_hash: record.block_hash.clone(),
+            height: 3,
+            tx_index: 0,
+            timestamp: 0,
+            ty: 1,
+            ty_sub: 0,
+            sender: String::new(),
+            receiver: Value::Null,
+            code: 0,
+            log: String::new(),
+            origin: origins[0].clone(),
+            result: Value::Null,
+            value: Value::Null,
+        }];
+        assert_eq!(verify_stored(&record, &origins, &txs), vec![]);
+        assert_eq!(verify_stored(&record, &[], &txs)[0].check, "stored_txs");
+
+        // compacted, the txs come from the tx tables.
+        record.block_data["block"]["data"] = json!({});
+        record.block_data["pruned"] = json!("compact");
+        assert_eq!(verify_stored(&record, &origins, &txs), vec![]);
+        record.block_data["pruned"] = json!("drop");
+        assert_eq!(verify_stored(&record, &origins, &txs), vec![]);
+        txs[0].tx_index = 1;
+        assert_eq!(verify_stored(&record, &origins, &txs)[0].check, "tx_index");
+    }
+}
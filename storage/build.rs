@@ -0,0 +1,5 @@
+// the migrations are embedded with `sqlx::migrate!`, rebuild when one is added.
+fn main() {
+    println!("cargo:rerun-if-changed=../module/migrations");
+    println!("cargo:rerun-if-changed=sqlite/migrations");
+}
@@ -0,0 +1,104 @@
+-- the records `SqliteStorage` reads back, the staking tables and the statistics are Postgres only.
+create table if not exists block (
+    block_hash varchar(64) not null,
+    height bigint not null,
+    size bigint not null,
+    tx_count bigint not null,
+    time timestamp not null,
+    app_hash varchar(64) not null,
+    proposer varchar(64) not null,
+    block_data text not null,
+    primary key (height)
+);
+create index if not exists block_hash_index on block(block_hash);
+
+create table if not exists "transaction" (
+    tx_hash varchar(64) not null,
+    block_hash varchar(64) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    code bigint not null,
+    ty integer not null,
+    ty_sub integer not null,
+    sender varchar(64) not null,
+    receiver text not null,
+    log text not null,
+    origin text not null,
+    result text not null,
+    value text not null,
+    tx_index bigint not null,
+    primary key (tx_hash, height)
+);
+create index if not exists tx_height_pos_index on "transaction"(height, tx_index);
+create index if not exists tx_time_index on "transaction"(timestamp);
+
+create table if not exists operations (
+    tx varchar(64) not null,
+    op_index int not null,
+    kind varchar(32) not null,
+    signer varchar(64) not null,
+    counterparty varchar(64) not null,
+    asset varchar(64),
+    amount varchar(48),
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx, op_index)
+);
+create index if not exists op_height_index on operations(height);
+
+create table if not exists tx_participants (
+    tx varchar(64) not null,
+    address varchar(64) not null,
+    role varchar(16) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    primary key (tx, address, role)
+);
+create index if not exists tp_addr_role_index on tx_participants(address, role);
+create index if not exists tp_height_index on tx_participants(height);
+-- the txs of an address are listed from its participant rows, latest block first.
+create index if not exists tp_addr_height_index on tx_participants(address, height desc);
+
+-- a tx of unknown catalog may be sent again at another height, each height keeps its row so a
+-- pruned block is rebuilt with all its txs.
+create table if not exists quarantined_txs (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    height bigint not null,
+    tx_index bigint not null,
+    origin text not null,
+    stage varchar(16) not null,
+    error text not null,
+    timestamp bigint not null,
+    primary key (tx, height)
+);
+create index if not exists qt_height_index on quarantined_txs(height);
+
+create table if not exists last_height (
+    tip varchar(8) not null,
+    height bigint not null,
+    primary key (tip)
+);
+
+create table if not exists e2n (
+    tx_hash varchar(64) not null,
+    block_hash varchar(64) not null,
+    sender varchar(64) not null,
+    receiver varchar(64) not null,
+    asset varchar(64) not null,
+    amount varchar(48) not null,
+    decimal integer not null,
+    height bigint not null,
+    timestamp bigint not null,
+    value text not null,
+    primary key (tx_hash)
+);
+create index if not exists e2n_sender_index on e2n(sender);
+create index if not exists e2n_receiver_index on e2n(receiver);
+
+create table if not exists e2n_last_height (
+    tip varchar(8) not null,
+    height bigint not null,
+    primary key (tip)
+);
@@ -0,0 +1,189 @@
+-- the staking, validator, uptime, mint, slashing and asset records and the addresses the chain
+-- statistics count, which 0001 left to Postgres, with the same columns as there.
+create table if not exists n2e (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    sender varchar(64) not null,
+    receiver varchar(64) not null,
+    asset varchar(64) not null,
+    amount varchar(48) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx)
+);
+create index if not exists n2e_sender_index on n2e(sender);
+create index if not exists n2e_receiver_index on n2e(receiver);
+create index if not exists n2e_height_index on n2e(height);
+
+create table if not exists delegations (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    sender varchar(64) not null,
+    amount bigint not null,
+    validator varchar(64) not null,
+    new_validator varchar(64) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx)
+);
+create index if not exists dlg_sender_index on delegations(sender);
+create index if not exists dlg_height_index on delegations(height);
+
+create table if not exists undelegations (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    sender varchar(64) not null,
+    amount bigint not null,
+    target_validator varchar(64) not null,
+    new_delegator varchar(64) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx)
+);
+create index if not exists ud_sender_index on undelegations(sender);
+create index if not exists ud_height_index on undelegations(height);
+
+create table if not exists claims (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    sender varchar(64) not null,
+    amount bigint not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx)
+);
+create index if not exists clm_sender_index on claims(sender);
+create index if not exists clm_height_index on claims(height);
+
+create table if not exists assets (
+    asset varchar(64) not null,
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    issuer varchar(64) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    ty integer not null,
+    content text not null,
+    primary key (asset, tx, ty)
+);
+create index if not exists ast_height_index on assets(height);
+
+create table if not exists validator_events (
+    tx varchar(64) not null,
+    op_index integer not null,
+    block varchar(64) not null,
+    validator varchar(64) not null,
+    staker varchar(64) not null,
+    kind varchar(32) not null,
+    commission_rate text,
+    memo text,
+    new_staker varchar(64),
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx, op_index, validator)
+);
+create index if not exists ve_validator_index on validator_events(validator, height);
+create index if not exists ve_staker_index on validator_events(staker);
+create index if not exists ve_height_index on validator_events(height);
+
+create table if not exists mints (
+    tx varchar(64) not null,
+    block varchar(64) not null,
+    idx int not null,
+    receiver varchar(64) not null,
+    amount bigint not null,
+    kind varchar(32) not null,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (tx, idx, kind)
+);
+create index if not exists mint_receiver_index on mints(receiver);
+create index if not exists mint_height_index on mints(height);
+
+create table if not exists slashing_events (
+    block varchar(64) not null,
+    tx varchar(64),
+    tx_index bigint not null,
+    op_index integer not null,
+    validator varchar(64) not null,
+    kind varchar(32) not null,
+    source varchar(16) not null,
+    evidence_height bigint,
+    power bigint,
+    penalty_rate text,
+    penalty_amount bigint,
+    height bigint not null,
+    timestamp bigint not null,
+    content text not null,
+    primary key (height, tx_index, op_index, validator)
+);
+create index if not exists se_validator_index on slashing_events(validator);
+
+create table if not exists validators (
+    address varchar(64) not null,
+    pubkey_type int not null,
+    pubkey varchar(64) not null,
+    primary key (address)
+);
+
+create table if not exists block_generation (
+    height bigint not null,
+    address varchar(64) not null,
+    power bigint not null,
+    priority bigint,
+    signature varchar(128),
+    time timestamp,
+    primary key (height, address)
+);
+create index if not exists bg_addr_height_index on block_generation(address, height);
+
+-- the daily uptime is counted from `block_generation` when read.
+create table if not exists validator_uptime_buckets (
+    address varchar(64) not null,
+    bucket bigint not null,
+    first_height bigint not null,
+    last_height bigint not null,
+    signed bigint not null,
+    missed bigint not null,
+    proposed bigint not null,
+    leading_missed bigint not null,
+    trailing_missed bigint not null,
+    longest_missed bigint not null,
+    primary key (address, bucket)
+);
+create index if not exists vub_bucket_index on validator_uptime_buckets(bucket);
+
+-- an address is counted once whatever the txs it appeared in.
+create table if not exists native_addrs (
+    tx varchar(64) not null,
+    address varchar(64) not null,
+    timestamp bigint not null,
+    primary key (tx, address)
+);
+create index if not exists ntvaddr_time_index on native_addrs(timestamp);
+
+create table if not exists evm_addrs (
+    tx varchar(64) not null,
+    address varchar(64) not null,
+    timestamp bigint not null,
+    primary key (tx, address)
+);
+create index if not exists evmaddr_time_index on evm_addrs(timestamp);
+
+create table if not exists prices (
+    name varchar(8) not null,
+    price varchar(16) not null,
+    primary key (name)
+);
+
+create table if not exists market (
+    name varchar(8) not null,
+    val text not null,
+    primary key (name)
+);
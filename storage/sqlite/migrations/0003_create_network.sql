@@ -0,0 +1,4 @@
+-- the network the database was first opened for, it holds no other, see `SqliteStorage::pin_network`.
+create table if not exists network (
+    name varchar(64) not null
+);
@@ -0,0 +1,1948 @@
+use crate::storage_err;
+use chrono::{NaiveDate, NaiveDateTime};
+use module::config::scanner_config::Config;
+use module::schema::{Block as ModuleBlock, PrismTxResult};
+use module::storage::{StorageError, StorageResult};
+use serde_json::Value;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{types::Json, Error, PgConnection, PgExecutor, PgPool, Row};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub use sqlx::Error as SqlxError;
+pub use sqlx::PgPool as SqlxPgPool;
+
+#[cfg(feature = "static-check")]
+use module::schema::LastHeight;
+
+/// Heights per row of `validator_uptime_buckets`.
+pub const UPTIME_BUCKET: i64 = 100;
+
+/// Connect to the configured database, refusing a schema this binary is not built for.
+pub async fn connect() -> StorageResult<PgPool> {
+    let pool = connect_unchecked().await.map_err(storage_err)?;
+    check_schema(&pool).await?;
+    Ok(pool)
+}
+
+/// Connect to the configured database whatever the schema version is, for migrating it.
+pub async fn connect_unchecked() -> Result<PgPool, Error> {
+    let config = Config::load().map_err(|e| Error::Configuration(e.into()))?;
+    pool_options(&config.network)
+        .max_connections(config.database.max_connections)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout))
+        .connect(&config.database.url)
+        .await
+}
+
+/// Pool options whose connections only read and write the rows of `network`.
+pub fn pool_options(network: &str) -> PgPoolOptions {
+    let network = network.to_string();
+    PgPoolOptions::new().after_connect(move |conn, _| {
+        let network = network.clone();
+        Box::pin(async move {
+            sqlx::query("SELECT set_config('findora.network', $1, false)")
+                .bind(network)
+                .execute(conn)
+                .await?;
+            Ok(())
+        })
+    })
+}
+
+#[cfg(not(feature = "static-check"))]
+pub async fn save(block: ModuleBlock, pool: &PgPool) -> Result<(), Error> {
+    ensure_partitions(&[block.height], pool).await?;
+
+    sqlx::query(
+            "INSERT INTO block VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT(network,height) DO UPDATE SET block_hash=$1, size=$3, tx_count=$4, time=$5, app_hash=$6, proposer=$7, block_data=$8")
+            .bind(&block.block_hash)
+            .bind(block.height)
+            .bind(block.size)
+            .bind(block.tx_count)
+            .bind(block.timestamp)
+            .bind(&block.app_hash)
+            .bind(&block.proposer)
+            .bind(&block.block_data)
+            .execute(pool)
+            .await?;
+
+    save_extras(&block, &mut *pool.acquire().await?).await?;
+
+    for tx in block.txs {
+        sqlx::query(
+            "INSERT INTO transaction VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14) ON CONFLICT(network,tx_hash,height) DO UPDATE SET tx_hash=$1,block_hash=$2,height=$3,timestamp=$4,code=$5,ty=$6,ty_sub=$7,sender=$8,receiver=$9,log=$10,origin=$11,result=$12,value=$13,tx_index=$14")
+            .bind(&tx.tx_hash)
+            .bind(&tx.block_hash)
+            .bind(tx.height)
+            .bind(tx.timestamp)
+            .bind(tx.code)
+            .bind(tx.ty)
+            .bind(tx.ty_sub)
+            .bind(&tx.sender)
+            .bind(&tx.receiver)
+            .bind(&tx.log)
+            .bind(&tx.origin)
+            .bind(&tx.result)
+            .bind(&tx.value)
+            .bind(tx.tx_index)
+            .execute(pool)
+            .await?;
+    }
+
+    for tx in block.evm_txs {
+        sqlx::query(
+            "INSERT INTO transaction VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14) ON CONFLICT(network,tx_hash,height) DO UPDATE SET tx_hash=$1,block_hash=$2,height=$3,timestamp=$4,code=$5,ty=$6,ty_sub=$7,sender=$8,receiver=$9,log=$10,origin=$11,result=$12,value=$13,tx_index=$14")
+            .bind(&tx.tx_hash)
+            .bind(&tx.block_hash)
+            .bind(tx.height)
+            .bind(tx.timestamp)
+            .bind(tx.code)
+            .bind(tx.ty)
+            .bind(tx.ty_sub)
+            .bind(&tx.sender)
+            .bind(&tx.receiver)
+            .bind(&tx.log)
+            .bind(&tx.origin)
+            .bind(&tx.result)
+            .bind(&tx.value)
+            .bind(tx.tx_index)
+            .execute(pool)
+            .await?;
+    }
+
+    for op in block.v2_operations {
+        save_operation(
+            &op.tx_hash,
+            op.op_index,
+            &op.kind,
+            &op.signer,
+            &op.counterparty,
+            op.asset.as_deref(),
+            op.amount.as_deref(),
+            op.height,
+            op.timestamp,
+            &op.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for p in block.tx_participants {
+        save_tx_participant(&p.tx_hash, &p.address, &p.role, p.height, p.timestamp, pool).await?;
+    }
+
+    for addr in block.evm_addrs {
+        sqlx::query("INSERT INTO evm_addrs(tx,address,timestamp) VALUES ($1,$2,$3)")
+            .bind(&addr.tx)
+            .bind(&addr.address)
+            .bind(addr.timestamp)
+            .execute(pool)
+            .await?;
+    }
+    for addr in block.native_addrs {
+        sqlx::query("INSERT INTO native_addrs(tx,address,timestamp) VALUES ($1,$2,$3)")
+            .bind(&addr.tx)
+            .bind(&addr.address)
+            .bind(addr.timestamp)
+            .execute(pool)
+            .await?;
+    }
+
+    for v in block.validators {
+        sqlx::query(
+                "INSERT INTO validators VALUES ($1, 0, $2) ON CONFLICT(network,address) DO UPDATE SET pubkey_type=0, pubkey=$2")
+                .bind(&v.address)
+                .bind(&v.pub_key.value)
+        .execute(pool)
+            .await?;
+
+        let power = v.power as i64;
+
+        let inserted: bool = sqlx::query(
+                "WITH old AS (SELECT 1 FROM block_generation WHERE network=current_network() AND height=$1 AND address=$2) INSERT INTO block_generation VALUES($1, $2, $3, $4, $5, $6) ON CONFLICT(network, height, address) DO UPDATE SET power=$3, priority=$4, signature=$5, time=$6 RETURNING NOT EXISTS (SELECT 1 FROM old) AS inserted")
+                .bind(block.height)
+                .bind(&v.address)
+                .bind(power)
+                .bind(v.priority)
+                .bind(v.signature.as_ref())
+                .bind(v.timestamp.as_ref())
+            .fetch_one(pool)
+            .await?
+            .try_get("inserted")?;
+
+        // count each height once, re-scanning must not inflate the counters.
+        if inserted {
+            save_uptime(
+                &v.address,
+                block.timestamp.date(),
+                v.signature.is_some(),
+                v.address == block.proposer,
+                pool,
+            )
+            .await?;
+        }
+    }
+    save_uptime_buckets(&[block.height], pool).await?;
+    Ok(())
+}
+
+/// Rows of the typed tables that only some txs produce, shared by `save` and `save_bulk`.
+async fn save_extras(block: &ModuleBlock, conn: &mut PgConnection) -> Result<(), Error> {
+    for tx in &block.v2_convert_account_txs {
+        save_n2e_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            &tx.receiver,
+            &tx.asset,
+            &tx.amount,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for tx in &block.v2_delegation_txs {
+        save_delegation_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            &tx.validator,
+            &tx.new_validator,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for tx in &block.v2_undelegation_txs {
+        save_undelegation_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            &tx.target_validator,
+            &tx.new_delegator,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for tx in &block.v2_claim_txs {
+        save_claim_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for tx in &block.v2_asset_txs {
+        save_asset_tx(
+            &tx.asset,
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.issuer,
+            tx.height,
+            tx.timestamp,
+            tx.issued,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for ev in &block.v2_validator_events {
+        save_validator_event(
+            &ev.tx_hash,
+            ev.op_index,
+            &ev.block_hash,
+            &ev.validator,
+            &ev.staker,
+            &ev.kind,
+            ev.commission_rate.as_ref(),
+            ev.memo.as_ref(),
+            ev.new_staker.as_deref(),
+            ev.height,
+            ev.timestamp,
+            &ev.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for tx in &block.v2_mint_txs {
+        save_mint_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            tx.idx,
+            &tx.receiver,
+            tx.amount,
+            &tx.kind,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    // a re-scan of the height supersedes what was quarantined before.
+    sqlx::query("DELETE FROM quarantined_txs WHERE network=current_network() AND height=$1")
+        .bind(block.height)
+        .execute(&mut *conn)
+        .await?;
+    for q in &block.quarantined_txs {
+        save_quarantined_tx(
+            &q.tx_hash,
+            &q.block_hash,
+            q.height,
+            q.tx_index,
+            &q.origin,
+            &q.stage,
+            &q.error,
+            q.timestamp,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    for ev in &block.v2_slashing_events {
+        save_slashing_event(
+            &ev.block_hash,
+            ev.tx_hash.as_deref(),
+            ev.tx_index,
+            ev.op_index,
+            &ev.validator,
+            &ev.kind,
+            &ev.source,
+            ev.evidence_height,
+            ev.power,
+            ev.penalty_rate.as_ref(),
+            ev.height,
+            ev.timestamp,
+            &ev.content,
+            &mut *conn,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "static-check")]
+pub async fn save(block: ModuleBlock, pool: &PgPool) -> Result<(), Error> {
+    ensure_partitions(&[block.height], pool).await?;
+
+    sqlx::query!(
+            "INSERT INTO block VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT(network,height) DO UPDATE SET block_hash=$1, size=$3, tx_count=$4, time=$5, app_hash=$6, proposer=$7, block_data=$8",
+                &block.block_hash,
+                &block.height,
+                &block.size,
+                &block.tx_count,
+                &block.timestamp,
+                &block.app_hash,
+                &block.proposer,
+                &block.block_data,
+        )
+        .execute(pool)
+        .await?;
+
+    for tx in block.txs {
+        sqlx::query!(
+                "INSERT INTO transaction VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14) ON CONFLICT(network,tx_hash,height) DO UPDATE SET tx_hash=$1,block_hash=$2,height=$3,timestamp=$4,code=$5,ty=$6,ty_sub=$7,sender=$8,receiver=$9,log=$10,origin=$11,result=$12,value=$13,tx_index=$14",
+                &tx.tx_hash, &tx.block_hash, &tx.height, &tx.timestamp, &tx.code, &tx.ty, &tx.ty_sub, &tx.sender,&tx.receiver,&tx.log, &tx.origin, &tx.result, &tx.value, &tx.tx_index
+            )
+            .execute(pool)
+            .await?;
+    }
+
+    for tx in block.evm_txs {
+        sqlx::query!(
+                "INSERT INTO transaction VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14) ON CONFLICT(network,tx_hash,height) DO UPDATE SET tx_hash=$1,block_hash=$2,height=$3,timestamp=$4,code=$5,ty=$6,ty_sub=$7,sender=$8,receiver=$9,log=$10,origin=$11,result=$12,value=$13,tx_index=$14",
+                 &tx.tx_hash, &tx.block_hash, &tx.height, &tx.timestamp, &tx.code, &tx.ty, &tx.ty_sub, &tx.sender,&tx.receiver,&tx.log, &tx.origin, &tx.result, &tx.value, &tx.tx_index
+            )
+            .execute(pool)
+            .await?;
+    }
+
+    for tx in block.v2_convert_account_txs {
+        save_n2e_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            &tx.receiver,
+            &tx.asset,
+            &tx.amount,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for tx in block.v2_delegation_txs {
+        save_delegation_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            &tx.validator,
+            &tx.new_validator,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for tx in block.v2_undelegation_txs {
+        save_undelegation_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            &tx.target_validator,
+            &tx.new_delegator,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for tx in block.v2_claim_txs {
+        save_claim_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.sender,
+            tx.amount,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for tx in block.v2_asset_txs {
+        save_asset_tx(
+            &tx.asset,
+            &tx.tx_hash,
+            &tx.block_hash,
+            &tx.issuer,
+            tx.height,
+            tx.timestamp,
+            tx.issued,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for ev in block.v2_validator_events {
+        save_validator_event(
+            &ev.tx_hash,
+            ev.op_index,
+            &ev.block_hash,
+            &ev.validator,
+            &ev.staker,
+            &ev.kind,
+            ev.commission_rate.as_ref(),
+            ev.memo.as_ref(),
+            ev.new_staker.as_deref(),
+            ev.height,
+            ev.timestamp,
+            &ev.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for tx in block.v2_mint_txs {
+        save_mint_tx(
+            &tx.tx_hash,
+            &tx.block_hash,
+            tx.idx,
+            &tx.receiver,
+            tx.amount,
+            &tx.kind,
+            tx.height,
+            tx.timestamp,
+            &tx.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for op in block.v2_operations {
+        save_operation(
+            &op.tx_hash,
+            op.op_index,
+            &op.kind,
+            &op.signer,
+            &op.counterparty,
+            op.asset.as_deref(),
+            op.amount.as_deref(),
+            op.height,
+            op.timestamp,
+            &op.content,
+            pool,
+        )
+        .await?;
+    }
+
+    for p in block.tx_participants {
+        save_tx_participant(&p.tx_hash, &p.address, &p.role, p.height, p.timestamp, pool).await?;
+    }
+
+    // a re-scan of the height supersedes what was quarantined before.
+    sqlx::query("DELETE FROM quarantined_txs WHERE network=current_network() AND height=$1")
+        .bind(block.height)
+        .execute(pool)
+        .await?;
+    for q in block.quarantined_txs {
+        save_quarantined_tx(
+            &q.tx_hash,
+            &q.block_hash,
+            q.height,
+            q.tx_index,
+            &q.origin,
+            &q.stage,
+            &q.error,
+            q.timestamp,
+            pool,
+        )
+        .await?;
+    }
+
+    for ev in block.v2_slashing_events {
+        save_slashing_event(
+            &ev.block_hash,
+            ev.tx_hash.as_deref(),
+            ev.tx_index,
+            ev.op_index,
+            &ev.validator,
+            &ev.kind,
+            &ev.source,
+            ev.evidence_height,
+            ev.power,
+            ev.penalty_rate.as_ref(),
+            ev.height,
+            ev.timestamp,
+            &ev.content,
+            pool,
+        )
+        .await?;
+    }
+    for addr in block.evm_addrs {
+        sqlx::query("INSERT INTO evm_addrs(tx,address,timestamp) VALUES ($1,$2,$3)")
+            .bind(&addr.tx)
+            .bind(&addr.address)
+            .bind(addr.timestamp)
+            .execute(pool)
+            .await?;
+    }
+    for addr in block.native_addrs {
+        sqlx::query("INSERT INTO native_addrs(tx,address,timestamp) VALUES ($1,$2,$3)")
+            .bind(&addr.tx)
+            .bind(&addr.address)
+            .bind(addr.timestamp)
+            .execute(pool)
+            .await?;
+    }
+    for v in block.validators {
+        sqlx::query!(
+                "INSERT INTO validators VALUES ($1, 0, $2) ON CONFLICT(network,address) DO UPDATE SET pubkey_type=0, pubkey=$2",
+                &v.address, &v.pub_key.value
+        ).execute(pool)
+            .await?;
+
+        let power: i64 = v.power.try_into()?;
+
+        let row = sqlx::query!(
+                "WITH old AS (SELECT 1 FROM block_generation WHERE network=current_network() AND height=$1 AND address=$2) INSERT INTO block_generation VALUES($1, $2, $3, $4, $5, $6) ON CONFLICT(network, height, address) DO UPDATE SET power=$3, priority=$4, signature=$5, time=$6 RETURNING NOT EXISTS (SELECT 1 FROM old) AS inserted",
+                    &block.height,
+                    &v.address,
+                    &power,
+                    &v.priority,
+                    v.signature.as_ref(),
+                    v.timestamp.as_ref()
+            ).fetch_one(pool)
+            .await?;
+
+        // count each height once, re-scanning must not inflate the counters.
+        if row.inserted.unwrap_or(false) {
+            save_uptime(
+                &v.address,
+                block.timestamp.date(),
+                v.signature.is_some(),
+                v.address == block.proposer,
+                pool,
+            )
+            .await?;
+        }
+    }
+    save_uptime_buckets(&[block.height], pool).await?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "static-check"))]
+pub async fn save_last_height(height: i64, pool: &PgPool) -> Result<(), Error> {
+    sqlx::query(
+        "INSERT INTO last_height VALUES($1, $2) ON CONFLICT(network,tip) DO UPDATE SET height=$2",
+    )
+    .bind("tip")
+    .bind(height)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "static-check")]
+pub async fn save_last_height(height: i64, pool: &PgPool) -> Result<(), Error> {
+    sqlx::query!(
+        "INSERT INTO last_height VALUES($1, $2) ON CONFLICT(network,tip) DO UPDATE SET height=$2",
+        "tip",
+        &height,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(not(feature = "static-check"))]
+pub async fn load_last_height(pool: &PgPool) -> Result<i64, Error> {
+    let row = sqlx::query("SELECT tip,height FROM last_height WHERE network=current_network()")
+        .fetch_one(pool)
+        .await?;
+    row.try_get("height")
+}
+
+#[cfg(feature = "static-check")]
+pub async fn load_last_height(pool: &PgPool) -> Result<i64, Error> {
+    let lh = sqlx::query_as!(
+        LastHeight,
+        "SELECT tip,height FROM last_height WHERE network=current_network()"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(lh.height)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// migrate
+////////////////////////////////////////////////////////////////////////////////////////////////////
+#[allow(clippy::too_many_arguments)]
+pub async fn save_evm_tx(
+    tx: &str,
+    block: &str,
+    evm_tx_hash: &str,
+    sender: &str,
+    receiver: &str,
+    amount: &str,
+    height: i64,
+    timestamp: i64,
+    content: Value,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO evm_txs VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,evm_tx=$3,sender=$4,receiver=$5,amount=$6,height=$7,timestamp=$8,content=$9")
+        .bind(tx)
+        .bind(block)
+        .bind(evm_tx_hash)
+        .bind(sender)
+        .bind(receiver)
+        .bind(amount)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn save_tx_type(tx: &str, ty: i32, pool: &PgPool) -> Result<(), Error> {
+    sqlx::query(
+        "INSERT INTO tx_types VALUES($1,$2) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,ty=$2",
+    )
+    .bind(tx)
+    .bind(ty)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_n2e_tx(
+    tx: &str,
+    block: &str,
+    sender: &str,
+    receiver: &str,
+    asset: &str,
+    amount: &str,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO n2e VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,sender=$3,receiver=$4,asset=$5,amount=$6,height=$7,timestamp=$8,content=$9")
+        .bind(tx)
+        .bind(block)
+        .bind(sender)
+        .bind(receiver)
+        .bind(asset)
+        .bind(amount)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_native_tx(
+    tx: &str,
+    block: &str,
+    address: &str,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO native_txs VALUES($1,$2,$3,$4,$5,$6) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,address=$3,height=$4,timestamp=$5,content=$6")
+        .bind(tx)
+        .bind(block)
+        .bind(address)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_delegation_tx(
+    tx: &str,
+    block: &str,
+    sender: &str,
+    amount: i64,
+    validator: &str,
+    new_validator: &str,
+    timestamp: i64,
+    height: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO delegations VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,sender=$3,amount=$4,validator=$5,new_validator=$6,height=$7,timestamp=$8,content=$9")
+        .bind(tx)
+        .bind(block)
+        .bind(sender)
+        .bind(amount)
+        .bind(validator)
+        .bind(new_validator)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_undelegation_tx(
+    tx: &str,
+    block: &str,
+    sender: &str,
+    amount: i64,
+    target_validator: &str,
+    new_delegator: &str,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO undelegations VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,sender=$3,amount=$4,target_validator=$5,new_delegator=$6,height=$7,timestamp=$8,content=$9")
+        .bind(tx)
+        .bind(block)
+        .bind(sender)
+        .bind(amount)
+        .bind(target_validator)
+        .bind(new_delegator)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_claim_tx(
+    tx: &str,
+    block: &str,
+    sender: &str,
+    amount: i64,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO claims VALUES($1,$2,$3,$4,$5,$6,$7) ON CONFLICT(network,tx) DO UPDATE SET tx=$1,block=$2,sender=$3,amount=$4,height=$5,timestamp=$6,content=$7")
+        .bind(tx)
+        .bind(block)
+        .bind(sender)
+        .bind(amount)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_asset_tx(
+    asset: &str,
+    tx: &str,
+    block: &str,
+    issuer: &str,
+    height: i64,
+    timestamp: i64,
+    ty: i32,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO assets VALUES($1,$2,$3,$4,$5,$6,$7,$8) ON CONFLICT(network,asset,tx,ty) DO UPDATE SET asset=$1,tx=$2,block=$3,issuer=$4,height=$5,timestamp=$6,ty=$7,content=$8")
+        .bind(asset)
+        .bind(tx)
+        .bind(block)
+        .bind(issuer)
+        .bind(height)
+        .bind(timestamp)
+        .bind(ty)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_validator_event(
+    tx: &str,
+    op_index: i32,
+    block: &str,
+    validator: &str,
+    staker: &str,
+    kind: &str,
+    commission_rate: Option<&Value>,
+    memo: Option<&Value>,
+    new_staker: Option<&str>,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO validator_events(tx,op_index,block,validator,staker,kind,commission_rate,memo,new_staker,height,timestamp,content) VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12) ON CONFLICT(network,tx,op_index,validator) DO UPDATE SET block=$3,staker=$5,kind=$6,commission_rate=$7,memo=$8,new_staker=$9,height=$10,timestamp=$11,content=$12")
+        .bind(tx)
+        .bind(op_index)
+        .bind(block)
+        .bind(validator)
+        .bind(staker)
+        .bind(kind)
+        .bind(commission_rate)
+        .bind(memo)
+        .bind(new_staker)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_mint_tx(
+    tx: &str,
+    block: &str,
+    idx: i32,
+    receiver: &str,
+    amount: i64,
+    kind: &str,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO mints VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9) ON CONFLICT(network,tx,idx,kind) DO UPDATE SET tx=$1,block=$2,idx=$3,receiver=$4,amount=$5,kind=$6,height=$7,timestamp=$8,content=$9")
+        .bind(tx)
+        .bind(block)
+        .bind(idx)
+        .bind(receiver)
+        .bind(amount)
+        .bind(kind)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+/// Save a slashing event. Governance ops don't carry the power of the punished validator,
+/// it is taken from the last block it signed, through the validator of the staker, so the
+/// penalty amount is known when the rate is.
+pub async fn save_slashing_event(
+    block: &str,
+    tx: Option<&str>,
+    tx_index: i64,
+    op_index: i32,
+    validator: &str,
+    kind: &str,
+    source: &str,
+    evidence_height: Option<i64>,
+    power: Option<i64>,
+    penalty_rate: Option<&Value>,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO slashing_events(block,tx,validator,kind,source,evidence_height,power,penalty_rate,height,timestamp,content,tx_index,op_index,penalty_amount) SELECT $1,$2,$3,$4,$5,$6,p.power,$8,$9,$10,$11,$12,$13,div(p.power::numeric*($8->>0)::numeric,NULLIF(($8->>1)::numeric,0))::bigint FROM (SELECT COALESCE($7,(SELECT g.power FROM block_generation g WHERE g.network=current_network() AND g.height<=$9 AND g.address=COALESCE((SELECT v.validator FROM validator_events v WHERE v.network=current_network() AND (v.staker=$3 OR v.new_staker=$3) AND v.validator<>'' AND v.height<=$9 ORDER BY v.height DESC LIMIT 1),$3) ORDER BY g.height DESC LIMIT 1)) AS power) p ON CONFLICT(network,height,tx_index,op_index,validator) DO UPDATE SET block=EXCLUDED.block,tx=EXCLUDED.tx,kind=EXCLUDED.kind,source=EXCLUDED.source,evidence_height=EXCLUDED.evidence_height,power=EXCLUDED.power,penalty_rate=EXCLUDED.penalty_rate,timestamp=EXCLUDED.timestamp,content=EXCLUDED.content,penalty_amount=EXCLUDED.penalty_amount")
+        .bind(block)
+        .bind(tx)
+        .bind(validator)
+        .bind(kind)
+        .bind(source)
+        .bind(evidence_height)
+        .bind(power)
+        .bind(penalty_rate)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .bind(tx_index)
+        .bind(op_index)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn save_uptime(
+    address: &str,
+    day: NaiveDate,
+    signed: bool,
+    proposed: bool,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    let signed = signed as i64;
+    let missed = 1 - signed;
+    let proposed = proposed as i64;
+    sqlx::query("INSERT INTO validator_uptime_daily VALUES($1,$2,$3,$4,$5) ON CONFLICT(network,address,day) DO UPDATE SET signed=validator_uptime_daily.signed+$3,missed=validator_uptime_daily.missed+$4,proposed=validator_uptime_daily.proposed+$5")
+        .bind(address)
+        .bind(day)
+        .bind(signed)
+        .bind(missed)
+        .bind(proposed)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Recount the uptime buckets of `heights` from `block_generation`, the explorer sums them
+/// for the uptime of the last blocks.
+pub async fn save_uptime_buckets<'e, E: sqlx::PgExecutor<'e>>(
+    heights: &[i64],
+    executor: E,
+) -> Result<(), Error> {
+    let buckets: BTreeSet<i64> = heights.iter().map(|h| h / UPTIME_BUCKET).collect();
+    // the missed runs of a bucket, its leading, trailing and longest ones are kept.
+    sqlx::query(
+        "WITH b AS (SELECT DISTINCT unnest($1::bigint[]) AS bucket), \
+        g AS (SELECT b.bucket,bg.address,bg.height,bg.signature IS NULL AS miss,coalesce(blk.proposer=bg.address,false) AS proposed \
+        FROM b JOIN block_generation bg ON bg.network=current_network() AND bg.height>=b.bucket*$2 AND bg.height<(b.bucket+1)*$2 \
+        LEFT JOIN block blk ON blk.network=current_network() AND blk.height=bg.height), \
+        runs AS (SELECT bucket,address,min(height) AS first,max(height) AS last,count(*) AS len FROM \
+        (SELECT bucket,address,height,height-row_number() OVER (PARTITION BY bucket,address ORDER BY height) AS grp FROM g WHERE miss) m \
+        GROUP BY bucket,address,grp), \
+        s AS (SELECT bucket,address,min(height) AS first_height,max(height) AS last_height,count(*) FILTER (WHERE NOT miss) AS signed,\
+        count(*) FILTER (WHERE miss) AS missed,count(*) FILTER (WHERE proposed) AS proposed FROM g GROUP BY bucket,address) \
+        INSERT INTO validator_uptime_buckets(address,bucket,first_height,last_height,signed,missed,proposed,leading_missed,trailing_missed,longest_missed) \
+        SELECT s.address,s.bucket,s.first_height,s.last_height,s.signed,s.missed,s.proposed,\
+        coalesce((SELECT len FROM runs r WHERE r.bucket=s.bucket AND r.address=s.address AND r.first=s.first_height),0),\
+        coalesce((SELECT len FROM runs r WHERE r.bucket=s.bucket AND r.address=s.address AND r.last=s.last_height),0),\
+        coalesce((SELECT max(len) FROM runs r WHERE r.bucket=s.bucket AND r.address=s.address),0) FROM s \
+        ON CONFLICT(network,address,bucket) DO UPDATE SET first_height=EXCLUDED.first_height,last_height=EXCLUDED.last_height,\
+        signed=EXCLUDED.signed,missed=EXCLUDED.missed,proposed=EXCLUDED.proposed,leading_missed=EXCLUDED.leading_missed,\
+        trailing_missed=EXCLUDED.trailing_missed,longest_missed=EXCLUDED.longest_missed",
+    )
+    .bind(buckets.into_iter().collect::<Vec<_>>())
+    .bind(UPTIME_BUCKET)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_operation(
+    tx: &str,
+    op_index: i32,
+    kind: &str,
+    signer: &str,
+    counterparty: &str,
+    asset: Option<&str>,
+    amount: Option<&str>,
+    height: i64,
+    timestamp: i64,
+    content: &Value,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO operations VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9,$10) ON CONFLICT(network,tx,op_index) DO UPDATE SET tx=$1,op_index=$2,kind=$3,signer=$4,counterparty=$5,asset=$6,amount=$7,height=$8,timestamp=$9,content=$10")
+        .bind(tx)
+        .bind(op_index)
+        .bind(kind)
+        .bind(signer)
+        .bind(counterparty)
+        .bind(asset)
+        .bind(amount)
+        .bind(height)
+        .bind(timestamp)
+        .bind(content)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn save_tx_participant(
+    tx: &str,
+    address: &str,
+    role: &str,
+    height: i64,
+    timestamp: i64,
+    pool: &PgPool,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO tx_participants VALUES($1,$2,$3,$4,$5) ON CONFLICT(network,tx,address,role) DO UPDATE SET height=$4,timestamp=$5")
+        .bind(tx)
+        .bind(address)
+        .bind(role)
+        .bind(height)
+        .bind(timestamp)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn save_quarantined_tx(
+    tx: &str,
+    block: &str,
+    height: i64,
+    tx_index: i64,
+    origin: &str,
+    stage: &str,
+    error: &str,
+    timestamp: i64,
+    pool: impl PgExecutor<'_>,
+) -> Result<(), Error> {
+    sqlx::query("INSERT INTO quarantined_txs VALUES($1,$2,$3,$4,$5,$6,$7,$8) ON CONFLICT(network,tx,height) DO UPDATE SET block=$2,tx_index=$4,origin=$5,stage=$6,error=$7,timestamp=$8")
+        .bind(tx)
+        .bind(block)
+        .bind(height)
+        .bind(tx_index)
+        .bind(origin)
+        .bind(stage)
+        .bind(error)
+        .bind(timestamp)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn load_quarantined_heights(pool: &PgPool) -> Result<Vec<i64>, Error> {
+    let rows = sqlx::query("SELECT DISTINCT height FROM quarantined_txs WHERE network=current_network() ORDER BY height")
+        .fetch_all(pool)
+        .await?;
+    let mut heights = vec![];
+    for row in rows {
+        heights.push(row.try_get("height")?);
+    }
+
+    Ok(heights)
+}
+
+/// Validators of the stakers by staker address, the latest the blocks below `height` gave
+/// them: the one they registered with a delegation, updated or replaced the staker of.
+pub async fn load_stakers(height: i64, pool: &PgPool) -> Result<HashMap<String, String>, Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT DISTINCT ON (staker) staker,validator FROM (\
+        SELECT staker,validator,height FROM validator_events WHERE network=current_network() AND height<$1 \
+        UNION ALL SELECT new_staker,validator,height FROM validator_events WHERE network=current_network() AND new_staker IS NOT NULL AND height<$1 \
+        UNION ALL SELECT sender,new_validator,height FROM delegations WHERE network=current_network() AND new_validator<>'' AND height<$1\
+        ) s ORDER BY staker,height DESC",
+    )
+    .bind(height)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// prism
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// Save the evm to native transfers found by the prismer.
+#[cfg(not(feature = "static-check"))]
+pub async fn save_prism_txs(res: Vec<PrismTxResult>, pool: &PgPool) -> Result<(), Error> {
+    for tr in res {
+        sqlx::query("INSERT INTO e2n VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10) ON CONFLICT(network,tx_hash) DO UPDATE SET tx_hash=$1,block_hash=$2,sender=$3,receiver=$4,asset=$5,amount=$6,decimal=$7,height=$8,timestamp=$9,value=$10")
+            .bind(&tr.tx_hash)
+            .bind(&tr.block_hash)
+            .bind(&tr.sender)
+            .bind(&tr.receiver)
+            .bind(&tr.asset)
+            .bind(&tr.amount)
+            .bind(tr.decimal)
+            .bind(tr.height)
+            .bind(tr.timestamp)
+            .bind(&tr.value)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Save the evm to native transfers found by the prismer.
+#[cfg(feature = "static-check")]
+pub async fn save_prism_txs(res: Vec<PrismTxResult>, pool: &PgPool) -> Result<(), Error> {
+    for tr in res {
+        sqlx::query!(
+                "INSERT INTO e2n VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10) ON CONFLICT(network,tx_hash) DO UPDATE SET tx_hash=$1,block_hash=$2,sender=$3,receiver=$4,asset=$5,amount=$6,decimal=$7,height=$8,timestamp=$9,value=$10",
+                &tr.tx_hash, &tr.block_hash, &tr.sender, &tr.receiver, &tr.asset, &tr.amount, &tr.decimal, &tr.height, &tr.timestamp, &tr.value
+            )
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "static-check"))]
+pub async fn save_prism_last_height(height: i64, pool: &PgPool) -> Result<(), Error> {
+    sqlx::query(
+        "INSERT INTO e2n_last_height VALUES($1, $2) ON CONFLICT(network,tip) DO UPDATE SET height=$2",
+    )
+    .bind("tip")
+    .bind(height)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(feature = "static-check")]
+pub async fn save_prism_last_height(height: i64, pool: &PgPool) -> Result<(), Error> {
+    sqlx::query!(
+        "INSERT INTO e2n_last_height VALUES($1, $2) ON CONFLICT(network,tip) DO UPDATE SET height=$2",
+        "tip",
+        &height,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(not(feature = "static-check"))]
+pub async fn load_prism_last_height(pool: &PgPool) -> Result<i64, Error> {
+    let row = sqlx::query("SELECT tip,height FROM e2n_last_height WHERE network=current_network()")
+        .fetch_one(pool)
+        .await?;
+    row.try_get("height")
+}
+
+#[cfg(feature = "static-check")]
+pub async fn load_prism_last_height(pool: &PgPool) -> Result<i64, Error> {
+    let lh = sqlx::query_as!(
+        LastHeight,
+        "SELECT tip,height FROM e2n_last_height WHERE network=current_network()"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(lh.height)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// partition
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// Partitions known to exist, by `height / size`.
+static PARTITIONS: Mutex<Option<(i64, BTreeSet<i64>)>> = Mutex::new(None);
+
+/// Create the height partitions holding `heights` and the ones right after them,
+/// so the tables are ready before the chain gets there.
+pub async fn ensure_partitions(heights: &[i64], pool: &PgPool) -> Result<(), Error> {
+    let cached = PARTITIONS.lock().unwrap().as_ref().map(|(size, _)| *size);
+    let size = match cached {
+        Some(size) => size,
+        None => {
+            let row = sqlx::query("SELECT size FROM partition_settings WHERE name='height'")
+                .fetch_one(pool)
+                .await?;
+            let size: i64 = row.try_get("size")?;
+            PARTITIONS
+                .lock()
+                .unwrap()
+                .get_or_insert((size, BTreeSet::new()))
+                .0
+        }
+    };
+
+    let missing: BTreeSet<i64> = {
+        let partitions = PARTITIONS.lock().unwrap();
+        let known = &partitions.as_ref().unwrap().1;
+        heights
+            .iter()
+            .flat_map(|h| [h / size, h / size + 1])
+            .filter(|p| !known.contains(p))
+            .collect()
+    };
+    for p in missing {
+        sqlx::query("SELECT ensure_height_partition($1)")
+            .bind(p * size)
+            .execute(pool)
+            .await?;
+        PARTITIONS.lock().unwrap().as_mut().unwrap().1.insert(p);
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// bulk
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// Tables written by `save_bulk` with one statement per batch.
+const BULK_TABLES: [&str; 9] = [
+    "block",
+    "transaction",
+    "evm_addrs",
+    "native_addrs",
+    "block_generation",
+    "operations",
+    "tx_participants",
+    "validator_uptime_daily",
+    "validator_uptime_buckets",
+];
+
+/// Save a batch of blocks with one multi-row `INSERT ... SELECT unnest(...)` per table.
+///
+/// Conflicting rows are updated the same way `save` does, so a batch may overlap heights
+/// already in the database. The typed tables only a few txs touch are still written per row.
+pub async fn save_bulk(blocks: &[ModuleBlock], pool: &PgPool) -> Result<(), Error> {
+    if blocks.is_empty() {
+        return Ok(());
+    }
+    let heights: Vec<_> = blocks.iter().map(|b| b.height).collect();
+    ensure_partitions(&heights, pool).await?;
+    let mut conn = pool.begin().await?;
+
+    let (mut hashes, mut sizes, mut tx_counts) = (vec![], vec![], vec![]);
+    let (mut times, mut app_hashes, mut proposers, mut data) = (vec![], vec![], vec![], vec![]);
+    for b in blocks {
+        hashes.push(b.block_hash.as_str());
+        sizes.push(b.size);
+        tx_counts.push(b.tx_count);
+        times.push(b.timestamp);
+        app_hashes.push(b.app_hash.as_str());
+        proposers.push(b.proposer.as_str());
+        data.push(Json(&b.block_data));
+    }
+    sqlx::query("INSERT INTO block SELECT * FROM unnest($1::varchar[],$2::bigint[],$3::bigint[],$4::bigint[],$5::timestamp[],$6::varchar[],$7::varchar[],$8::jsonb[]) ON CONFLICT(network,height) DO UPDATE SET block_hash=EXCLUDED.block_hash,size=EXCLUDED.size,tx_count=EXCLUDED.tx_count,time=EXCLUDED.time,app_hash=EXCLUDED.app_hash,proposer=EXCLUDED.proposer,block_data=EXCLUDED.block_data")
+        .bind(&hashes)
+        .bind(&heights)
+        .bind(&sizes)
+        .bind(&tx_counts)
+        .bind(&times)
+        .bind(&app_hashes)
+        .bind(&proposers)
+        .bind(&data)
+        .execute(&mut *conn)
+        .await?;
+
+    // a row may be affected only once per statement, the later one wins.
+    let mut seen = HashSet::new();
+    let txs: Vec<_> = blocks
+        .iter()
+        .rev()
+        .flat_map(|b| b.txs.iter().chain(b.evm_txs.iter()).rev())
+        .filter(|tx| seen.insert(tx.tx_hash.as_str()))
+        .collect();
+    sqlx::query("INSERT INTO transaction SELECT * FROM unnest($1::varchar[],$2::varchar[],$3::bigint[],$4::bigint[],$5::bigint[],$6::integer[],$7::integer[],$8::varchar[],$9::jsonb[],$10::text[],$11::text[],$12::jsonb[],$13::jsonb[],$14::bigint[]) ON CONFLICT(network,tx_hash,height) DO UPDATE SET block_hash=EXCLUDED.block_hash,timestamp=EXCLUDED.timestamp,code=EXCLUDED.code,ty=EXCLUDED.ty,ty_sub=EXCLUDED.ty_sub,sender=EXCLUDED.sender,receiver=EXCLUDED.receiver,log=EXCLUDED.log,origin=EXCLUDED.origin,result=EXCLUDED.result,value=EXCLUDED.value,tx_index=EXCLUDED.tx_index")
+        .bind(txs.iter().map(|t| t.tx_hash.as_str()).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.block_hash.as_str()).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.height).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.timestamp).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.code).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.ty).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.ty_sub).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.sender.as_str()).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| Json(&t.receiver)).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.log.as_str()).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.origin.as_str()).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| Json(&t.result)).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| Json(&t.value)).collect::<Vec<_>>())
+        .bind(txs.iter().map(|t| t.tx_index).collect::<Vec<_>>())
+        .execute(&mut *conn)
+        .await?;
+
+    let mut seen = HashSet::new();
+    let ops: Vec<_> = blocks
+        .iter()
+        .rev()
+        .flat_map(|b| b.v2_operations.iter().rev())
+        .filter(|op| seen.insert((op.tx_hash.as_str(), op.op_index)))
+        .collect();
+    sqlx::query("INSERT INTO operations SELECT * FROM unnest($1::varchar[],$2::int[],$3::varchar[],$4::varchar[],$5::varchar[],$6::varchar[],$7::varchar[],$8::bigint[],$9::bigint[],$10::jsonb[]) ON CONFLICT(network,tx,op_index) DO UPDATE SET kind=EXCLUDED.kind,signer=EXCLUDED.signer,counterparty=EXCLUDED.counterparty,asset=EXCLUDED.asset,amount=EXCLUDED.amount,height=EXCLUDED.height,timestamp=EXCLUDED.timestamp,content=EXCLUDED.content")
+        .bind(ops.iter().map(|o| o.tx_hash.as_str()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.op_index).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.kind.as_str()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.signer.as_str()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.counterparty.as_str()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.asset.as_deref()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.amount.as_deref()).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.height).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| o.timestamp).collect::<Vec<_>>())
+        .bind(ops.iter().map(|o| Json(&o.content)).collect::<Vec<_>>())
+        .execute(&mut *conn)
+        .await?;
+
+    let mut seen = HashSet::new();
+    let parts: Vec<_> = blocks
+        .iter()
+        .flat_map(|b| b.tx_participants.iter())
+        .filter(|p| seen.insert((p.tx_hash.as_str(), p.address.as_str(), p.role.as_str())))
+        .collect();
+    sqlx::query("INSERT INTO tx_participants SELECT * FROM unnest($1::varchar[],$2::varchar[],$3::varchar[],$4::bigint[],$5::bigint[]) ON CONFLICT(network,tx,address,role) DO UPDATE SET height=EXCLUDED.height,timestamp=EXCLUDED.timestamp")
+        .bind(parts.iter().map(|p| p.tx_hash.as_str()).collect::<Vec<_>>())
+        .bind(parts.iter().map(|p| p.address.as_str()).collect::<Vec<_>>())
+        .bind(parts.iter().map(|p| p.role.as_str()).collect::<Vec<_>>())
+        .bind(parts.iter().map(|p| p.height).collect::<Vec<_>>())
+        .bind(parts.iter().map(|p| p.timestamp).collect::<Vec<_>>())
+        .execute(&mut *conn)
+        .await?;
+
+    for (table, addrs) in [
+        (
+            "evm_addrs",
+            blocks
+                .iter()
+                .flat_map(|b| b.evm_addrs.iter())
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "native_addrs",
+            blocks
+                .iter()
+                .flat_map(|b| b.native_addrs.iter())
+                .collect::<Vec<_>>(),
+        ),
+    ] {
+        sqlx::query(&format!("INSERT INTO {table}(tx,address,timestamp) SELECT * FROM unnest($1::varchar[],$2::varchar[],$3::bigint[])"))
+            .bind(addrs.iter().map(|a| a.tx.as_str()).collect::<Vec<_>>())
+            .bind(addrs.iter().map(|a| a.address.as_str()).collect::<Vec<_>>())
+            .bind(addrs.iter().map(|a| a.timestamp).collect::<Vec<_>>())
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let mut seen = HashSet::new();
+    let validators: Vec<_> = blocks
+        .iter()
+        .rev()
+        .flat_map(|b| b.validators.iter())
+        .filter(|v| seen.insert(v.address.as_str()))
+        .collect();
+    sqlx::query("INSERT INTO validators SELECT a, 0, p FROM unnest($1::varchar[],$2::varchar[]) AS v(a,p) ON CONFLICT(network,address) DO UPDATE SET pubkey_type=0, pubkey=EXCLUDED.pubkey")
+        .bind(validators.iter().map(|v| v.address.as_str()).collect::<Vec<_>>())
+        .bind(validators.iter().map(|v| v.pub_key.value.as_str()).collect::<Vec<_>>())
+        .execute(&mut *conn)
+        .await?;
+
+    let (mut g_heights, mut g_addrs, mut g_powers) = (vec![], vec![], vec![]);
+    let (mut g_priorities, mut g_sigs, mut g_times) = (vec![], vec![], vec![]);
+    for b in blocks {
+        for v in &b.validators {
+            g_heights.push(b.height);
+            g_addrs.push(v.address.as_str());
+            g_powers.push(v.power as i64);
+            g_priorities.push(v.priority);
+            g_sigs.push(v.signature.as_deref());
+            g_times.push(v.timestamp);
+        }
+    }
+    // count each height once, re-scanning must not inflate the counters. `xmax` can not tell
+    // inserts from updates here, system columns are not available on partitioned tables.
+    sqlx::query("WITH old AS (SELECT height,address FROM block_generation WHERE network=current_network() AND height = ANY($7)), g AS (INSERT INTO block_generation SELECT * FROM unnest($1::bigint[],$2::varchar[],$3::bigint[],$4::bigint[],$5::varchar[],$6::timestamp[]) ON CONFLICT(network,height,address) DO UPDATE SET power=EXCLUDED.power,priority=EXCLUDED.priority,signature=EXCLUDED.signature,time=EXCLUDED.time RETURNING height,address,signature,NOT EXISTS (SELECT 1 FROM old WHERE old.height = block_generation.height AND old.address = block_generation.address) AS inserted), d AS (SELECT * FROM unnest($7::bigint[],$8::date[],$9::varchar[]) AS d(height,day,proposer)) INSERT INTO validator_uptime_daily SELECT g.address, d.day, count(g.signature), count(*) - count(g.signature), count(*) FILTER (WHERE g.address = d.proposer) FROM g JOIN d ON d.height = g.height WHERE g.inserted GROUP BY g.address, d.day ON CONFLICT(network,address,day) DO UPDATE SET signed=validator_uptime_daily.signed+EXCLUDED.signed,missed=validator_uptime_daily.missed+EXCLUDED.missed,proposed=validator_uptime_daily.proposed+EXCLUDED.proposed")
+        .bind(&g_heights)
+        .bind(&g_addrs)
+        .bind(&g_powers)
+        .bind(&g_priorities)
+        .bind(&g_sigs)
+        .bind(&g_times)
+        .bind(&heights)
+        .bind(times.iter().map(NaiveDateTime::date).collect::<Vec<_>>())
+        .bind(&proposers)
+        .execute(&mut *conn)
+        .await?;
+    save_uptime_buckets(&heights, &mut *conn).await?;
+    for b in blocks {
+        save_extras(b, &mut conn).await?;
+    }
+
+    conn.commit().await?;
+
+    Ok(())
+}
+
+/// Drop the secondary indexes of the bulk tables before an initial load.
+///
+/// The definitions are kept in `deferred_indexes` until `restore_indexes`, so an interrupted
+/// load can still restore them. Indexes backing a constraint are kept for the upserts.
+pub async fn defer_indexes(pool: &PgPool) -> Result<usize, Error> {
+    sqlx::query("INSERT INTO deferred_indexes SELECT indexname, indexdef FROM pg_indexes i WHERE schemaname = current_schema() AND tablename = ANY($1) AND NOT EXISTS (SELECT 1 FROM pg_constraint c WHERE c.conindid = format('%I.%I', i.schemaname, i.indexname)::regclass) ON CONFLICT DO NOTHING")
+        .bind(BULK_TABLES.to_vec())
+        .execute(pool)
+        .await?;
+
+    let rows = sqlx::query("SELECT name FROM deferred_indexes")
+        .fetch_all(pool)
+        .await?;
+    for row in &rows {
+        let name: String = row.try_get("name")?;
+        sqlx::query(&format!("DROP INDEX IF EXISTS \"{name}\""))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Recreate the indexes dropped by `defer_indexes`, returns how many were restored.
+pub async fn restore_indexes(pool: &PgPool) -> Result<usize, Error> {
+    let rows = sqlx::query("SELECT name, def FROM deferred_indexes")
+        .fetch_all(pool)
+        .await?;
+    for row in &rows {
+        let name: String = row.try_get("name")?;
+        let def: String = row.try_get("def")?;
+        let def = def
+            .replacen("CREATE INDEX ", "CREATE INDEX IF NOT EXISTS ", 1)
+            .replacen(
+                "CREATE UNIQUE INDEX ",
+                "CREATE UNIQUE INDEX IF NOT EXISTS ",
+                1,
+            );
+        sqlx::query(&def).execute(pool).await?;
+        sqlx::query("DELETE FROM deferred_indexes WHERE name=$1")
+            .bind(&name)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(rows.len())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// prune
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// How the `block_data` of blocks out of the retention window is pruned.
+///
+/// The explorer rebuilds the full block from the tables, `transaction.origin` is always kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PruneMode {
+    /// Strip the txs, they are rebuilt from `transaction.origin`.
+    Compact,
+    /// Also strip the commit signatures `block_generation` keeps, they are rebuilt from it.
+    Drop,
+}
+
+impl PruneMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PruneMode::Compact => "compact",
+            PruneMode::Drop => "drop",
+        }
+    }
+
+    /// The pruned `block_data` of a row of `block`.
+    fn block_data(&self) -> &'static str {
+        match self {
+            PruneMode::Compact => "(block_data #- '{block,data,txs}') || '{\"pruned\":\"compact\"}'",
+            PruneMode::Drop => "CASE WHEN jsonb_typeof(block_data #> '{block,last_commit,signatures}')='array' \
+                THEN jsonb_set(block_data #- '{block,data,txs}', '{block,last_commit,signatures}', COALESCE(\
+                (SELECT jsonb_agg(CASE WHEN EXISTS (SELECT 1 FROM block_generation g WHERE g.network=block.network AND g.height=block.height \
+                AND g.address=s->>'validator_address' AND g.signature=s->>'signature') THEN s - 'signature' ELSE s END ORDER BY i) \
+                FROM jsonb_array_elements(block_data #> '{block,last_commit,signatures}') WITH ORDINALITY AS e(s, i)), '[]')) \
+                ELSE block_data #- '{block,data,txs}' END || '{\"pruned\":\"drop\"}'",
+        }
+    }
+
+    /// Blocks not pruned by this mode yet, a dropped block is not compacted again.
+    fn pending(&self) -> &'static str {
+        match self {
+            PruneMode::Compact => "NOT block_data ? 'pruned'",
+            PruneMode::Drop => "(block_data->>'pruned') IS DISTINCT FROM 'drop'",
+        }
+    }
+}
+
+/// The height the blocks below are pruned with `mode`, a dropped block being compacted too.
+pub async fn load_prune_height(mode: PruneMode, pool: &PgPool) -> Result<i64, Error> {
+    let row = sqlx::query("SELECT COALESCE(max(height), 0) AS height FROM prune_heights WHERE network=current_network() AND mode IN ($1, 'drop')")
+        .bind(mode.name())
+        .fetch_one(pool)
+        .await?;
+    row.try_get("height")
+}
+
+/// Blocks in `[from, to)` that `mode` would prune, and the bytes it would reclaim, estimated.
+pub async fn prune_estimate(
+    from: i64,
+    to: i64,
+    mode: PruneMode,
+    pool: &PgPool,
+) -> Result<(i64, i64), Error> {
+    let row = sqlx::query(&format!(
+        "SELECT count(*) AS blocks, COALESCE(sum(pg_column_size(block_data) - pg_column_size({})), 0)::bigint AS bytes FROM block WHERE network=current_network() AND height>=$1 AND height<$2 AND {}",
+        mode.block_data(),
+        mode.pending()
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+    Ok((row.try_get("blocks")?, row.try_get("bytes")?))
+}
+
+/// Prune the blocks in `[from, to)` and move the prune height of `mode` to `to`, returns how
+/// many blocks were rewritten.
+pub async fn prune_blocks(
+    from: i64,
+    to: i64,
+    mode: PruneMode,
+    pool: &PgPool,
+) -> Result<u64, Error> {
+    let mut conn = pool.begin().await?;
+    let res = sqlx::query(&format!(
+        "UPDATE block SET block_data={} WHERE network=current_network() AND height>=$1 AND height<$2 AND {}",
+        mode.block_data(),
+        mode.pending()
+    ))
+    .bind(from)
+    .bind(to)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("INSERT INTO prune_heights (mode, height) VALUES ($1, $2) ON CONFLICT(network, mode) DO UPDATE SET height=GREATEST(prune_heights.height, $2)")
+        .bind(mode.name())
+        .bind(to)
+        .execute(&mut *conn)
+        .await?;
+    conn.commit().await?;
+    debug!("Pruned ({}) blocks in [{},{}).", mode.name(), from, to);
+
+    Ok(res.rows_affected())
+}
+
+/// Prune the blocks in `[from, before)`, `batch` heights per statement. Returns how many blocks
+/// were rewritten.
+pub async fn prune(
+    mut from: i64,
+    before: i64,
+    mode: PruneMode,
+    batch: i64,
+    pool: &PgPool,
+) -> Result<u64, Error> {
+    let mut pruned = 0;
+    while from < before {
+        let to = (from + batch.max(1)).min(before);
+        pruned += prune_blocks(from, to, mode, pool).await?;
+        from = to;
+    }
+    Ok(pruned)
+}
+
+/// Signatures of the last commit of the block at `height` by validator, as `block_generation`
+/// keeps them.
+pub async fn commit_signatures(height: i64, pool: &PgPool) -> Result<Vec<(String, String)>, Error> {
+    sqlx::query_as("SELECT address, signature FROM block_generation WHERE network=current_network() AND height=$1 AND signature IS NOT NULL")
+        .bind(height)
+        .fetch_all(pool)
+        .await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// schema
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// The migrations of `module/migrations`, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../module/migrations");
+
+/// Versions of the embedded migrations, ascending.
+pub fn embedded_migrations() -> Vec<i64> {
+    MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| m.version)
+        .collect()
+}
+
+/// The schema version this binary is built for.
+pub fn expected_schema_version() -> i64 {
+    embedded_migrations().last().copied().unwrap_or_default()
+}
+
+/// Applied migrations with whether they completed, `None` if no migration ever ran.
+pub async fn applied_migrations(pool: &PgPool) -> Result<Option<Vec<(i64, bool)>>, Error> {
+    let row = sqlx::query("SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS initialized")
+        .fetch_one(pool)
+        .await?;
+    if !row.try_get::<bool, _>("initialized")? {
+        return Ok(None);
+    }
+    let rows = sqlx::query("SELECT version,success FROM _sqlx_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await?;
+    rows.iter()
+        .map(|r| Ok((r.try_get("version")?, r.try_get("success")?)))
+        .collect::<Result<Vec<_>, Error>>()
+        .map(Some)
+}
+
+/// Record the embedded migrations up to `version` as applied without running them, for a
+/// database migrated by hand. Already recorded ones are kept.
+pub async fn baseline_schema(version: i64, pool: &PgPool) -> StorageResult<Vec<i64>> {
+    if !embedded_migrations().contains(&version) {
+        return Err(StorageError::Backend(format!(
+            "{version} is not the version of an embedded migration."
+        )));
+    }
+    let mut conn = pool.acquire().await.map_err(storage_err)?;
+    conn.ensure_migrations_table()
+        .await
+        .map_err(|e| StorageError::Backend(e.to_string()))?;
+    let mut recorded = vec![];
+    for m in MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && m.version <= version)
+    {
+        let inserted = sqlx::query(
+            "INSERT INTO _sqlx_migrations(version,description,success,checksum,execution_time) \
+            VALUES ($1,$2,true,$3,0) ON CONFLICT(version) DO NOTHING",
+        )
+        .bind(m.version)
+        .bind(&*m.description)
+        .bind(&*m.checksum)
+        .execute(&mut *conn)
+        .await
+        .map_err(storage_err)?
+        .rows_affected();
+        if inserted > 0 {
+            recorded.push(m.version);
+        }
+    }
+    Ok(recorded)
+}
+
+/// Fails unless exactly the embedded migrations were applied.
+pub async fn check_schema(pool: &PgPool) -> StorageResult<()> {
+    let expected = expected_schema_version();
+    let Some(applied) = applied_migrations(pool).await.map_err(storage_err)? else {
+        return Err(StorageError::Backend(format!(
+            "Database schema is not initialized, run `scanner-cli schema` to migrate it to version {expected}."
+        )));
+    };
+    if let Some((version, _)) = applied.iter().find(|(_, success)| !success) {
+        return Err(StorageError::Backend(format!(
+            "Migration {version} did not complete, repair the database then run `scanner-cli schema`."
+        )));
+    }
+
+    let current = applied.last().map(|(v, _)| *v).unwrap_or_default();
+    if current > expected {
+        return Err(StorageError::Backend(format!(
+            "Database schema version {current} is newer than version {expected} of this binary, upgrade it."
+        )));
+    }
+    let applied: HashSet<i64> = applied.into_iter().map(|(v, _)| v).collect();
+    let pending: Vec<i64> = embedded_migrations()
+        .into_iter()
+        .filter(|v| !applied.contains(v))
+        .collect();
+    if !pending.is_empty() {
+        return Err(StorageError::Backend(format!(
+            "Database schema version {current} is behind version {expected} of this binary, migrations {pending:?} are pending, run `scanner-cli schema`."
+        )));
+    }
+    Ok(())
+}
+
+/// A pool on a fresh schema `name` of the database at `TEST_DATABASE_URL`, migrated to the
+/// embedded version, for the tests of the crates built on this one. `None` if the env var is
+/// not set, the test is then skipped.
+pub async fn test_pool(name: &str) -> Option<PgPool> {
+    use sqlx::postgres::PgConnectOptions;
+
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL is not set, skipping {name}");
+        return None;
+    };
+    let admin = PgPool::connect(&url).await.unwrap();
+    sqlx::query(&format!("DROP SCHEMA IF EXISTS {name} CASCADE"))
+        .execute(&admin)
+        .await
+        .unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {name}"))
+        .execute(&admin)
+        .await
+        .unwrap();
+    let options = url
+        .parse::<PgConnectOptions>()
+        .unwrap()
+        .options([("search_path", name)]);
+    let pool = pool_options("mainnet")
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .unwrap();
+    MIGRATOR.run(&pool).await.unwrap();
+    Some(pool)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_embedded_migrations() {
+        let versions = embedded_migrations();
+        assert!(versions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(expected_schema_version(), 20261019000012);
+        // every migration can be reverted with `schema --undo`.
+        assert!(MIGRATOR.iter().all(|m| m.migration_type.is_reversible()));
+    }
+
+    #[tokio::test]
+    async fn test_baseline_schema() -> StorageResult<()> {
+        let Some(pool) = test_pool("test_baseline_schema").await else {
+            return Ok(());
+        };
+        let migrate_err = |e: sqlx::migrate::MigrateError| StorageError::Backend(e.to_string());
+        // tables migrated by hand up to 11, without their records.
+        MIGRATOR
+            .undo(&pool, 20261019000011)
+            .await
+            .map_err(migrate_err)?;
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version>20261019000004")
+            .execute(&pool)
+            .await
+            .map_err(storage_err)?;
+        assert!(baseline_schema(20261019000013, &pool).await.is_err());
+        let recorded = baseline_schema(20261019000011, &pool).await?;
+        assert_eq!(recorded.first(), Some(&20261019000005));
+        assert_eq!(recorded.last(), Some(&20261019000011));
+        assert!(baseline_schema(20261019000011, &pool).await?.is_empty());
+        assert!(check_schema(&pool).await.is_err());
+        // the pending ones then run as usual.
+        MIGRATOR.run(&pool).await.map_err(migrate_err)?;
+        check_schema(&pool).await
+    }
+
+    #[tokio::test]
+    async fn test_load_stakers() -> Result<(), Error> {
+        let Some(pool) = test_pool("test_load_stakers").await else {
+            return Ok(());
+        };
+        let validator = "A".repeat(40);
+        save_delegation_tx(
+            "d",
+            "b",
+            "staker",
+            1,
+            &validator,
+            &validator,
+            1,
+            100,
+            &json!({}),
+            &pool,
+        )
+        .await?;
+        let event = |tx, op_index, staker, kind, new_staker, height| {
+            save_validator_event(
+                tx,
+                op_index,
+                "b",
+                &validator,
+                staker,
+                kind,
+                None,
+                None,
+                new_staker,
+                height,
+                height,
+                &Value::Null,
+                &pool,
+            )
+        };
+        // two ops of the same kind for the same validator in a tx.
+        event("u", 0, "staker", "UpdateStaker", None, 2).await?;
+        event("u", 1, "staker", "UpdateStaker", None, 2).await?;
+        event("r", 0, "staker", "ReplaceStaker", Some("staker2"), 3).await?;
+        let events: i64 = sqlx::query_scalar("SELECT count(*) FROM validator_events")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(events, 3);
+
+        let stakers = load_stakers(3, &pool).await?;
+        assert_eq!(stakers.len(), 1);
+        assert_eq!(stakers["staker"], validator);
+        let stakers = load_stakers(4, &pool).await?;
+        assert_eq!(stakers["staker2"], validator);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slashing_events() -> Result<(), Error> {
+        let Some(pool) = test_pool("test_slashing_events").await else {
+            return Ok(());
+        };
+        let validator = "A".repeat(40);
+        save_validator_event(
+            "c",
+            0,
+            "b",
+            &validator,
+            "staker",
+            "Create",
+            None,
+            None,
+            None,
+            1,
+            1,
+            &Value::Null,
+            &pool,
+        )
+        .await?;
+        ensure_partitions(&[4], &pool).await?;
+        sqlx::query("INSERT INTO block_generation VALUES(4,$1,2000,0,NULL,now())")
+            .bind(&validator)
+            .execute(&pool)
+            .await?;
+
+        let rate = json!([5, 100]);
+        let save = |tx, tx_index, validator: &'static str, power| {
+            let rate = rate.clone();
+            let pool = pool.clone();
+            async move {
+                save_slashing_event(
+                    "b",
+                    tx,
+                    tx_index,
+                    0,
+                    validator,
+                    "DuplicateVote",
+                    if tx.is_some() {
+                        "governance"
+                    } else {
+                        "evidence"
+                    },
+                    None,
+                    power,
+                    Some(&rate),
+                    5,
+                    5,
+                    &Value::Null,
+                    &pool,
+                )
+                .await
+            }
+        };
+        // two punishments of the staker at the same height, and evidence against another one.
+        save(Some("g1"), 0, "staker", None).await?;
+        save(Some("g2"), 1, "staker", None).await?;
+        save(None, -1, "BBBB", Some(300)).await?;
+        save(None, -1, "BBBB", Some(300)).await?;
+
+        let rows: Vec<(i64, Option<i64>, Option<i64>)> = sqlx::query_as(
+            "SELECT tx_index,power,penalty_amount FROM slashing_events ORDER BY tx_index",
+        )
+        .fetch_all(&pool)
+        .await?;
+        assert_eq!(
+            rows,
+            [
+                (-1, Some(300), Some(15)),
+                (0, Some(2000), Some(100)),
+                (1, Some(2000), Some(100)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tx_index_backfill() -> Result<(), Error> {
+        let Some(pool) = test_pool("test_tx_index_backfill").await else {
+            return Ok(());
+        };
+        // back to the schema before `tx_index`, with txs saved then.
+        MIGRATOR.undo(&pool, 20261019000004).await?;
+        sqlx::query("INSERT INTO block VALUES('B',5,0,3,now(),'','',$1)")
+            .bind(json!({"block": {"data": {"txs": ["o0", "o1", "o2"]}}}))
+            .execute(&pool)
+            .await?;
+        for (tx, origin) in [("t2", "o2"), ("t0", "o0")] {
+            sqlx::query("INSERT INTO transaction VALUES($1,'B',5,0,0,0,0,'','{}','',$2,'{}','{}')")
+                .bind(tx)
+                .bind(origin)
+                .execute(&pool)
+                .await?;
+        }
+        MIGRATOR.run(&pool).await?;
+
+        let rows: Vec<(String, i64)> =
+            sqlx::query_as("SELECT tx_hash,tx_index FROM transaction ORDER BY tx_hash")
+                .fetch_all(&pool)
+                .await?;
+        assert_eq!(rows, [("t0".to_string(), 0), ("t2".to_string(), 2)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,2797 @@
+//! `module::storage::Storage` over Postgres and SQLite, shared by the scanner, the prismer and
+//! the explorer.
+#[macro_use]
+extern crate log;
+
+pub mod db;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use module::config::scanner_config::Config;
+use module::schema::{
+    Block as ModuleBlock, PrismTxResult, Transaction, V2AssetTx, V2ClaimTx, V2ConvertAccountTx,
+    V2DelegationTx, V2MintTx, V2Operation, V2UndelegationTx, V2ValidatorEvent,
+};
+use module::storage::{
+    DailyMint, DailyUptime, SlashingRecord, TxDistribution, UptimeBucket, ValidatorRecord,
+};
+use serde_json::Value;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::PgRow;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow};
+use sqlx::{
+    types::Json, Database, Encode, FromRow, PgPool, Postgres, QueryBuilder, Sqlite,
+    SqliteConnection, SqlitePool, Type,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub use module::storage::{BlockRecord, Storage, StorageError, StorageResult, TxFilter};
+
+/// Open the configured storage. A `sqlite:` url opens a SQLite database, anything else
+/// Postgres, along with its pool for the maintenance only Postgres supports.
+pub async fn connect() -> StorageResult<(Arc<dyn Storage>, Option<PgPool>)> {
+    let config =
+        Config::load().map_err(|e| StorageError::Backend(format!("Invalid config: {e}")))?;
+    let url = config.database.url;
+    if url.starts_with("sqlite:") {
+        let storage = SqliteStorage::connect(&url).await.map_err(storage_err)?;
+        storage.pin_network(&config.network).await?;
+        Ok((Arc::new(storage), None))
+    } else {
+        let pool = db::connect().await?;
+        Ok((Arc::new(PgStorage::new(pool.clone())), Some(pool)))
+    }
+}
+
+pub(crate) fn storage_err(e: sqlx::Error) -> StorageError {
+    match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        e => StorageError::Backend(e.to_string()),
+    }
+}
+
+#[derive(FromRow)]
+struct BlockRow {
+    block_hash: String,
+    height: i64,
+    size: i64,
+    tx_count: i64,
+    time: NaiveDateTime,
+    app_hash: String,
+    proposer: String,
+    block_data: Json<Value>,
+}
+
+impl From<BlockRow> for BlockRecord {
+    fn from(r: BlockRow) -> Self {
+        BlockRecord {
+            block_hash: r.block_hash,
+            height: r.height,
+            size: r.size,
+            tx_count: r.tx_count,
+            time: r.time,
+            app_hash: r.app_hash,
+            proposer: r.proposer,
+            block_data: r.block_data.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct TxRow {
+    tx_hash: String,
+    block_hash: String,
+    height: i64,
+    tx_index: i64,
+    timestamp: i64,
+    ty: i32,
+    ty_sub: i32,
+    sender: String,
+    receiver: Json<Value>,
+    code: i64,
+    log: String,
+    origin: String,
+    result: Json<Value>,
+    value: Json<Value>,
+}
+
+impl From<TxRow> for Transaction {
+    fn from(r: TxRow) -> Self {
+        Transaction {
+            tx_hash: r.tx_hash,
+            block_hash: r.block_hash,
+            height: r.height,
+            tx_index: r.tx_index,
+            timestamp: r.timestamp,
+            ty: r.ty,
+            ty_sub: r.ty_sub,
+            sender: r.sender,
+            receiver: r.receiver.0,
+            code: r.code,
+            log: r.log,
+            origin: r.origin,
+            result: r.result.0,
+            value: r.value.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct OperationRow {
+    tx: String,
+    op_index: i32,
+    kind: String,
+    signer: String,
+    counterparty: String,
+    asset: Option<String>,
+    amount: Option<String>,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<OperationRow> for V2Operation {
+    fn from(r: OperationRow) -> Self {
+        V2Operation {
+            tx_hash: r.tx,
+            op_index: r.op_index,
+            kind: r.kind,
+            signer: r.signer,
+            counterparty: r.counterparty,
+            asset: r.asset,
+            amount: r.amount,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct PrismRow {
+    tx_hash: String,
+    block_hash: String,
+    sender: String,
+    receiver: String,
+    asset: String,
+    amount: String,
+    decimal: i32,
+    height: i64,
+    timestamp: i64,
+    value: Json<Value>,
+}
+
+impl From<PrismRow> for PrismTxResult {
+    fn from(r: PrismRow) -> Self {
+        PrismTxResult {
+            tx_hash: r.tx_hash,
+            block_hash: r.block_hash,
+            sender: r.sender,
+            receiver: r.receiver,
+            asset: r.asset,
+            amount: r.amount,
+            decimal: r.decimal as i64,
+            height: r.height,
+            timestamp: r.timestamp,
+            value: r.value.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct N2eRow {
+    tx: String,
+    block: String,
+    sender: String,
+    receiver: String,
+    asset: String,
+    amount: String,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<N2eRow> for V2ConvertAccountTx {
+    fn from(r: N2eRow) -> Self {
+        V2ConvertAccountTx {
+            tx_hash: r.tx,
+            block_hash: r.block,
+            sender: r.sender,
+            receiver: r.receiver,
+            asset: r.asset,
+            amount: r.amount,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ClaimRow {
+    tx: String,
+    block: String,
+    sender: String,
+    amount: i64,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<ClaimRow> for V2ClaimTx {
+    fn from(r: ClaimRow) -> Self {
+        V2ClaimTx {
+            tx_hash: r.tx,
+            block_hash: r.block,
+            sender: r.sender,
+            amount: r.amount,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct DelegationRow {
+    tx: String,
+    block: String,
+    sender: String,
+    amount: i64,
+    validator: String,
+    new_validator: String,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<DelegationRow> for V2DelegationTx {
+    fn from(r: DelegationRow) -> Self {
+        V2DelegationTx {
+            tx_hash: r.tx,
+            block_hash: r.block,
+            sender: r.sender,
+            amount: r.amount,
+            validator: r.validator,
+            new_validator: r.new_validator,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct UndelegationRow {
+    tx: String,
+    block: String,
+    sender: String,
+    amount: i64,
+    target_validator: String,
+    new_delegator: String,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<UndelegationRow> for V2UndelegationTx {
+    fn from(r: UndelegationRow) -> Self {
+        V2UndelegationTx {
+            tx_hash: r.tx,
+            block_hash: r.block,
+            sender: r.sender,
+            amount: r.amount,
+            target_validator: r.target_validator,
+            new_delegator: r.new_delegator,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct AssetRow {
+    asset: String,
+    tx: String,
+    block: String,
+    issuer: String,
+    height: i64,
+    timestamp: i64,
+    ty: i32,
+    content: Json<Value>,
+}
+
+impl From<AssetRow> for V2AssetTx {
+    fn from(r: AssetRow) -> Self {
+        V2AssetTx {
+            asset: r.asset,
+            tx_hash: r.tx,
+            block_hash: r.block,
+            issuer: r.issuer,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+            issued: r.ty,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct MintRow {
+    tx: String,
+    block: String,
+    idx: i32,
+    receiver: String,
+    amount: i64,
+    kind: String,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<MintRow> for V2MintTx {
+    fn from(r: MintRow) -> Self {
+        V2MintTx {
+            tx_hash: r.tx,
+            block_hash: r.block,
+            idx: r.idx,
+            receiver: r.receiver,
+            amount: r.amount,
+            kind: r.kind,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ValidatorEventRow {
+    tx: String,
+    op_index: i32,
+    block: String,
+    validator: String,
+    staker: String,
+    kind: String,
+    commission_rate: Option<Json<Value>>,
+    memo: Option<Json<Value>>,
+    new_staker: Option<String>,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<ValidatorEventRow> for V2ValidatorEvent {
+    fn from(r: ValidatorEventRow) -> Self {
+        V2ValidatorEvent {
+            tx_hash: r.tx,
+            op_index: r.op_index,
+            block_hash: r.block,
+            validator: r.validator,
+            staker: r.staker,
+            kind: r.kind,
+            commission_rate: r.commission_rate.map(|v| v.0),
+            memo: r.memo.map(|v| v.0),
+            new_staker: r.new_staker,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct SlashingRow {
+    block: String,
+    tx: Option<String>,
+    validator: String,
+    kind: String,
+    source: String,
+    evidence_height: Option<i64>,
+    power: Option<i64>,
+    penalty_rate: Option<Json<Value>>,
+    penalty_amount: Option<i64>,
+    height: i64,
+    timestamp: i64,
+    content: Json<Value>,
+}
+
+impl From<SlashingRow> for SlashingRecord {
+    fn from(r: SlashingRow) -> Self {
+        SlashingRecord {
+            block_hash: r.block,
+            tx_hash: r.tx,
+            validator: r.validator,
+            kind: r.kind,
+            source: r.source,
+            evidence_height: r.evidence_height,
+            power: r.power,
+            penalty_rate: r.penalty_rate.map(|v| v.0),
+            penalty_amount: r.penalty_amount,
+            height: r.height,
+            timestamp: r.timestamp,
+            content: r.content.0,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct UptimeBucketRow {
+    address: String,
+    first_height: i64,
+    last_height: i64,
+    signed: i64,
+    missed: i64,
+    proposed: i64,
+    leading_missed: i64,
+    trailing_missed: i64,
+    longest_missed: i64,
+}
+
+impl From<UptimeBucketRow> for UptimeBucket {
+    fn from(r: UptimeBucketRow) -> Self {
+        UptimeBucket {
+            address: r.address,
+            first_height: r.first_height,
+            last_height: r.last_height,
+            signed: r.signed,
+            missed: r.missed,
+            proposed: r.proposed,
+            leading_missed: r.leading_missed,
+            trailing_missed: r.trailing_missed,
+            longest_missed: r.longest_missed,
+        }
+    }
+}
+
+const BLOCK_COLUMNS: &str = "block_hash,height,size,tx_count,time,app_hash,proposer,block_data";
+const TX_COLUMNS: &str =
+    "tx_hash,block_hash,height,tx_index,timestamp,ty,ty_sub,sender,receiver,code,log,origin,result,value";
+const OPERATION_COLUMNS: &str =
+    "tx,op_index,kind,signer,counterparty,asset,amount,height,timestamp,content";
+const PRISM_COLUMNS: &str =
+    "tx_hash,block_hash,sender,receiver,asset,amount,decimal,height,timestamp,value";
+const N2E_COLUMNS: &str = "tx,block,sender,receiver,asset,amount,height,timestamp,content";
+const CLAIM_COLUMNS: &str = "tx,block,sender,amount,height,timestamp,content";
+const DELEGATION_COLUMNS: &str =
+    "tx,block,sender,amount,validator,new_validator,height,timestamp,content";
+const UNDELEGATION_COLUMNS: &str =
+    "tx,block,sender,amount,target_validator,new_delegator,height,timestamp,content";
+const ASSET_COLUMNS: &str = "asset,tx,block,issuer,height,timestamp,ty,content";
+const MINT_COLUMNS: &str = "tx,block,idx,receiver,amount,kind,height,timestamp,content";
+const VALIDATOR_EVENT_COLUMNS: &str =
+    "tx,op_index,block,validator,staker,kind,commission_rate,memo,new_staker,height,timestamp,content";
+const SLASHING_COLUMNS: &str =
+    "block,tx,validator,kind,source,evidence_height,power,penalty_rate,penalty_amount,height,timestamp,content";
+const UPTIME_BUCKET_COLUMNS: &str =
+    "address,first_height,last_height,signed,missed,proposed,leading_missed,trailing_missed,longest_missed";
+
+/// Rows of the network of the connection in Postgres, a SQLite database holds a single network.
+const PG_SCOPE: &str = "network=current_network()";
+const SQLITE_SCOPE: &str = "true";
+
+/// `columns` of the table aliased `alias`.
+fn columns_of(columns: &str, alias: &str) -> String {
+    columns
+        .split(',')
+        .map(|c| format!("{alias}.{c}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `scope` for the rows of the table aliased `alias`.
+fn scope_of(scope: &str, alias: &str) -> String {
+    if scope == SQLITE_SCOPE {
+        scope.to_string()
+    } else {
+        format!("{alias}.{scope}")
+    }
+}
+
+/// The condition on the role of an address in the participants aliased by the argument.
+type RoleCondition = fn(&str) -> String;
+
+/// The address filters of a listing.
+fn participant_filters(filter: &TxFilter) -> Vec<(&str, RoleCondition)> {
+    let mut filters: Vec<(&str, RoleCondition)> = vec![];
+    if let Some(from) = &filter.from {
+        filters.push((from, |p| format!(" AND {p}.role='sender'")));
+    }
+    if let Some(to) = &filter.to {
+        filters.push((to, |p| format!(" AND {p}.role<>'sender'")));
+    }
+    if let Some(address) = &filter.address {
+        filters.push((address, |_| String::new()));
+    }
+    filters
+}
+
+/// The tables and conditions of a tx listing both backends share, the transactions are
+/// aliased `t`. `scope` selects the rows of the network.
+///
+/// Listings of an address are driven by its rows of `tx_participants`, which keep the height
+/// and timestamp of the tx, a tx is listed once whatever roles the address has in it.
+fn push_tx_filter<'a, DB: Database>(
+    qb: &mut QueryBuilder<'a, DB>,
+    scope: &str,
+    filter: &'a TxFilter,
+) where
+    &'a str: Encode<'a, DB> + Type<DB>,
+    i32: Encode<'a, DB> + Type<DB>,
+    i64: Encode<'a, DB> + Type<DB>,
+{
+    let participants = participant_filters(filter);
+    let time = match participants.split_first() {
+        Some(((address, role), others)) => {
+            qb.push(format!(
+                " FROM tx_participants p JOIN \"transaction\" t ON t.tx_hash=p.tx AND t.height=p.height \
+                WHERE {} AND {} AND p.address=",
+                scope_of(scope, "p"),
+                scope_of(scope, "t"),
+            ))
+            .push_bind(*address)
+            .push(role("p"))
+            .push(format!(
+                " AND NOT EXISTS (SELECT 1 FROM tx_participants d WHERE {} AND d.tx=p.tx \
+                AND d.address=p.address AND d.role<p.role{})",
+                scope_of(scope, "d"),
+                role("d"),
+            ));
+            for (address, role) in others {
+                qb.push(format!(
+                    " AND EXISTS (SELECT 1 FROM tx_participants q WHERE {} AND q.tx=p.tx AND q.address=",
+                    scope_of(scope, "q"),
+                ))
+                .push_bind(*address)
+                .push(role("q"))
+                .push(")");
+            }
+            "p"
+        }
+        None => {
+            qb.push(format!(
+                " FROM \"transaction\" t WHERE {}",
+                scope_of(scope, "t")
+            ));
+            "t"
+        }
+    };
+    if let Some(ty) = filter.ty {
+        qb.push(" AND t.ty=").push_bind(ty);
+    }
+    if let Some(height) = filter.height {
+        qb.push(format!(" AND {time}.height=")).push_bind(height);
+    }
+    if let Some(start_time) = filter.start_time {
+        qb.push(format!(" AND {time}.timestamp>="))
+            .push_bind(start_time);
+    }
+    if let Some(end_time) = filter.end_time {
+        qb.push(format!(" AND {time}.timestamp<="))
+            .push_bind(end_time);
+    }
+}
+
+/// Txs are listed in chain order within a block, the latest block first, following the
+/// participant rows of an address listing.
+fn tx_order(filter: &TxFilter) -> &'static str {
+    if participant_filters(filter).is_empty() {
+        " ORDER BY t.height DESC, t.tx_index ASC"
+    } else {
+        " ORDER BY p.height DESC, t.tx_index ASC"
+    }
+}
+
+/// The rows of the network, of `column` equal to the value where one is set.
+fn push_conditions<'a, DB: Database>(
+    qb: &mut QueryBuilder<'a, DB>,
+    scope: &str,
+    conditions: &[(&str, Option<&'a str>)],
+) where
+    &'a str: Encode<'a, DB> + Type<DB>,
+{
+    qb.push(format!(" WHERE {scope}"));
+    for (column, value) in conditions {
+        if let Some(value) = value {
+            qb.push(format!(" AND {column}=")).push_bind(*value);
+        }
+    }
+}
+
+/// Slashing events of the network, of `validator` if set. Governance punishments are recorded
+/// under the staker, evidence under the validator address.
+fn push_slashing_filter<'a, DB: Database>(
+    qb: &mut QueryBuilder<'a, DB>,
+    scope: &str,
+    validator: Option<&'a str>,
+) where
+    &'a str: Encode<'a, DB> + Type<DB>,
+{
+    qb.push(format!(" WHERE {scope}"));
+    if let Some(validator) = validator {
+        qb.push(" AND (validator=")
+            .push_bind(validator)
+            .push(format!(
+                " OR validator IN (SELECT staker FROM validator_events WHERE {scope} AND validator="
+            ))
+            .push_bind(validator)
+            .push("))");
+    }
+}
+
+/// Unix time bounds on `timestamp`, both included.
+fn push_time_bounds<'a, DB: Database>(
+    qb: &mut QueryBuilder<'a, DB>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) where
+    i64: Encode<'a, DB> + Type<DB>,
+{
+    if let Some(start_time) = start_time {
+        qb.push(" AND timestamp>=").push_bind(start_time);
+    }
+    if let Some(end_time) = end_time {
+        qb.push(" AND timestamp<=").push_bind(end_time);
+    }
+}
+
+/// Distinct native and evm addresses within the time bounds.
+fn address_counts_query<'a, DB: Database>(
+    scope: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> QueryBuilder<'a, DB>
+where
+    i64: Encode<'a, DB> + Type<DB>,
+{
+    let mut qb = QueryBuilder::new("SELECT ");
+    for (i, table) in ["native_addrs", "evm_addrs"].into_iter().enumerate() {
+        if i > 0 {
+            qb.push(",");
+        }
+        qb.push(format!(
+            "(SELECT count(DISTINCT address) FROM {table} WHERE {scope}"
+        ));
+        push_time_bounds(&mut qb, start_time, end_time);
+        qb.push(")");
+    }
+    qb
+}
+
+/// Tx counts by kind, see `TxDistribution`.
+fn tx_distribution_sql(scope: &str) -> String {
+    format!(
+        "SELECT (SELECT count(*) FROM \"transaction\" WHERE {scope} AND ty=0),\
+        (SELECT count(*) FROM \"transaction\" WHERE {scope} AND ty_sub IN (2,3,4)),\
+        (SELECT count(*) FROM \"transaction\" WHERE {scope} AND ty=1),\
+        (SELECT count(*) FROM n2e WHERE {scope}),(SELECT count(*) FROM e2n WHERE {scope})"
+    )
+}
+
+/// The total and the daily rows of the mints within the time bounds, `day` formats the unix
+/// time of the mints as a UTC date.
+fn daily_mints_queries<'a, DB: Database>(
+    scope: &str,
+    day: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> (QueryBuilder<'a, DB>, QueryBuilder<'a, DB>)
+where
+    i64: Encode<'a, DB> + Type<DB>,
+{
+    // minted over the same range as the daily rows.
+    let mut total = QueryBuilder::new(format!(
+        "SELECT CAST(COALESCE(sum(amount),0) AS bigint) FROM mints WHERE {scope}"
+    ));
+    push_time_bounds(&mut total, start_time, end_time);
+    let mut daily = QueryBuilder::new(format!(
+        "SELECT {day} AS date,CAST(sum(amount) AS bigint),count(*) FROM mints WHERE {scope}"
+    ));
+    push_time_bounds(&mut daily, start_time, end_time);
+    daily.push(" GROUP BY date ORDER BY date DESC");
+    (total, daily)
+}
+
+/// The validator at `address` from its key and the staker, commission rate, memo and unix
+/// time of its latest event carrying metadata.
+/// The staker, commission rate, memo and time of the latest event carrying metadata.
+type ValidatorMeta = (String, Option<Json<Value>>, Option<Json<Value>>, i64);
+
+fn validator_record(
+    address: &str,
+    pubkey: Option<String>,
+    meta: Option<ValidatorMeta>,
+) -> StorageResult<ValidatorRecord> {
+    if pubkey.is_none() && meta.is_none() {
+        return Err(StorageError::NotFound);
+    }
+    let (staker, commission_rate, memo, updated_at) = match meta {
+        Some((staker, commission_rate, memo, updated_at)) => (
+            Some(staker),
+            commission_rate.map(|v| v.0),
+            memo.map(|v| v.0),
+            Some(updated_at),
+        ),
+        None => (None, None, None, None),
+    };
+    Ok(ValidatorRecord {
+        address: address.to_string(),
+        pubkey,
+        staker,
+        commission_rate,
+        memo,
+        updated_at,
+    })
+}
+
+fn daily_uptime((day, signed, missed, proposed): (String, i64, i64, i64)) -> DailyUptime {
+    DailyUptime {
+        day,
+        signed,
+        missed,
+        proposed,
+    }
+}
+
+fn tx_distribution((native, privacy, evm, n2e, e2n): (i64, i64, i64, i64, i64)) -> TxDistribution {
+    TxDistribution {
+        native,
+        privacy,
+        evm,
+        n2e,
+        e2n,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// postgres
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// Height bounds of the time range of a tx listing, so only the partitions of that range are
+/// scanned.
+fn push_height_bounds(qb: &mut QueryBuilder<Postgres>, filter: &TxFilter) {
+    if let Some(start_time) = filter.start_time {
+        qb.push(" AND t.height>=(SELECT min(height) FROM block WHERE network=current_network() AND time>=to_timestamp(")
+            .push_bind(start_time)
+            .push(") AT TIME ZONE 'UTC')");
+    }
+    if let Some(end_time) = filter.end_time {
+        qb.push(" AND t.height<=(SELECT max(height) FROM block WHERE network=current_network() AND time<=to_timestamp(")
+            .push_bind(end_time)
+            .push(") AT TIME ZONE 'UTC')");
+    }
+}
+
+/// The Postgres schema of `module/migrations`, written with `db`.
+pub struct PgStorage {
+    pool: PgPool,
+}
+
+impl PgStorage {
+    pub fn new(pool: PgPool) -> Self {
+        PgStorage { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// A page of the rows of `table` `filter` selects, in `order`, along with their count.
+    async fn page<'a, R, T>(
+        &self,
+        table: &str,
+        columns: &str,
+        filter: impl Fn(&mut QueryBuilder<'a, Postgres>),
+        order: &str,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<T>)>
+    where
+        R: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        T: From<R>,
+    {
+        let mut qb = QueryBuilder::new(format!("SELECT count(*) FROM {table}"));
+        filter(&mut qb);
+        let total: i64 = qb
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+
+        let mut qb = QueryBuilder::new(format!("SELECT {columns} FROM {table}"));
+        filter(&mut qb);
+        qb.push(format!(" ORDER BY {order} LIMIT "))
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows: Vec<R> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((total, rows.into_iter().map(Into::into).collect()))
+    }
+
+    /// The row of `table` of the tx `hash`.
+    async fn by_tx<R, T>(&self, table: &str, columns: &str, hash: &str) -> StorageResult<T>
+    where
+        R: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        T: From<R>,
+    {
+        let sql = format!("SELECT {columns} FROM {table} WHERE {PG_SCOPE} AND tx=$1");
+        let row: R = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+}
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn save_block(&self, block: ModuleBlock) -> StorageResult<()> {
+        db::save(block, &self.pool).await.map_err(storage_err)
+    }
+
+    async fn save_blocks(&self, blocks: &[ModuleBlock]) -> StorageResult<()> {
+        db::save_bulk(blocks, &self.pool).await.map_err(storage_err)
+    }
+
+    async fn save_last_height(&self, height: i64) -> StorageResult<()> {
+        db::save_last_height(height, &self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn load_last_height(&self) -> StorageResult<i64> {
+        db::load_last_height(&self.pool).await.map_err(storage_err)
+    }
+
+    async fn load_quarantined_heights(&self) -> StorageResult<Vec<i64>> {
+        db::load_quarantined_heights(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn stakers(&self, height: i64) -> StorageResult<HashMap<String, String>> {
+        db::load_stakers(height, &self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn save_prism_txs(&self, txs: Vec<PrismTxResult>) -> StorageResult<()> {
+        db::save_prism_txs(txs, &self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn save_prism_last_height(&self, height: i64) -> StorageResult<()> {
+        db::save_prism_last_height(height, &self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn load_prism_last_height(&self) -> StorageResult<i64> {
+        db::load_prism_last_height(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn block_by_height(&self, height: i64) -> StorageResult<BlockRecord> {
+        let sql = format!("SELECT {BLOCK_COLUMNS} FROM block WHERE {PG_SCOPE} AND height=$1");
+        let row: BlockRow = sqlx::query_as(&sql)
+            .bind(height)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn block_by_hash(&self, hash: &str) -> StorageResult<BlockRecord> {
+        // the height of the hash prunes the other partitions.
+        let sql = format!(
+            "SELECT {BLOCK_COLUMNS} FROM block WHERE {PG_SCOPE} AND height=\
+            (SELECT height FROM block_heights WHERE {PG_SCOPE} AND block_hash=$1)"
+        );
+        let row: BlockRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn blocks(&self, limit: i64, offset: i64) -> StorageResult<(i64, Vec<BlockRecord>)> {
+        let total: Option<i64> =
+            sqlx::query_scalar(&format!("SELECT max(height) FROM block WHERE {PG_SCOPE}"))
+                .fetch_one(&self.pool)
+                .await
+                .map_err(storage_err)?;
+        let sql =
+            format!("SELECT {BLOCK_COLUMNS} FROM block WHERE {PG_SCOPE} ORDER BY height DESC LIMIT $1 OFFSET $2");
+        let rows: Vec<BlockRow> = sqlx::query_as(&sql)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((
+            total.unwrap_or_default(),
+            rows.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    async fn tx_origins(&self, height: i64) -> StorageResult<Vec<String>> {
+        sqlx::query_scalar(&format!(
+            "SELECT origin FROM (SELECT origin,tx_index FROM transaction WHERE {PG_SCOPE} AND height=$1 \
+            UNION ALL SELECT origin,tx_index FROM quarantined_txs WHERE {PG_SCOPE} AND height=$1) t ORDER BY tx_index",
+        ))
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)
+    }
+
+    async fn commit_signatures(&self, height: i64) -> StorageResult<Vec<(String, String)>> {
+        db::commit_signatures(height, &self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn tx_by_hash(&self, hash: &str) -> StorageResult<Transaction> {
+        let sql = format!(
+            "SELECT {TX_COLUMNS} FROM transaction WHERE {PG_SCOPE} AND tx_hash=$1 AND height=\
+            (SELECT height FROM tx_heights WHERE {PG_SCOPE} AND tx_hash=$1)"
+        );
+        let row: TxRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn txs(
+        &self,
+        filter: &TxFilter,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<Transaction>)> {
+        let total = self.tx_count(filter).await?;
+
+        let mut qb = QueryBuilder::new(format!("SELECT {}", columns_of(TX_COLUMNS, "t")));
+        push_tx_filter(&mut qb, PG_SCOPE, filter);
+        push_height_bounds(&mut qb, filter);
+        qb.push(tx_order(filter))
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows: Vec<TxRow> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((total, rows.into_iter().map(Into::into).collect()))
+    }
+
+    async fn tx_operations(&self, hash: &str) -> StorageResult<Vec<V2Operation>> {
+        let sql =
+            format!("SELECT {OPERATION_COLUMNS} FROM operations WHERE {PG_SCOPE} AND tx=$1 ORDER BY op_index");
+        let rows: Vec<OperationRow> = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn prism_tx_by_hash(&self, hash: &str) -> StorageResult<PrismTxResult> {
+        let sql = format!("SELECT {PRISM_COLUMNS} FROM e2n WHERE {PG_SCOPE} AND tx_hash=$1");
+        let row: PrismRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn prism_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<PrismTxResult>)> {
+        self.page::<PrismRow, _>(
+            "e2n",
+            PRISM_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("sender", from), ("receiver", to)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn n2e_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ConvertAccountTx>)> {
+        self.page::<N2eRow, _>(
+            "n2e",
+            N2E_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("sender", from), ("receiver", to)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn n2e_tx_by_hash(&self, hash: &str) -> StorageResult<V2ConvertAccountTx> {
+        self.by_tx::<N2eRow, _>("n2e", N2E_COLUMNS, hash).await
+    }
+
+    async fn claims(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ClaimTx>)> {
+        self.page::<ClaimRow, _>(
+            "claims",
+            CLAIM_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn claim_by_hash(&self, hash: &str) -> StorageResult<V2ClaimTx> {
+        self.by_tx::<ClaimRow, _>("claims", CLAIM_COLUMNS, hash)
+            .await
+    }
+
+    async fn delegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2DelegationTx>)> {
+        self.page::<DelegationRow, _>(
+            "delegations",
+            DELEGATION_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn delegation_by_hash(&self, hash: &str) -> StorageResult<V2DelegationTx> {
+        self.by_tx::<DelegationRow, _>("delegations", DELEGATION_COLUMNS, hash)
+            .await
+    }
+
+    async fn undelegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2UndelegationTx>)> {
+        self.page::<UndelegationRow, _>(
+            "undelegations",
+            UNDELEGATION_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn undelegation_by_hash(&self, hash: &str) -> StorageResult<V2UndelegationTx> {
+        self.by_tx::<UndelegationRow, _>("undelegations", UNDELEGATION_COLUMNS, hash)
+            .await
+    }
+
+    async fn assets(
+        &self,
+        asset: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2AssetTx>)> {
+        self.page::<AssetRow, _>(
+            "assets",
+            ASSET_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("asset", asset)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn mints(
+        &self,
+        receiver: Option<&str>,
+        kind: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2MintTx>)> {
+        self.page::<MintRow, _>(
+            "mints",
+            MINT_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("receiver", receiver), ("kind", kind)]),
+            "height DESC, idx",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn daily_mints(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, Vec<DailyMint>)> {
+        let (mut total, mut daily) = daily_mints_queries::<Postgres>(
+            PG_SCOPE,
+            "to_char(to_timestamp(timestamp) AT TIME ZONE 'UTC','YYYY-MM-DD')",
+            start_time,
+            end_time,
+        );
+        let total: i64 = total
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        let rows: Vec<(String, i64, i64)> = daily
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((
+            total,
+            rows.into_iter()
+                .map(|(date, amount, count)| DailyMint {
+                    date,
+                    amount,
+                    count,
+                })
+                .collect(),
+        ))
+    }
+
+    async fn validator(&self, address: &str) -> StorageResult<ValidatorRecord> {
+        let pubkey: Option<String> = sqlx::query_scalar(
+            "SELECT pubkey FROM validators WHERE network=current_network() AND address=$1",
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(storage_err)?;
+
+        // the latest event carrying metadata holds the current commission rate and memo.
+        let meta: Option<ValidatorMeta> = sqlx::query_as(
+            "SELECT staker,commission_rate,memo,timestamp FROM validator_events \
+                WHERE network=current_network() AND validator=$1 AND memo IS NOT NULL \
+                ORDER BY height DESC LIMIT 1",
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(storage_err)?;
+
+        let mut record = validator_record(address, pubkey, meta)?;
+        // follow staker replacements issued after the metadata was set.
+        if let (Some(staker), Some(updated_at)) = (&record.staker, record.updated_at) {
+            let replaced: Option<Option<String>> = sqlx::query_scalar(
+                "SELECT new_staker FROM validator_events \
+                WHERE network=current_network() AND staker=$1 AND kind='ReplaceStaker' \
+                AND timestamp>=$2 ORDER BY height DESC LIMIT 1",
+            )
+            .bind(staker)
+            .bind(updated_at)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_err)?;
+            if let Some(new_staker) = replaced {
+                record.staker = new_staker;
+            }
+        }
+        Ok(record)
+    }
+
+    async fn validator_events(
+        &self,
+        address: &str,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ValidatorEvent>)> {
+        self.page::<ValidatorEventRow, _>(
+            "validator_events",
+            VALIDATOR_EVENT_COLUMNS,
+            |qb| push_conditions(qb, PG_SCOPE, &[("validator", Some(address))]),
+            "height DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn slashing_events(
+        &self,
+        validator: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<SlashingRecord>)> {
+        self.page::<SlashingRow, _>(
+            "slashing_events",
+            SLASHING_COLUMNS,
+            |qb| push_slashing_filter(qb, PG_SCOPE, validator),
+            "height DESC,tx_index,op_index",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn uptime_buckets(
+        &self,
+        addresses: &[String],
+        start: i64,
+    ) -> StorageResult<Vec<UptimeBucket>> {
+        let sql = format!(
+            "SELECT {UPTIME_BUCKET_COLUMNS} FROM validator_uptime_buckets \
+            WHERE {PG_SCOPE} AND address=ANY($1) AND bucket>=$2 ORDER BY address,bucket"
+        );
+        let rows: Vec<UptimeBucketRow> = sqlx::query_as(&sql)
+            .bind(addresses)
+            .bind(start)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn uptime_validators(
+        &self,
+        start: i64,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<String>)> {
+        let total: i64 = sqlx::query_scalar(
+            "SELECT count(DISTINCT address) FROM validator_uptime_buckets \
+            WHERE network=current_network() AND bucket>=$1",
+        )
+        .bind(start)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        let addresses = sqlx::query_scalar(
+            "SELECT DISTINCT address FROM validator_uptime_buckets \
+            WHERE network=current_network() AND bucket>=$1 ORDER BY address LIMIT $2 OFFSET $3",
+        )
+        .bind(start)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        Ok((total, addresses))
+    }
+
+    async fn daily_uptime(&self, address: &str, days: i64) -> StorageResult<Vec<DailyUptime>> {
+        let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT day::text,signed,missed,proposed FROM validator_uptime_daily \
+            WHERE network=current_network() AND address=$1 ORDER BY day DESC LIMIT $2",
+        )
+        .bind(address)
+        .bind(days)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        Ok(rows.into_iter().map(daily_uptime).collect())
+    }
+
+    async fn tx_count(&self, filter: &TxFilter) -> StorageResult<i64> {
+        let mut qb = QueryBuilder::new("SELECT count(*)");
+        push_tx_filter(&mut qb, PG_SCOPE, filter);
+        push_height_bounds(&mut qb, filter);
+        qb.build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn tx_distribution(&self) -> StorageResult<TxDistribution> {
+        let row: (i64, i64, i64, i64, i64) = sqlx::query_as(&tx_distribution_sql(PG_SCOPE))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(tx_distribution(row))
+    }
+
+    async fn address_counts(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, i64)> {
+        address_counts_query::<Postgres>(PG_SCOPE, start_time, end_time)
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn load_price(&self, name: &str) -> StorageResult<String> {
+        sqlx::query_scalar("SELECT price FROM prices WHERE name=$1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn save_price(&self, name: &str, price: &str) -> StorageResult<()> {
+        sqlx::query("INSERT INTO prices VALUES($1,$2) ON CONFLICT(name) DO UPDATE SET price=$2")
+            .bind(name)
+            .bind(price)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn load_market(&self, name: &str) -> StorageResult<Value> {
+        let market: Json<Value> = sqlx::query_scalar("SELECT val FROM market WHERE name=$1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(market.0)
+    }
+
+    async fn save_market(&self, name: &str, market: &Value) -> StorageResult<()> {
+        sqlx::query("INSERT INTO market VALUES($1,$2) ON CONFLICT(name) DO UPDATE SET val=$2")
+            .bind(name)
+            .bind(Json(market))
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+// sqlite
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// The schema of `storage/sqlite/migrations`, embedded at build time. Numbered on their own,
+/// they don't follow the Postgres versions.
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./sqlite/migrations");
+
+/// A SQLite database for tests and small deployments, holding a single network.
+///
+/// It keeps the tables of Postgres but for the daily uptime, counted from `block_generation`
+/// when read, and the ones of the maintenance only Postgres supports: prune, migrate.
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Open, or create, the database at `url` and bring its schema up to date.
+    /// `sqlite::memory:` opens a private in-memory database.
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal);
+        // every connection to `:memory:` opens a database of its own, keep a single one alive.
+        let pool = if url.contains(":memory:") {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?
+        } else {
+            SqlitePoolOptions::new().connect_with(options).await?
+        };
+        SQLITE_MIGRATOR.run(&pool).await?;
+        Ok(SqliteStorage { pool })
+    }
+
+    pub async fn memory() -> Result<Self, sqlx::Error> {
+        Self::connect("sqlite::memory:").await
+    }
+
+    /// Pin the database to `network`, the one it was first opened for, and fail for any other
+    /// since its rows don't carry their network.
+    pub async fn pin_network(&self, network: &str) -> StorageResult<()> {
+        sqlx::query("INSERT INTO network SELECT ? WHERE NOT EXISTS (SELECT 1 FROM network)")
+            .bind(network)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        let pinned: String = sqlx::query_scalar("SELECT name FROM network")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        if pinned != network {
+            return Err(StorageError::Backend(format!(
+                "the SQLite database holds network `{pinned}`, not `{network}`"
+            )));
+        }
+        Ok(())
+    }
+
+    /// A page of the rows of `table` `filter` selects, in `order`, along with their count.
+    async fn page<'a, R, T>(
+        &self,
+        table: &str,
+        columns: &str,
+        filter: impl Fn(&mut QueryBuilder<'a, Sqlite>),
+        order: &str,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<T>)>
+    where
+        R: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+        T: From<R>,
+    {
+        let mut qb = QueryBuilder::new(format!("SELECT count(*) FROM {table}"));
+        filter(&mut qb);
+        let total: i64 = qb
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+
+        let mut qb = QueryBuilder::new(format!("SELECT {columns} FROM {table}"));
+        filter(&mut qb);
+        qb.push(format!(" ORDER BY {order} LIMIT "))
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows: Vec<R> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((total, rows.into_iter().map(Into::into).collect()))
+    }
+
+    /// The row of `table` of the tx `hash`.
+    async fn by_tx<R, T>(&self, table: &str, columns: &str, hash: &str) -> StorageResult<T>
+    where
+        R: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+        T: From<R>,
+    {
+        let sql = format!("SELECT {columns} FROM {table} WHERE tx=?");
+        let row: R = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+}
+
+/// Replace the records at the height of `block`.
+async fn sqlite_save(block: &ModuleBlock, conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    for table in [
+        "\"transaction\"",
+        "operations",
+        "tx_participants",
+        "quarantined_txs",
+        "n2e",
+        "delegations",
+        "undelegations",
+        "claims",
+        "assets",
+        "validator_events",
+        "mints",
+        "slashing_events",
+        "block_generation",
+    ] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE height=?"))
+            .bind(block.height)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query(&format!(
+        "INSERT OR REPLACE INTO block({BLOCK_COLUMNS}) VALUES (?,?,?,?,?,?,?,?)"
+    ))
+    .bind(&block.block_hash)
+    .bind(block.height)
+    .bind(block.size)
+    .bind(block.tx_count)
+    .bind(block.timestamp)
+    .bind(&block.app_hash)
+    .bind(&block.proposer)
+    .bind(Json(&block.block_data))
+    .execute(&mut *conn)
+    .await?;
+
+    for tx in block.txs.iter().chain(&block.evm_txs) {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO \"transaction\"({TX_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(tx.height)
+        .bind(tx.tx_index)
+        .bind(tx.timestamp)
+        .bind(tx.ty)
+        .bind(tx.ty_sub)
+        .bind(&tx.sender)
+        .bind(Json(&tx.receiver))
+        .bind(tx.code)
+        .bind(&tx.log)
+        .bind(&tx.origin)
+        .bind(Json(&tx.result))
+        .bind(Json(&tx.value))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for op in &block.v2_operations {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO operations({OPERATION_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&op.tx_hash)
+        .bind(op.op_index)
+        .bind(&op.kind)
+        .bind(&op.signer)
+        .bind(&op.counterparty)
+        .bind(&op.asset)
+        .bind(&op.amount)
+        .bind(op.height)
+        .bind(op.timestamp)
+        .bind(Json(&op.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for p in &block.tx_participants {
+        sqlx::query("INSERT OR IGNORE INTO tx_participants VALUES (?,?,?,?,?)")
+            .bind(&p.tx_hash)
+            .bind(&p.address)
+            .bind(&p.role)
+            .bind(p.height)
+            .bind(p.timestamp)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    for q in &block.quarantined_txs {
+        sqlx::query("INSERT OR REPLACE INTO quarantined_txs VALUES (?,?,?,?,?,?,?,?)")
+            .bind(&q.tx_hash)
+            .bind(&q.block_hash)
+            .bind(q.height)
+            .bind(q.tx_index)
+            .bind(&q.origin)
+            .bind(&q.stage)
+            .bind(&q.error)
+            .bind(q.timestamp)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlite_save_staking(block, conn).await?;
+    sqlite_save_validators(block, conn).await
+}
+
+/// Replace the staking, asset and mint records at the height of `block`.
+async fn sqlite_save_staking(
+    block: &ModuleBlock,
+    conn: &mut SqliteConnection,
+) -> Result<(), sqlx::Error> {
+    for tx in &block.v2_convert_account_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO n2e({N2E_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(&tx.sender)
+        .bind(&tx.receiver)
+        .bind(&tx.asset)
+        .bind(&tx.amount)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for tx in &block.v2_delegation_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO delegations({DELEGATION_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(&tx.sender)
+        .bind(tx.amount)
+        .bind(&tx.validator)
+        .bind(&tx.new_validator)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for tx in &block.v2_undelegation_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO undelegations({UNDELEGATION_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(&tx.sender)
+        .bind(tx.amount)
+        .bind(&tx.target_validator)
+        .bind(&tx.new_delegator)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for tx in &block.v2_claim_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO claims({CLAIM_COLUMNS}) VALUES (?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(&tx.sender)
+        .bind(tx.amount)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for tx in &block.v2_asset_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO assets({ASSET_COLUMNS}) VALUES (?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.asset)
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(&tx.issuer)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(tx.issued)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for ev in &block.v2_validator_events {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO validator_events({VALIDATOR_EVENT_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&ev.tx_hash)
+        .bind(ev.op_index)
+        .bind(&ev.block_hash)
+        .bind(&ev.validator)
+        .bind(&ev.staker)
+        .bind(&ev.kind)
+        .bind(ev.commission_rate.as_ref().map(Json))
+        .bind(ev.memo.as_ref().map(Json))
+        .bind(&ev.new_staker)
+        .bind(ev.height)
+        .bind(ev.timestamp)
+        .bind(Json(&ev.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for tx in &block.v2_mint_txs {
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO mints({MINT_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?)"
+        ))
+        .bind(&tx.tx_hash)
+        .bind(&tx.block_hash)
+        .bind(tx.idx)
+        .bind(&tx.receiver)
+        .bind(tx.amount)
+        .bind(&tx.kind)
+        .bind(tx.height)
+        .bind(tx.timestamp)
+        .bind(Json(&tx.content))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Replace the validators, signatures, slashing events and addresses at the height of
+/// `block`, and recount its uptime bucket.
+async fn sqlite_save_validators(
+    block: &ModuleBlock,
+    conn: &mut SqliteConnection,
+) -> Result<(), sqlx::Error> {
+    for v in &block.validators {
+        sqlx::query("INSERT OR REPLACE INTO validators VALUES (?,0,?)")
+            .bind(&v.address)
+            .bind(&v.pub_key.value)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO block_generation VALUES (?,?,?,?,?,?)")
+            .bind(block.height)
+            .bind(&v.address)
+            .bind(v.power as i64)
+            .bind(v.priority)
+            .bind(&v.signature)
+            .bind(v.timestamp)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // as in `db::save_slashing_event`, governance ops don't carry the power of the punished
+    // validator, it is taken from the last block it signed.
+    for ev in &block.v2_slashing_events {
+        sqlx::query(
+            "INSERT OR REPLACE INTO slashing_events(block,tx,validator,kind,source,evidence_height,power,\
+            penalty_rate,height,timestamp,content,tx_index,op_index,penalty_amount) \
+            SELECT ?1,?2,?3,?4,?5,?6,p.power,?8,?9,?10,?11,?12,?13,\
+            p.power*json_extract(?8,'$[0]')/NULLIF(json_extract(?8,'$[1]'),0) \
+            FROM (SELECT COALESCE(?7,(SELECT g.power FROM block_generation g WHERE g.height<=?9 \
+            AND g.address=COALESCE((SELECT v.validator FROM validator_events v WHERE (v.staker=?3 \
+            OR v.new_staker=?3) AND v.validator<>'' AND v.height<=?9 ORDER BY v.height DESC LIMIT 1),?3) \
+            ORDER BY g.height DESC LIMIT 1)) AS power) p",
+        )
+        .bind(&ev.block_hash)
+        .bind(&ev.tx_hash)
+        .bind(&ev.validator)
+        .bind(&ev.kind)
+        .bind(&ev.source)
+        .bind(ev.evidence_height)
+        .bind(ev.power)
+        .bind(ev.penalty_rate.as_ref().map(Json))
+        .bind(ev.height)
+        .bind(ev.timestamp)
+        .bind(Json(&ev.content))
+        .bind(ev.tx_index)
+        .bind(ev.op_index)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for (table, addrs) in [
+        ("native_addrs", &block.native_addrs),
+        ("evm_addrs", &block.evm_addrs),
+    ] {
+        for addr in addrs {
+            sqlx::query(&format!("INSERT OR IGNORE INTO {table} VALUES (?,?,?)"))
+                .bind(&addr.tx)
+                .bind(&addr.address)
+                .bind(addr.timestamp)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    // the missed runs of the bucket, its leading, trailing and longest ones are kept, as in
+    // `db::save_uptime_buckets`.
+    sqlx::query(
+        "WITH g AS (SELECT bg.address,bg.height,bg.signature IS NULL AS miss,\
+        coalesce(blk.proposer=bg.address,0) AS proposed FROM block_generation bg \
+        LEFT JOIN block blk ON blk.height=bg.height WHERE bg.height>=?1*?2 AND bg.height<(?1+1)*?2), \
+        runs AS (SELECT address,min(height) AS first,max(height) AS last,count(*) AS len FROM \
+        (SELECT address,height,height-row_number() OVER (PARTITION BY address ORDER BY height) AS grp \
+        FROM g WHERE miss) m GROUP BY address,grp), \
+        s AS (SELECT address,min(height) AS first_height,max(height) AS last_height,\
+        sum(NOT miss) AS signed,sum(miss) AS missed,sum(proposed) AS proposed FROM g GROUP BY address) \
+        INSERT INTO validator_uptime_buckets(address,bucket,first_height,last_height,signed,missed,\
+        proposed,leading_missed,trailing_missed,longest_missed) \
+        SELECT s.address,?1,s.first_height,s.last_height,s.signed,s.missed,s.proposed,\
+        coalesce((SELECT len FROM runs r WHERE r.address=s.address AND r.first=s.first_height),0),\
+        coalesce((SELECT len FROM runs r WHERE r.address=s.address AND r.last=s.last_height),0),\
+        coalesce((SELECT max(len) FROM runs r WHERE r.address=s.address),0) FROM s WHERE true \
+        ON CONFLICT(address,bucket) DO UPDATE SET first_height=excluded.first_height,\
+        last_height=excluded.last_height,signed=excluded.signed,missed=excluded.missed,\
+        proposed=excluded.proposed,leading_missed=excluded.leading_missed,\
+        trailing_missed=excluded.trailing_missed,longest_missed=excluded.longest_missed",
+    )
+    .bind(block.height / db::UPTIME_BUCKET)
+    .bind(db::UPTIME_BUCKET)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn save_block(&self, block: ModuleBlock) -> StorageResult<()> {
+        self.save_blocks(&[block]).await
+    }
+
+    async fn save_blocks(&self, blocks: &[ModuleBlock]) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await.map_err(storage_err)?;
+        for block in blocks {
+            sqlite_save(block, &mut tx).await.map_err(storage_err)?;
+        }
+        tx.commit().await.map_err(storage_err)
+    }
+
+    async fn save_last_height(&self, height: i64) -> StorageResult<()> {
+        sqlx::query("INSERT OR REPLACE INTO last_height VALUES ('tip', ?)")
+            .bind(height)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn load_last_height(&self) -> StorageResult<i64> {
+        sqlx::query_scalar("SELECT height FROM last_height")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn load_quarantined_heights(&self) -> StorageResult<Vec<i64>> {
+        sqlx::query_scalar("SELECT DISTINCT height FROM quarantined_txs ORDER BY height")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn stakers(&self, height: i64) -> StorageResult<HashMap<String, String>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT staker,validator FROM (SELECT staker,validator,\
+            row_number() OVER (PARTITION BY staker ORDER BY height DESC) AS n FROM (\
+            SELECT staker,validator,height FROM validator_events WHERE height<?1 \
+            UNION ALL SELECT new_staker,validator,height FROM validator_events WHERE new_staker IS NOT NULL AND height<?1 \
+            UNION ALL SELECT sender,new_validator,height FROM delegations WHERE new_validator<>'' AND height<?1\
+            ) s) WHERE n=1",
+        )
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn save_prism_txs(&self, txs: Vec<PrismTxResult>) -> StorageResult<()> {
+        for tr in txs {
+            sqlx::query(&format!(
+                "INSERT OR REPLACE INTO e2n({PRISM_COLUMNS}) VALUES (?,?,?,?,?,?,?,?,?,?)"
+            ))
+            .bind(&tr.tx_hash)
+            .bind(&tr.block_hash)
+            .bind(&tr.sender)
+            .bind(&tr.receiver)
+            .bind(&tr.asset)
+            .bind(&tr.amount)
+            .bind(tr.decimal)
+            .bind(tr.height)
+            .bind(tr.timestamp)
+            .bind(Json(&tr.value))
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        }
+        Ok(())
+    }
+
+    async fn save_prism_last_height(&self, height: i64) -> StorageResult<()> {
+        sqlx::query("INSERT OR REPLACE INTO e2n_last_height VALUES ('tip', ?)")
+            .bind(height)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn load_prism_last_height(&self) -> StorageResult<i64> {
+        sqlx::query_scalar("SELECT height FROM e2n_last_height")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn block_by_height(&self, height: i64) -> StorageResult<BlockRecord> {
+        let sql = format!("SELECT {BLOCK_COLUMNS} FROM block WHERE height=?");
+        let row: BlockRow = sqlx::query_as(&sql)
+            .bind(height)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn block_by_hash(&self, hash: &str) -> StorageResult<BlockRecord> {
+        let sql = format!("SELECT {BLOCK_COLUMNS} FROM block WHERE block_hash=?");
+        let row: BlockRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn blocks(&self, limit: i64, offset: i64) -> StorageResult<(i64, Vec<BlockRecord>)> {
+        let total: Option<i64> = sqlx::query_scalar("SELECT max(height) FROM block")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        let sql =
+            format!("SELECT {BLOCK_COLUMNS} FROM block ORDER BY height DESC LIMIT ? OFFSET ?");
+        let rows: Vec<BlockRow> = sqlx::query_as(&sql)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((
+            total.unwrap_or_default(),
+            rows.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    async fn tx_origins(&self, height: i64) -> StorageResult<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT origin FROM (SELECT origin,tx_index FROM \"transaction\" WHERE height=?1 \
+            UNION ALL SELECT origin,tx_index FROM quarantined_txs WHERE height=?1) t ORDER BY tx_index",
+        )
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)
+    }
+
+    async fn commit_signatures(&self, height: i64) -> StorageResult<Vec<(String, String)>> {
+        sqlx::query_as(
+            "SELECT address,signature FROM block_generation WHERE height=? AND signature IS NOT NULL",
+        )
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)
+    }
+
+    async fn tx_by_hash(&self, hash: &str) -> StorageResult<Transaction> {
+        let sql = format!("SELECT {TX_COLUMNS} FROM \"transaction\" WHERE tx_hash=?");
+        let row: TxRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn txs(
+        &self,
+        filter: &TxFilter,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<Transaction>)> {
+        let total = self.tx_count(filter).await?;
+
+        let mut qb = QueryBuilder::new(format!("SELECT {}", columns_of(TX_COLUMNS, "t")));
+        push_tx_filter(&mut qb, SQLITE_SCOPE, filter);
+        qb.push(tx_order(filter))
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows: Vec<TxRow> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((total, rows.into_iter().map(Into::into).collect()))
+    }
+
+    async fn tx_operations(&self, hash: &str) -> StorageResult<Vec<V2Operation>> {
+        let sql =
+            format!("SELECT {OPERATION_COLUMNS} FROM operations WHERE tx=? ORDER BY op_index");
+        let rows: Vec<OperationRow> = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn prism_tx_by_hash(&self, hash: &str) -> StorageResult<PrismTxResult> {
+        let sql = format!("SELECT {PRISM_COLUMNS} FROM e2n WHERE tx_hash=?");
+        let row: PrismRow = sqlx::query_as(&sql)
+            .bind(hash)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(row.into())
+    }
+
+    async fn prism_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<PrismTxResult>)> {
+        self.page::<PrismRow, _>(
+            "e2n",
+            PRISM_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("sender", from), ("receiver", to)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn n2e_txs(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ConvertAccountTx>)> {
+        self.page::<N2eRow, _>(
+            "n2e",
+            N2E_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("sender", from), ("receiver", to)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn n2e_tx_by_hash(&self, hash: &str) -> StorageResult<V2ConvertAccountTx> {
+        self.by_tx::<N2eRow, _>("n2e", N2E_COLUMNS, hash).await
+    }
+
+    async fn claims(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ClaimTx>)> {
+        self.page::<ClaimRow, _>(
+            "claims",
+            CLAIM_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn claim_by_hash(&self, hash: &str) -> StorageResult<V2ClaimTx> {
+        self.by_tx::<ClaimRow, _>("claims", CLAIM_COLUMNS, hash)
+            .await
+    }
+
+    async fn delegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2DelegationTx>)> {
+        self.page::<DelegationRow, _>(
+            "delegations",
+            DELEGATION_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn delegation_by_hash(&self, hash: &str) -> StorageResult<V2DelegationTx> {
+        self.by_tx::<DelegationRow, _>("delegations", DELEGATION_COLUMNS, hash)
+            .await
+    }
+
+    async fn undelegations(
+        &self,
+        from: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2UndelegationTx>)> {
+        self.page::<UndelegationRow, _>(
+            "undelegations",
+            UNDELEGATION_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("sender", from)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn undelegation_by_hash(&self, hash: &str) -> StorageResult<V2UndelegationTx> {
+        self.by_tx::<UndelegationRow, _>("undelegations", UNDELEGATION_COLUMNS, hash)
+            .await
+    }
+
+    async fn assets(
+        &self,
+        asset: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2AssetTx>)> {
+        self.page::<AssetRow, _>(
+            "assets",
+            ASSET_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("asset", asset)]),
+            "timestamp DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn mints(
+        &self,
+        receiver: Option<&str>,
+        kind: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2MintTx>)> {
+        self.page::<MintRow, _>(
+            "mints",
+            MINT_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("receiver", receiver), ("kind", kind)]),
+            "height DESC, idx",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn validator_events(
+        &self,
+        address: &str,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<V2ValidatorEvent>)> {
+        self.page::<ValidatorEventRow, _>(
+            "validator_events",
+            VALIDATOR_EVENT_COLUMNS,
+            |qb| push_conditions(qb, SQLITE_SCOPE, &[("validator", Some(address))]),
+            "height DESC",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn slashing_events(
+        &self,
+        validator: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<SlashingRecord>)> {
+        self.page::<SlashingRow, _>(
+            "slashing_events",
+            SLASHING_COLUMNS,
+            |qb| push_slashing_filter(qb, SQLITE_SCOPE, validator),
+            "height DESC,tx_index,op_index",
+            limit,
+            offset,
+        )
+        .await
+    }
+
+    async fn daily_mints(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, Vec<DailyMint>)> {
+        let (mut total, mut daily) = daily_mints_queries::<Sqlite>(
+            SQLITE_SCOPE,
+            "strftime('%Y-%m-%d',timestamp,'unixepoch')",
+            start_time,
+            end_time,
+        );
+        let total: i64 = total
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        let rows: Vec<(String, i64, i64)> = daily
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok((
+            total,
+            rows.into_iter()
+                .map(|(date, amount, count)| DailyMint {
+                    date,
+                    amount,
+                    count,
+                })
+                .collect(),
+        ))
+    }
+
+    async fn validator(&self, address: &str) -> StorageResult<ValidatorRecord> {
+        let pubkey: Option<String> =
+            sqlx::query_scalar("SELECT pubkey FROM validators WHERE address=?")
+                .bind(address)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(storage_err)?;
+        let meta: Option<ValidatorMeta> = sqlx::query_as(
+            "SELECT staker,commission_rate,memo,timestamp FROM validator_events \
+                WHERE validator=? AND memo IS NOT NULL ORDER BY height DESC LIMIT 1",
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(storage_err)?;
+
+        let mut record = validator_record(address, pubkey, meta)?;
+        if let (Some(staker), Some(updated_at)) = (&record.staker, record.updated_at) {
+            let replaced: Option<Option<String>> = sqlx::query_scalar(
+                "SELECT new_staker FROM validator_events WHERE staker=? AND kind='ReplaceStaker' \
+                AND timestamp>=? ORDER BY height DESC LIMIT 1",
+            )
+            .bind(staker)
+            .bind(updated_at)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_err)?;
+            if let Some(new_staker) = replaced {
+                record.staker = new_staker;
+            }
+        }
+        Ok(record)
+    }
+
+    async fn uptime_buckets(
+        &self,
+        addresses: &[String],
+        start: i64,
+    ) -> StorageResult<Vec<UptimeBucket>> {
+        if addresses.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut qb = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {UPTIME_BUCKET_COLUMNS} FROM validator_uptime_buckets WHERE bucket>="
+        ));
+        qb.push_bind(start).push(" AND address IN (");
+        let mut separated = qb.separated(",");
+        for address in addresses {
+            separated.push_bind(address.as_str());
+        }
+        qb.push(") ORDER BY address,bucket");
+        let rows: Vec<UptimeBucketRow> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn uptime_validators(
+        &self,
+        start: i64,
+        limit: i64,
+        offset: i64,
+    ) -> StorageResult<(i64, Vec<String>)> {
+        let total: i64 = sqlx::query_scalar(
+            "SELECT count(DISTINCT address) FROM validator_uptime_buckets WHERE bucket>=?",
+        )
+        .bind(start)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        let addresses = sqlx::query_scalar(
+            "SELECT DISTINCT address FROM validator_uptime_buckets WHERE bucket>=? \
+            ORDER BY address LIMIT ? OFFSET ?",
+        )
+        .bind(start)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        Ok((total, addresses))
+    }
+
+    async fn daily_uptime(&self, address: &str, days: i64) -> StorageResult<Vec<DailyUptime>> {
+        let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+            "SELECT date(b.time) AS day,sum(g.signature IS NOT NULL),sum(g.signature IS NULL),\
+            sum(b.proposer=g.address) FROM block_generation g JOIN block b ON b.height=g.height \
+            WHERE g.address=? GROUP BY day ORDER BY day DESC LIMIT ?",
+        )
+        .bind(address)
+        .bind(days)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(storage_err)?;
+        Ok(rows.into_iter().map(daily_uptime).collect())
+    }
+
+    async fn tx_count(&self, filter: &TxFilter) -> StorageResult<i64> {
+        let mut qb = QueryBuilder::<Sqlite>::new("SELECT count(*)");
+        push_tx_filter(&mut qb, SQLITE_SCOPE, filter);
+        qb.build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn tx_distribution(&self) -> StorageResult<TxDistribution> {
+        let row: (i64, i64, i64, i64, i64) = sqlx::query_as(&tx_distribution_sql(SQLITE_SCOPE))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(tx_distribution(row))
+    }
+
+    async fn address_counts(
+        &self,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> StorageResult<(i64, i64)> {
+        address_counts_query::<Sqlite>(SQLITE_SCOPE, start_time, end_time)
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn load_price(&self, name: &str) -> StorageResult<String> {
+        sqlx::query_scalar("SELECT price FROM prices WHERE name=?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)
+    }
+
+    async fn save_price(&self, name: &str, price: &str) -> StorageResult<()> {
+        sqlx::query("INSERT OR REPLACE INTO prices VALUES (?,?)")
+            .bind(name)
+            .bind(price)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+
+    async fn load_market(&self, name: &str) -> StorageResult<Value> {
+        let market: Json<Value> = sqlx::query_scalar("SELECT val FROM market WHERE name=?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(market.0)
+    }
+
+    async fn save_market(&self, name: &str, market: &Value) -> StorageResult<()> {
+        sqlx::query("INSERT OR REPLACE INTO market VALUES (?,?)")
+            .bind(name)
+            .bind(Json(market))
+            .execute(&self.pool)
+            .await
+            .map_err(storage_err)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn block(height: i64) -> ModuleBlock {
+        let tx = |i: i64| {
+            json!({
+                "tx_hash": format!("{height}{i}"), "block_hash": format!("B{height}"),
+                "height": height, "tx_index": i, "timestamp": height * 10, "ty": 0, "ty_sub": 2,
+                "sender": "fra1a", "receiver": {"addrs": ["fra1b"]}, "code": 0, "log": "",
+                "origin": format!("origin{height}{i}"), "result": {}, "value": {}
+            })
+        };
+        serde_json::from_value(json!({
+            "block_hash": format!("B{height}"), "height": height, "size": 10, "tx_count": 2,
+            "timestamp": "2022-04-15T01:02:03", "app_hash": "A", "proposer": "P",
+            "evm_addrs": [], "native_addrs": [], "txs": [tx(0), tx(2)], "evm_txs": [],
+            "validators": [], "v2_convert_account_txs": [], "v2_undelegation_txs": [],
+            "v2_delegation_txs": [], "v2_claim_txs": [], "v2_asset_txs": [],
+            "v2_validator_events": [], "v2_mint_txs": [], "v2_slashing_events": [],
+            "v2_operations": [{
+                "tx_hash": format!("{height}0"), "op_index": 0, "kind": "Transfer",
+                "signer": "fra1a", "counterparty": "fra1b", "asset": null, "amount": "1",
+                "height": height, "timestamp": height * 10, "content": {}
+            }],
+            "tx_participants": [
+                {"tx_hash": format!("{height}0"), "address": "fra1a", "role": "sender",
+                 "height": height, "timestamp": height * 10},
+                {"tx_hash": format!("{height}2"), "address": "fra1b", "role": "receiver",
+                 "height": height, "timestamp": height * 10}
+            ],
+            "quarantined_txs": [{
+                "tx_hash": format!("Q{height}"), "block_hash": format!("B{height}"),
+                "height": height, "tx_index": 1, "origin": format!("origin{height}1"),
+                "stage": "decode", "error": "e", "timestamp": height * 10
+            }],
+            "block_data": {"height": height}
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage() -> StorageResult<()> {
+        let storage = SqliteStorage::memory().await.map_err(storage_err)?;
+        assert!(matches!(
+            storage.load_last_height().await,
+            Err(StorageError::NotFound)
+        ));
+
+        storage.save_blocks(&[block(1), block(2)]).await?;
+        // saving a height again replaces its records.
+        storage.save_block(block(2)).await?;
+        storage.save_last_height(2).await?;
+        assert_eq!(storage.load_last_height().await?, 2);
+        assert_eq!(storage.load_quarantined_heights().await?, vec![1, 2]);
+
+        let b = storage.block_by_hash("B2").await?;
+        assert_eq!((b.height, b.block_data["height"].as_i64()), (2, Some(2)));
+        assert_eq!(storage.block_by_height(1).await?.block_hash, "B1");
+        let (total, blocks) = storage.blocks(1, 1).await?;
+        assert_eq!((total, blocks[0].height), (2, 1));
+        assert_eq!(
+            storage.tx_origins(2).await?,
+            vec!["origin20", "origin21", "origin22"]
+        );
+
+        let tx = storage.tx_by_hash("10").await?;
+        assert_eq!(tx.receiver["addrs"][0], "fra1b");
+        let filter = TxFilter {
+            height: Some(2),
+            ..Default::default()
+        };
+        let (total, txs) = storage.txs(&filter, 10, 0).await?;
+        assert_eq!(total, 2);
+        assert_eq!(txs[0].tx_hash, "20");
+        let filter = TxFilter {
+            to: Some("fra1b".into()),
+            ..Default::default()
+        };
+        assert_eq!(storage.txs(&filter, 10, 0).await?.0, 2);
+        let filter = TxFilter {
+            address: Some("fra1a".into()),
+            start_time: Some(20),
+            ..Default::default()
+        };
+        let (_, txs) = storage.txs(&filter, 10, 0).await?;
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].tx_hash, "20");
+        assert_eq!(storage.tx_operations("20").await?[0].kind, "Transfer");
+        assert!(storage.tx_operations("21").await?.is_empty());
+
+        let e2n: PrismTxResult = serde_json::from_value(json!({
+            "tx_hash": "E", "block_hash": "B2", "sender": "0xa", "receiver": "fra1b",
+            "asset": "FRA", "amount": "1", "decimal": 6, "height": 2, "timestamp": 20,
+            "value": {}
+        }))
+        .unwrap();
+        storage.save_prism_txs(vec![e2n]).await?;
+        storage.save_prism_last_height(2).await?;
+        assert_eq!(storage.load_prism_last_height().await?, 2);
+        assert_eq!(storage.prism_tx_by_hash("E").await?.decimal, 6);
+        assert_eq!(storage.prism_txs(Some("0xa"), None, 10, 0).await?.0, 1);
+        assert_eq!(storage.prism_txs(None, Some("fra1a"), 10, 0).await?.0, 0);
+        Ok(())
+    }
+
+    /// The staking, validator and statistics records of a block read back.
+    async fn check_staking(storage: &dyn Storage) -> StorageResult<()> {
+        let record = |hash: &str| {
+            json!({"tx_hash": hash, "block_hash": "B1", "height": 1,
+            "timestamp": 10, "content": {}})
+        };
+        let with = |mut v: Value, fields: Value| {
+            v.as_object_mut()
+                .unwrap()
+                .extend(fields.as_object().unwrap().clone());
+            v
+        };
+        let validator = |signature: Option<&str>| {
+            json!([{"address": "V", "power": 100,
+            "pub_key": {"type": "tendermint/PubKeyEd25519", "value": "K"}, "priority": 0,
+            "signature": signature, "timestamp": null}])
+        };
+
+        let mut b1 = serde_json::to_value(block(1)).unwrap();
+        b1["v2_delegation_txs"] = json!([with(
+            record("D"),
+            json!({"sender": "fra1a",
+            "amount": 5, "validator": "V", "new_validator": ""})
+        )]);
+        b1["v2_undelegation_txs"] = json!([with(
+            record("U"),
+            json!({"sender": "fra1a",
+            "amount": 2, "target_validator": "V", "new_delegator": ""})
+        )]);
+        b1["v2_claim_txs"] = json!([with(record("C"), json!({"sender": "fra1a", "amount": 1}))]);
+        b1["v2_convert_account_txs"] = json!([with(
+            record("N"),
+            json!({"sender": "fra1a",
+            "receiver": "0xb", "asset": "FRA", "amount": "3"})
+        )]);
+        b1["v2_asset_txs"] = json!([with(
+            record("A"),
+            json!({"asset": "AS", "issuer": "fra1a",
+            "issued": 1})
+        )]);
+        b1["v2_mint_txs"] = json!([with(
+            record("M"),
+            json!({"idx": 0, "receiver": "fra1a",
+            "amount": 7, "kind": "Coinbase"})
+        )]);
+        b1["v2_validator_events"] = json!([with(
+            record("VE"),
+            json!({"op_index": 0,
+            "validator": "V", "staker": "fra1s", "kind": "Create", "commission_rate": [1, 10],
+            "memo": {"name": "v"}, "new_staker": null})
+        )]);
+        b1["validators"] = validator(Some("sig"));
+        b1["native_addrs"] = json!([{"tx": "10", "address": "fra1a", "timestamp": 10}]);
+        b1["evm_addrs"] = json!([{"tx": "E", "address": "0xb", "timestamp": 10}]);
+        let b1: ModuleBlock = serde_json::from_value(b1).unwrap();
+        let b2 = || {
+            let mut b = serde_json::to_value(block(2)).unwrap();
+            b["validators"] = validator(None);
+            // a governance punishment of the staker, its power is the one of its validator.
+            b["v2_slashing_events"] = json!([{"block_hash": "B2", "tx_hash": "S",
+                "tx_index": 0, "op_index": 0, "validator": "fra1s", "kind": "Governance",
+                "source": "governance", "evidence_height": null, "power": null,
+                "penalty_rate": [1, 10], "height": 2, "timestamp": 20, "content": {}}]);
+            serde_json::from_value::<ModuleBlock>(b).unwrap()
+        };
+        storage.save_blocks(&[b1, b2()]).await?;
+        // saving a height again replaces its records.
+        storage.save_block(b2()).await?;
+
+        assert_eq!(storage.delegations(Some("fra1a"), 10, 0).await?.0, 1);
+        assert_eq!(storage.delegation_by_hash("D").await?.amount, 5);
+        assert_eq!(storage.undelegations(None, 10, 0).await?.1[0].amount, 2);
+        assert_eq!(storage.claim_by_hash("C").await?.sender, "fra1a");
+        // filters are bound, not spliced into the query.
+        let injected = Some("x' OR '1'='1");
+        assert_eq!(storage.claims(injected, 10, 0).await?.0, 0);
+        assert_eq!(storage.delegations(injected, 10, 0).await?.0, 0);
+        assert_eq!(storage.undelegations(injected, 10, 0).await?.0, 0);
+        assert_eq!(storage.n2e_txs(injected, injected, 10, 0).await?.0, 0);
+        assert_eq!(storage.assets(injected, 10, 0).await?.0, 0);
+        assert_eq!(storage.slashing_events(injected, 10, 0).await?.0, 0);
+        assert_eq!(
+            storage.n2e_txs(Some("fra1a"), Some("0xb"), 10, 0).await?.0,
+            1
+        );
+        assert_eq!(storage.n2e_txs(Some("0xb"), None, 10, 0).await?.0, 0);
+        assert_eq!(storage.assets(Some("AS"), 10, 0).await?.1[0].issued, 1);
+        assert_eq!(
+            storage
+                .mints(Some("fra1a"), Some("Coinbase"), 10, 0)
+                .await?
+                .0,
+            1
+        );
+        let (total, daily) = storage.daily_mints(None, None).await?;
+        assert_eq!(total, 7);
+        assert_eq!(daily[0].date, "1970-01-01");
+
+        let v = storage.validator("V").await?;
+        assert_eq!(
+            (v.pubkey.as_deref(), v.staker.as_deref()),
+            (Some("K"), Some("fra1s"))
+        );
+        assert_eq!(v.memo, Some(json!({"name": "v"})));
+        assert!(matches!(
+            storage.validator("X").await,
+            Err(StorageError::NotFound)
+        ));
+        assert_eq!(storage.validator_events("V", 10, 0).await?.0, 1);
+        assert_eq!(storage.stakers(2).await?["fra1s"], "V");
+        let (total, slashing) = storage.slashing_events(Some("V"), 10, 0).await?;
+        assert_eq!(total, 1);
+        assert_eq!(
+            (slashing[0].power, slashing[0].penalty_amount),
+            (Some(100), Some(10))
+        );
+
+        let buckets = storage.uptime_buckets(&["V".to_string()], 0).await?;
+        assert_eq!(buckets.len(), 1);
+        let b = &buckets[0];
+        assert_eq!((b.signed, b.missed, b.trailing_missed), (1, 1, 1));
+        assert_eq!(
+            storage.uptime_validators(0, 10, 0).await?,
+            (1, vec!["V".to_string()])
+        );
+        let daily = storage.daily_uptime("V", 30).await?;
+        assert_eq!((daily.len(), daily[0].signed, daily[0].missed), (1, 1, 1));
+
+        let filter = TxFilter {
+            ty: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(storage.tx_count(&filter).await?, 4);
+        let distribution = storage.tx_distribution().await?;
+        assert_eq!((distribution.privacy, distribution.n2e), (4, 1));
+        assert_eq!(storage.address_counts(None, None).await?, (1, 1));
+
+        storage.save_price("fra", "0.1").await?;
+        storage.save_price("fra", "0.2").await?;
+        assert_eq!(storage.load_price("fra").await?, "0.2");
+        storage.save_market("fra", &json!({"prices": []})).await?;
+        assert_eq!(storage.load_market("fra").await?, json!({"prices": []}));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_staking() -> StorageResult<()> {
+        let storage = SqliteStorage::memory().await.map_err(storage_err)?;
+        check_staking(&storage).await?;
+        if let Some(pool) = db::test_pool("test_staking").await {
+            check_staking(&PgStorage::new(pool)).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pin_network() -> StorageResult<()> {
+        let storage = SqliteStorage::memory().await.map_err(storage_err)?;
+        storage.pin_network("mainnet").await?;
+        storage.pin_network("mainnet").await?;
+        assert!(storage.pin_network("testnet").await.is_err());
+        Ok(())
+    }
+
+    /// Txs of several blocks and of a single one are both listed in chain order.
+    async fn check_tx_order(storage: &dyn Storage) -> StorageResult<()> {
+        let mut b3 = block(3);
+        // same timestamp as the txs of block 2, and saved out of block order.
+        for tx in &mut b3.txs {
+            tx.timestamp = 20;
+        }
+        b3.txs.reverse();
+        storage.save_blocks(&[block(2), b3]).await?;
+
+        let hashes = |txs: Vec<Transaction>| txs.into_iter().map(|t| t.tx_hash).collect::<Vec<_>>();
+        let (_, txs) = storage.txs(&TxFilter::default(), 10, 0).await?;
+        assert_eq!(hashes(txs), ["30", "32", "20", "22"]);
+        let filter = TxFilter {
+            height: Some(3),
+            ..Default::default()
+        };
+        let (_, txs) = storage.txs(&filter, 10, 0).await?;
+        assert_eq!(hashes(txs), ["30", "32"]);
+        let (_, txs) = storage.txs(&TxFilter::default(), 2, 1).await?;
+        assert_eq!(hashes(txs), ["32", "20"]);
+        Ok(())
+    }
+
+    /// Address listings come from the participant rows, once per tx whatever the roles.
+    async fn check_address_txs(storage: &dyn Storage) -> StorageResult<()> {
+        let mut b = block(2);
+        let participant = |tx: &str, address: &str, role: &str| {
+            serde_json::from_value(json!({"tx_hash": tx, "address": address, "role": role,
+                "height": 2, "timestamp": 20}))
+            .unwrap()
+        };
+        // a transfer of fra1a to itself and fra1b, then one to both of them.
+        b.tx_participants
+            .push(participant("20", "fra1a", "receiver"));
+        b.tx_participants
+            .push(participant("20", "fra1b", "receiver"));
+        b.tx_participants
+            .push(participant("22", "fra1a", "receiver"));
+        storage.save_blocks(&[block(1), b]).await?;
+
+        let list = |from: Option<&str>, to: Option<&str>, address: Option<&str>| {
+            let filter = TxFilter {
+                from: from.map(Into::into),
+                to: to.map(Into::into),
+                address: address.map(Into::into),
+                ..Default::default()
+            };
+            async move {
+                let (total, txs) = storage.txs(&filter, 10, 0).await?;
+                let hashes: Vec<_> = txs.into_iter().map(|t| t.tx_hash).collect();
+                assert_eq!(total, hashes.len() as i64);
+                StorageResult::Ok(hashes)
+            }
+        };
+        assert_eq!(list(None, None, Some("fra1a")).await?, ["20", "22", "10"]);
+        assert_eq!(list(None, Some("fra1a"), None).await?, ["20", "22"]);
+        assert_eq!(list(None, Some("fra1b"), None).await?, ["20", "22", "12"]);
+        assert_eq!(list(Some("fra1a"), Some("fra1b"), None).await?, ["20"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_save_blocks_atomic() -> StorageResult<()> {
+        let Some(pool) = db::test_pool("test_save_blocks_atomic").await else {
+            return Ok(());
+        };
+        let storage = PgStorage::new(pool);
+        // the quarantined tx of the second block can't be saved, nor can the batch.
+        let mut b2 = block(2);
+        b2.quarantined_txs[0].stage = "s".repeat(17);
+        assert!(storage.save_blocks(&[block(1), b2]).await.is_err());
+        assert!(matches!(
+            storage.block_by_height(1).await,
+            Err(StorageError::NotFound)
+        ));
+        assert!(storage.load_quarantined_heights().await?.is_empty());
+
+        storage.save_blocks(&[block(1), block(2)]).await?;
+        assert_eq!(storage.load_quarantined_heights().await?, vec![1, 2]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_heights() -> StorageResult<()> {
+        let Some(pool) = db::test_pool("test_hash_heights").await else {
+            return Ok(());
+        };
+        let storage = PgStorage::new(pool.clone());
+        storage.save_blocks(&[block(1), block(2)]).await?;
+        assert_eq!(storage.block_by_hash("B2").await?.height, 2);
+        assert_eq!(storage.tx_by_hash("12").await?.height, 1);
+
+        // a tx saved again at another height moves there, the replaced block hash is gone.
+        let mut b3 = block(3);
+        b3.txs[1].tx_hash = "12".into();
+        let mut b2 = block(2);
+        b2.block_hash = "B2'".into();
+        storage.save_blocks(&[b2, b3]).await?;
+        assert_eq!(storage.tx_by_hash("12").await?.height, 3);
+        let copies: i64 = sqlx::query_scalar("SELECT count(*) FROM transaction WHERE tx_hash='12'")
+            .fetch_one(&pool)
+            .await
+            .map_err(storage_err)?;
+        assert_eq!(copies, 1);
+        assert!(matches!(
+            storage.block_by_hash("B2").await,
+            Err(StorageError::NotFound)
+        ));
+        assert_eq!(storage.block_by_hash("B2'").await?.height, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tx_order() -> StorageResult<()> {
+        let storage = SqliteStorage::memory().await.map_err(storage_err)?;
+        check_tx_order(&storage).await?;
+        let storage = SqliteStorage::memory().await.map_err(storage_err)?;
+        check_address_txs(&storage).await?;
+        if let Some(pool) = db::test_pool("test_tx_order").await {
+            check_tx_order(&PgStorage::new(pool)).await?;
+        }
+        if let Some(pool) = db::test_pool("test_address_txs").await {
+            check_address_txs(&PgStorage::new(pool)).await?;
+        }
+        Ok(())
+    }
+}